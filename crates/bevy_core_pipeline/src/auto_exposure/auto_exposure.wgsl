@@ -32,8 +32,15 @@ struct AutoExposure {
     speed_up: f32,
     speed_down: f32,
     exponential_transition_distance: f32,
+    metering_mode: u32,
+    spot_radius: f32,
 }
 
+// Must match the variants of `AutoExposureMetering`
+const METERING_AVERAGE: u32 = 0u;
+const METERING_CENTER_WEIGHTED: u32 = 1u;
+const METERING_SPOT: u32 = 2u;
+
 struct CompensationCurve {
     min_log_lum: f32,
     inv_log_lum_range: f32,
@@ -86,7 +93,28 @@ fn color_to_bin(hdr: vec3<f32>) -> u32 {
 fn metering_weight(coords: vec2<f32>) -> u32 {
     let pos = vec2<i32>(coords * vec2<f32>(textureDimensions(tex_mask)));
     let mask = textureLoad(tex_mask, pos, 0).r;
-    return u32(mask * 16.0);
+    return u32(mask * metering_preset_weight(coords) * 16.0);
+}
+
+// The weight of the metering preset at the given UV coordinates.
+fn metering_preset_weight(coords: vec2<f32>) -> f32 {
+    // The offset from the center of the screen, in units of the screen height.
+    let dim = vec2<f32>(textureDimensions(tex_color));
+    let offset = (coords - 0.5) * vec2(dim.x / dim.y, 1.0);
+
+    switch settings.metering_mode {
+        case METERING_CENTER_WEIGHTED: {
+            // Fall off from the center to the corners of the screen.
+            let corner = 0.5 * vec2(dim.x / dim.y, 1.0);
+            return 1.0 - smoothstep(0.0, 1.0, length(offset) / length(corner));
+        }
+        case METERING_SPOT: {
+            return select(0.0, 1.0, length(offset) <= settings.spot_radius);
+        }
+        default: {
+            return 1.0;
+        }
+    }
 }
 
 @compute @workgroup_size(16, 16, 1)
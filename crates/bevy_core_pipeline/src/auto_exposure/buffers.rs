@@ -7,15 +7,16 @@ use bevy_render::{
 use bevy_utils::{Entry, HashMap};
 
 use super::pipeline::AutoExposureSettingsUniform;
-use super::AutoExposureSettings;
+use super::{AutoExposureMetering, AutoExposureSettings};
 
 #[derive(Resource, Default)]
-pub(super) struct AutoExposureBuffers {
-    pub(super) buffers: HashMap<Entity, AutoExposureBuffer>,
+pub(crate) struct AutoExposureBuffers {
+    pub(crate) buffers: HashMap<Entity, AutoExposureBuffer>,
 }
 
-pub(super) struct AutoExposureBuffer {
-    pub(super) state: StorageBuffer<f32>,
+pub(crate) struct AutoExposureBuffer {
+    /// The exposure compensation in EV that the tonemapping adds to the exposure of the view
+    pub(crate) state: StorageBuffer<f32>,
     pub(super) settings: UniformBuffer<AutoExposureSettingsUniform>,
 }
 
@@ -49,6 +50,11 @@ pub(super) fn prepare_buffers(
         let (min_log_lum, max_log_lum) = settings.range.into_inner();
         let (low_percent, high_percent) = settings.filter.into_inner();
         let initial_state = 0.0f32.clamp(min_log_lum, max_log_lum);
+        let (metering_mode, spot_radius) = match settings.metering {
+            AutoExposureMetering::Average => (0, 0.0),
+            AutoExposureMetering::CenterWeighted => (1, 0.0),
+            AutoExposureMetering::Spot { radius } => (2, radius),
+        };
 
         let settings = AutoExposureSettingsUniform {
             min_log_lum,
@@ -59,6 +65,8 @@ pub(super) fn prepare_buffers(
             speed_up: settings.speed_brighten,
             speed_down: settings.speed_darken,
             exponential_transition_distance: settings.exponential_transition_distance,
+            metering_mode,
+            spot_radius,
         };
 
         match buffers.buffers.entry(entity) {
@@ -1,3 +1,5 @@
+use std::ops::RangeInclusive;
+
 use bevy_asset::prelude::*;
 use bevy_ecs::system::{lifetimeless::SRes, SystemParamItem};
 use bevy_math::{cubic_splines::CubicGenerator, FloatExt, Vec2};
@@ -108,8 +110,6 @@ impl AutoExposureCompensationCurve {
         let mut lut = [0.0; LUT_SIZE];
 
         let mut previous = curve.position(0.0);
-        let mut min_compensation = previous.y;
-        let mut max_compensation = previous.y;
 
         for segment in curve {
             if segment.position(0.0) != previous {
@@ -135,17 +135,49 @@ impl AutoExposureCompensationCurve {
                 for i in lut_begin.ceil() as usize..=lut_end.floor() as usize {
                     let t = (i as f32 - lut_begin) * lut_inv_range;
                     lut[i] = previous.y.lerp(current.y, t);
-                    min_compensation = min_compensation.min(lut[i]);
-                    max_compensation = max_compensation.max(lut[i]);
                 }
 
                 previous = current;
             }
         }
 
+        Ok(Self::from_lut(min_log_lum, max_log_lum, lut))
+    }
+
+    /// Build an [`AutoExposureCompensationCurve`] from a function that maps the average log
+    /// luminance of the scene in EV-100 to an exposure compensation value in F-stops.
+    ///
+    /// The function is sampled over `log_lum_range`. Scenes outside of it use the compensation of
+    /// the nearest end of the range.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use bevy_asset::prelude::*;
+    /// # use bevy_core_pipeline::auto_exposure::AutoExposureCompensationCurve;
+    /// # let mut compensation_curves = Assets::<AutoExposureCompensationCurve>::default();
+    /// // Let dark scenes stay darker, and bright scenes brighter, than middle gray.
+    /// let curve: Handle<AutoExposureCompensationCurve> = compensation_curves.add(
+    ///     AutoExposureCompensationCurve::from_fn(-8.0..=8.0, |ev| 0.25 * ev),
+    /// );
+    /// ```
+    pub fn from_fn(log_lum_range: RangeInclusive<f32>, f: impl Fn(f32) -> f32) -> Self {
+        let (min_log_lum, max_log_lum) = log_lum_range.into_inner();
+
+        let lut = std::array::from_fn(|i| {
+            f(min_log_lum.lerp(max_log_lum, i as f32 / (LUT_SIZE - 1) as f32))
+        });
+
+        Self::from_lut(min_log_lum, max_log_lum, lut)
+    }
+
+    /// Quantizes a lookup table of exposure compensation values.
+    fn from_lut(min_log_lum: f32, max_log_lum: f32, lut: [f32; LUT_SIZE]) -> Self {
+        let min_compensation = lut.iter().copied().fold(f32::INFINITY, f32::min);
+        let max_compensation = lut.iter().copied().fold(f32::NEG_INFINITY, f32::max);
         let compensation_range = max_compensation - min_compensation;
 
-        Ok(Self {
+        Self {
             min_log_lum,
             max_log_lum,
             min_compensation,
@@ -156,7 +188,7 @@ impl AutoExposureCompensationCurve {
             } else {
                 [0; LUT_SIZE]
             },
-        })
+        }
     }
 }
 
@@ -20,13 +20,14 @@ mod node;
 mod pipeline;
 mod settings;
 
-use buffers::{extract_buffers, prepare_buffers, AutoExposureBuffers};
+pub(crate) use buffers::AutoExposureBuffers;
+use buffers::{extract_buffers, prepare_buffers};
 pub use compensation_curve::{AutoExposureCompensationCurve, AutoExposureCompensationCurveError};
 use node::AutoExposureNode;
 use pipeline::{
     AutoExposurePass, AutoExposurePipeline, ViewAutoExposurePipeline, METERING_SHADER_HANDLE,
 };
-pub use settings::AutoExposureSettings;
+pub use settings::{AutoExposureMetering, AutoExposureSettings};
 
 use crate::auto_exposure::compensation_curve::GpuAutoExposureCompensationCurve;
 use crate::core_3d::graph::{Core3d, Node3d};
@@ -79,7 +80,11 @@ impl Plugin for AutoExposurePlugin {
             .add_render_graph_node::<AutoExposureNode>(Core3d, node::AutoExposure)
             .add_render_graph_edges(
                 Core3d,
-                (Node3d::EndMainPass, node::AutoExposure, Node3d::Tonemapping),
+                (
+                    Node3d::EndMainPassUpscaling,
+                    node::AutoExposure,
+                    Node3d::Tonemapping,
+                ),
             );
     }
 
@@ -36,6 +36,8 @@ pub struct AutoExposureSettingsUniform {
     pub(super) speed_up: f32,
     pub(super) speed_down: f32,
     pub(super) exponential_transition_distance: f32,
+    pub(super) metering_mode: u32,
+    pub(super) spot_radius: f32,
 }
 
 #[derive(PartialEq, Eq, Hash, Clone)]
@@ -82,6 +82,12 @@ pub struct AutoExposureSettings {
     /// implementation.
     pub metering_mask: Handle<Image>,
 
+    /// A weighting of the screen to apply when metering, in addition to the
+    /// [`metering_mask`](Self::metering_mask).
+    ///
+    /// The default value is [`AutoExposureMetering::Average`].
+    pub metering: AutoExposureMetering,
+
     /// Exposure compensation curve to apply after metering.
     /// The default value is a flat line at 0.0.
     /// For more information, see [`AutoExposureCompensationCurve`].
@@ -97,7 +103,27 @@ impl Default for AutoExposureSettings {
             speed_darken: 1.0,
             exponential_transition_distance: 1.5,
             metering_mask: default(),
+            metering: default(),
             compensation_curve: default(),
         }
     }
 }
+
+/// A preset weighting of the screen to use when metering, see [`AutoExposureSettings::metering`].
+///
+/// The weights are multiplied with the metering mask, so a preset can be combined with a mask
+/// that excludes e.g. a UI-heavy region.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Reflect)]
+pub enum AutoExposureMetering {
+    /// All pixels contribute equally.
+    #[default]
+    Average,
+    /// Pixels contribute more the closer they are to the center of the screen, down to nothing in
+    /// the corners.
+    CenterWeighted,
+    /// Only pixels within a circle around the center of the screen contribute.
+    Spot {
+        /// The radius of the circle, as a fraction of the screen height.
+        radius: f32,
+    },
+}
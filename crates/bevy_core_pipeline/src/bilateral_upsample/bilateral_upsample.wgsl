@@ -0,0 +1,103 @@
+// Upsamples a low resolution texture with the full resolution depth, and optionally normals, as
+// guides.
+//
+// Each full resolution pixel blends the four nearest low resolution pixels bilinearly, leaving
+// out the ones whose depth or normal differ too much from its own. When all four are left out,
+// the one with the closest depth is used instead, so that the low resolution texture doesn't
+// bleed over the edges of the meshes.
+
+#import bevy_core_pipeline::fullscreen_vertex_shader::FullscreenVertexOutput
+
+struct BilateralUpsampleSettings {
+    downsample_factor: u32,
+    depth_threshold: f32,
+    normal_threshold: f32,
+}
+
+@group(0) @binding(0) var low_resolution_color: texture_2d<f32>;
+#ifdef MULTISAMPLED
+@group(0) @binding(1) var low_resolution_depth: texture_depth_multisampled_2d;
+@group(0) @binding(2) var full_resolution_depth: texture_depth_multisampled_2d;
+#else
+@group(0) @binding(1) var low_resolution_depth: texture_depth_2d;
+@group(0) @binding(2) var full_resolution_depth: texture_depth_2d;
+#endif
+@group(0) @binding(3) var<uniform> settings: BilateralUpsampleSettings;
+#ifdef NORMALS
+#ifdef MULTISAMPLED
+@group(0) @binding(4) var full_resolution_normals: texture_multisampled_2d<f32>;
+#else
+@group(0) @binding(4) var full_resolution_normals: texture_2d<f32>;
+#endif
+#endif
+
+// The difference between two depths relative to the closest one, which is about the relative
+// difference of their view space depths with a perspective projection.
+fn depth_difference(a: f32, b: f32) -> f32 {
+    return abs(a - b) / max(max(a, b), 1e-6);
+}
+
+#ifdef NORMALS
+// Reads a world space normal encoded like the ones of the normal prepass. Only the first sample is
+// read with MSAA.
+fn load_normal(coords: vec2<i32>) -> vec3<f32> {
+    let max_coords = vec2<i32>(textureDimensions(full_resolution_normals)) - 1;
+    let encoded = textureLoad(full_resolution_normals, clamp(coords, vec2(0), max_coords), 0).xyz;
+    return normalize(encoded * 2.0 - 1.0);
+}
+#endif
+
+@fragment
+fn upsample(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
+    let full_resolution_coords = vec2<i32>(floor(in.position.xy));
+    let depth = textureLoad(full_resolution_depth, full_resolution_coords, 0);
+#ifdef NORMALS
+    let normal = load_normal(full_resolution_coords);
+#endif
+
+    // The position of the pixel in the low resolution texture, relative to the centers of the
+    // four nearest low resolution pixels.
+    let factor = i32(max(settings.downsample_factor, 1u));
+    let max_coords = vec2<i32>(textureDimensions(low_resolution_color)) - 1;
+    let position = in.position.xy / f32(factor) - 0.5;
+    let base_coords = vec2<i32>(floor(position));
+    let f = fract(position);
+
+    var offsets = array<vec2<i32>, 4>(vec2(0, 0), vec2(1, 0), vec2(0, 1), vec2(1, 1));
+    var weights = array<f32, 4>(
+        (1.0 - f.x) * (1.0 - f.y),
+        f.x * (1.0 - f.y),
+        (1.0 - f.x) * f.y,
+        f.x * f.y,
+    );
+
+    var color_sum = vec4(0.0);
+    var weight_sum = 0.0;
+    var nearest_color = vec4(0.0);
+    var nearest_difference = 3.40282347e38;
+    for (var i = 0; i < 4; i += 1) {
+        let coords = clamp(base_coords + offsets[i], vec2(0), max_coords);
+        let color = textureLoad(low_resolution_color, coords, 0);
+        let difference = depth_difference(textureLoad(low_resolution_depth, coords, 0), depth);
+
+        if difference < nearest_difference {
+            nearest_difference = difference;
+            nearest_color = color;
+        }
+
+        var similar = difference <= settings.depth_threshold;
+#ifdef NORMALS
+        let low_resolution_normal = load_normal(coords * factor);
+        similar = similar && dot(normal, low_resolution_normal) >= settings.normal_threshold;
+#endif
+        if similar {
+            color_sum += color * weights[i];
+            weight_sum += weights[i];
+        }
+    }
+
+    if weight_sum <= 1e-5 {
+        return nearest_color;
+    }
+    return color_sum / weight_sum;
+}
@@ -0,0 +1,28 @@
+// Downsamples a depth texture into the low resolution depth of a bilateral upsample.
+//
+// The `downsample_depth` pass keeps the farthest depth of each 2x2 block of the full resolution
+// depth texture, so that the low resolution pixels belong to whatever is visible behind the edges
+// of the meshes in front. The bilateral upsample then uses this depth to keep the low resolution
+// color from bleeding over those edges.
+
+#import bevy_core_pipeline::fullscreen_vertex_shader::FullscreenVertexOutput
+
+#ifdef MULTISAMPLED
+@group(0) @binding(0) var depth_texture: texture_depth_multisampled_2d;
+#else
+@group(0) @binding(0) var depth_texture: texture_depth_2d;
+#endif
+
+@fragment
+fn downsample_depth(in: FullscreenVertexOutput) -> @builtin(frag_depth) f32 {
+    let max_coords = vec2<i32>(textureDimensions(depth_texture)) - 1;
+    let coords = vec2<i32>(floor(in.position.xy)) * 2;
+
+    // Depth is reversed, so the farthest depth is the smallest one. Only the first sample is read
+    // with MSAA.
+    let depth_00 = textureLoad(depth_texture, min(coords, max_coords), 0);
+    let depth_10 = textureLoad(depth_texture, min(coords + vec2(1, 0), max_coords), 0);
+    let depth_01 = textureLoad(depth_texture, min(coords + vec2(0, 1), max_coords), 0);
+    let depth_11 = textureLoad(depth_texture, min(coords + vec2(1, 1), max_coords), 0);
+    return min(min(depth_00, depth_10), min(depth_01, depth_11));
+}
@@ -0,0 +1,544 @@
+//! A depth-aware upsampling pass, for the effects that render at a lower resolution than their
+//! view.
+//!
+//! Upsampling a low resolution buffer bilinearly makes it bleed over the edges of the meshes in
+//! front of it, or blurs it over the background. The bilateral upsample blends the four nearest
+//! low resolution pixels like bilinear filtering, but leaves out the ones whose depth, and
+//! optionally normal, differ too much from the full resolution pixel, so that the edges stay
+//! sharp.
+//!
+//! An effect that uses it:
+//! - renders its low resolution buffer, along with a low resolution depth texture where each pixel
+//!   holds the depth the buffer was computed at, which can be downsampled from the full
+//!   resolution depth with the [`DownsampleDepthPipeline`],
+//! - prepares a [`BilateralUpsample`] for each view with [`BilateralUpsamplePipeline::prepare`],
+//!   in [`RenderSet::PrepareResources`],
+//! - and calls [`BilateralUpsample::render`] from its render graph node.
+//!
+//! See [`HalfResolutionTransparency`](crate::half_resolution_transparency::HalfResolutionTransparency)
+//! for an example.
+
+use crate::{
+    core_3d::CORE_3D_DEPTH_FORMAT, fullscreen_vertex_shader::fullscreen_shader_vertex_state,
+};
+use bevy_app::prelude::*;
+use bevy_asset::{load_internal_asset, Handle};
+use bevy_ecs::prelude::*;
+use bevy_math::UVec2;
+use bevy_render::{
+    camera::Viewport,
+    render_resource::{
+        binding_types::{
+            texture_2d, texture_2d_multisampled, texture_depth_2d, texture_depth_2d_multisampled,
+            uniform_buffer,
+        },
+        *,
+    },
+    renderer::{RenderContext, RenderDevice, RenderQueue},
+    Render, RenderApp, RenderSet,
+};
+
+/// The settings of a [`BilateralUpsample`].
+#[derive(ShaderType, Clone, Copy, Debug)]
+pub struct BilateralUpsampleSettings {
+    /// How many times smaller the low resolution texture is than the full resolution one, along
+    /// each axis.
+    ///
+    /// The default value is 2.
+    pub downsample_factor: u32,
+    /// The largest relative difference between the depth of a full resolution pixel and the depth
+    /// of a low resolution pixel for them to be blended together.
+    ///
+    /// When all four nearest low resolution pixels are further away than that, the full
+    /// resolution pixel takes the color of the one with the closest depth instead. Lower values
+    /// give sharper edges, and higher values smoother gradients.
+    ///
+    /// The default value is 0.1.
+    pub depth_threshold: f32,
+    /// The smallest cosine of the angle between the normal of a full resolution pixel and the
+    /// normal of a low resolution pixel for them to be blended together.
+    ///
+    /// This is only used when the pipeline is specialized with
+    /// [`BilateralUpsamplePipelineKey::normals`].
+    ///
+    /// The default value is 0.9.
+    pub normal_threshold: f32,
+}
+
+impl Default for BilateralUpsampleSettings {
+    fn default() -> Self {
+        BilateralUpsampleSettings {
+            downsample_factor: 2,
+            depth_threshold: 0.1,
+            normal_threshold: 0.9,
+        }
+    }
+}
+
+const BILATERAL_UPSAMPLE_SHADER_HANDLE: Handle<Shader> =
+    Handle::weak_from_u128(8106492417366723851);
+const DOWNSAMPLE_DEPTH_SHADER_HANDLE: Handle<Shader> = Handle::weak_from_u128(2739462810385937264);
+
+/// Adds the [`BilateralUpsamplePipeline`] and the [`DownsampleDepthPipeline`], for the effects
+/// that upsample their low resolution buffers with them.
+pub struct BilateralUpsamplePlugin;
+
+impl Plugin for BilateralUpsamplePlugin {
+    fn build(&self, app: &mut App) {
+        load_internal_asset!(
+            app,
+            BILATERAL_UPSAMPLE_SHADER_HANDLE,
+            "bilateral_upsample.wgsl",
+            Shader::from_wgsl
+        );
+        load_internal_asset!(
+            app,
+            DOWNSAMPLE_DEPTH_SHADER_HANDLE,
+            "downsample_depth.wgsl",
+            Shader::from_wgsl
+        );
+
+        let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+        render_app
+            .init_resource::<SpecializedRenderPipelines<BilateralUpsamplePipeline>>()
+            .init_resource::<SpecializedRenderPipelines<DownsampleDepthPipeline>>()
+            .init_resource::<BilateralUpsampleUniforms>()
+            .add_systems(
+                Render,
+                prepare_bilateral_upsample_uniforms.in_set(RenderSet::PrepareResourcesFlush),
+            );
+    }
+
+    fn finish(&self, app: &mut App) {
+        let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+        render_app
+            .init_resource::<BilateralUpsamplePipeline>()
+            .init_resource::<DownsampleDepthPipeline>();
+    }
+}
+
+/// The settings of all the [`BilateralUpsample`]s of this frame.
+#[derive(Resource, Default)]
+pub struct BilateralUpsampleUniforms {
+    uniforms: DynamicUniformBuffer<BilateralUpsampleSettings>,
+}
+
+fn prepare_bilateral_upsample_uniforms(
+    mut uniforms: ResMut<BilateralUpsampleUniforms>,
+    render_device: Res<RenderDevice>,
+    render_queue: Res<RenderQueue>,
+) {
+    if uniforms.uniforms.is_empty() {
+        return;
+    }
+    uniforms
+        .uniforms
+        .write_buffer(&render_device, &render_queue);
+    // The buffer keeps this frame's settings, the upsamples of the next frame push theirs again
+    uniforms.uniforms.clear();
+}
+
+/// The bind group layouts of the [`BilateralUpsamplePipeline`], indexed by whether the depth
+/// textures are multisampled and whether normals are used.
+#[derive(Resource)]
+pub struct BilateralUpsamplePipeline {
+    layouts: [[BindGroupLayout; 2]; 2],
+}
+
+impl BilateralUpsamplePipeline {
+    fn layout(&self, key: &BilateralUpsamplePipelineKey) -> &BindGroupLayout {
+        &self.layouts[key.multisampled as usize][key.normals as usize]
+    }
+
+    /// Specializes the pipeline for `key`, and queues `settings` for this frame.
+    ///
+    /// This must be called in [`RenderSet::PrepareResources`], every frame the upsample is
+    /// rendered.
+    pub fn prepare(
+        &self,
+        pipeline_cache: &PipelineCache,
+        pipelines: &mut SpecializedRenderPipelines<Self>,
+        uniforms: &mut BilateralUpsampleUniforms,
+        key: BilateralUpsamplePipelineKey,
+        settings: &BilateralUpsampleSettings,
+    ) -> BilateralUpsample {
+        BilateralUpsample {
+            pipeline: pipelines.specialize(pipeline_cache, self, key),
+            key,
+            uniform_offset: uniforms.uniforms.push(settings),
+        }
+    }
+}
+
+impl FromWorld for BilateralUpsamplePipeline {
+    fn from_world(render_world: &mut World) -> Self {
+        let render_device = render_world.resource::<RenderDevice>();
+        let create_layout = |multisampled: bool, normals: bool| {
+            let (depth_texture, normal_texture) = if multisampled {
+                (
+                    texture_depth_2d_multisampled(),
+                    texture_2d_multisampled(TextureSampleType::Float { filterable: false }),
+                )
+            } else {
+                (
+                    texture_depth_2d(),
+                    texture_2d(TextureSampleType::Float { filterable: false }),
+                )
+            };
+            let entries = (
+                // Low resolution color
+                texture_2d(TextureSampleType::Float { filterable: false }),
+                // Low resolution depth
+                depth_texture,
+                // Full resolution depth
+                depth_texture,
+                uniform_buffer::<BilateralUpsampleSettings>(true),
+            );
+            let label = format!(
+                "bilateral_upsample_{}{}_bind_group_layout",
+                if multisampled {
+                    "multisampled"
+                } else {
+                    "single_sampled"
+                },
+                if normals { "_normals" } else { "" },
+            );
+            if normals {
+                render_device.create_bind_group_layout(
+                    label.as_str(),
+                    &BindGroupLayoutEntries::sequential(
+                        ShaderStages::FRAGMENT,
+                        (entries.0, entries.1, entries.2, entries.3, normal_texture),
+                    ),
+                )
+            } else {
+                render_device.create_bind_group_layout(
+                    label.as_str(),
+                    &BindGroupLayoutEntries::sequential(ShaderStages::FRAGMENT, entries),
+                )
+            }
+        };
+
+        BilateralUpsamplePipeline {
+            layouts: [false, true].map(|multisampled| {
+                [false, true].map(|normals| create_layout(multisampled, normals))
+            }),
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
+pub struct BilateralUpsamplePipelineKey {
+    /// The format of the texture the upsampled color is written to.
+    pub texture_format: TextureFormat,
+    /// The sample count of the texture the upsampled color is written to.
+    pub samples: u32,
+    /// Whether the depth and normal textures are multisampled. Only their first sample is read.
+    pub multisampled: bool,
+    /// Whether the full resolution normals, such as the ones of the
+    /// [`NormalPrepass`](crate::prepass::NormalPrepass), are used as a guide as well.
+    pub normals: bool,
+    /// How the upsampled color is blended with the texture it is written to.
+    pub blend: Option<BlendState>,
+}
+
+impl SpecializedRenderPipeline for BilateralUpsamplePipeline {
+    type Key = BilateralUpsamplePipelineKey;
+
+    fn specialize(&self, key: Self::Key) -> RenderPipelineDescriptor {
+        let mut shader_defs = vec![];
+        if key.multisampled {
+            shader_defs.push("MULTISAMPLED".into());
+        }
+        if key.normals {
+            shader_defs.push("NORMALS".into());
+        }
+
+        RenderPipelineDescriptor {
+            label: Some("bilateral_upsample_pipeline".into()),
+            layout: vec![self.layout(&key).clone()],
+            vertex: fullscreen_shader_vertex_state(),
+            fragment: Some(FragmentState {
+                shader: BILATERAL_UPSAMPLE_SHADER_HANDLE,
+                shader_defs,
+                entry_point: "upsample".into(),
+                targets: vec![Some(ColorTargetState {
+                    format: key.texture_format,
+                    blend: key.blend,
+                    write_mask: ColorWrites::ALL,
+                })],
+            }),
+            primitive: PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: MultisampleState {
+                count: key.samples,
+                ..MultisampleState::default()
+            },
+            push_constant_ranges: Vec::new(),
+        }
+    }
+}
+
+/// A bilateral upsample prepared for this frame by [`BilateralUpsamplePipeline::prepare`].
+#[derive(Clone, Copy, Debug)]
+pub struct BilateralUpsample {
+    pipeline: CachedRenderPipelineId,
+    key: BilateralUpsamplePipelineKey,
+    uniform_offset: u32,
+}
+
+/// The textures read by a [`BilateralUpsample`].
+pub struct BilateralUpsampleTextures<'a> {
+    /// The low resolution texture to upsample.
+    pub color: &'a TextureView,
+    /// The depth of each pixel of the low resolution texture.
+    pub low_resolution_depth: &'a TextureView,
+    /// The full resolution depth, such as the
+    /// [`ViewDepthTexture`](bevy_render::view::ViewDepthTexture) of the view.
+    pub depth: &'a TextureView,
+    /// The full resolution normals, when the pipeline is specialized with
+    /// [`BilateralUpsamplePipelineKey::normals`].
+    ///
+    /// The normals are encoded like the ones of the [`NormalPrepass`](crate::prepass::NormalPrepass),
+    /// and the normal of a low resolution pixel is read from the full resolution pixel at its top
+    /// left corner.
+    pub normals: Option<&'a TextureView>,
+}
+
+impl BilateralUpsample {
+    /// Upsamples `textures.color` into `color_attachment`, within `viewport`.
+    ///
+    /// The viewport is the full resolution one, and the low resolution textures are expected to
+    /// hold the scaled down viewport, at `viewport.physical_position / downsample_factor`. Does
+    /// nothing when the pipeline isn't ready yet, or when the normals are missing.
+    pub fn render(
+        &self,
+        render_context: &mut RenderContext,
+        world: &World,
+        textures: BilateralUpsampleTextures,
+        color_attachment: RenderPassColorAttachment,
+        viewport: Option<&Viewport>,
+    ) {
+        let upsample_pipeline = world.resource::<BilateralUpsamplePipeline>();
+        let Some(pipeline) = world
+            .resource::<PipelineCache>()
+            .get_render_pipeline(self.pipeline)
+        else {
+            return;
+        };
+        let Some(uniforms) = world
+            .resource::<BilateralUpsampleUniforms>()
+            .uniforms
+            .binding()
+        else {
+            return;
+        };
+
+        let layout = upsample_pipeline.layout(&self.key);
+        let bind_group = match (self.key.normals, textures.normals) {
+            (false, _) => render_context.render_device().create_bind_group(
+                "bilateral_upsample_bind_group",
+                layout,
+                &BindGroupEntries::sequential((
+                    textures.color,
+                    textures.low_resolution_depth,
+                    textures.depth,
+                    uniforms,
+                )),
+            ),
+            (true, Some(normals)) => render_context.render_device().create_bind_group(
+                "bilateral_upsample_bind_group",
+                layout,
+                &BindGroupEntries::sequential((
+                    textures.color,
+                    textures.low_resolution_depth,
+                    textures.depth,
+                    uniforms,
+                    normals,
+                )),
+            ),
+            (true, None) => return,
+        };
+
+        let mut render_pass = render_context.begin_tracked_render_pass(RenderPassDescriptor {
+            label: Some("bilateral_upsample_pass"),
+            color_attachments: &[Some(color_attachment)],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        if let Some(viewport) = viewport {
+            render_pass.set_camera_viewport(viewport);
+        }
+        render_pass.set_render_pipeline(pipeline);
+        render_pass.set_bind_group(0, &bind_group, &[self.uniform_offset]);
+        render_pass.draw(0..3, 0..1);
+    }
+}
+
+/// Downsamples a full resolution depth texture by two along each axis, keeping the farthest depth
+/// of each 2x2 block of pixels, into the low resolution depth read by a [`BilateralUpsample`].
+#[derive(Resource)]
+pub struct DownsampleDepthPipeline {
+    /// Layout with the full resolution depth, used when MSAA is off
+    single_sampled: BindGroupLayout,
+    /// Layout used when MSAA is on, and so the depth texture is multisampled
+    multisampled: BindGroupLayout,
+}
+
+impl DownsampleDepthPipeline {
+    fn layout(&self, multisampled: bool) -> &BindGroupLayout {
+        if multisampled {
+            &self.multisampled
+        } else {
+            &self.single_sampled
+        }
+    }
+
+    /// Specializes the pipeline for `key`.
+    pub fn prepare(
+        &self,
+        pipeline_cache: &PipelineCache,
+        pipelines: &mut SpecializedRenderPipelines<Self>,
+        key: DownsampleDepthPipelineKey,
+    ) -> DownsampleDepth {
+        DownsampleDepth {
+            pipeline: pipelines.specialize(pipeline_cache, self, key),
+            key,
+        }
+    }
+}
+
+impl FromWorld for DownsampleDepthPipeline {
+    fn from_world(render_world: &mut World) -> Self {
+        let render_device = render_world.resource::<RenderDevice>();
+        let create_layout = |label: &str, depth_texture: BindGroupLayoutEntryBuilder| {
+            render_device.create_bind_group_layout(
+                format!("downsample_depth_{label}_bind_group_layout").as_str(),
+                &BindGroupLayoutEntries::single(ShaderStages::FRAGMENT, depth_texture),
+            )
+        };
+
+        DownsampleDepthPipeline {
+            single_sampled: create_layout("single_sampled", texture_depth_2d()),
+            multisampled: create_layout("multisampled", texture_depth_2d_multisampled()),
+        }
+    }
+}
+
+/// The key of the [`DownsampleDepthPipeline`].
+///
+/// The low resolution depth texture is written as a [`CORE_3D_DEPTH_FORMAT`] depth attachment.
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
+pub struct DownsampleDepthPipelineKey {
+    /// The sample count of both the full resolution and the low resolution depth textures.
+    pub samples: u32,
+}
+
+impl SpecializedRenderPipeline for DownsampleDepthPipeline {
+    type Key = DownsampleDepthPipelineKey;
+
+    fn specialize(&self, key: Self::Key) -> RenderPipelineDescriptor {
+        let mut shader_defs = vec![];
+        if key.samples > 1 {
+            shader_defs.push("MULTISAMPLED".into());
+        }
+
+        RenderPipelineDescriptor {
+            label: Some("downsample_depth_pipeline".into()),
+            layout: vec![self.layout(key.samples > 1).clone()],
+            vertex: fullscreen_shader_vertex_state(),
+            fragment: Some(FragmentState {
+                shader: DOWNSAMPLE_DEPTH_SHADER_HANDLE,
+                shader_defs,
+                entry_point: "downsample_depth".into(),
+                targets: vec![],
+            }),
+            primitive: PrimitiveState::default(),
+            depth_stencil: Some(DepthStencilState {
+                format: CORE_3D_DEPTH_FORMAT,
+                depth_write_enabled: true,
+                depth_compare: CompareFunction::Always,
+                stencil: StencilState::default(),
+                bias: DepthBiasState::default(),
+            }),
+            multisample: MultisampleState {
+                count: key.samples,
+                ..MultisampleState::default()
+            },
+            push_constant_ranges: Vec::new(),
+        }
+    }
+}
+
+/// A depth downsample prepared by [`DownsampleDepthPipeline::prepare`].
+#[derive(Clone, Copy, Debug)]
+pub struct DownsampleDepth {
+    pipeline: CachedRenderPipelineId,
+    key: DownsampleDepthPipelineKey,
+}
+
+impl DownsampleDepth {
+    /// Downsamples `depth` into `low_resolution_depth`, within `viewport`.
+    ///
+    /// The viewport is the low resolution one, see [`half_resolution_viewport`]. Returns `false`
+    /// without doing anything when the pipeline isn't ready yet.
+    pub fn render(
+        &self,
+        render_context: &mut RenderContext,
+        world: &World,
+        depth: &TextureView,
+        low_resolution_depth: &TextureView,
+        viewport: Option<&Viewport>,
+    ) -> bool {
+        let Some(pipeline) = world
+            .resource::<PipelineCache>()
+            .get_render_pipeline(self.pipeline)
+        else {
+            return false;
+        };
+
+        let bind_group = render_context.render_device().create_bind_group(
+            "downsample_depth_bind_group",
+            world
+                .resource::<DownsampleDepthPipeline>()
+                .layout(self.key.samples > 1),
+            &BindGroupEntries::single(depth),
+        );
+
+        let mut render_pass = render_context.begin_tracked_render_pass(RenderPassDescriptor {
+            label: Some("downsample_depth_pass"),
+            color_attachments: &[],
+            depth_stencil_attachment: Some(RenderPassDepthStencilAttachment {
+                view: low_resolution_depth,
+                depth_ops: Some(Operations {
+                    load: LoadOp::Clear(0.0),
+                    store: StoreOp::Store,
+                }),
+                stencil_ops: None,
+            }),
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        if let Some(viewport) = viewport {
+            render_pass.set_camera_viewport(viewport);
+        }
+        render_pass.set_render_pipeline(pipeline);
+        render_pass.set_bind_group(0, &bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+        true
+    }
+}
+
+/// Returns the viewport of the half resolution textures of a view with a custom viewport.
+pub fn half_resolution_viewport(viewport: &Viewport) -> Viewport {
+    Viewport {
+        physical_position: viewport.physical_position / 2,
+        physical_size: (viewport.physical_size / 2).max(UVec2::ONE),
+        depth: viewport.depth.clone(),
+    }
+}
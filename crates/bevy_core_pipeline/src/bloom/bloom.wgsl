@@ -6,10 +6,35 @@
 // * [COD] - Next Generation Post Processing in Call of Duty - http://www.iryoku.com/next-generation-post-processing-in-call-of-duty-advanced-warfare
 // * [PBB] - Physically Based Bloom - https://learnopengl.com/Guest-Articles/2022/Phys.-Based-Bloom
 
+#ifdef LAYER_INTENSITY
+#import bevy_core_pipeline::mesh_mask::MESH_MASK_BLOOM_LAYER_BITS
+#endif
+
 struct BloomUniforms {
     threshold_precomputations: vec4<f32>,
     viewport: vec4<f32>,
     aspect: f32,
+    lens_dirt_intensity: f32,
+    intensity: f32,
+    low_frequency_boost: f32,
+    low_frequency_boost_curvature: f32,
+    high_pass_frequency: f32,
+    max_mip: f32,
+    layer_intensity: array<vec4<f32>, 2>,
+    lens_flare_ghost: vec4<f32>,
+    lens_flare_halo: vec4<f32>,
+    lens_flare_starburst: vec4<f32>,
+    lens_flare_threshold: f32,
+    lens_flare_ghost_count: u32,
+    lens_flare_starburst_count: u32,
+};
+
+struct BloomMipUniform {
+    mip: f32,
+#ifdef SIXTEEN_BYTE_ALIGNMENT
+    // WebGL2 structs must be 16 byte aligned.
+    _webgl2_padding: vec3<f32>
+#endif
 };
 
 @group(0) @binding(0) var input_texture: texture_2d<f32>;
@@ -17,6 +42,30 @@ struct BloomUniforms {
 
 @group(0) @binding(2) var<uniform> uniforms: BloomUniforms;
 
+#ifdef FIRST_DOWNSAMPLE
+#ifdef RESPONSE_CURVE
+@group(0) @binding(3) var response_curve: texture_2d<f32>;
+#endif
+#ifdef LAYER_INTENSITY
+#ifdef MULTISAMPLED_MESH_MASK
+@group(1) @binding(0) var mesh_mask: texture_multisampled_2d<u32>;
+#else
+@group(1) @binding(0) var mesh_mask: texture_2d<u32>;
+#endif
+#endif
+#else
+// Set per upsampling pass
+@group(0) @binding(3) var<uniform> mip_uniform: BloomMipUniform;
+
+#ifdef LENS_DIRT
+@group(0) @binding(4) var lens_dirt_texture: texture_2d<f32>;
+#endif
+
+#ifdef LENS_FLARE
+@group(1) @binding(0) var lens_flare_texture: texture_2d<f32>;
+#endif
+#endif
+
 #ifdef FIRST_DOWNSAMPLE
 // https://catlikecoding.com/unity/tutorials/advanced-rendering/bloom/#3.4
 fn soft_threshold(color: vec3<f32>) -> vec3<f32> {
@@ -28,6 +77,19 @@ fn soft_threshold(color: vec3<f32>) -> vec3<f32> {
     contribution /= max(brightness, 0.00001); // Prevent division by 0
     return color * contribution;
 }
+
+#ifdef RESPONSE_CURVE
+// Looks up the fraction of the color that blooms in the user-provided response curve,
+// which maps the brightness range [0.0, inf) to [0.0, 1.0] along its width.
+fn apply_response_curve(color: vec3<f32>) -> vec3<f32> {
+    let brightness = max(color.r, max(color.g, color.b));
+    let x = brightness / (brightness + 1.0);
+    // Sample between the centers of the first and last texels
+    let width = f32(textureDimensions(response_curve).x);
+    let uv = vec2<f32>((x * (width - 1.0) + 0.5) / width, 0.5);
+    return color * textureSampleLevel(response_curve, s, uv, 0.0).r;
+}
+#endif
 #endif
 
 // luminance coefficients from Rec. 709.
@@ -48,21 +110,35 @@ fn karis_average(color: vec3<f32>) -> f32 {
     return 1.0 / (1.0 + luma);
 }
 
+// Scales a sample of the rendered frame by the intensity of the bloom layer of the mesh covering it,
+// which the mesh mask prepass writes. The mask has the size of the rendered frame, so the offset of
+// the sample is in pixels of the mask too.
+fn apply_layer_intensity(sample: vec4<f32>, uv: vec2<f32>, offset: vec2<i32>) -> vec3<f32> {
+#ifdef LAYER_INTENSITY
+    let size = vec2<i32>(textureDimensions(mesh_mask));
+    let coords = clamp(vec2<i32>(uv * vec2<f32>(size)) + offset, vec2(0), size - 1);
+    let layer = textureLoad(mesh_mask, coords, 0).r & MESH_MASK_BLOOM_LAYER_BITS;
+    return sample.rgb * uniforms.layer_intensity[layer / 4u][layer % 4u];
+#else
+    return sample.rgb;
+#endif
+}
+
 // [COD] slide 153
 fn sample_input_13_tap(uv: vec2<f32>) -> vec3<f32> {
-    let a = textureSample(input_texture, s, uv, vec2<i32>(-2, 2)).rgb;
-    let b = textureSample(input_texture, s, uv, vec2<i32>(0, 2)).rgb;
-    let c = textureSample(input_texture, s, uv, vec2<i32>(2, 2)).rgb;
-    let d = textureSample(input_texture, s, uv, vec2<i32>(-2, 0)).rgb;
-    let e = textureSample(input_texture, s, uv).rgb;
-    let f = textureSample(input_texture, s, uv, vec2<i32>(2, 0)).rgb;
-    let g = textureSample(input_texture, s, uv, vec2<i32>(-2, -2)).rgb;
-    let h = textureSample(input_texture, s, uv, vec2<i32>(0, -2)).rgb;
-    let i = textureSample(input_texture, s, uv, vec2<i32>(2, -2)).rgb;
-    let j = textureSample(input_texture, s, uv, vec2<i32>(-1, 1)).rgb;
-    let k = textureSample(input_texture, s, uv, vec2<i32>(1, 1)).rgb;
-    let l = textureSample(input_texture, s, uv, vec2<i32>(-1, -1)).rgb;
-    let m = textureSample(input_texture, s, uv, vec2<i32>(1, -1)).rgb;
+    let a = apply_layer_intensity(textureSample(input_texture, s, uv, vec2<i32>(-2, 2)), uv, vec2(-2, 2));
+    let b = apply_layer_intensity(textureSample(input_texture, s, uv, vec2<i32>(0, 2)), uv, vec2(0, 2));
+    let c = apply_layer_intensity(textureSample(input_texture, s, uv, vec2<i32>(2, 2)), uv, vec2(2, 2));
+    let d = apply_layer_intensity(textureSample(input_texture, s, uv, vec2<i32>(-2, 0)), uv, vec2(-2, 0));
+    let e = apply_layer_intensity(textureSample(input_texture, s, uv), uv, vec2(0));
+    let f = apply_layer_intensity(textureSample(input_texture, s, uv, vec2<i32>(2, 0)), uv, vec2(2, 0));
+    let g = apply_layer_intensity(textureSample(input_texture, s, uv, vec2<i32>(-2, -2)), uv, vec2(-2, -2));
+    let h = apply_layer_intensity(textureSample(input_texture, s, uv, vec2<i32>(0, -2)), uv, vec2(0, -2));
+    let i = apply_layer_intensity(textureSample(input_texture, s, uv, vec2<i32>(2, -2)), uv, vec2(2, -2));
+    let j = apply_layer_intensity(textureSample(input_texture, s, uv, vec2<i32>(-1, 1)), uv, vec2(-1, 1));
+    let k = apply_layer_intensity(textureSample(input_texture, s, uv, vec2<i32>(1, 1)), uv, vec2(1, 1));
+    let l = apply_layer_intensity(textureSample(input_texture, s, uv, vec2<i32>(-1, -1)), uv, vec2(-1, -1));
+    let m = apply_layer_intensity(textureSample(input_texture, s, uv, vec2<i32>(1, -1)), uv, vec2(1, -1));
 
 #ifdef FIRST_DOWNSAMPLE
     // [COD] slide 168
@@ -128,7 +204,9 @@ fn downsample_first(@location(0) output_uv: vec2<f32>) -> @location(0) vec4<f32>
     // with f32::MAX (E+38) Chrome fails with ":value 340282346999999984391321947108527833088.0 cannot be represented as 'f32'"
     sample = clamp(sample, vec3<f32>(0.0001), vec3<f32>(3.40282347E+37));
 
-#ifdef USE_THRESHOLD
+#ifdef RESPONSE_CURVE
+    sample = apply_response_curve(sample);
+#else ifdef USE_THRESHOLD
     sample = soft_threshold(sample);
 #endif
 
@@ -138,10 +216,128 @@ fn downsample_first(@location(0) output_uv: vec2<f32>) -> @location(0) vec4<f32>
 
 @fragment
 fn downsample(@location(0) uv: vec2<f32>) -> @location(0) vec4<f32> {
-    return vec4<f32>(sample_input_13_tap(uv), 1.0);
+#ifdef HALF_RESOLUTION_INPUT
+    // The half resolution prefilter texture holds the viewport where the main texture does
+    let sample_uv = uniforms.viewport.xy + uv * uniforms.viewport.zw;
+#else
+    let sample_uv = uv;
+#endif
+    return vec4<f32>(sample_input_13_tap(sample_uv), 1.0);
+}
+
+// Samples the parts of the bloom input bright enough to cause a lens flare.
+fn sample_flare_source(uv: vec2<f32>) -> vec3<f32> {
+    let color = textureSampleLevel(input_texture, s, uv, 0.0).rgb;
+    return max(color - uniforms.lens_flare_threshold, vec3<f32>(0.0));
 }
 
+// How much a flare sample at `uv` contributes, fading out towards the corners of the screen
+// so the features don't pop in and out as bright spots cross its edges.
+fn flare_edge_fade(uv: vec2<f32>, exponent: f32) -> f32 {
+    return pow(1.0 - saturate(length(vec2<f32>(0.5) - uv) / 0.70710678), exponent);
+}
+
+// Number of samples along each streak of the starburst.
+const STARBURST_SAMPLE_COUNT: u32 = 8u;
+const TAU: f32 = 6.28318530718;
+
+// A pseudo lens flare, rendered to a separate texture composited by the final upsampling pass.
+// See https://john-chapman.github.io/2017/11/05/pseudo-lens-flare.html
+@fragment
+fn lens_flare(@location(0) uv: vec2<f32>) -> @location(0) vec4<f32> {
+    var flare = vec3<f32>(0.0);
+
+    // Ghosts and the halo are reflections of the image through the center of the screen.
+    let flipped_uv = vec2<f32>(1.0) - uv;
+    let ghost_step = (vec2<f32>(0.5) - flipped_uv) * uniforms.lens_flare_ghost.w;
+
+    var ghosts = vec3<f32>(0.0);
+    for (var i = 0u; i < uniforms.lens_flare_ghost_count; i += 1u) {
+        let ghost_uv = flipped_uv + ghost_step * f32(i);
+        ghosts += sample_flare_source(ghost_uv) * flare_edge_fade(ghost_uv, 10.0);
+    }
+    flare += ghosts * uniforms.lens_flare_ghost.rgb;
+
+    if uniforms.lens_flare_halo.w > 0.0 {
+        // The halo is round on screen, so its radius is corrected for the aspect ratio.
+        let to_center = (vec2<f32>(0.5) - flipped_uv) * vec2<f32>(uniforms.aspect, 1.0);
+        let halo_offset = normalize(to_center + vec2<f32>(1e-5)) * uniforms.lens_flare_halo.w;
+        let halo_uv = flipped_uv + halo_offset / vec2<f32>(uniforms.aspect, 1.0);
+        flare += sample_flare_source(halo_uv) * flare_edge_fade(halo_uv, 5.0)
+            * uniforms.lens_flare_halo.rgb;
+    }
+
+    // The starburst smears bright spots along evenly spaced directions, fading out along them.
+    var starburst = vec3<f32>(0.0);
+    for (var i = 0u; i < uniforms.lens_flare_starburst_count; i += 1u) {
+        let angle = TAU * f32(i) / f32(uniforms.lens_flare_starburst_count);
+        let direction = vec2<f32>(cos(angle) / uniforms.aspect, sin(angle));
+        for (var j = 1u; j <= STARBURST_SAMPLE_COUNT; j += 1u) {
+            let t = f32(j) / f32(STARBURST_SAMPLE_COUNT);
+            let weight = (1.0 - t) * (1.0 - t);
+            starburst += sample_flare_source(uv - direction * t * uniforms.lens_flare_starburst.w)
+                * weight;
+        }
+    }
+    flare += starburst * uniforms.lens_flare_starburst.rgb / f32(STARBURST_SAMPLE_COUNT);
+
+    return vec4<f32>(flare, 1.0);
+}
+
+// Calculates the blend intensity of a blur pyramid level during the upsampling + compositing stage.
+//
+// All pyramid levels are upsampled and blended into higher frequency ones using this function.
+// The final (highest frequency) pyramid level is not blended into anything, therefore a `mip`
+// of 0.0 indicates the second-highest frequency pyramid level (the 0th mip of the bloom texture,
+// with the original image being the actual highest frequency level) and 1.0 the lowest.
+//
+// This function can be visually previewed for all values of `mip` with tweakable
+// `BloomSettings` parameters on Desmos graphing calculator: https://www.desmos.com/calculator/ncc8xbhzzl
+fn compute_blend_factor(mip: f32) -> f32 {
+    var lf_boost = (1.0 - pow(1.0 - mip, 1.0 / (1.0 - uniforms.low_frequency_boost_curvature)))
+        * uniforms.low_frequency_boost;
+    let high_pass_lq = 1.0 - clamp(
+        (mip - uniforms.high_pass_frequency) / uniforms.high_pass_frequency,
+        0.0,
+        1.0
+    );
+#ifndef ADDITIVE_COMPOSITE
+    lf_boost *= 1.0 - uniforms.intensity;
+#endif
+
+    return (uniforms.intensity + lf_boost) * high_pass_lq;
+}
+
+#ifdef CONVOLUTION
+// Composites the output of bloom_convolution.wgsl, where the scene covers half of the FFT grid along its larger dimension.
+fn sample_convolution(uv: vec2<f32>) -> vec3<f32> {
+    let scene_extent = 0.5 * vec2<f32>(uniforms.aspect, 1.0) / max(uniforms.aspect, 1.0);
+    return textureSample(input_texture, s, uv * scene_extent).rgb;
+}
+#endif
+
+#ifndef FIRST_DOWNSAMPLE
 @fragment
 fn upsample(@location(0) uv: vec2<f32>) -> @location(0) vec4<f32> {
-    return vec4<f32>(sample_input_3x3_tent(uv), 1.0);
+#ifdef CONVOLUTION
+    var sample = sample_convolution(uv);
+    let blend = uniforms.intensity;
+#else
+    var sample = sample_input_3x3_tent(uv);
+    let blend = compute_blend_factor(mip_uniform.mip / uniforms.max_mip);
+#endif
+
+#ifdef LENS_FLARE
+    // The flare is light scattered by the lens too, so it is composited the same way as the bloom.
+    sample += textureSample(lens_flare_texture, s, uv).rgb;
+#endif
+
+#ifdef LENS_DIRT
+    // Dirt on the lens catches scattered light, so it brightens the bloom where it is present.
+    let lens_dirt = textureSample(lens_dirt_texture, s, uv).rgb;
+    sample += sample * lens_dirt * uniforms.lens_dirt_intensity;
+#endif
+
+    return vec4<f32>(sample, blend);
 }
+#endif
@@ -0,0 +1,192 @@
+// Convolution bloom works by transforming both the thresholded scene and a kernel image to the frequency domain
+// with a fast Fourier transform (FFT), multiplying them together and transforming the result back.
+//
+// The transforms are done in a square grid of FFT_SIZE texels, one row or column per workgroup, in workgroup memory.
+// The complex values of each color channel are kept in two textures, one for the real and one for the imaginary parts.
+//
+// References:
+// * [FFT] - Cooley-Tukey FFT algorithm - https://en.wikipedia.org/wiki/Cooley%E2%80%93Tukey_FFT_algorithm
+// * [CONV] - Convolution theorem - https://en.wikipedia.org/wiki/Convolution_theorem
+
+// Must match `FFT_SIZE` in convolution.rs
+const FFT_SIZE: u32 = 512u;
+const HALF_FFT_SIZE: u32 = 256u;
+const LOG2_FFT_SIZE: u32 = 9u;
+
+const TAU: f32 = 6.28318530718;
+
+var<workgroup> data_re: array<vec4<f32>, FFT_SIZE>;
+var<workgroup> data_im: array<vec4<f32>, FFT_SIZE>;
+
+#ifdef INPUT
+@group(0) @binding(0) var input_texture: texture_2d<f32>;
+@group(0) @binding(1) var input_sampler: sampler;
+#else
+@group(0) @binding(0) var input_re: texture_2d<f32>;
+@group(0) @binding(1) var input_im: texture_2d<f32>;
+#endif
+
+#ifdef OUTPUT
+@group(0) @binding(2) var output: texture_storage_2d<rgba16float, write>;
+#else
+@group(0) @binding(2) var output_re: texture_storage_2d<rgba32float, write>;
+@group(0) @binding(3) var output_im: texture_storage_2d<rgba32float, write>;
+#endif
+
+#ifdef CONVOLVE
+@group(0) @binding(4) var kernel_re: texture_2d<f32>;
+@group(0) @binding(5) var kernel_im: texture_2d<f32>;
+#endif
+
+fn bit_reversed(index: u32) -> u32 {
+    return reverseBits(index) >> (32u - LOG2_FFT_SIZE);
+}
+
+// Loads the grid texel at `coords` into workgroup memory at the bit-reversed `index`, as expected by `fft`.
+fn load(coords: vec2<u32>, index: u32) {
+    let reversed = bit_reversed(index);
+
+#ifdef INPUT
+    let uv = (vec2<f32>(coords) + 0.5) / f32(FFT_SIZE);
+#ifdef KERNEL
+    // Center the kernel on the origin of the grid, wrapping around its edges.
+    let value = textureSampleLevel(input_texture, input_sampler, fract(uv + 0.5), 0.0).rgb;
+#else
+    // The scene covers half of the grid along its larger dimension. The rest is left black so
+    // light scattered past the edges of the screen doesn't wrap around to the other side.
+    let size = vec2<f32>(textureDimensions(input_texture));
+    let scene_extent = 0.5 * size / max(size.x, size.y);
+    var value = vec3<f32>(0.0);
+    if all(uv < scene_extent) {
+        value = textureSampleLevel(input_texture, input_sampler, uv / scene_extent, 0.0).rgb;
+    }
+#endif
+    data_re[reversed] = vec4<f32>(value, 0.0);
+    data_im[reversed] = vec4<f32>(0.0);
+#else
+    data_re[reversed] = textureLoad(input_re, coords, 0);
+    data_im[reversed] = textureLoad(input_im, coords, 0);
+#endif
+}
+
+fn store(coords: vec2<u32>, index: u32) {
+#ifdef OUTPUT
+    // The transforms are unnormalized, so the inverse one has to be divided by the number of grid texels.
+    let scale = 1.0 / f32(FFT_SIZE * FFT_SIZE);
+    textureStore(output, coords, vec4<f32>(data_re[index].rgb * scale, 1.0));
+#else
+    textureStore(output_re, coords, data_re[index]);
+    textureStore(output_im, coords, data_im[index]);
+#endif
+}
+
+// An in-place radix-2 Cooley-Tukey FFT of the workgroup memory. [FFT]
+// The input must be in bit-reversed order, the output is in natural order.
+//
+// `direction` is -1.0 for the forward transform and 1.0 for the inverse one.
+fn fft(thread: u32, direction: f32) {
+    for (var stage = 0u; stage < LOG2_FFT_SIZE; stage += 1u) {
+        workgroupBarrier();
+
+        // Each thread computes one butterfly per stage
+        let half_size = 1u << stage;
+        let k = thread & (half_size - 1u);
+        let i = ((thread >> stage) << (stage + 1u)) + k;
+        let j = i + half_size;
+
+        let angle = direction * TAU * f32(k) / f32(half_size << 1u);
+        let twiddle = vec2<f32>(cos(angle), sin(angle));
+
+        let a_re = data_re[i];
+        let a_im = data_im[i];
+        let b_re = data_re[j] * twiddle.x - data_im[j] * twiddle.y;
+        let b_im = data_re[j] * twiddle.y + data_im[j] * twiddle.x;
+
+        data_re[i] = a_re + b_re;
+        data_im[i] = a_im + b_im;
+        data_re[j] = a_re - b_re;
+        data_im[j] = a_im - b_im;
+    }
+
+    workgroupBarrier();
+}
+
+#ifdef CONVOLVE
+// Multiplies the transformed scene with the transformed kernel. [CONV]
+fn convolve(column: u32, index: u32) {
+    // The zero frequency term is the sum of all kernel texels, dividing by it makes the convolution preserve energy.
+    let kernel_sum = textureLoad(kernel_re, vec2<u32>(0u), 0).rgb;
+    let normalization = 1.0 / max(dot(kernel_sum, vec3<f32>(0.2126, 0.7152, 0.0722)), 0.0001);
+
+    let k_re = textureLoad(kernel_re, vec2<u32>(column, index), 0) * normalization;
+    let k_im = textureLoad(kernel_im, vec2<u32>(column, index), 0) * normalization;
+    let re = data_re[index];
+    let im = data_im[index];
+
+    data_re[index] = re * k_re - im * k_im;
+    data_im[index] = re * k_im + im * k_re;
+}
+
+// Reorders the workgroup memory to bit-reversed order, so it can be transformed again.
+fn reorder(thread: u32) {
+    let re_a = data_re[thread];
+    let im_a = data_im[thread];
+    let re_b = data_re[thread + HALF_FFT_SIZE];
+    let im_b = data_im[thread + HALF_FFT_SIZE];
+
+    workgroupBarrier();
+
+    data_re[bit_reversed(thread)] = re_a;
+    data_im[bit_reversed(thread)] = im_a;
+    data_re[bit_reversed(thread + HALF_FFT_SIZE)] = re_b;
+    data_im[bit_reversed(thread + HALF_FFT_SIZE)] = im_b;
+}
+#endif
+
+// Workgroup size is HALF_FFT_SIZE, each thread handles two texels of a row.
+@compute @workgroup_size(256, 1, 1)
+fn rows(
+    @builtin(workgroup_id) workgroup_id: vec3<u32>,
+    @builtin(local_invocation_index) thread: u32,
+) {
+    let row = workgroup_id.x;
+
+    load(vec2<u32>(thread, row), thread);
+    load(vec2<u32>(thread + HALF_FFT_SIZE, row), thread + HALF_FFT_SIZE);
+
+#ifdef OUTPUT
+    fft(thread, 1.0);
+#else
+    fft(thread, -1.0);
+#endif
+
+    store(vec2<u32>(thread, row), thread);
+    store(vec2<u32>(thread + HALF_FFT_SIZE, row), thread + HALF_FFT_SIZE);
+}
+
+// Workgroup size is HALF_FFT_SIZE, each thread handles two texels of a column.
+@compute @workgroup_size(256, 1, 1)
+fn columns(
+    @builtin(workgroup_id) workgroup_id: vec3<u32>,
+    @builtin(local_invocation_index) thread: u32,
+) {
+    let column = workgroup_id.x;
+
+    load(vec2<u32>(column, thread), thread);
+    load(vec2<u32>(column, thread + HALF_FFT_SIZE), thread + HALF_FFT_SIZE);
+
+    fft(thread, -1.0);
+
+#ifdef CONVOLVE
+    // The scene is convolved and transformed back along the columns right away,
+    // which saves a round trip through the textures.
+    convolve(column, thread);
+    convolve(column, thread + HALF_FFT_SIZE);
+    workgroupBarrier();
+    reorder(thread);
+    fft(thread, 1.0);
+#endif
+
+    store(vec2<u32>(column, thread), thread);
+    store(vec2<u32>(column, thread + HALF_FFT_SIZE), thread + HALF_FFT_SIZE);
+}
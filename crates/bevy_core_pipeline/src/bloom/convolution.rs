@@ -0,0 +1,552 @@
+use super::{
+    downsampling_pipeline::BloomDownsamplingPipeline, BloomMethod, BloomSettings, BloomTexture,
+};
+use bevy_asset::{AssetId, Handle};
+use bevy_ecs::{
+    prelude::{Component, Entity},
+    system::{Commands, Query, Res, ResMut, Resource},
+    world::{FromWorld, World},
+};
+use bevy_render::{
+    render_asset::RenderAssets,
+    render_resource::{
+        binding_types::{sampler, texture_2d, texture_storage_2d},
+        *,
+    },
+    renderer::{RenderContext, RenderDevice, RenderQueue},
+    texture::{CachedTexture, GpuImage, Image, TextureCache},
+};
+use bevy_utils::HashMap;
+
+pub const BLOOM_CONVOLUTION_SHADER_HANDLE: Handle<Shader> =
+    Handle::weak_from_u128(283047126459813720);
+
+/// Size of the square grid the convolution is computed in, must match `bloom_convolution.wgsl`
+pub const FFT_SIZE: u32 = 512;
+
+/// Format of the textures holding the real and imaginary parts of the transformed grid
+const SPECTRUM_TEXTURE_FORMAT: TextureFormat = TextureFormat::Rgba32Float;
+
+/// Format of the convolved bloom, which is composited by the final upsampling pass
+const OUTPUT_TEXTURE_FORMAT: TextureFormat = TextureFormat::Rgba16Float;
+
+#[derive(Resource)]
+pub struct BloomConvolutionPipeline {
+    /// Layout of the passes reading from an image, with a texture, a sampler, and two storage textures
+    pub input_bind_group_layout: BindGroupLayout,
+    /// Layout of the passes reading from a transformed grid, with two textures and two storage textures
+    pub fft_bind_group_layout: BindGroupLayout,
+    /// [`Self::fft_bind_group_layout`] with the two textures of the transformed kernel appended
+    pub convolve_bind_group_layout: BindGroupLayout,
+    /// Layout of the last pass, with two textures and the output storage texture
+    pub output_bind_group_layout: BindGroupLayout,
+}
+
+#[derive(PartialEq, Eq, Hash, Clone, Copy)]
+pub enum BloomConvolutionPass {
+    /// Transforms the rows of the thresholded scene
+    SceneRows,
+    /// Transforms the rows of the kernel image
+    KernelRows,
+    /// Transforms the columns of the kernel
+    KernelColumns,
+    /// Transforms the columns of the scene, multiplies them with the kernel,
+    /// and transforms them back
+    SceneColumns,
+    /// Transforms the rows of the convolved scene back
+    InverseRows,
+}
+
+#[derive(Component)]
+pub struct BloomConvolutionPipelineIds {
+    pub scene_rows: CachedComputePipelineId,
+    pub kernel_rows: CachedComputePipelineId,
+    pub kernel_columns: CachedComputePipelineId,
+    pub scene_columns: CachedComputePipelineId,
+    pub inverse_rows: CachedComputePipelineId,
+}
+
+impl BloomConvolutionPipelineIds {
+    /// Returns the pipelines of the passes convolving the scene in the order they run in, if they
+    /// are all ready.
+    pub fn get<'a>(&self, pipeline_cache: &'a PipelineCache) -> Option<[&'a ComputePipeline; 3]> {
+        Some([
+            pipeline_cache.get_compute_pipeline(self.scene_rows)?,
+            pipeline_cache.get_compute_pipeline(self.scene_columns)?,
+            pipeline_cache.get_compute_pipeline(self.inverse_rows)?,
+        ])
+    }
+}
+
+#[derive(Component)]
+pub struct BloomConvolutionTextures {
+    /// Real and imaginary parts of the grid after transforming its rows
+    rows: [CachedTexture; 2],
+    /// Real and imaginary parts of the scene after convolving its columns
+    columns: [CachedTexture; 2],
+    /// The convolved bloom
+    pub output: CachedTexture,
+}
+
+#[derive(Component)]
+pub struct BloomConvolutionBindGroups {
+    /// The mip of the bloom texture the scene is read from
+    pub input_mip: u32,
+    scene_rows: BindGroup,
+    scene_columns: BindGroup,
+    inverse_rows: BindGroup,
+}
+
+/// The transformed kernels of [`BloomMethod::Convolution`], by kernel image.
+///
+/// They are kept from frame to frame, instead of being taken from the [`TextureCache`], so that
+/// each kernel is only transformed once, and again when its image changes.
+#[derive(Resource, Default)]
+pub struct BloomConvolutionKernels(HashMap<AssetId<Image>, BloomConvolutionKernel>);
+
+struct BloomConvolutionKernel {
+    /// Real and imaginary parts of the transformed kernel
+    spectrum: [TextureView; 2],
+    /// The view of the kernel image that was transformed, which changes when the image is
+    /// modified or resized
+    transformed: Option<TextureViewId>,
+}
+
+impl FromWorld for BloomConvolutionPipeline {
+    fn from_world(world: &mut World) -> Self {
+        let render_device = world.resource::<RenderDevice>();
+
+        let input_bind_group_layout = render_device.create_bind_group_layout(
+            "bloom_convolution_input_bind_group_layout",
+            &BindGroupLayoutEntries::sequential(
+                ShaderStages::COMPUTE,
+                (
+                    // Input image
+                    texture_2d(TextureSampleType::Float { filterable: true }),
+                    // Sampler
+                    sampler(SamplerBindingType::Filtering),
+                    // Output real and imaginary parts
+                    texture_storage_2d(SPECTRUM_TEXTURE_FORMAT, StorageTextureAccess::WriteOnly),
+                    texture_storage_2d(SPECTRUM_TEXTURE_FORMAT, StorageTextureAccess::WriteOnly),
+                ),
+            ),
+        );
+
+        let fft_bind_group_layout = render_device.create_bind_group_layout(
+            "bloom_convolution_fft_bind_group_layout",
+            &BindGroupLayoutEntries::sequential(
+                ShaderStages::COMPUTE,
+                (
+                    // Input real and imaginary parts
+                    texture_2d(TextureSampleType::Float { filterable: false }),
+                    texture_2d(TextureSampleType::Float { filterable: false }),
+                    // Output real and imaginary parts
+                    texture_storage_2d(SPECTRUM_TEXTURE_FORMAT, StorageTextureAccess::WriteOnly),
+                    texture_storage_2d(SPECTRUM_TEXTURE_FORMAT, StorageTextureAccess::WriteOnly),
+                ),
+            ),
+        );
+
+        let convolve_bind_group_layout = render_device.create_bind_group_layout(
+            "bloom_convolution_convolve_bind_group_layout",
+            &BindGroupLayoutEntries::sequential(
+                ShaderStages::COMPUTE,
+                (
+                    // Input real and imaginary parts
+                    texture_2d(TextureSampleType::Float { filterable: false }),
+                    texture_2d(TextureSampleType::Float { filterable: false }),
+                    // Output real and imaginary parts
+                    texture_storage_2d(SPECTRUM_TEXTURE_FORMAT, StorageTextureAccess::WriteOnly),
+                    texture_storage_2d(SPECTRUM_TEXTURE_FORMAT, StorageTextureAccess::WriteOnly),
+                    // Kernel real and imaginary parts
+                    texture_2d(TextureSampleType::Float { filterable: false }),
+                    texture_2d(TextureSampleType::Float { filterable: false }),
+                ),
+            ),
+        );
+
+        let output_bind_group_layout = render_device.create_bind_group_layout(
+            "bloom_convolution_output_bind_group_layout",
+            &BindGroupLayoutEntries::sequential(
+                ShaderStages::COMPUTE,
+                (
+                    // Input real and imaginary parts
+                    texture_2d(TextureSampleType::Float { filterable: false }),
+                    texture_2d(TextureSampleType::Float { filterable: false }),
+                    // Output
+                    texture_storage_2d(OUTPUT_TEXTURE_FORMAT, StorageTextureAccess::WriteOnly),
+                ),
+            ),
+        );
+
+        BloomConvolutionPipeline {
+            input_bind_group_layout,
+            fft_bind_group_layout,
+            convolve_bind_group_layout,
+            output_bind_group_layout,
+        }
+    }
+}
+
+impl SpecializedComputePipeline for BloomConvolutionPipeline {
+    type Key = BloomConvolutionPass;
+
+    fn specialize(&self, pass: Self::Key) -> ComputePipelineDescriptor {
+        let (layout, shader_defs, entry_point) = match pass {
+            BloomConvolutionPass::SceneRows => {
+                (&self.input_bind_group_layout, vec!["INPUT".into()], "rows")
+            }
+            BloomConvolutionPass::KernelRows => (
+                &self.input_bind_group_layout,
+                vec!["INPUT".into(), "KERNEL".into()],
+                "rows",
+            ),
+            BloomConvolutionPass::KernelColumns => (&self.fft_bind_group_layout, vec![], "columns"),
+            BloomConvolutionPass::SceneColumns => (
+                &self.convolve_bind_group_layout,
+                vec!["CONVOLVE".into()],
+                "columns",
+            ),
+            BloomConvolutionPass::InverseRows => (
+                &self.output_bind_group_layout,
+                vec!["OUTPUT".into()],
+                "rows",
+            ),
+        };
+
+        ComputePipelineDescriptor {
+            label: Some("bloom_convolution_pipeline".into()),
+            layout: vec![layout.clone()],
+            push_constant_ranges: vec![],
+            shader: BLOOM_CONVOLUTION_SHADER_HANDLE,
+            shader_defs,
+            entry_point: entry_point.into(),
+        }
+    }
+}
+
+pub fn prepare_convolution_pipelines(
+    mut commands: Commands,
+    pipeline_cache: Res<PipelineCache>,
+    mut pipelines: ResMut<SpecializedComputePipelines<BloomConvolutionPipeline>>,
+    pipeline: Res<BloomConvolutionPipeline>,
+    views: Query<(Entity, &BloomSettings)>,
+) {
+    for (entity, settings) in &views {
+        if !matches!(settings.method, BloomMethod::Convolution { .. }) {
+            continue;
+        }
+
+        let mut specialize = |pass| pipelines.specialize(&pipeline_cache, &pipeline, pass);
+
+        commands.entity(entity).insert(BloomConvolutionPipelineIds {
+            scene_rows: specialize(BloomConvolutionPass::SceneRows),
+            kernel_rows: specialize(BloomConvolutionPass::KernelRows),
+            kernel_columns: specialize(BloomConvolutionPass::KernelColumns),
+            scene_columns: specialize(BloomConvolutionPass::SceneColumns),
+            inverse_rows: specialize(BloomConvolutionPass::InverseRows),
+        });
+    }
+}
+
+pub fn prepare_convolution_textures(
+    mut commands: Commands,
+    mut texture_cache: ResMut<TextureCache>,
+    render_device: Res<RenderDevice>,
+    views: Query<(Entity, &BloomSettings)>,
+) {
+    for (entity, settings) in &views {
+        if !matches!(settings.method, BloomMethod::Convolution { .. }) {
+            continue;
+        }
+
+        let mut get_texture = |label, format| {
+            texture_cache.get(
+                &render_device,
+                TextureDescriptor {
+                    label: Some(label),
+                    size: Extent3d {
+                        width: FFT_SIZE,
+                        height: FFT_SIZE,
+                        depth_or_array_layers: 1,
+                    },
+                    mip_level_count: 1,
+                    sample_count: 1,
+                    dimension: TextureDimension::D2,
+                    format,
+                    usage: TextureUsages::STORAGE_BINDING | TextureUsages::TEXTURE_BINDING,
+                    view_formats: &[],
+                },
+            )
+        };
+
+        let mut get_spectrum_textures = |label| {
+            [
+                get_texture(label, SPECTRUM_TEXTURE_FORMAT),
+                get_texture(label, SPECTRUM_TEXTURE_FORMAT),
+            ]
+        };
+
+        let rows = get_spectrum_textures("bloom_convolution_rows_texture");
+        let columns = get_spectrum_textures("bloom_convolution_columns_texture");
+        let output = get_texture("bloom_convolution_output_texture", OUTPUT_TEXTURE_FORMAT);
+
+        commands.entity(entity).insert(BloomConvolutionTextures {
+            rows,
+            columns,
+            output,
+        });
+    }
+}
+
+pub fn prepare_convolution_kernels(
+    render_device: Res<RenderDevice>,
+    mut kernels: ResMut<BloomConvolutionKernels>,
+    views: Query<&BloomSettings>,
+) {
+    let used_kernels: Vec<_> = views
+        .iter()
+        .filter_map(|settings| match &settings.method {
+            BloomMethod::Convolution { kernel } => Some(kernel.id()),
+            BloomMethod::Pyramid => None,
+        })
+        .collect();
+    kernels.0.retain(|id, _| used_kernels.contains(id));
+
+    for id in used_kernels {
+        kernels.0.entry(id).or_insert_with(|| {
+            let spectrum = [0, 1].map(|_| {
+                render_device
+                    .create_texture(&TextureDescriptor {
+                        label: Some("bloom_convolution_kernel_texture"),
+                        size: Extent3d {
+                            width: FFT_SIZE,
+                            height: FFT_SIZE,
+                            depth_or_array_layers: 1,
+                        },
+                        mip_level_count: 1,
+                        sample_count: 1,
+                        dimension: TextureDimension::D2,
+                        format: SPECTRUM_TEXTURE_FORMAT,
+                        usage: TextureUsages::STORAGE_BINDING | TextureUsages::TEXTURE_BINDING,
+                        view_formats: &[],
+                    })
+                    .create_view(&TextureViewDescriptor::default())
+            });
+            BloomConvolutionKernel {
+                spectrum,
+                transformed: None,
+            }
+        });
+    }
+}
+
+/// Transforms the kernels whose image was added or changed since they were last transformed.
+///
+/// The rows of the kernel are transformed into the rows textures of a view using it, which the
+/// scene overwrites later in the frame.
+#[allow(clippy::too_many_arguments)]
+pub fn transform_convolution_kernels(
+    render_device: Res<RenderDevice>,
+    render_queue: Res<RenderQueue>,
+    pipeline_cache: Res<PipelineCache>,
+    downsampling_pipeline: Res<BloomDownsamplingPipeline>,
+    convolution_pipeline: Res<BloomConvolutionPipeline>,
+    mut kernels: ResMut<BloomConvolutionKernels>,
+    views: Query<(
+        &BloomSettings,
+        &BloomConvolutionTextures,
+        &BloomConvolutionPipelineIds,
+    )>,
+    images: Res<RenderAssets<GpuImage>>,
+) {
+    let mut command_encoder = render_device.create_command_encoder(&CommandEncoderDescriptor {
+        label: Some("bloom_convolution_kernel_command_encoder"),
+    });
+    let mut transformed_any = false;
+
+    for (settings, textures, pipeline_ids) in &views {
+        let BloomMethod::Convolution {
+            kernel: kernel_image,
+        } = &settings.method
+        else {
+            continue;
+        };
+        let (Some(kernel), Some(kernel_image)) = (
+            kernels.0.get_mut(&kernel_image.id()),
+            images.get(kernel_image),
+        ) else {
+            continue;
+        };
+        if kernel.transformed == Some(kernel_image.texture_view.id()) {
+            continue;
+        }
+        let (Some(rows_pipeline), Some(columns_pipeline)) = (
+            pipeline_cache.get_compute_pipeline(pipeline_ids.kernel_rows),
+            pipeline_cache.get_compute_pipeline(pipeline_ids.kernel_columns),
+        ) else {
+            continue;
+        };
+
+        let [rows_re, rows_im] = &textures.rows;
+        let [kernel_re, kernel_im] = &kernel.spectrum;
+
+        let rows_bind_group = render_device.create_bind_group(
+            "bloom_convolution_kernel_rows_bind_group",
+            &convolution_pipeline.input_bind_group_layout,
+            &BindGroupEntries::sequential((
+                &kernel_image.texture_view,
+                &downsampling_pipeline.sampler,
+                &rows_re.default_view,
+                &rows_im.default_view,
+            )),
+        );
+        let columns_bind_group = render_device.create_bind_group(
+            "bloom_convolution_kernel_columns_bind_group",
+            &convolution_pipeline.fft_bind_group_layout,
+            &BindGroupEntries::sequential((
+                &rows_re.default_view,
+                &rows_im.default_view,
+                kernel_re,
+                kernel_im,
+            )),
+        );
+
+        let mut kernel_pass = command_encoder.begin_compute_pass(&ComputePassDescriptor {
+            label: Some("bloom_convolution_kernel_pass"),
+            timestamp_writes: None,
+        });
+        for (pipeline, bind_group) in [
+            (rows_pipeline, &rows_bind_group),
+            (columns_pipeline, &columns_bind_group),
+        ] {
+            kernel_pass.set_pipeline(pipeline);
+            kernel_pass.set_bind_group(0, bind_group, &[]);
+            // One workgroup per row or column
+            kernel_pass.dispatch_workgroups(FFT_SIZE, 1, 1);
+        }
+        drop(kernel_pass);
+
+        kernel.transformed = Some(kernel_image.texture_view.id());
+        transformed_any = true;
+    }
+
+    if transformed_any {
+        render_queue.submit([command_encoder.finish()]);
+    }
+}
+
+pub fn prepare_convolution_bind_groups(
+    mut commands: Commands,
+    render_device: Res<RenderDevice>,
+    downsampling_pipeline: Res<BloomDownsamplingPipeline>,
+    convolution_pipeline: Res<BloomConvolutionPipeline>,
+    kernels: Res<BloomConvolutionKernels>,
+    views: Query<(
+        Entity,
+        &BloomSettings,
+        &BloomTexture,
+        &BloomConvolutionTextures,
+    )>,
+    images: Res<RenderAssets<GpuImage>>,
+) {
+    let sampler = &downsampling_pipeline.sampler;
+
+    for (entity, settings, bloom_texture, textures) in &views {
+        let BloomMethod::Convolution {
+            kernel: kernel_image,
+        } = &settings.method
+        else {
+            continue;
+        };
+        // The scene can only be convolved once the current kernel image has been transformed
+        let (Some(kernel), Some(kernel_image)) =
+            (kernels.0.get(&kernel_image.id()), images.get(kernel_image))
+        else {
+            continue;
+        };
+        if kernel.transformed != Some(kernel_image.texture_view.id()) {
+            continue;
+        }
+
+        // Read the scene from the smallest mip that still covers its part of the grid
+        let input_size = bloom_texture.mip_size(0).max_element();
+        let input_mip = (input_size / (FFT_SIZE / 2))
+            .max(1)
+            .ilog2()
+            .min(bloom_texture.mip_count - 1);
+
+        let [rows_re, rows_im] = &textures.rows;
+        let [columns_re, columns_im] = &textures.columns;
+        let [kernel_re, kernel_im] = &kernel.spectrum;
+
+        let scene_rows = render_device.create_bind_group(
+            "bloom_convolution_scene_rows_bind_group",
+            &convolution_pipeline.input_bind_group_layout,
+            &BindGroupEntries::sequential((
+                &bloom_texture.view(input_mip),
+                sampler,
+                &rows_re.default_view,
+                &rows_im.default_view,
+            )),
+        );
+
+        let scene_columns = render_device.create_bind_group(
+            "bloom_convolution_scene_columns_bind_group",
+            &convolution_pipeline.convolve_bind_group_layout,
+            &BindGroupEntries::sequential((
+                &rows_re.default_view,
+                &rows_im.default_view,
+                &columns_re.default_view,
+                &columns_im.default_view,
+                kernel_re,
+                kernel_im,
+            )),
+        );
+
+        let inverse_rows = render_device.create_bind_group(
+            "bloom_convolution_inverse_rows_bind_group",
+            &convolution_pipeline.output_bind_group_layout,
+            &BindGroupEntries::sequential((
+                &columns_re.default_view,
+                &columns_im.default_view,
+                &textures.output.default_view,
+            )),
+        );
+
+        commands.entity(entity).insert(BloomConvolutionBindGroups {
+            input_mip,
+            scene_rows,
+            scene_columns,
+            inverse_rows,
+        });
+    }
+}
+
+/// Convolves the scene with the transformed kernel into [`BloomConvolutionTextures::output`].
+pub fn run_convolution(
+    render_context: &mut RenderContext,
+    pipelines: [&ComputePipeline; 3],
+    bind_groups: &BloomConvolutionBindGroups,
+) {
+    let bind_groups = [
+        &bind_groups.scene_rows,
+        &bind_groups.scene_columns,
+        &bind_groups.inverse_rows,
+    ];
+
+    let mut convolution_pass =
+        render_context
+            .command_encoder()
+            .begin_compute_pass(&ComputePassDescriptor {
+                label: Some("bloom_convolution_pass"),
+                timestamp_writes: None,
+            });
+
+    for (pipeline, bind_group) in pipelines.into_iter().zip(bind_groups) {
+        convolution_pass.set_pipeline(pipeline);
+        convolution_pass.set_bind_group(0, bind_group, &[]);
+        // One workgroup per row or column
+        convolution_pass.dispatch_workgroups(FFT_SIZE, 1, 1);
+    }
+}
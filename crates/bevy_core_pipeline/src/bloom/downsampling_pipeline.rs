@@ -1,29 +1,49 @@
-use super::{BloomSettings, BLOOM_SHADER_HANDLE, BLOOM_TEXTURE_FORMAT};
-use crate::fullscreen_vertex_shader::fullscreen_shader_vertex_state;
+use super::{half_resolution::uses_half_resolution, BloomSettings, BLOOM_SHADER_HANDLE};
+use crate::{fullscreen_vertex_shader::fullscreen_shader_vertex_state, prepass::MeshMaskPrepass};
 use bevy_ecs::{
     prelude::{Component, Entity},
+    query::Has,
     system::{Commands, Query, Res, ResMut, Resource},
     world::{FromWorld, World},
 };
 use bevy_math::Vec4;
 use bevy_render::{
+    camera::MainPassResolutionOverride,
+    render_asset::RenderAssets,
     render_resource::{
-        binding_types::{sampler, texture_2d, uniform_buffer},
+        binding_types::{sampler, texture_2d, texture_2d_multisampled, uniform_buffer},
         *,
     },
     renderer::RenderDevice,
+    texture::GpuImage,
+    view::{Msaa, ViewTarget},
 };
+use bevy_utils::warn_once;
 
 #[derive(Component)]
 pub struct BloomDownsamplingPipelineIds {
     pub main: CachedRenderPipelineId,
     pub first: CachedRenderPipelineId,
+    /// Downsamples the half resolution prefilter texture into the first mip, when
+    /// [`BloomSettings::half_resolution`] is used
+    pub half_resolution: Option<CachedRenderPipelineId>,
+    /// Whether the first pass reads the bloom layers from the [`MeshMaskPrepass`] texture
+    pub layer_intensity: bool,
 }
 
 #[derive(Resource)]
 pub struct BloomDownsamplingPipeline {
     /// Layout with a texture, a sampler, and uniforms
     pub bind_group_layout: BindGroupLayout,
+    /// Layout of the first pass when [`BloomPrefilterSettings::response_curve`] is used, with the response curve appended
+    ///
+    /// [`BloomPrefilterSettings::response_curve`]: super::BloomPrefilterSettings::response_curve
+    pub response_curve_bind_group_layout: BindGroupLayout,
+    /// Layout of the second bind group of the first pass when [`BloomSettings::layer_intensity`]
+    /// is used, with the [`MeshMaskPrepass`] texture
+    pub mesh_mask_bind_group_layout: BindGroupLayout,
+    /// Like [`Self::mesh_mask_bind_group_layout`], for a multisampled mesh mask
+    pub multisampled_mesh_mask_bind_group_layout: BindGroupLayout,
     pub sampler: Sampler,
 }
 
@@ -31,6 +51,11 @@ pub struct BloomDownsamplingPipeline {
 pub struct BloomDownsamplingPipelineKeys {
     prefilter: bool,
     first_downsample: bool,
+    response_curve: bool,
+    layer_intensity: bool,
+    multisampled_mesh_mask: bool,
+    half_resolution_input: bool,
+    texture_format: TextureFormat,
 }
 
 /// The uniform struct extracted from [`BloomSettings`] attached to a Camera.
@@ -41,6 +66,23 @@ pub struct BloomUniforms {
     pub threshold_precomputations: Vec4,
     pub viewport: Vec4,
     pub aspect: f32,
+    pub lens_dirt_intensity: f32,
+    // Used to compute the blend factor of each upsampling pass in the shader
+    pub intensity: f32,
+    pub low_frequency_boost: f32,
+    pub low_frequency_boost_curvature: f32,
+    pub high_pass_frequency: f32,
+    pub max_mip: f32,
+    // `BloomSettings::layer_intensity`, packed into vectors for uniform buffer alignment
+    pub layer_intensity: [Vec4; 2],
+    // `BloomSettings::lens_flare`, with the linear colors of each feature in xyz, and the ghost
+    // spacing, the halo radius and the starburst length in w
+    pub lens_flare_ghost: Vec4,
+    pub lens_flare_halo: Vec4,
+    pub lens_flare_starburst: Vec4,
+    pub lens_flare_threshold: f32,
+    pub lens_flare_ghost_count: u32,
+    pub lens_flare_starburst_count: u32,
 }
 
 impl FromWorld for BloomDownsamplingPipeline {
@@ -63,6 +105,38 @@ impl FromWorld for BloomDownsamplingPipeline {
             ),
         );
 
+        let response_curve_bind_group_layout = render_device.create_bind_group_layout(
+            "bloom_downsampling_response_curve_bind_group_layout",
+            &BindGroupLayoutEntries::sequential(
+                ShaderStages::FRAGMENT,
+                (
+                    // Input texture binding
+                    texture_2d(TextureSampleType::Float { filterable: true }),
+                    // Sampler binding
+                    sampler(SamplerBindingType::Filtering),
+                    // Downsampling settings binding
+                    uniform_buffer::<BloomUniforms>(true),
+                    // Response curve binding
+                    texture_2d(TextureSampleType::Float { filterable: true }),
+                ),
+            ),
+        );
+
+        let mesh_mask_bind_group_layout = render_device.create_bind_group_layout(
+            "bloom_downsampling_mesh_mask_bind_group_layout",
+            &BindGroupLayoutEntries::single(
+                ShaderStages::FRAGMENT,
+                texture_2d(TextureSampleType::Uint),
+            ),
+        );
+        let multisampled_mesh_mask_bind_group_layout = render_device.create_bind_group_layout(
+            "bloom_downsampling_multisampled_mesh_mask_bind_group_layout",
+            &BindGroupLayoutEntries::single(
+                ShaderStages::FRAGMENT,
+                texture_2d_multisampled(TextureSampleType::Uint),
+            ),
+        );
+
         // Sampler
         let sampler = render_device.create_sampler(&SamplerDescriptor {
             min_filter: FilterMode::Linear,
@@ -74,6 +148,9 @@ impl FromWorld for BloomDownsamplingPipeline {
 
         BloomDownsamplingPipeline {
             bind_group_layout,
+            response_curve_bind_group_layout,
+            mesh_mask_bind_group_layout,
+            multisampled_mesh_mask_bind_group_layout,
             sampler,
         }
     }
@@ -83,7 +160,18 @@ impl SpecializedRenderPipeline for BloomDownsamplingPipeline {
     type Key = BloomDownsamplingPipelineKeys;
 
     fn specialize(&self, key: Self::Key) -> RenderPipelineDescriptor {
-        let layout = vec![self.bind_group_layout.clone()];
+        let mut layout = if key.first_downsample && key.response_curve {
+            vec![self.response_curve_bind_group_layout.clone()]
+        } else {
+            vec![self.bind_group_layout.clone()]
+        };
+        if key.first_downsample && key.layer_intensity {
+            layout.push(if key.multisampled_mesh_mask {
+                self.multisampled_mesh_mask_bind_group_layout.clone()
+            } else {
+                self.mesh_mask_bind_group_layout.clone()
+            });
+        }
 
         let entry_point = if key.first_downsample {
             "downsample_first".into()
@@ -101,6 +189,21 @@ impl SpecializedRenderPipeline for BloomDownsamplingPipeline {
             shader_defs.push("USE_THRESHOLD".into());
         }
 
+        if key.first_downsample && key.response_curve {
+            shader_defs.push("RESPONSE_CURVE".into());
+        }
+
+        if key.first_downsample && key.layer_intensity {
+            shader_defs.push("LAYER_INTENSITY".into());
+            if key.multisampled_mesh_mask {
+                shader_defs.push("MULTISAMPLED_MESH_MASK".into());
+            }
+        }
+
+        if key.half_resolution_input {
+            shader_defs.push("HALF_RESOLUTION_INPUT".into());
+        }
+
         RenderPipelineDescriptor {
             label: Some(
                 if key.first_downsample {
@@ -117,7 +220,7 @@ impl SpecializedRenderPipeline for BloomDownsamplingPipeline {
                 shader_defs,
                 entry_point,
                 targets: vec![Some(ColorTargetState {
-                    format: BLOOM_TEXTURE_FORMAT,
+                    format: key.texture_format,
                     blend: None,
                     write_mask: ColorWrites::ALL,
                 })],
@@ -135,10 +238,26 @@ pub fn prepare_downsampling_pipeline(
     pipeline_cache: Res<PipelineCache>,
     mut pipelines: ResMut<SpecializedRenderPipelines<BloomDownsamplingPipeline>>,
     pipeline: Res<BloomDownsamplingPipeline>,
-    views: Query<(Entity, &BloomSettings)>,
+    views: Query<(
+        Entity,
+        &BloomSettings,
+        Has<MainPassResolutionOverride>,
+        Has<MeshMaskPrepass>,
+    )>,
+    images: Res<RenderAssets<GpuImage>>,
+    msaa: Res<Msaa>,
 ) {
-    for (entity, settings) in &views {
+    for (entity, settings, resolution_override, mesh_mask_prepass) in &views {
         let prefilter = settings.prefilter_settings.threshold > 0.0;
+        let response_curve = response_curve_image(settings, &images).is_some();
+        let half_resolution = uses_half_resolution(settings, resolution_override);
+        let layer_intensity = settings.uses_layer_intensity();
+        if layer_intensity && !mesh_mask_prepass {
+            warn_once!(
+                "BloomSettings::layer_intensity requires a MeshMaskPrepass on the camera, \
+                ignoring it"
+            );
+        }
 
         let pipeline_id = pipelines.specialize(
             &pipeline_cache,
@@ -146,6 +265,11 @@ pub fn prepare_downsampling_pipeline(
             BloomDownsamplingPipelineKeys {
                 prefilter,
                 first_downsample: false,
+                response_curve: false,
+                layer_intensity: false,
+                multisampled_mesh_mask: false,
+                half_resolution_input: false,
+                texture_format: settings.texture_format.into(),
             },
         );
 
@@ -155,14 +279,54 @@ pub fn prepare_downsampling_pipeline(
             BloomDownsamplingPipelineKeys {
                 prefilter,
                 first_downsample: true,
+                response_curve,
+                layer_intensity: layer_intensity && mesh_mask_prepass,
+                multisampled_mesh_mask: msaa.samples() > 1,
+                half_resolution_input: false,
+                // The prefilter writes to the half resolution texture instead of the first mip
+                texture_format: if half_resolution {
+                    ViewTarget::TEXTURE_FORMAT_HDR
+                } else {
+                    settings.texture_format.into()
+                },
             },
         );
 
+        let pipeline_half_resolution_id = half_resolution.then(|| {
+            pipelines.specialize(
+                &pipeline_cache,
+                &pipeline,
+                BloomDownsamplingPipelineKeys {
+                    prefilter,
+                    first_downsample: false,
+                    response_curve: false,
+                    layer_intensity: false,
+                    multisampled_mesh_mask: false,
+                    half_resolution_input: true,
+                    texture_format: settings.texture_format.into(),
+                },
+            )
+        });
+
         commands
             .entity(entity)
             .insert(BloomDownsamplingPipelineIds {
                 first: pipeline_first_id,
                 main: pipeline_id,
+                half_resolution: pipeline_half_resolution_id,
+                layer_intensity: layer_intensity && mesh_mask_prepass,
             });
     }
 }
+
+/// Returns the response curve of the bloom prefilter settings, if any is set and it has been loaded.
+pub fn response_curve_image<'a>(
+    settings: &BloomSettings,
+    images: &'a RenderAssets<GpuImage>,
+) -> Option<&'a GpuImage> {
+    settings
+        .prefilter_settings
+        .response_curve
+        .as_ref()
+        .and_then(|response_curve| images.get(response_curve))
+}
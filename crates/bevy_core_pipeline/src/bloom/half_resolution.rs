@@ -0,0 +1,137 @@
+use super::{upsampling_pipeline::bloom_blend_state, BloomSettings};
+use crate::{
+    bilateral_upsample::{
+        BilateralUpsample, BilateralUpsamplePipeline, BilateralUpsamplePipelineKey,
+        BilateralUpsampleSettings, BilateralUpsampleUniforms, DownsampleDepth,
+        DownsampleDepthPipeline, DownsampleDepthPipelineKey,
+    },
+    core_3d::{Camera3d, CORE_3D_DEPTH_FORMAT},
+};
+use bevy_ecs::{
+    prelude::{Component, Entity},
+    query::Has,
+    system::{Commands, Query, Res, ResMut},
+};
+use bevy_render::{
+    camera::{ExtractedCamera, MainPassResolutionOverride},
+    prelude::Msaa,
+    render_resource::*,
+    renderer::RenderDevice,
+    texture::{CachedTexture, TextureCache},
+    view::ViewTarget,
+};
+
+/// Returns `true` if the bloom of a view is composited at half resolution.
+///
+/// The depth of a view rendering its main pass at a lower resolution doesn't line up with its
+/// image anymore, so it can't guide the composite.
+pub fn uses_half_resolution(settings: &BloomSettings, resolution_override: bool) -> bool {
+    settings.half_resolution && !resolution_override
+}
+
+/// The textures and passes of [`BloomSettings::half_resolution`]
+#[derive(Component)]
+pub struct BloomHalfResolution {
+    /// Holds the bright parts of the image extracted by the first downsampling pass, and then
+    /// the bloom composited by the final upsampling pass
+    pub color: CachedTexture,
+    /// The farthest depth of each 2x2 block of pixels of the view
+    pub depth: CachedTexture,
+    pub downsample_depth: DownsampleDepth,
+    pub upsample: BilateralUpsample,
+}
+
+/// Makes the depth textures of the cameras with half resolution bloom readable, since the
+/// composite is guided by it.
+pub fn configure_bloom_depth_textures(mut cameras: Query<(&mut Camera3d, &BloomSettings)>) {
+    for (mut camera_3d, settings) in &mut cameras {
+        if settings.half_resolution {
+            let mut depth_texture_usages = TextureUsages::from(camera_3d.depth_texture_usages);
+            depth_texture_usages |= TextureUsages::TEXTURE_BINDING;
+            camera_3d.depth_texture_usages = depth_texture_usages.into();
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn prepare_bloom_half_resolution(
+    mut commands: Commands,
+    mut texture_cache: ResMut<TextureCache>,
+    render_device: Res<RenderDevice>,
+    pipeline_cache: Res<PipelineCache>,
+    mut downsample_depth_pipelines: ResMut<SpecializedRenderPipelines<DownsampleDepthPipeline>>,
+    downsample_depth_pipeline: Res<DownsampleDepthPipeline>,
+    mut upsample_pipelines: ResMut<SpecializedRenderPipelines<BilateralUpsamplePipeline>>,
+    upsample_pipeline: Res<BilateralUpsamplePipeline>,
+    mut upsample_uniforms: ResMut<BilateralUpsampleUniforms>,
+    msaa: Res<Msaa>,
+    views: Query<(
+        Entity,
+        &ExtractedCamera,
+        &BloomSettings,
+        Has<MainPassResolutionOverride>,
+    )>,
+) {
+    for (entity, camera, settings, resolution_override) in &views {
+        if !uses_half_resolution(settings, resolution_override) {
+            continue;
+        }
+        let Some(target_size) = camera.physical_target_size else {
+            continue;
+        };
+
+        let color_descriptor = TextureDescriptor {
+            label: Some("bloom_half_resolution_texture"),
+            size: Extent3d {
+                width: target_size.x.div_ceil(2),
+                height: target_size.y.div_ceil(2),
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            // The final upsampling pass writes its blend factor to the alpha channel
+            format: ViewTarget::TEXTURE_FORMAT_HDR,
+            usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        };
+        let color = texture_cache.get(&render_device, color_descriptor.clone());
+        let depth = texture_cache.get(
+            &render_device,
+            TextureDescriptor {
+                label: Some("bloom_half_resolution_depth_texture"),
+                sample_count: msaa.samples(),
+                format: CORE_3D_DEPTH_FORMAT,
+                ..color_descriptor
+            },
+        );
+
+        let downsample_depth = downsample_depth_pipeline.prepare(
+            &pipeline_cache,
+            &mut downsample_depth_pipelines,
+            DownsampleDepthPipelineKey {
+                samples: msaa.samples(),
+            },
+        );
+        let upsample = upsample_pipeline.prepare(
+            &pipeline_cache,
+            &mut upsample_pipelines,
+            &mut upsample_uniforms,
+            BilateralUpsamplePipelineKey {
+                texture_format: ViewTarget::TEXTURE_FORMAT_HDR,
+                samples: 1,
+                multisampled: msaa.samples() > 1,
+                normals: false,
+                blend: Some(bloom_blend_state(settings.composite_mode)),
+            },
+            &BilateralUpsampleSettings::default(),
+        );
+
+        commands.entity(entity).insert(BloomHalfResolution {
+            color,
+            depth,
+            downsample_depth,
+            upsample,
+        });
+    }
+}
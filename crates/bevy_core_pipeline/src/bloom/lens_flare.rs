@@ -0,0 +1,209 @@
+use super::{
+    downsampling_pipeline::{BloomDownsamplingPipeline, BloomUniforms},
+    upsampling_pipeline::BloomUpsamplingPipeline,
+    BloomSettings, BloomTexture, BLOOM_SHADER_HANDLE,
+};
+use crate::fullscreen_vertex_shader::fullscreen_shader_vertex_state;
+use bevy_ecs::{
+    prelude::{Component, Entity},
+    system::{Commands, Query, Res, ResMut, Resource},
+    world::{FromWorld, World},
+};
+use bevy_render::{
+    extract_component::ComponentUniforms,
+    render_resource::{
+        binding_types::{sampler, texture_2d, uniform_buffer},
+        *,
+    },
+    renderer::{RenderContext, RenderDevice},
+    texture::{CachedTexture, TextureCache},
+};
+
+#[derive(Resource)]
+pub struct BloomLensFlarePipeline {
+    /// Layout with the bloom texture, a sampler, and uniforms
+    pub bind_group_layout: BindGroupLayout,
+}
+
+#[derive(Component)]
+pub struct BloomLensFlarePipelineId(pub CachedRenderPipelineId);
+
+/// The lens flare, rendered at the resolution of the second mip of the bloom texture
+#[derive(Component)]
+pub struct BloomLensFlareTexture(CachedTexture);
+
+#[derive(Component)]
+pub struct BloomLensFlareBindGroups {
+    /// Reads the first mip of the bloom texture to render the flare
+    lens_flare: BindGroup,
+    /// Reads the flare in the final upsampling pass
+    pub composite: BindGroup,
+}
+
+impl FromWorld for BloomLensFlarePipeline {
+    fn from_world(world: &mut World) -> Self {
+        let render_device = world.resource::<RenderDevice>();
+
+        let bind_group_layout = render_device.create_bind_group_layout(
+            "bloom_lens_flare_bind_group_layout",
+            &BindGroupLayoutEntries::sequential(
+                ShaderStages::FRAGMENT,
+                (
+                    // Bloom texture
+                    texture_2d(TextureSampleType::Float { filterable: true }),
+                    // Sampler
+                    sampler(SamplerBindingType::Filtering),
+                    // BloomUniforms
+                    uniform_buffer::<BloomUniforms>(true),
+                ),
+            ),
+        );
+
+        BloomLensFlarePipeline { bind_group_layout }
+    }
+}
+
+impl SpecializedRenderPipeline for BloomLensFlarePipeline {
+    type Key = TextureFormat;
+
+    fn specialize(&self, texture_format: Self::Key) -> RenderPipelineDescriptor {
+        RenderPipelineDescriptor {
+            label: Some("bloom_lens_flare_pipeline".into()),
+            layout: vec![self.bind_group_layout.clone()],
+            vertex: fullscreen_shader_vertex_state(),
+            fragment: Some(FragmentState {
+                shader: BLOOM_SHADER_HANDLE,
+                shader_defs: vec![],
+                entry_point: "lens_flare".into(),
+                targets: vec![Some(ColorTargetState {
+                    format: texture_format,
+                    blend: None,
+                    write_mask: ColorWrites::ALL,
+                })],
+            }),
+            primitive: PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: MultisampleState::default(),
+            push_constant_ranges: Vec::new(),
+        }
+    }
+}
+
+pub fn prepare_lens_flare_pipelines(
+    mut commands: Commands,
+    pipeline_cache: Res<PipelineCache>,
+    mut pipelines: ResMut<SpecializedRenderPipelines<BloomLensFlarePipeline>>,
+    pipeline: Res<BloomLensFlarePipeline>,
+    views: Query<(Entity, &BloomSettings)>,
+) {
+    for (entity, settings) in &views {
+        if settings.lens_flare.is_none() {
+            continue;
+        }
+
+        let pipeline_id =
+            pipelines.specialize(&pipeline_cache, &pipeline, settings.texture_format.into());
+
+        commands
+            .entity(entity)
+            .insert(BloomLensFlarePipelineId(pipeline_id));
+    }
+}
+
+pub fn prepare_lens_flare_textures(
+    mut commands: Commands,
+    mut texture_cache: ResMut<TextureCache>,
+    render_device: Res<RenderDevice>,
+    views: Query<(Entity, &BloomSettings, &BloomTexture)>,
+) {
+    for (entity, settings, bloom_texture) in &views {
+        if settings.lens_flare.is_none() {
+            continue;
+        }
+
+        let size = bloom_texture.mip_size(1);
+        let texture = texture_cache.get(
+            &render_device,
+            TextureDescriptor {
+                label: Some("bloom_lens_flare_texture"),
+                size: Extent3d {
+                    width: size.x,
+                    height: size.y,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: TextureDimension::D2,
+                format: settings.texture_format.into(),
+                usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING,
+                view_formats: &[],
+            },
+        );
+
+        commands
+            .entity(entity)
+            .insert(BloomLensFlareTexture(texture));
+    }
+}
+
+pub fn prepare_lens_flare_bind_groups(
+    mut commands: Commands,
+    render_device: Res<RenderDevice>,
+    downsampling_pipeline: Res<BloomDownsamplingPipeline>,
+    upsampling_pipeline: Res<BloomUpsamplingPipeline>,
+    lens_flare_pipeline: Res<BloomLensFlarePipeline>,
+    views: Query<(Entity, &BloomTexture, &BloomLensFlareTexture)>,
+    uniforms: Res<ComponentUniforms<BloomUniforms>>,
+) {
+    let Some(uniforms) = uniforms.binding() else {
+        return;
+    };
+
+    for (entity, bloom_texture, lens_flare_texture) in &views {
+        let lens_flare = render_device.create_bind_group(
+            "bloom_lens_flare_bind_group",
+            &lens_flare_pipeline.bind_group_layout,
+            &BindGroupEntries::sequential((
+                &bloom_texture.view(0),
+                &downsampling_pipeline.sampler,
+                uniforms.clone(),
+            )),
+        );
+
+        let composite = render_device.create_bind_group(
+            "bloom_lens_flare_composite_bind_group",
+            &upsampling_pipeline.lens_flare_bind_group_layout,
+            &BindGroupEntries::single(&lens_flare_texture.0.default_view),
+        );
+
+        commands.entity(entity).insert(BloomLensFlareBindGroups {
+            lens_flare,
+            composite,
+        });
+    }
+}
+
+/// Renders the lens flare from the first mip of the bloom texture, which must hold the
+/// downsampled scene, so this has to run before the upsampling passes.
+pub fn run_lens_flare(
+    render_context: &mut RenderContext,
+    pipeline: &RenderPipeline,
+    bind_groups: &BloomLensFlareBindGroups,
+    textures: &BloomLensFlareTexture,
+    uniform_index: u32,
+) {
+    let mut lens_flare_pass = render_context.begin_tracked_render_pass(RenderPassDescriptor {
+        label: Some("bloom_lens_flare_pass"),
+        color_attachments: &[Some(RenderPassColorAttachment {
+            view: &textures.0.default_view,
+            resolve_target: None,
+            ops: Operations::default(),
+        })],
+        depth_stencil_attachment: None,
+        timestamp_writes: None,
+        occlusion_query_set: None,
+    });
+    lens_flare_pass.set_render_pipeline(pipeline);
+    lens_flare_pass.set_bind_group(0, &bind_groups.lens_flare, &[uniform_index]);
+    lens_flare_pass.draw(0..3, 0..1);
+}
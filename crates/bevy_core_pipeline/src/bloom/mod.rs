@@ -1,13 +1,20 @@
+mod convolution;
 mod downsampling_pipeline;
+mod half_resolution;
+mod lens_flare;
 mod settings;
 mod upsampling_pipeline;
 
-use bevy_color::{Gray, LinearRgba};
-pub use settings::{BloomCompositeMode, BloomPrefilterSettings, BloomSettings};
+pub use settings::{
+    BloomCompositeMode, BloomLensFlare, BloomMethod, BloomPrefilterSettings, BloomSettings,
+    BloomTextureFormat, BLOOM_LAYER_COUNT,
+};
 
 use crate::{
+    bilateral_upsample::{half_resolution_viewport, BilateralUpsampleTextures},
     core_2d::graph::{Core2d, Node2d},
     core_3d::graph::{Core3d, Node3d},
+    prepass::ViewPrepassTextures,
 };
 use bevy_app::{App, Plugin};
 use bevy_asset::{load_internal_asset, Handle};
@@ -19,38 +26,68 @@ use bevy_render::{
     extract_component::{
         ComponentUniforms, DynamicUniformIndex, ExtractComponentPlugin, UniformComponentPlugin,
     },
+    render_asset::RenderAssets,
     render_graph::{NodeRunError, RenderGraphApp, RenderGraphContext, ViewNode, ViewNodeRunner},
     render_resource::*,
     renderer::{RenderContext, RenderDevice},
-    texture::{CachedTexture, TextureCache},
-    view::ViewTarget,
+    texture::{CachedTexture, GpuImage, TextureCache},
+    view::{Msaa, ViewDepthTexture, ViewTarget},
     Render, RenderApp, RenderSet,
 };
+use convolution::{
+    prepare_convolution_bind_groups, prepare_convolution_kernels, prepare_convolution_pipelines,
+    prepare_convolution_textures, run_convolution, transform_convolution_kernels,
+    BloomConvolutionBindGroups, BloomConvolutionKernels, BloomConvolutionPipeline,
+    BloomConvolutionPipelineIds, BloomConvolutionTextures, BLOOM_CONVOLUTION_SHADER_HANDLE,
+};
 use downsampling_pipeline::{
-    prepare_downsampling_pipeline, BloomDownsamplingPipeline, BloomDownsamplingPipelineIds,
-    BloomUniforms,
+    prepare_downsampling_pipeline, response_curve_image, BloomDownsamplingPipeline,
+    BloomDownsamplingPipelineIds, BloomUniforms,
+};
+use half_resolution::{
+    configure_bloom_depth_textures, prepare_bloom_half_resolution, BloomHalfResolution,
+};
+use lens_flare::{
+    prepare_lens_flare_bind_groups, prepare_lens_flare_pipelines, prepare_lens_flare_textures,
+    run_lens_flare, BloomLensFlareBindGroups, BloomLensFlarePipeline, BloomLensFlarePipelineId,
+    BloomLensFlareTexture,
 };
 use upsampling_pipeline::{
-    prepare_upsampling_pipeline, BloomUpsamplingPipeline, UpsamplingPipelineIds,
+    lens_dirt_image, prepare_upsampling_pipeline, BloomUpsamplingPipeline, UpsamplingPipelineIds,
 };
 
 const BLOOM_SHADER_HANDLE: Handle<Shader> = Handle::weak_from_u128(929599476923908);
 
-const BLOOM_TEXTURE_FORMAT: TextureFormat = TextureFormat::Rg11b10Float;
-
-// Maximum size of each dimension for the largest mipchain texture used in downscaling/upscaling.
-// 512 behaves well with the UV offset of 0.004 used in bloom.wgsl
-const MAX_MIP_DIMENSION: u32 = 512;
+// How many times we can halve the resolution minus one so we don't go unnecessarily low,
+// capped by the configured maximum
+fn bloom_mip_count(settings: &BloomSettings) -> u32 {
+    let mip_count = settings
+        .max_mip_dimension
+        .checked_ilog2()
+        .unwrap_or(0)
+        .max(2)
+        - 1;
+    mip_count.min(settings.max_mip_count).max(2)
+}
 
 pub struct BloomPlugin;
 
 impl Plugin for BloomPlugin {
     fn build(&self, app: &mut App) {
         load_internal_asset!(app, BLOOM_SHADER_HANDLE, "bloom.wgsl", Shader::from_wgsl);
+        load_internal_asset!(
+            app,
+            BLOOM_CONVOLUTION_SHADER_HANDLE,
+            "bloom_convolution.wgsl",
+            Shader::from_wgsl
+        );
 
         app.register_type::<BloomSettings>();
         app.register_type::<BloomPrefilterSettings>();
         app.register_type::<BloomCompositeMode>();
+        app.register_type::<BloomMethod>();
+        app.register_type::<BloomTextureFormat>();
+        app.register_type::<BloomLensFlare>();
         app.add_plugins((
             ExtractComponentPlugin::<BloomSettings>::default(),
             UniformComponentPlugin::<BloomUniforms>::default(),
@@ -62,20 +99,44 @@ impl Plugin for BloomPlugin {
         render_app
             .init_resource::<SpecializedRenderPipelines<BloomDownsamplingPipeline>>()
             .init_resource::<SpecializedRenderPipelines<BloomUpsamplingPipeline>>()
+            .init_resource::<SpecializedComputePipelines<BloomConvolutionPipeline>>()
+            .init_resource::<BloomConvolutionKernels>()
+            .init_resource::<SpecializedRenderPipelines<BloomLensFlarePipeline>>()
             .add_systems(
                 Render,
                 (
+                    configure_bloom_depth_textures.in_set(RenderSet::ManageViews),
                     prepare_downsampling_pipeline.in_set(RenderSet::Prepare),
                     prepare_upsampling_pipeline.in_set(RenderSet::Prepare),
+                    prepare_convolution_pipelines.in_set(RenderSet::Prepare),
+                    prepare_lens_flare_pipelines.in_set(RenderSet::Prepare),
                     prepare_bloom_textures.in_set(RenderSet::PrepareResources),
+                    prepare_convolution_textures.in_set(RenderSet::PrepareResources),
+                    prepare_convolution_kernels.in_set(RenderSet::PrepareResources),
+                    prepare_bloom_half_resolution.in_set(RenderSet::PrepareResources),
+                    // The lens flare texture is sized after the bloom texture
+                    prepare_lens_flare_textures
+                        .in_set(RenderSet::PrepareResources)
+                        .after(prepare_bloom_textures),
                     prepare_bloom_bind_groups.in_set(RenderSet::PrepareBindGroups),
+                    (
+                        transform_convolution_kernels,
+                        prepare_convolution_bind_groups,
+                    )
+                        .chain()
+                        .in_set(RenderSet::PrepareBindGroups),
+                    prepare_lens_flare_bind_groups.in_set(RenderSet::PrepareBindGroups),
                 ),
             )
             // Add bloom to the 3d render graph
             .add_render_graph_node::<ViewNodeRunner<BloomNode>>(Core3d, Node3d::Bloom)
             .add_render_graph_edges(
                 Core3d,
-                (Node3d::EndMainPass, Node3d::Bloom, Node3d::Tonemapping),
+                (
+                    Node3d::EndMainPassUpscaling,
+                    Node3d::Bloom,
+                    Node3d::Tonemapping,
+                ),
             )
             // Add bloom to the 2d render graph
             .add_render_graph_node::<ViewNodeRunner<BloomNode>>(Core2d, Node2d::Bloom)
@@ -91,7 +152,9 @@ impl Plugin for BloomPlugin {
         };
         render_app
             .init_resource::<BloomDownsamplingPipeline>()
-            .init_resource::<BloomUpsamplingPipeline>();
+            .init_resource::<BloomUpsamplingPipeline>()
+            .init_resource::<BloomConvolutionPipeline>()
+            .init_resource::<BloomLensFlarePipeline>();
     }
 }
 
@@ -107,6 +170,16 @@ impl ViewNode for BloomNode {
         &'static BloomSettings,
         &'static UpsamplingPipelineIds,
         &'static BloomDownsamplingPipelineIds,
+        Option<&'static BloomConvolutionPipelineIds>,
+        Option<&'static BloomConvolutionBindGroups>,
+        Option<&'static BloomLensFlarePipelineId>,
+        Option<&'static BloomLensFlareTexture>,
+        Option<&'static BloomLensFlareBindGroups>,
+        (
+            Option<&'static BloomHalfResolution>,
+            Option<&'static ViewDepthTexture>,
+            Option<&'static ViewPrepassTextures>,
+        ),
     );
 
     // Atypically for a post-processing effect, we do not need to
@@ -125,6 +198,12 @@ impl ViewNode for BloomNode {
             bloom_settings,
             upsampling_pipeline_ids,
             downsampling_pipeline_ids,
+            convolution_pipeline_ids,
+            convolution_bind_groups,
+            lens_flare_pipeline_id,
+            lens_flare_texture,
+            lens_flare_bind_groups,
+            (half_resolution, depth, prepass_textures),
         ): QueryItem<Self::ViewQuery>,
         world: &World,
     ) -> Result<(), NodeRunError> {
@@ -153,6 +232,91 @@ impl ViewNode for BloomNode {
             return Ok(());
         };
 
+        let convolution = match bloom_settings.method {
+            BloomMethod::Pyramid => None,
+            BloomMethod::Convolution { .. } => {
+                let (Some(pipelines), Some(bind_groups)) = (
+                    convolution_pipeline_ids.and_then(|ids| ids.get(pipeline_cache)),
+                    convolution_bind_groups,
+                ) else {
+                    return Ok(());
+                };
+                Some((pipelines, bind_groups))
+            }
+        };
+
+        // The final pass composites the lens flare when it is enabled, so it can't run without it
+        let lens_flare = match bloom_settings.lens_flare {
+            None => None,
+            Some(_) => {
+                let (Some(pipeline), Some(texture), Some(bind_groups)) = (
+                    lens_flare_pipeline_id.and_then(|id| pipeline_cache.get_render_pipeline(id.0)),
+                    lens_flare_texture,
+                    lens_flare_bind_groups,
+                ) else {
+                    return Ok(());
+                };
+                Some((pipeline, texture, bind_groups))
+            }
+        };
+
+        // The pyramid is computed from a half resolution prefilter texture, and composited from it
+        // with a bilateral upsample
+        let half_resolution = match downsampling_pipeline_ids.half_resolution {
+            None => None,
+            Some(pipeline_id) => {
+                let (Some(pipeline), Some(half_resolution), Some(depth)) = (
+                    pipeline_cache.get_render_pipeline(pipeline_id),
+                    half_resolution,
+                    depth,
+                ) else {
+                    return Ok(());
+                };
+                Some((pipeline, half_resolution, depth))
+            }
+        };
+        let half_resolution_viewport = camera.viewport.as_ref().map(half_resolution_viewport);
+
+        // The first downsample scales each pixel by the intensity of the bloom layer of its mesh
+        let mesh_mask_bind_group = if downsampling_pipeline_ids.layer_intensity {
+            let Some(mesh_mask) = prepass_textures.and_then(ViewPrepassTextures::mesh_mask_view)
+            else {
+                return Ok(());
+            };
+            let layout = if world.resource::<Msaa>().samples() > 1 {
+                &downsampling_pipeline_res.multisampled_mesh_mask_bind_group_layout
+            } else {
+                &downsampling_pipeline_res.mesh_mask_bind_group_layout
+            };
+            Some(render_context.render_device().create_bind_group(
+                "bloom_downsampling_mesh_mask_bind_group",
+                layout,
+                &BindGroupEntries::single(mesh_mask),
+            ))
+        } else {
+            None
+        };
+
+        // The convolution only needs the scene downsampled up to the mip it reads from,
+        // and replaces all upsample passes except the final one
+        let (downsampled_mip_count, upsampled_mip_count) = match convolution {
+            Some((_, bind_groups)) => (bind_groups.input_mip + 1, 1),
+            None => (bloom_texture.mip_count, bloom_texture.mip_count),
+        };
+
+        // The composite is guided by the depth, so nothing is rendered until it can be downsampled
+        if let Some((_, half_resolution, depth)) = half_resolution {
+            if !half_resolution.downsample_depth.render(
+                render_context,
+                world,
+                depth.view(),
+                &half_resolution.depth.default_view,
+                half_resolution_viewport.as_ref(),
+            ) {
+                return Ok(());
+            }
+        }
+
         render_context.command_encoder().push_debug_group("bloom");
 
         let diagnostics = render_context.diagnostic_recorder();
@@ -160,23 +324,40 @@ impl ViewNode for BloomNode {
 
         // First downsample pass
         {
-            let downsampling_first_bind_group = render_context.render_device().create_bind_group(
-                "bloom_downsampling_first_bind_group",
-                &downsampling_pipeline_res.bind_group_layout,
-                &BindGroupEntries::sequential((
-                    // Read from main texture directly
-                    view_target.main_texture_view(),
-                    &bind_groups.sampler,
-                    uniforms.clone(),
-                )),
-            );
+            let images = world.resource::<RenderAssets<GpuImage>>();
+            let downsampling_first_bind_group = match response_curve_image(bloom_settings, images) {
+                Some(response_curve) => render_context.render_device().create_bind_group(
+                    "bloom_downsampling_first_response_curve_bind_group",
+                    &downsampling_pipeline_res.response_curve_bind_group_layout,
+                    &BindGroupEntries::sequential((
+                        // Read from main texture directly
+                        view_target.main_texture_view(),
+                        &bind_groups.sampler,
+                        uniforms.clone(),
+                        &response_curve.texture_view,
+                    )),
+                ),
+                None => render_context.render_device().create_bind_group(
+                    "bloom_downsampling_first_bind_group",
+                    &downsampling_pipeline_res.bind_group_layout,
+                    &BindGroupEntries::sequential((
+                        // Read from main texture directly
+                        view_target.main_texture_view(),
+                        &bind_groups.sampler,
+                        uniforms.clone(),
+                    )),
+                ),
+            };
 
-            let view = &bloom_texture.view(0);
+            let view = match half_resolution {
+                Some((_, half_resolution, _)) => half_resolution.color.default_view.clone(),
+                None => bloom_texture.view(0),
+            };
             let mut downsampling_first_pass =
                 render_context.begin_tracked_render_pass(RenderPassDescriptor {
                     label: Some("bloom_downsampling_first_pass"),
                     color_attachments: &[Some(RenderPassColorAttachment {
-                        view,
+                        view: &view,
                         resolve_target: None,
                         ops: Operations::default(),
                     })],
@@ -190,11 +371,49 @@ impl ViewNode for BloomNode {
                 &downsampling_first_bind_group,
                 &[uniform_index.index()],
             );
+            if let Some(mesh_mask_bind_group) = &mesh_mask_bind_group {
+                downsampling_first_pass.set_bind_group(1, mesh_mask_bind_group, &[]);
+            }
+            if half_resolution.is_some() {
+                if let Some(viewport) = half_resolution_viewport.as_ref() {
+                    downsampling_first_pass.set_camera_viewport(viewport);
+                }
+            }
             downsampling_first_pass.draw(0..3, 0..1);
         }
 
+        // Downsample the half resolution prefilter texture into the first mip
+        if let Some((pipeline, half_resolution, _)) = half_resolution {
+            let bind_group = render_context.render_device().create_bind_group(
+                "bloom_downsampling_half_resolution_bind_group",
+                &downsampling_pipeline_res.bind_group_layout,
+                &BindGroupEntries::sequential((
+                    &half_resolution.color.default_view,
+                    &bind_groups.sampler,
+                    uniforms.clone(),
+                )),
+            );
+
+            let view = &bloom_texture.view(0);
+            let mut downsampling_pass =
+                render_context.begin_tracked_render_pass(RenderPassDescriptor {
+                    label: Some("bloom_downsampling_half_resolution_pass"),
+                    color_attachments: &[Some(RenderPassColorAttachment {
+                        view,
+                        resolve_target: None,
+                        ops: Operations::default(),
+                    })],
+                    depth_stencil_attachment: None,
+                    timestamp_writes: None,
+                    occlusion_query_set: None,
+                });
+            downsampling_pass.set_render_pipeline(pipeline);
+            downsampling_pass.set_bind_group(0, &bind_group, &[uniform_index.index()]);
+            downsampling_pass.draw(0..3, 0..1);
+        }
+
         // Other downsample passes
-        for mip in 1..bloom_texture.mip_count {
+        for mip in 1..downsampled_mip_count {
             let view = &bloom_texture.view(mip);
             let mut downsampling_pass =
                 render_context.begin_tracked_render_pass(RenderPassDescriptor {
@@ -217,8 +436,23 @@ impl ViewNode for BloomNode {
             downsampling_pass.draw(0..3, 0..1);
         }
 
+        if let Some((pipelines, bind_groups)) = convolution {
+            run_convolution(render_context, pipelines, bind_groups);
+        }
+
+        // The flare reads the first mip before the upsampling passes blend into it
+        if let Some((pipeline, texture, bind_groups)) = lens_flare {
+            run_lens_flare(
+                render_context,
+                pipeline,
+                bind_groups,
+                texture,
+                uniform_index.index(),
+            );
+        }
+
         // Upsample passes except the final one
-        for mip in (1..bloom_texture.mip_count).rev() {
+        for mip in (1..upsampled_mip_count).rev() {
             let view = &bloom_texture.view(mip - 1);
             let mut upsampling_pass =
                 render_context.begin_tracked_render_pass(RenderPassDescriptor {
@@ -241,23 +475,26 @@ impl ViewNode for BloomNode {
                 &bind_groups.upsampling_bind_groups[(bloom_texture.mip_count - mip - 1) as usize],
                 &[uniform_index.index()],
             );
-            let blend = compute_blend_factor(
-                bloom_settings,
-                mip as f32,
-                (bloom_texture.mip_count - 1) as f32,
-            );
-            upsampling_pass.set_blend_constant(LinearRgba::gray(blend));
             upsampling_pass.draw(0..3, 0..1);
         }
 
         // Final upsample pass
         // This is very similar to the above upsampling passes with the only difference
-        // being the pipeline (which itself is barely different) and the color attachment
+        // being the pipeline (which itself is barely different) and the color attachment,
+        // which is the half resolution texture when the bloom is composited from it
         {
+            let color_attachment = match half_resolution {
+                Some((_, half_resolution, _)) => RenderPassColorAttachment {
+                    view: &half_resolution.color.default_view,
+                    resolve_target: None,
+                    ops: Operations::default(),
+                },
+                None => view_target.get_unsampled_color_attachment(),
+            };
             let mut upsampling_final_pass =
                 render_context.begin_tracked_render_pass(RenderPassDescriptor {
                     label: Some("bloom_upsampling_final_pass"),
-                    color_attachments: &[Some(view_target.get_unsampled_color_attachment())],
+                    color_attachments: &[Some(color_attachment)],
                     depth_stencil_attachment: None,
                     timestamp_writes: None,
                     occlusion_query_set: None,
@@ -268,15 +505,35 @@ impl ViewNode for BloomNode {
                 &bind_groups.upsampling_bind_groups[(bloom_texture.mip_count - 1) as usize],
                 &[uniform_index.index()],
             );
-            if let Some(viewport) = camera.viewport.as_ref() {
+            if let Some((_, _, bind_groups)) = lens_flare {
+                upsampling_final_pass.set_bind_group(1, &bind_groups.composite, &[]);
+            }
+            let viewport = match half_resolution {
+                Some(_) => half_resolution_viewport.as_ref(),
+                None => camera.viewport.as_ref(),
+            };
+            if let Some(viewport) = viewport {
                 upsampling_final_pass.set_camera_viewport(viewport);
             }
-            let blend =
-                compute_blend_factor(bloom_settings, 0.0, (bloom_texture.mip_count - 1) as f32);
-            upsampling_final_pass.set_blend_constant(LinearRgba::gray(blend));
             upsampling_final_pass.draw(0..3, 0..1);
         }
 
+        // Composite the half resolution bloom, keeping it from bleeding over the edges of meshes
+        if let Some((_, half_resolution, depth)) = half_resolution {
+            half_resolution.upsample.render(
+                render_context,
+                world,
+                BilateralUpsampleTextures {
+                    color: &half_resolution.color.default_view,
+                    low_resolution_depth: &half_resolution.depth.default_view,
+                    depth: depth.view(),
+                    normals: None,
+                },
+                view_target.get_unsampled_color_attachment(),
+                camera.viewport.as_ref(),
+            );
+        }
+
         time_span.end(render_context.command_encoder());
         render_context.command_encoder().pop_debug_group();
 
@@ -322,23 +579,37 @@ impl BloomTexture {
                 ..Default::default()
             })
     }
+
+    #[cfg(any(
+        not(feature = "webgl"),
+        not(target_arch = "wasm32"),
+        feature = "webgpu"
+    ))]
+    fn mip_size(&self, mip_level: u32) -> UVec2 {
+        let size = self.texture.texture.size();
+        UVec2::new(size.width >> mip_level, size.height >> mip_level).max(UVec2::ONE)
+    }
+    #[cfg(all(feature = "webgl", target_arch = "wasm32", not(feature = "webgpu")))]
+    fn mip_size(&self, mip_level: u32) -> UVec2 {
+        let size = self.texture[mip_level as usize].texture.size();
+        UVec2::new(size.width, size.height)
+    }
 }
 
 fn prepare_bloom_textures(
     mut commands: Commands,
     mut texture_cache: ResMut<TextureCache>,
     render_device: Res<RenderDevice>,
-    views: Query<(Entity, &ExtractedCamera), With<BloomSettings>>,
+    views: Query<(Entity, &ExtractedCamera, &BloomSettings)>,
 ) {
-    for (entity, camera) in &views {
+    for (entity, camera, settings) in &views {
         if let Some(UVec2 {
             x: width,
             y: height,
         }) = camera.physical_viewport_size
         {
-            // How many times we can halve the resolution minus one so we don't go unnecessarily low
-            let mip_count = MAX_MIP_DIMENSION.ilog2().max(2) - 1;
-            let mip_height_ratio = MAX_MIP_DIMENSION as f32 / height as f32;
+            let mip_count = bloom_mip_count(settings);
+            let mip_height_ratio = settings.max_mip_dimension as f32 / height as f32;
 
             let texture_descriptor = TextureDescriptor {
                 label: Some("bloom_texture"),
@@ -350,7 +621,7 @@ fn prepare_bloom_textures(
                 mip_level_count: mip_count,
                 sample_count: 1,
                 dimension: TextureDimension::D2,
-                format: BLOOM_TEXTURE_FORMAT,
+                format: settings.texture_format.into(),
                 usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING,
                 view_formats: &[],
             };
@@ -398,12 +669,18 @@ fn prepare_bloom_bind_groups(
     render_device: Res<RenderDevice>,
     downsampling_pipeline: Res<BloomDownsamplingPipeline>,
     upsampling_pipeline: Res<BloomUpsamplingPipeline>,
-    views: Query<(Entity, &BloomTexture)>,
+    views: Query<(
+        Entity,
+        &BloomTexture,
+        &BloomSettings,
+        Option<&BloomConvolutionTextures>,
+    )>,
     uniforms: Res<ComponentUniforms<BloomUniforms>>,
+    images: Res<RenderAssets<GpuImage>>,
 ) {
     let sampler = &downsampling_pipeline.sampler;
 
-    for (entity, bloom_texture) in &views {
+    for (entity, bloom_texture, bloom_settings, convolution_textures) in &views {
         let bind_group_count = bloom_texture.mip_count as usize - 1;
 
         let mut downsampling_bind_groups = Vec::with_capacity(bind_group_count);
@@ -420,17 +697,52 @@ fn prepare_bloom_bind_groups(
         }
 
         let mut upsampling_bind_groups = Vec::with_capacity(bind_group_count);
-        for mip in (0..bloom_texture.mip_count).rev() {
-            upsampling_bind_groups.push(render_device.create_bind_group(
+        for mip in (1..bloom_texture.mip_count).rev() {
+            upsampling_bind_groups.push(
+                render_device.create_bind_group(
+                    "bloom_upsampling_bind_group",
+                    &upsampling_pipeline.bind_group_layout,
+                    &BindGroupEntries::sequential((
+                        &bloom_texture.view(mip),
+                        sampler,
+                        uniforms.binding().unwrap(),
+                        upsampling_pipeline.mip_uniforms[mip as usize]
+                            .binding()
+                            .unwrap(),
+                    )),
+                ),
+            );
+        }
+
+        // The final pass composites onto the main texture, which is where the lens dirt applies
+        let final_input = match convolution_textures {
+            Some(convolution_textures) => convolution_textures.output.default_view.clone(),
+            None => bloom_texture.view(0),
+        };
+        let upsampling_final_bind_group = match lens_dirt_image(bloom_settings, &images) {
+            Some(lens_dirt) => render_device.create_bind_group(
+                "bloom_upsampling_lens_dirt_bind_group",
+                &upsampling_pipeline.lens_dirt_bind_group_layout,
+                &BindGroupEntries::sequential((
+                    &final_input,
+                    sampler,
+                    uniforms.binding().unwrap(),
+                    upsampling_pipeline.mip_uniforms[0].binding().unwrap(),
+                    &lens_dirt.texture_view,
+                )),
+            ),
+            None => render_device.create_bind_group(
                 "bloom_upsampling_bind_group",
                 &upsampling_pipeline.bind_group_layout,
                 &BindGroupEntries::sequential((
-                    &bloom_texture.view(mip),
+                    &final_input,
                     sampler,
                     uniforms.binding().unwrap(),
+                    upsampling_pipeline.mip_uniforms[0].binding().unwrap(),
                 )),
-            ));
-        }
+            ),
+        };
+        upsampling_bind_groups.push(upsampling_final_bind_group);
 
         commands.entity(entity).insert(BloomBindGroups {
             downsampling_bind_groups: downsampling_bind_groups.into_boxed_slice(),
@@ -439,37 +751,3 @@ fn prepare_bloom_bind_groups(
         });
     }
 }
-
-/// Calculates blend intensities of blur pyramid levels
-/// during the upsampling + compositing stage.
-///
-/// The function assumes all pyramid levels are upsampled and
-/// blended into higher frequency ones using this function to
-/// calculate blend levels every time. The final (highest frequency)
-/// pyramid level in not blended into anything therefore this function
-/// is not applied to it. As a result, the *mip* parameter of 0 indicates
-/// the second-highest frequency pyramid level (in our case that is the
-/// 0th mip of the bloom texture with the original image being the
-/// actual highest frequency level).
-///
-/// Parameters:
-/// * `mip` - the index of the lower frequency pyramid level (0 - `max_mip`, where 0 indicates highest frequency mip but not the highest frequency image).
-/// * `max_mip` - the index of the lowest frequency pyramid level.
-///
-/// This function can be visually previewed for all values of *mip* (normalized) with tweakable
-/// [`BloomSettings`] parameters on [Desmos graphing calculator](https://www.desmos.com/calculator/ncc8xbhzzl).
-fn compute_blend_factor(bloom_settings: &BloomSettings, mip: f32, max_mip: f32) -> f32 {
-    let mut lf_boost = (1.0
-        - (1.0 - (mip / max_mip)).powf(1.0 / (1.0 - bloom_settings.low_frequency_boost_curvature)))
-        * bloom_settings.low_frequency_boost;
-    let high_pass_lq = 1.0
-        - (((mip / max_mip) - bloom_settings.high_pass_frequency)
-            / bloom_settings.high_pass_frequency)
-            .clamp(0.0, 1.0);
-    lf_boost *= match bloom_settings.composite_mode {
-        BloomCompositeMode::EnergyConserving => 1.0 - bloom_settings.intensity,
-        BloomCompositeMode::Additive => 1.0,
-    };
-
-    (bloom_settings.intensity + lf_boost) * high_pass_lq
-}
@@ -1,8 +1,21 @@
-use super::downsampling_pipeline::BloomUniforms;
-use bevy_ecs::{prelude::Component, query::QueryItem, reflect::ReflectComponent};
+use super::{bloom_mip_count, downsampling_pipeline::BloomUniforms};
+use crate::core_3d::{Camera3d, DEPTH_TEXTURE_SAMPLING_SUPPORTED};
+use bevy_asset::Handle;
+use bevy_color::{Color, ColorToComponents, LinearRgba};
+use bevy_ecs::{
+    prelude::Component,
+    query::{Has, QueryItem},
+    reflect::ReflectComponent,
+};
 use bevy_math::{AspectRatio, URect, UVec4, Vec4};
 use bevy_reflect::{std_traits::ReflectDefault, Reflect};
-use bevy_render::{extract_component::ExtractComponent, prelude::Camera};
+use bevy_render::{
+    extract_component::ExtractComponent,
+    prelude::Camera,
+    render_asset::RenderAssetUsages,
+    render_resource::{Extent3d, TextureDimension, TextureFormat},
+    texture::Image,
+};
 
 /// Applies a bloom effect to an HDR-enabled 2d or 3d camera.
 ///
@@ -102,9 +115,89 @@ pub struct BloomSettings {
     /// configured in a non-energy-conserving way,
     /// otherwise set to [`BloomCompositeMode::EnergyConserving`].
     pub composite_mode: BloomCompositeMode,
+
+    /// A texture emulating dirt and smudges on the camera lens (default: `None`).
+    ///
+    /// The texture is stretched over the camera's viewport and sampled when compositing
+    /// the bloom onto the image. Brighter texels catch more of the scattered light,
+    /// making the bloom stronger in those parts of the screen.
+    pub lens_dirt: Option<Handle<Image>>,
+
+    /// How much [`lens_dirt`](Self::lens_dirt) strengthens the bloom (default: 1.0).
+    ///
+    /// * 0.0 - the lens dirt has no effect
+    /// * 1.0 - the bloom is doubled where the lens dirt texture is white
+    pub lens_dirt_intensity: f32,
+
+    /// A screen-space lens flare, emulating light reflecting between the elements of the
+    /// camera lens (default: `None`).
+    pub lens_flare: Option<BloomLensFlare>,
+
+    /// The technique used to scatter the light (default: [`BloomMethod::Pyramid`]).
+    pub method: BloomMethod,
+
+    /// The height of the largest mip of the bloom pyramid in pixels, its width following the
+    /// aspect ratio of the viewport (default: [`BloomSettings::DEFAULT_MAX_MIP_DIMENSION`]).
+    ///
+    /// The bloom is computed at this resolution regardless of the camera's. Lowering it makes
+    /// the bloom cheaper, at the cost of blockier highlights.
+    pub max_mip_dimension: u32,
+
+    /// The maximum number of mips in the bloom pyramid (default: `u32::MAX`).
+    ///
+    /// By default the pyramid is halved until it is only a few pixels tall, letting light
+    /// scatter across the whole screen. Lowering this stops the blur earlier, making the
+    /// bloom tighter and cheaper. At least 2 mips are always used.
+    pub max_mip_count: u32,
+
+    /// The format of the texture the bloom is computed in
+    /// (default: [`BloomTextureFormat::Rg11b10Float`]).
+    ///
+    /// [`BloomTextureFormat::Rgba16Float`] has more precision, which avoids the slight color shifts
+    /// of the default format, at the cost of twice the memory and bandwidth.
+    ///
+    /// The bloom is always composited in the format of the camera's main texture.
+    pub texture_format: BloomTextureFormat,
+
+    /// Scales the bloom of each render layer (default: 1.0 for every layer).
+    ///
+    /// A mesh belongs to the bloom layer of the lowest [`RenderLayers`] layer it is on, if that is
+    /// one of the first [`BLOOM_LAYER_COUNT`] layers, and to layer 0 otherwise. This lets e.g. a
+    /// VFX layer bloom strongly while the rest of the world blooms mildly.
+    ///
+    /// The layer of each pixel is read from the texture written by the [`MeshMaskPrepass`], which
+    /// must be added to the camera for this to take effect. Like the other prepasses, it only
+    /// covers opaque and alpha-masked meshes, forward or deferred; pixels of transparent meshes
+    /// use the layer of the meshes behind them.
+    ///
+    /// [`RenderLayers`]: bevy_render::view::RenderLayers
+    /// [`MeshMaskPrepass`]: crate::prepass::MeshMaskPrepass
+    pub layer_intensity: [f32; BLOOM_LAYER_COUNT],
+
+    /// Extracts the bright parts of the image and composites the bloom at half the resolution of
+    /// the camera (default: `false`).
+    ///
+    /// The bloom pyramid is computed from a half resolution prefilter texture, and its final
+    /// level is blended into that same texture, which is then upsampled onto the image with a
+    /// [bilateral upsample](crate::bilateral_upsample) guided by the depth of the camera, so that
+    /// the bloom stays sharp along the edges of the meshes. The passes running at the camera's
+    /// resolution are the most expensive ones of the bloom, and this makes them about four times
+    /// cheaper, which is useful for mobile and web targets.
+    ///
+    /// Only 3D cameras that can sample their depth texture support this, and not while they render
+    /// their main pass at a lower resolution, e.g. with an upscaler. It has no effect otherwise.
+    pub half_resolution: bool,
 }
 
+/// The number of render layers [`BloomSettings::layer_intensity`] can be set for.
+pub const BLOOM_LAYER_COUNT: usize = 8;
+
 impl BloomSettings {
+    /// The default [`max_mip_dimension`](Self::max_mip_dimension).
+    ///
+    /// 512 behaves well with the UV offset of 0.004 used in `bloom.wgsl`.
+    pub const DEFAULT_MAX_MIP_DIMENSION: u32 = 512;
+
     /// The default bloom preset.
     ///
     /// This uses the [`EnergyConserving`](BloomCompositeMode::EnergyConserving) composite mode.
@@ -116,8 +209,18 @@ impl BloomSettings {
         prefilter_settings: BloomPrefilterSettings {
             threshold: 0.0,
             threshold_softness: 0.0,
+            response_curve: None,
         },
         composite_mode: BloomCompositeMode::EnergyConserving,
+        lens_dirt: None,
+        lens_dirt_intensity: 1.0,
+        lens_flare: None,
+        method: BloomMethod::Pyramid,
+        max_mip_dimension: Self::DEFAULT_MAX_MIP_DIMENSION,
+        max_mip_count: u32::MAX,
+        texture_format: BloomTextureFormat::Rg11b10Float,
+        layer_intensity: [1.0; BLOOM_LAYER_COUNT],
+        half_resolution: false,
     };
 
     /// A preset that's similar to how older games did bloom.
@@ -129,8 +232,18 @@ impl BloomSettings {
         prefilter_settings: BloomPrefilterSettings {
             threshold: 0.6,
             threshold_softness: 0.2,
+            response_curve: None,
         },
         composite_mode: BloomCompositeMode::Additive,
+        lens_dirt: None,
+        lens_dirt_intensity: 1.0,
+        lens_flare: None,
+        method: BloomMethod::Pyramid,
+        max_mip_dimension: Self::DEFAULT_MAX_MIP_DIMENSION,
+        max_mip_count: u32::MAX,
+        texture_format: BloomTextureFormat::Rg11b10Float,
+        layer_intensity: [1.0; BLOOM_LAYER_COUNT],
+        half_resolution: false,
     };
 
     /// A preset that applies a very strong bloom, and blurs the whole screen.
@@ -142,9 +255,27 @@ impl BloomSettings {
         prefilter_settings: BloomPrefilterSettings {
             threshold: 0.0,
             threshold_softness: 0.0,
+            response_curve: None,
         },
         composite_mode: BloomCompositeMode::EnergyConserving,
+        lens_dirt: None,
+        lens_dirt_intensity: 1.0,
+        lens_flare: None,
+        method: BloomMethod::Pyramid,
+        max_mip_dimension: Self::DEFAULT_MAX_MIP_DIMENSION,
+        max_mip_count: u32::MAX,
+        texture_format: BloomTextureFormat::Rg11b10Float,
+        layer_intensity: [1.0; BLOOM_LAYER_COUNT],
+        half_resolution: false,
     };
+
+    /// Returns `true` if [`layer_intensity`](Self::layer_intensity) differs between layers or
+    /// scales the bloom, requiring the bloom layer of each pixel.
+    pub fn uses_layer_intensity(&self) -> bool {
+        self.layer_intensity
+            .iter()
+            .any(|&intensity| intensity != 1.0)
+    }
 }
 
 impl Default for BloomSettings {
@@ -175,6 +306,120 @@ pub struct BloomPrefilterSettings {
     ///
     /// Values outside of the range [0.0, 1.0] will be clamped.
     pub threshold_softness: f32,
+
+    /// A custom response curve used instead of the soft threshold (default: `None`).
+    ///
+    /// The image is a lookup table read along its width. The red channel of each texel is the
+    /// fraction of a pixel's color that blooms, where a pixel with a brightness (its largest
+    /// color component) of `b` reads the texel at `b / (b + 1)`. When set,
+    /// [`threshold`](Self::threshold) and [`threshold_softness`](Self::threshold_softness)
+    /// are ignored.
+    ///
+    /// The image must not use an sRGB format. [`BloomPrefilterSettings::bake_response_curve`]
+    /// creates one from a function.
+    pub response_curve: Option<Handle<Image>>,
+}
+
+impl BloomPrefilterSettings {
+    /// Bakes `curve` into an image usable as [`response_curve`](Self::response_curve).
+    ///
+    /// `curve` maps the brightness of a pixel to the fraction of its color that blooms,
+    /// which is clamped between 0.0 and 1.0.
+    ///
+    /// ```
+    /// # use bevy_core_pipeline::bloom::BloomPrefilterSettings;
+    /// // Only bloom highlights brighter than 2.0, with a short linear ramp
+    /// let lut = BloomPrefilterSettings::bake_response_curve(|brightness| brightness - 2.0);
+    /// ```
+    pub fn bake_response_curve(curve: impl Fn(f32) -> f32) -> Image {
+        const LUT_SIZE: u32 = 256;
+
+        let data = (0..LUT_SIZE)
+            .map(|i| {
+                // Inverse of the mapping from brightness to texel used by the shader
+                let x = i as f32 / (LUT_SIZE - 1) as f32;
+                let brightness = x / (1.0 - x);
+                (curve(brightness).clamp(0.0, 1.0) * 255.0).round() as u8
+            })
+            .collect();
+
+        Image::new(
+            Extent3d {
+                width: LUT_SIZE,
+                height: 1,
+                depth_or_array_layers: 1,
+            },
+            TextureDimension::D2,
+            data,
+            TextureFormat::R8Unorm,
+            RenderAssetUsages::RENDER_WORLD,
+        )
+    }
+}
+
+/// A screen-space lens flare generated from the bright parts of the image, set with
+/// [`BloomSettings::lens_flare`].
+///
+/// The flare is made of three features:
+/// * Ghosts: copies of the bright parts of the image, mirrored through the center of the screen.
+/// * A halo: a ring around the center of the screen, lit by the bright parts on its opposite side.
+/// * A starburst: streaks radiating from the bright parts of the image.
+///
+/// The flare is computed at a quarter of the bloom resolution from its first mip, and is
+/// composited along with the bloom, so it is scaled by [`BloomSettings::intensity`] and
+/// brightened by [`BloomSettings::lens_dirt`].
+#[derive(Debug, Clone, Reflect)]
+#[reflect(Default)]
+pub struct BloomLensFlare {
+    /// The brightness above which parts of the image cause a flare (default: 1.0).
+    pub threshold: f32,
+
+    /// The number of ghosts (default: 4).
+    pub ghost_count: u32,
+
+    /// The distance between consecutive ghosts, as a fraction of the distance from the
+    /// bright part of the image to the center of the screen (default: 0.35).
+    pub ghost_spacing: f32,
+
+    /// The color the ghosts are multiplied by, its brightness scaling them
+    /// (default: a dim orange).
+    pub ghost_color: Color,
+
+    /// The radius of the halo, as a fraction of the height of the screen (default: 0.6).
+    ///
+    /// Zero disables the halo.
+    pub halo_radius: f32,
+
+    /// The color the halo is multiplied by, its brightness scaling it (default: a dim blue).
+    pub halo_color: Color,
+
+    /// The number of streaks of the starburst (default: 6).
+    ///
+    /// Zero disables the starburst.
+    pub starburst_count: u32,
+
+    /// The length of the streaks of the starburst, as a fraction of the height of the screen
+    /// (default: 0.1).
+    pub starburst_length: f32,
+
+    /// The color the starburst is multiplied by, its brightness scaling it (default: dim white).
+    pub starburst_color: Color,
+}
+
+impl Default for BloomLensFlare {
+    fn default() -> Self {
+        Self {
+            threshold: 1.0,
+            ghost_count: 4,
+            ghost_spacing: 0.35,
+            ghost_color: Color::linear_rgb(0.2, 0.12, 0.05),
+            halo_radius: 0.6,
+            halo_color: Color::linear_rgb(0.05, 0.1, 0.2),
+            starburst_count: 6,
+            starburst_length: 0.1,
+            starburst_color: Color::linear_rgb(0.3, 0.3, 0.3),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Reflect, PartialEq, Eq, Hash, Copy)]
@@ -183,13 +428,63 @@ pub enum BloomCompositeMode {
     Additive,
 }
 
+/// The formats [`BloomSettings::texture_format`] supports, which are all float formats that can
+/// be both rendered to and filtered.
+#[derive(Debug, Clone, Copy, Reflect, PartialEq, Eq, Hash, Default)]
+pub enum BloomTextureFormat {
+    /// 32 bits per pixel, without an alpha channel and with less precision in the blue channel.
+    #[default]
+    Rg11b10Float,
+    /// 64 bits per pixel.
+    Rgba16Float,
+}
+
+impl From<BloomTextureFormat> for TextureFormat {
+    fn from(format: BloomTextureFormat) -> Self {
+        match format {
+            BloomTextureFormat::Rg11b10Float => TextureFormat::Rg11b10Float,
+            BloomTextureFormat::Rgba16Float => TextureFormat::Rgba16Float,
+        }
+    }
+}
+
+/// The technique used by [`BloomSettings`] to scatter the light of bright parts of the image.
+#[derive(Debug, Clone, Reflect, PartialEq, Default)]
+pub enum BloomMethod {
+    /// Blurs the image by repeatedly downsampling and upsampling it, blending the blurred
+    /// levels using the frequency parameters of [`BloomSettings`].
+    #[default]
+    Pyramid,
+
+    /// Convolves the image with a kernel image using a fast Fourier transform (FFT).
+    ///
+    /// This is a lot more expensive than [`BloomMethod::Pyramid`], but can produce any
+    /// scattering shape, such as the starbursts and streaks of real camera lenses.
+    ///
+    /// The kernel is centered on each pixel and spans twice the larger dimension of the
+    /// viewport, so its light should be concentrated around its center. It is normalized by
+    /// its total luminance, so only its shape and color matter, while the strength of the bloom
+    /// is still controlled by [`BloomSettings::intensity`].
+    ///
+    /// [`BloomSettings::low_frequency_boost`], [`BloomSettings::low_frequency_boost_curvature`]
+    /// and [`BloomSettings::high_pass_frequency`] have no effect with this method.
+    ///
+    /// Requires compute shader support.
+    Convolution {
+        /// The image the bright parts of the scene are convolved with.
+        kernel: Handle<Image>,
+    },
+}
+
 impl ExtractComponent for BloomSettings {
-    type QueryData = (&'static Self, &'static Camera);
+    type QueryData = (&'static Self, &'static Camera, Has<Camera3d>);
 
     type QueryFilter = ();
     type Out = (Self, BloomUniforms);
 
-    fn extract_component((settings, camera): QueryItem<'_, Self::QueryData>) -> Option<Self::Out> {
+    fn extract_component(
+        (settings, camera, is_3d): QueryItem<'_, Self::QueryData>,
+    ) -> Option<Self::Out> {
         match (
             camera.physical_viewport_rect(),
             camera.physical_viewport_size(),
@@ -201,6 +496,8 @@ impl ExtractComponent for BloomSettings {
                 let threshold = settings.prefilter_settings.threshold;
                 let threshold_softness = settings.prefilter_settings.threshold_softness;
                 let knee = threshold * threshold_softness.clamp(0.0, 1.0);
+                // Unused unless the lens flare is enabled
+                let lens_flare = settings.lens_flare.clone().unwrap_or_default();
 
                 let uniform = BloomUniforms {
                     threshold_precomputations: Vec4::new(
@@ -213,9 +510,39 @@ impl ExtractComponent for BloomSettings {
                         / UVec4::new(target_size.x, target_size.y, target_size.x, target_size.y)
                             .as_vec4(),
                     aspect: AspectRatio::from_pixels(size.x, size.y).into(),
+                    lens_dirt_intensity: settings.lens_dirt_intensity,
+                    intensity: settings.intensity,
+                    low_frequency_boost: settings.low_frequency_boost,
+                    low_frequency_boost_curvature: settings.low_frequency_boost_curvature,
+                    high_pass_frequency: settings.high_pass_frequency,
+                    max_mip: (bloom_mip_count(settings) - 1) as f32,
+                    layer_intensity: [
+                        Vec4::from_slice(&settings.layer_intensity[..4]),
+                        Vec4::from_slice(&settings.layer_intensity[4..]),
+                    ],
+                    lens_flare_ghost: LinearRgba::from(lens_flare.ghost_color)
+                        .to_vec3()
+                        .extend(lens_flare.ghost_spacing),
+                    lens_flare_halo: LinearRgba::from(lens_flare.halo_color)
+                        .to_vec3()
+                        .extend(lens_flare.halo_radius),
+                    lens_flare_starburst: LinearRgba::from(lens_flare.starburst_color)
+                        .to_vec3()
+                        .extend(lens_flare.starburst_length),
+                    lens_flare_threshold: lens_flare.threshold,
+                    lens_flare_ghost_count: lens_flare.ghost_count,
+                    lens_flare_starburst_count: lens_flare.starburst_count,
+                };
+
+                // The half resolution composite is guided by the depth of 3D cameras
+                let settings = BloomSettings {
+                    half_resolution: settings.half_resolution
+                        && is_3d
+                        && DEPTH_TEXTURE_SAMPLING_SUPPORTED,
+                    ..settings.clone()
                 };
 
-                Some((settings.clone(), uniform))
+                Some((settings, uniform))
             }
             _ => None,
         }
@@ -1,19 +1,23 @@
 use super::{
-    downsampling_pipeline::BloomUniforms, BloomCompositeMode, BloomSettings, BLOOM_SHADER_HANDLE,
-    BLOOM_TEXTURE_FORMAT,
+    downsampling_pipeline::BloomUniforms, half_resolution::uses_half_resolution,
+    BloomCompositeMode, BloomMethod, BloomSettings, BLOOM_SHADER_HANDLE,
 };
 use crate::fullscreen_vertex_shader::fullscreen_shader_vertex_state;
 use bevy_ecs::{
     prelude::{Component, Entity},
+    query::Has,
     system::{Commands, Query, Res, ResMut, Resource},
     world::{FromWorld, World},
 };
 use bevy_render::{
+    camera::MainPassResolutionOverride,
+    render_asset::RenderAssets,
     render_resource::{
         binding_types::{sampler, texture_2d, uniform_buffer},
         *,
     },
-    renderer::RenderDevice,
+    renderer::{RenderDevice, RenderQueue},
+    texture::GpuImage,
     view::ViewTarget,
 };
 
@@ -26,17 +30,41 @@ pub struct UpsamplingPipelineIds {
 #[derive(Resource)]
 pub struct BloomUpsamplingPipeline {
     pub bind_group_layout: BindGroupLayout,
+    /// Layout of the final pass when [`BloomSettings::lens_dirt`] is used, with the lens dirt texture appended
+    pub lens_dirt_bind_group_layout: BindGroupLayout,
+    /// Second layout of the final pass when [`BloomSettings::lens_flare`] is used, with the lens flare texture
+    pub lens_flare_bind_group_layout: BindGroupLayout,
+    /// The per-pass uniforms of each mip a bloom texture can have, indexed by the mip being read from
+    pub mip_uniforms: Vec<UniformBuffer<BloomMipUniform>>,
+}
+
+/// The uniform struct of a single upsampling pass, telling the shader which mip it upsamples.
+#[derive(ShaderType, Clone)]
+pub struct BloomMipUniform {
+    /// The mip being upsampled, 0.0 being the highest frequency one
+    pub mip: f32,
+    #[cfg(all(feature = "webgl", target_arch = "wasm32", not(feature = "webgpu")))]
+    // WebGL2 structs must be 16 byte aligned.
+    pub _webgl2_padding: bevy_math::Vec3,
 }
 
 #[derive(PartialEq, Eq, Hash, Clone)]
 pub struct BloomUpsamplingPipelineKeys {
     composite_mode: BloomCompositeMode,
     final_pipeline: bool,
+    lens_dirt: bool,
+    lens_flare: bool,
+    convolution: bool,
+    /// Whether the final pipeline writes to the half resolution texture, without blending
+    half_resolution: bool,
+    /// The bloom texture format, or the view's main texture format for the final pipeline
+    texture_format: TextureFormat,
 }
 
 impl FromWorld for BloomUpsamplingPipeline {
     fn from_world(world: &mut World) -> Self {
         let render_device = world.resource::<RenderDevice>();
+        let render_queue = world.resource::<RenderQueue>();
 
         let bind_group_layout = render_device.create_bind_group_layout(
             "bloom_upsampling_bind_group_layout",
@@ -49,11 +77,66 @@ impl FromWorld for BloomUpsamplingPipeline {
                     sampler(SamplerBindingType::Filtering),
                     // BloomUniforms
                     uniform_buffer::<BloomUniforms>(true),
+                    // BloomMipUniform
+                    uniform_buffer::<BloomMipUniform>(false),
+                ),
+            ),
+        );
+
+        let lens_dirt_bind_group_layout = render_device.create_bind_group_layout(
+            "bloom_upsampling_lens_dirt_bind_group_layout",
+            &BindGroupLayoutEntries::sequential(
+                ShaderStages::FRAGMENT,
+                (
+                    // Input texture
+                    texture_2d(TextureSampleType::Float { filterable: true }),
+                    // Sampler
+                    sampler(SamplerBindingType::Filtering),
+                    // BloomUniforms
+                    uniform_buffer::<BloomUniforms>(true),
+                    // BloomMipUniform
+                    uniform_buffer::<BloomMipUniform>(false),
+                    // Lens dirt texture
+                    texture_2d(TextureSampleType::Float { filterable: true }),
                 ),
             ),
         );
 
-        BloomUpsamplingPipeline { bind_group_layout }
+        let lens_flare_bind_group_layout = render_device.create_bind_group_layout(
+            "bloom_upsampling_lens_flare_bind_group_layout",
+            &BindGroupLayoutEntries::single(
+                ShaderStages::FRAGMENT,
+                // Lens flare texture
+                texture_2d(TextureSampleType::Float { filterable: true }),
+            ),
+        );
+
+        // The uniforms of each pass never change, so they only need to be written once for
+        // every mip a texture can have
+        let mip_count = render_device.limits().max_texture_dimension_2d.ilog2() + 1;
+        let mip_uniforms = (0..mip_count)
+            .map(|mip| {
+                let mut uniform = UniformBuffer::from(BloomMipUniform {
+                    mip: mip as f32,
+                    #[cfg(all(
+                        feature = "webgl",
+                        target_arch = "wasm32",
+                        not(feature = "webgpu")
+                    ))]
+                    _webgl2_padding: Default::default(),
+                });
+                uniform.set_label(Some("bloom_mip_uniform"));
+                uniform.write_buffer(render_device, render_queue);
+                uniform
+            })
+            .collect();
+
+        BloomUpsamplingPipeline {
+            bind_group_layout,
+            lens_dirt_bind_group_layout,
+            lens_flare_bind_group_layout,
+            mip_uniforms,
+        }
     }
 }
 
@@ -61,62 +144,45 @@ impl SpecializedRenderPipeline for BloomUpsamplingPipeline {
     type Key = BloomUpsamplingPipelineKeys;
 
     fn specialize(&self, key: Self::Key) -> RenderPipelineDescriptor {
-        let texture_format = if key.final_pipeline {
-            ViewTarget::TEXTURE_FORMAT_HDR
+        let mut shader_defs = vec![];
+
+        if key.composite_mode == BloomCompositeMode::Additive {
+            shader_defs.push("ADDITIVE_COMPOSITE".into());
+        }
+
+        if key.convolution {
+            shader_defs.push("CONVOLUTION".into());
+        }
+
+        let mut layout = vec![if key.final_pipeline && key.lens_dirt {
+            shader_defs.push("LENS_DIRT".into());
+            self.lens_dirt_bind_group_layout.clone()
         } else {
-            BLOOM_TEXTURE_FORMAT
-        };
-
-        let color_blend = match key.composite_mode {
-            BloomCompositeMode::EnergyConserving => {
-                // At the time of developing this we decided to blend our
-                // blur pyramid levels using native WGPU render pass blend
-                // constants. They are set in the bloom node's run function.
-                // This seemed like a good approach at the time which allowed
-                // us to perform complex calculations for blend levels on the CPU,
-                // however, we missed the fact that this prevented us from using
-                // textures to customize bloom appearance on individual parts
-                // of the screen and create effects such as lens dirt or
-                // screen blur behind certain UI elements.
-                //
-                // TODO: Use alpha instead of blend constants and move
-                // compute_blend_factor to the shader. The shader
-                // will likely need to know current mip number or
-                // mip "angle" (original texture is 0deg, max mip is 90deg)
-                // so make sure you give it that as a uniform.
-                // That does have to be provided per each pass unlike other
-                // uniforms that are set once.
-                BlendComponent {
-                    src_factor: BlendFactor::Constant,
-                    dst_factor: BlendFactor::OneMinusConstant,
-                    operation: BlendOperation::Add,
-                }
-            }
-            BloomCompositeMode::Additive => BlendComponent {
-                src_factor: BlendFactor::Constant,
-                dst_factor: BlendFactor::One,
-                operation: BlendOperation::Add,
-            },
-        };
+            self.bind_group_layout.clone()
+        }];
+
+        if key.final_pipeline && key.lens_flare {
+            shader_defs.push("LENS_FLARE".into());
+            layout.push(self.lens_flare_bind_group_layout.clone());
+        }
 
         RenderPipelineDescriptor {
             label: Some("bloom_upsampling_pipeline".into()),
-            layout: vec![self.bind_group_layout.clone()],
+            layout,
             vertex: fullscreen_shader_vertex_state(),
             fragment: Some(FragmentState {
                 shader: BLOOM_SHADER_HANDLE,
-                shader_defs: vec![],
+                shader_defs,
                 entry_point: "upsample".into(),
                 targets: vec![Some(ColorTargetState {
-                    format: texture_format,
-                    blend: Some(BlendState {
-                        color: color_blend,
-                        alpha: BlendComponent {
-                            src_factor: BlendFactor::Zero,
-                            dst_factor: BlendFactor::One,
-                            operation: BlendOperation::Add,
-                        },
-                    }),
+                    format: key.texture_format,
+                    // The half resolution texture keeps the blend factor, for the bilateral
+                    // upsample to blend with
+                    blend: if key.final_pipeline && key.half_resolution {
+                        None
+                    } else {
+                        Some(bloom_blend_state(key.composite_mode))
+                    },
                     write_mask: ColorWrites::ALL,
                 })],
             }),
@@ -133,15 +199,26 @@ pub fn prepare_upsampling_pipeline(
     pipeline_cache: Res<PipelineCache>,
     mut pipelines: ResMut<SpecializedRenderPipelines<BloomUpsamplingPipeline>>,
     pipeline: Res<BloomUpsamplingPipeline>,
-    views: Query<(Entity, &BloomSettings)>,
+    views: Query<(
+        Entity,
+        &BloomSettings,
+        &ViewTarget,
+        Has<MainPassResolutionOverride>,
+    )>,
+    images: Res<RenderAssets<GpuImage>>,
 ) {
-    for (entity, settings) in &views {
+    for (entity, settings, view_target, resolution_override) in &views {
         let pipeline_id = pipelines.specialize(
             &pipeline_cache,
             &pipeline,
             BloomUpsamplingPipelineKeys {
                 composite_mode: settings.composite_mode,
                 final_pipeline: false,
+                lens_dirt: false,
+                lens_flare: false,
+                convolution: false,
+                half_resolution: false,
+                texture_format: settings.texture_format.into(),
             },
         );
 
@@ -151,6 +228,12 @@ pub fn prepare_upsampling_pipeline(
             BloomUpsamplingPipelineKeys {
                 composite_mode: settings.composite_mode,
                 final_pipeline: true,
+                lens_dirt: lens_dirt_image(settings, &images).is_some(),
+                lens_flare: settings.lens_flare.is_some(),
+                // The convolved bloom is composited by the final pass
+                convolution: matches!(settings.method, BloomMethod::Convolution { .. }),
+                half_resolution: uses_half_resolution(settings, resolution_override),
+                texture_format: view_target.main_texture_format(),
             },
         );
 
@@ -160,3 +243,39 @@ pub fn prepare_upsampling_pipeline(
         });
     }
 }
+
+/// The blend state compositing the bloom, whose blend factor is computed per-pixel in the shader
+/// and output as alpha, so it can be modulated by textures (e.g. lens dirt).
+pub fn bloom_blend_state(composite_mode: BloomCompositeMode) -> BlendState {
+    let color = match composite_mode {
+        BloomCompositeMode::EnergyConserving => BlendComponent {
+            src_factor: BlendFactor::SrcAlpha,
+            dst_factor: BlendFactor::OneMinusSrcAlpha,
+            operation: BlendOperation::Add,
+        },
+        BloomCompositeMode::Additive => BlendComponent {
+            src_factor: BlendFactor::SrcAlpha,
+            dst_factor: BlendFactor::One,
+            operation: BlendOperation::Add,
+        },
+    };
+    BlendState {
+        color,
+        alpha: BlendComponent {
+            src_factor: BlendFactor::Zero,
+            dst_factor: BlendFactor::One,
+            operation: BlendOperation::Add,
+        },
+    }
+}
+
+/// Returns the lens dirt texture of the bloom settings, if any is set and it has been loaded.
+pub fn lens_dirt_image<'a>(
+    settings: &BloomSettings,
+    images: &'a RenderAssets<GpuImage>,
+) -> Option<&'a GpuImage> {
+    settings
+        .lens_dirt
+        .as_ref()
+        .and_then(|lens_dirt| images.get(lens_dirt))
+}
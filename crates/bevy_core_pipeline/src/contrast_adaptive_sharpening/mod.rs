@@ -28,7 +28,8 @@ pub use node::CASNode;
 /// Applies a contrast adaptive sharpening (CAS) filter to the camera.
 ///
 /// CAS is usually used in combination with shader based anti-aliasing methods
-/// such as FXAA or TAA to regain some of the lost detail from the blurring that they introduce.
+/// such as FXAA, SMAA or TAA to regain some of the lost detail from the blurring that they introduce.
+/// It runs after all of them, and before upscaling and the UI.
 ///
 /// CAS is designed to adjust the amount of sharpening applied to different areas of an image
 /// based on the local contrast. This can help avoid over-sharpening areas with high contrast
@@ -138,7 +139,8 @@ impl Plugin for CASPlugin {
                         Node3d::ContrastAdaptiveSharpening,
                         Node3d::EndMainPassPostProcessing,
                     ),
-                );
+                )
+                .add_render_graph_edge(Core3d, Node3d::Smaa, Node3d::ContrastAdaptiveSharpening);
         }
         {
             render_app
@@ -155,7 +157,8 @@ impl Plugin for CASPlugin {
                         Node2d::ContrastAdaptiveSharpening,
                         Node2d::EndMainPassPostProcessing,
                     ),
-                );
+                )
+                .add_render_graph_edge(Core2d, Node2d::Smaa, Node2d::ContrastAdaptiveSharpening);
         }
     }
 
@@ -234,6 +237,24 @@ impl SpecializedRenderPipeline for CASPipeline {
     }
 }
 
+impl PrewarmRenderPipeline for CASPipeline {
+    fn warm_keys(&self) -> Vec<Self::Key> {
+        let mut keys = Vec::new();
+        for texture_format in [
+            ViewTarget::TEXTURE_FORMAT_HDR,
+            TextureFormat::bevy_default(),
+        ] {
+            for denoise in [false, true] {
+                keys.push(CASPipelineKey {
+                    texture_format,
+                    denoise,
+                });
+            }
+        }
+        keys
+    }
+}
+
 fn prepare_cas_pipelines(
     mut commands: Commands,
     pipeline_cache: Res<PipelineCache>,
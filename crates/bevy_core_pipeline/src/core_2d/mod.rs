@@ -19,10 +19,14 @@ pub mod graph {
         EndMainPass,
         Bloom,
         Tonemapping,
+        Kuwahara,
+        Vignette,
         Fxaa,
         Smaa,
         Upscaling,
         ContrastAdaptiveSharpening,
+        Pixelate,
+        Posterize,
         EndMainPassPostProcessing,
     }
 }
@@ -2,7 +2,7 @@ use super::{Camera3d, ViewTransmissionTexture};
 use crate::core_3d::Transmissive3d;
 use bevy_ecs::{prelude::*, query::QueryItem};
 use bevy_render::{
-    camera::ExtractedCamera,
+    camera::{ExtractedCamera, MainPassResolutionOverride, Viewport},
     render_graph::{NodeRunError, RenderGraphContext, ViewNode},
     render_phase::ViewSortedRenderPhases,
     render_resource::{Extent3d, RenderPassDescriptor, StoreOp},
@@ -25,13 +25,16 @@ impl ViewNode for MainTransmissivePass3dNode {
         &'static ViewTarget,
         Option<&'static ViewTransmissionTexture>,
         &'static ViewDepthTexture,
+        Option<&'static MainPassResolutionOverride>,
     );
 
     fn run(
         &self,
         graph: &mut RenderGraphContext,
         render_context: &mut RenderContext,
-        (camera, camera_3d, target, transmission, depth): QueryItem<Self::ViewQuery>,
+        (camera, camera_3d, target, transmission, depth, resolution_override): QueryItem<
+            Self::ViewQuery,
+        >,
         world: &World,
     ) -> Result<(), NodeRunError> {
         let view_entity = graph.view_entity();
@@ -93,8 +96,11 @@ impl ViewNode for MainTransmissivePass3dNode {
                     let mut render_pass =
                         render_context.begin_tracked_render_pass(render_pass_descriptor.clone());
 
-                    if let Some(viewport) = camera.viewport.as_ref() {
-                        render_pass.set_camera_viewport(viewport);
+                    if let Some(viewport) = Viewport::from_viewport_and_override(
+                        camera.viewport.as_ref(),
+                        resolution_override,
+                    ) {
+                        render_pass.set_camera_viewport(&viewport);
                     }
 
                     // render items in range
@@ -104,8 +110,11 @@ impl ViewNode for MainTransmissivePass3dNode {
                 let mut render_pass =
                     render_context.begin_tracked_render_pass(render_pass_descriptor);
 
-                if let Some(viewport) = camera.viewport.as_ref() {
-                    render_pass.set_camera_viewport(viewport);
+                if let Some(viewport) = Viewport::from_viewport_and_override(
+                    camera.viewport.as_ref(),
+                    resolution_override,
+                ) {
+                    render_pass.set_camera_viewport(&viewport);
                 }
 
                 transmissive_phase.render(&mut render_pass, world, view_entity);
@@ -23,18 +23,27 @@ pub mod graph {
         StartMainPass,
         MainOpaquePass,
         MainTransmissivePass,
+        WeightedBlendedTransparentPass,
         MainTransparentPass,
+        HalfResolutionTransparentPass,
         EndMainPass,
+        StartMainPassUpscaling,
+        Fsr,
+        EndMainPassUpscaling,
         Taa,
         MotionBlur,
         Bloom,
         AutoExposure,
         DepthOfField,
         Tonemapping,
+        Kuwahara,
+        Vignette,
         Fxaa,
         Smaa,
         Upscaling,
         ContrastAdaptiveSharpening,
+        Pixelate,
+        Posterize,
         EndMainPassPostProcessing,
     }
 }
@@ -104,9 +113,10 @@ use crate::{
     },
     dof::DepthOfFieldNode,
     prepass::{
-        node::PrepassNode, AlphaMask3dPrepass, DeferredPrepass, DepthPrepass, MotionVectorPrepass,
-        NormalPrepass, Opaque3dPrepass, OpaqueNoLightmap3dBinKey, ViewPrepassTextures,
-        MOTION_VECTOR_PREPASS_FORMAT, NORMAL_PREPASS_FORMAT,
+        node::PrepassNode, AlphaMask3dPrepass, DeferredPrepass, DepthPrepass, MeshMaskPrepass,
+        MotionVectorPrepass, NormalPrepass, Opaque3dPrepass, OpaqueNoLightmap3dBinKey,
+        ViewPrepassTextures, MESH_MASK_PREPASS_FORMAT, MOTION_VECTOR_PREPASS_FORMAT,
+        NORMAL_PREPASS_FORMAT,
     },
     skybox::SkyboxPlugin,
     tonemapping::TonemappingNode,
@@ -175,6 +185,8 @@ impl Plugin for Core3dPlugin {
                 Node3d::MainTransparentPass,
             )
             .add_render_graph_node::<EmptyNode>(Core3d, Node3d::EndMainPass)
+            .add_render_graph_node::<EmptyNode>(Core3d, Node3d::StartMainPassUpscaling)
+            .add_render_graph_node::<EmptyNode>(Core3d, Node3d::EndMainPassUpscaling)
             .add_render_graph_node::<ViewNodeRunner<DepthOfFieldNode>>(Core3d, Node3d::DepthOfField)
             .add_render_graph_node::<ViewNodeRunner<TonemappingNode>>(Core3d, Node3d::Tonemapping)
             .add_render_graph_node::<EmptyNode>(Core3d, Node3d::EndMainPassPostProcessing)
@@ -191,6 +203,8 @@ impl Plugin for Core3dPlugin {
                     Node3d::MainTransmissivePass,
                     Node3d::MainTransparentPass,
                     Node3d::EndMainPass,
+                    Node3d::StartMainPassUpscaling,
+                    Node3d::EndMainPassUpscaling,
                     Node3d::Tonemapping,
                     Node3d::EndMainPassPostProcessing,
                     Node3d::Upscaling,
@@ -540,6 +554,7 @@ pub fn extract_camera_prepass_phase(
                 Has<NormalPrepass>,
                 Has<MotionVectorPrepass>,
                 Has<DeferredPrepass>,
+                Has<MeshMaskPrepass>,
             ),
             With<Camera3d>,
         >,
@@ -548,14 +563,21 @@ pub fn extract_camera_prepass_phase(
 ) {
     live_entities.clear();
 
-    for (entity, camera, depth_prepass, normal_prepass, motion_vector_prepass, deferred_prepass) in
-        cameras_3d.iter()
+    for (
+        entity,
+        camera,
+        depth_prepass,
+        normal_prepass,
+        motion_vector_prepass,
+        deferred_prepass,
+        mesh_mask_prepass,
+    ) in cameras_3d.iter()
     {
         if !camera.is_active {
             continue;
         }
 
-        if depth_prepass || normal_prepass || motion_vector_prepass {
+        if depth_prepass || normal_prepass || motion_vector_prepass || mesh_mask_prepass {
             opaque_3d_prepass_phases.insert_or_clear(entity);
             alpha_mask_3d_prepass_phases.insert_or_clear(entity);
         } else {
@@ -587,6 +609,9 @@ pub fn extract_camera_prepass_phase(
         if deferred_prepass {
             entity.insert(DeferredPrepass);
         }
+        if mesh_mask_prepass {
+            entity.insert(MeshMaskPrepass);
+        }
     }
 
     opaque_3d_prepass_phases.retain(|entity, _| live_entities.contains(entity));
@@ -801,6 +826,7 @@ pub fn prepare_prepass_textures(
         Has<NormalPrepass>,
         Has<MotionVectorPrepass>,
         Has<DeferredPrepass>,
+        Has<MeshMaskPrepass>,
     )>,
 ) {
     let mut depth_textures = HashMap::default();
@@ -808,8 +834,16 @@ pub fn prepare_prepass_textures(
     let mut deferred_textures = HashMap::default();
     let mut deferred_lighting_id_textures = HashMap::default();
     let mut motion_vectors_textures = HashMap::default();
-    for (entity, camera, depth_prepass, normal_prepass, motion_vector_prepass, deferred_prepass) in
-        &views_3d
+    let mut mesh_mask_textures = HashMap::default();
+    for (
+        entity,
+        camera,
+        depth_prepass,
+        normal_prepass,
+        motion_vector_prepass,
+        deferred_prepass,
+        mesh_mask_prepass,
+    ) in &views_3d
     {
         if !opaque_3d_prepass_phases.contains_key(&entity)
             && !alpha_mask_3d_prepass_phases.contains_key(&entity)
@@ -938,6 +972,28 @@ pub fn prepare_prepass_textures(
                 .clone()
         });
 
+        let cached_mesh_mask_texture = mesh_mask_prepass.then(|| {
+            mesh_mask_textures
+                .entry(camera.target.clone())
+                .or_insert_with(|| {
+                    texture_cache.get(
+                        &render_device,
+                        TextureDescriptor {
+                            label: Some("prepass_mesh_mask_texture"),
+                            size,
+                            mip_level_count: 1,
+                            sample_count: msaa.samples(),
+                            dimension: TextureDimension::D2,
+                            format: MESH_MASK_PREPASS_FORMAT,
+                            usage: TextureUsages::RENDER_ATTACHMENT
+                                | TextureUsages::TEXTURE_BINDING,
+                            view_formats: &[],
+                        },
+                    )
+                })
+                .clone()
+        });
+
         commands.entity(entity).insert(ViewPrepassTextures {
             depth: cached_depth_texture
                 .map(|t| ColorAttachment::new(t, None, Some(LinearRgba::BLACK))),
@@ -952,6 +1008,8 @@ pub fn prepare_prepass_textures(
                 .map(|t| ColorAttachment::new(t, None, Some(LinearRgba::BLACK))),
             deferred_lighting_pass_id: cached_deferred_lighting_pass_id_texture
                 .map(|t| ColorAttachment::new(t, None, Some(LinearRgba::BLACK))),
+            mesh_mask: cached_mesh_mask_texture
+                .map(|t| ColorAttachment::new(t, None, Some(LinearRgba::BLACK))),
             size,
         });
     }
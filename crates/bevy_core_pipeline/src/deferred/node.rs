@@ -5,7 +5,7 @@ use bevy_render::render_graph::ViewNode;
 use bevy_render::render_phase::{TrackedRenderPass, ViewBinnedRenderPhases};
 use bevy_render::render_resource::{CommandEncoderDescriptor, StoreOp};
 use bevy_render::{
-    camera::ExtractedCamera,
+    camera::{ExtractedCamera, MainPassResolutionOverride, Viewport},
     render_graph::{NodeRunError, RenderGraphContext},
     render_resource::RenderPassDescriptor,
     renderer::RenderContext,
@@ -30,13 +30,17 @@ impl ViewNode for DeferredGBufferPrepassNode {
         &'static ExtractedCamera,
         &'static ViewDepthTexture,
         &'static ViewPrepassTextures,
+        Option<&'static MainPassResolutionOverride>,
     );
 
     fn run<'w>(
         &self,
         graph: &mut RenderGraphContext,
         render_context: &mut RenderContext<'w>,
-        (view, camera, view_depth_texture, view_prepass_textures): QueryItem<'w, Self::ViewQuery>,
+        (view, camera, view_depth_texture, view_prepass_textures, resolution_override): QueryItem<
+            'w,
+            Self::ViewQuery,
+        >,
         world: &'w World,
     ) -> Result<(), NodeRunError> {
         let (Some(opaque_deferred_phases), Some(alpha_mask_deferred_phases)) = (
@@ -112,6 +116,13 @@ impl ViewNode for DeferredGBufferPrepassNode {
                 .map(|deferred_lighting_pass_id| deferred_lighting_pass_id.get_attachment()),
         );
 
+        color_attachments.push(
+            view_prepass_textures
+                .mesh_mask
+                .as_ref()
+                .map(|mesh_mask_texture| mesh_mask_texture.get_attachment()),
+        );
+
         // If all color attachments are none: clear the color attachment list so that no fragment shader is required
         if color_attachments.iter().all(Option::is_none) {
             color_attachments.clear();
@@ -139,8 +150,10 @@ impl ViewNode for DeferredGBufferPrepassNode {
                 occlusion_query_set: None,
             });
             let mut render_pass = TrackedRenderPass::new(&render_device, render_pass);
-            if let Some(viewport) = camera.viewport.as_ref() {
-                render_pass.set_camera_viewport(viewport);
+            if let Some(viewport) =
+                Viewport::from_viewport_and_override(camera.viewport.as_ref(), resolution_override)
+            {
+                render_pass.set_camera_viewport(&viewport);
             }
 
             // Opaque draws
@@ -13,6 +13,10 @@
 // simultaneously using multiple render targets to cut the total number of
 // passes down to two.
 //
+// The aperture blur isn't separable. It gathers samples from concentric rings
+// squashed into the shape of the aperture polygon, or weighted by a bokeh
+// texture, in a single pass.
+//
 // [1]: https://colinbarrebrisebois.com/2017/04/18/hexagonal-bokeh-blur-revisited-part-2-improved-2-pass-version/
 
 #import bevy_core_pipeline::fullscreen_vertex_shader::FullscreenVertexOutput
@@ -55,12 +59,15 @@ struct DepthOfFieldParams {
     /// [`DepthOfFieldSettings`] for more information.
     max_depth: f32,
 
+    /// The number of blades of the aperture, used by the aperture blur. Values
+    /// below 3 produce a circular aperture.
+    aperture_blade_count: u32,
+
+    /// The clockwise rotation of the aperture polygon in radians.
+    aperture_rotation: f32,
+
     /// Padding.
-    pad_a: u32,
-    /// Padding.
-    pad_b: u32,
-    /// Padding.
-    pad_c: u32,
+    pad: u32,
 }
 
 // The first bokeh pass outputs to two render targets. We declare them here.
@@ -89,6 +96,11 @@ struct DualOutput {
 @group(0) @binding(3) var color_texture_b: texture_2d<f32>;
 #endif  // DUAL_INPUT
 
+// The image of the aperture, used by the aperture blur in place of the polygon.
+#ifdef BOKEH_TEXTURE
+@group(0) @binding(3) var bokeh_texture: texture_2d<f32>;
+#endif  // BOKEH_TEXTURE
+
 // The global uniforms, representing data backed by buffers shared among all
 // views in the scene.
 
@@ -106,6 +118,8 @@ const SIN_NEG_FRAC_PI_6: f32 = -0.5;
 const COS_NEG_FRAC_PI_5_6: f32 = -0.8660254037844387;
 // sin(-150°), used for the bokeh blur.
 const SIN_NEG_FRAC_PI_5_6: f32 = -0.5;
+// 2π, used for the aperture blur.
+const TAU: f32 = 6.283185307179586;
 
 // Calculates and returns the diameter (not the radius) of the [circle of
 // confusion].
@@ -299,3 +313,77 @@ fn bokeh_pass_1(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
     return mix(output_0, output_1, 0.5);
 }
 #endif
+
+// Returns the distance from the center of the aperture polygon to its edge in
+// the direction `angle`, relative to the radius of its circumscribed circle.
+fn aperture_edge_distance(angle: f32) -> f32 {
+    if (dof_params.aperture_blade_count < 3u) {
+        return 1.0;
+    }
+
+    // Find the angle relative to the middle of the blade in this direction.
+    // The edge of a regular polygon is at a distance of cos(π / n) from its
+    // center in the middle of a side, which grows as 1 / cos(θ) away from it.
+    let sector = TAU / f32(dof_params.aperture_blade_count);
+    let relative_angle = angle - dof_params.aperture_rotation;
+    let blade_angle = relative_angle - sector * floor(relative_angle / sector) - 0.5 * sector;
+    return cos(0.5 * sector) / cos(blade_angle);
+}
+
+// Calculates the aperture blur, which gathers samples in the shape of the
+// aperture.
+@fragment
+fn aperture(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
+    let coc = calculate_circle_of_confusion(in.position);
+    let texture_size = vec2<f32>(textureDimensions(color_texture_a));
+    let uv = in.position.xy / texture_size;
+    let center = textureSampleLevel(color_texture_a, color_texture_sampler, uv, 0.0).rgb;
+
+    // Space the rings of samples roughly two texels apart, the bilinear
+    // filtering covering the texels in between.
+    let radius = coc * 0.5;
+    let ring_count = i32(ceil(radius * 0.5));
+    if (ring_count == 0) {
+        return vec4(center, 1.0);
+    }
+
+    // A tiny weight on the center sample keeps the result defined even if the
+    // bokeh texture is black everywhere.
+#ifdef BOKEH_TEXTURE
+    var weight_sum = textureSampleLevel(bokeh_texture, color_texture_sampler, vec2(0.5), 0.0).rgb
+        + vec3(0.0001);
+#else   // BOKEH_TEXTURE
+    var weight_sum = vec3(1.0);
+#endif  // BOKEH_TEXTURE
+    var sum = center * weight_sum;
+
+    for (var ring = 1; ring <= ring_count; ring += 1) {
+        // Keep the density of samples constant by adding 8 samples per ring,
+        // and stagger every other ring to avoid radial streaks.
+        let sample_count = ring * 8;
+        let ring_radius = f32(ring) / f32(ring_count);
+        for (var i = 0; i < sample_count; i += 1) {
+            let angle = TAU * (f32(i) + 0.5 * f32(ring & 1)) / f32(sample_count);
+            let direction = vec2(cos(angle), sin(angle));
+
+#ifdef BOKEH_TEXTURE
+            let offset = direction * ring_radius;
+            let weight = textureSampleLevel(
+                bokeh_texture, color_texture_sampler, offset * 0.5 + 0.5, 0.0).rgb;
+#else   // BOKEH_TEXTURE
+            // Squash the rings into the aperture polygon.
+            let offset = direction * ring_radius * aperture_edge_distance(angle);
+            let weight = vec3(1.0);
+#endif  // BOKEH_TEXTURE
+
+            // A point of light spreads over the aperture shape around it, so a
+            // fragment gathers it from the opposite direction.
+            let sample_uv = uv - offset * radius / texture_size;
+            sum += textureSampleLevel(color_texture_a, color_texture_sampler, sample_uv, 0.0).rgb
+                * weight;
+            weight_sum += weight;
+        }
+    }
+
+    return vec4(sum / weight_sum, 1.0);
+}
@@ -28,6 +28,7 @@ use bevy_ecs::{
 use bevy_render::{
     camera::{PhysicalCameraParameters, Projection},
     extract_component::{ComponentUniforms, DynamicUniformIndex, UniformComponentPlugin},
+    render_asset::RenderAssets,
     render_graph::{
         NodeRunError, RenderGraphApp as _, RenderGraphContext, ViewNode, ViewNodeRunner,
     },
@@ -43,7 +44,7 @@ use bevy_render::{
         TextureDescriptor, TextureDimension, TextureFormat, TextureSampleType, TextureUsages,
     },
     renderer::{RenderContext, RenderDevice},
-    texture::{BevyDefault, CachedTexture, TextureCache},
+    texture::{BevyDefault, CachedTexture, GpuImage, Image, TextureCache},
     view::{
         prepare_view_targets, ExtractedView, Msaa, ViewDepthTexture, ViewTarget, ViewUniform,
         ViewUniformOffset, ViewUniforms,
@@ -51,7 +52,7 @@ use bevy_render::{
     Extract, ExtractSchedule, Render, RenderApp, RenderSet,
 };
 use bevy_utils::{info_once, prelude::default, warn_once};
-use smallvec::SmallVec;
+use smallvec::{smallvec, SmallVec};
 
 use crate::{
     core_3d::{
@@ -67,7 +68,7 @@ const DOF_SHADER_HANDLE: Handle<Shader> = Handle::weak_from_u128(203186118073921
 pub struct DepthOfFieldPlugin;
 
 /// Depth of field settings.
-#[derive(Component, Clone, Copy)]
+#[derive(Component, Clone)]
 pub struct DepthOfFieldSettings {
     /// The appearance of the effect.
     pub mode: DepthOfFieldMode,
@@ -83,10 +84,16 @@ pub struct DepthOfFieldSettings {
     /// [image sensor format]: https://en.wikipedia.org/wiki/Image_sensor_format
     ///
     /// [Super 35]: https://en.wikipedia.org/wiki/Super_35
+    ///
+    /// This is ignored if the camera has [`PhysicalCameraParameters`], whose
+    /// sensor height is used instead.
     pub sensor_height: f32,
 
     /// Along with the focal length, controls how much objects not in focus are
     /// blurred.
+    ///
+    /// This is ignored if the camera has [`PhysicalCameraParameters`], whose
+    /// aperture is used instead.
     pub aperture_f_stops: f32,
 
     /// The maximum diameter, in pixels, that we allow a circle of confusion to be.
@@ -107,6 +114,23 @@ pub struct DepthOfFieldSettings {
     /// this value can be used to essentially adjust how "far away" the skybox
     /// or background are.
     pub max_depth: f32,
+
+    /// The number of blades of the lens diaphragm, which gives the bokeh of
+    /// [`DepthOfFieldMode::Aperture`] its polygonal shape.
+    ///
+    /// Values below 3 produce a circular aperture. The default is 6.
+    pub aperture_blade_count: u32,
+
+    /// The clockwise rotation of the aperture polygon in radians.
+    pub aperture_rotation: f32,
+
+    /// An image of the aperture used as the bokeh shape of
+    /// [`DepthOfFieldMode::Aperture`], replacing the polygon.
+    ///
+    /// The color of each texel weights the light scattered in its direction,
+    /// so colored or uneven textures can simulate lens imperfections. The
+    /// image is stretched to fit the circle of confusion.
+    pub bokeh_texture: Option<Handle<Image>>,
 }
 
 /// Controls the appearance of the effect.
@@ -122,6 +146,20 @@ pub enum DepthOfFieldMode {
     /// [Wikipedia's article on *bokeh*]: https://en.wikipedia.org/wiki/Bokeh
     Bokeh,
 
+    /// A simulation that gathers samples in the shape of the lens aperture,
+    /// producing polygonal bokeh.
+    ///
+    /// The shape is controlled by
+    /// [`DepthOfFieldSettings::aperture_blade_count`] and
+    /// [`DepthOfFieldSettings::aperture_rotation`], or by
+    /// [`DepthOfFieldSettings::bokeh_texture`] if present.
+    ///
+    /// This is the most flexible mode, but also the slowest: its cost grows
+    /// with the square of the circle of confusion, so consider lowering
+    /// [`DepthOfFieldSettings::max_circle_of_confusion_diameter`] when using
+    /// it.
+    Aperture,
+
     /// A faster simulation, in which out-of-focus areas are simply blurred.
     ///
     /// This is less accurate to actual lens behavior and is generally less
@@ -159,12 +197,16 @@ pub struct DepthOfFieldUniform {
     /// [`DepthOfFieldSettings`] for more information.
     max_depth: f32,
 
+    /// The number of blades of the aperture. See the comment in
+    /// [`DepthOfFieldSettings`] for more information.
+    aperture_blade_count: u32,
+
+    /// The rotation of the aperture in radians. See the comment in
+    /// [`DepthOfFieldSettings`] for more information.
+    aperture_rotation: f32,
+
     /// Padding.
-    pad_a: u32,
-    /// Padding.
-    pad_b: u32,
-    /// Padding.
-    pad_c: u32,
+    pad: u32,
 }
 
 /// A key that uniquely identifies depth of field pipelines.
@@ -176,6 +218,8 @@ pub struct DepthOfFieldPipelineKey {
     hdr: bool,
     /// Whether the render target is multisampled.
     multisample: bool,
+    /// Whether the aperture pass uses a bokeh texture.
+    bokeh_texture: bool,
 }
 
 /// Identifies a specific depth of field render pass.
@@ -189,6 +233,8 @@ enum DofPass {
     BokehPass0,
     /// The second bokeh pass: two diagonals.
     BokehPass1,
+    /// The single pass gathering samples in the shape of the aperture.
+    Aperture,
 }
 
 impl Plugin for DepthOfFieldPlugin {
@@ -272,6 +318,10 @@ pub enum DepthOfFieldPipelines {
         pass_0: CachedRenderPipelineId,
         pass_1: CachedRenderPipelineId,
     },
+    Aperture {
+        pipeline: CachedRenderPipelineId,
+        bokeh_texture: bool,
+    },
 }
 
 struct DepthOfFieldPipelineRenderInfo {
@@ -280,6 +330,7 @@ struct DepthOfFieldPipelineRenderInfo {
     pipeline: CachedRenderPipelineId,
     is_dual_input: bool,
     is_dual_output: bool,
+    uses_bokeh_texture: bool,
 }
 
 /// The extra texture used as the second render target for the hexagonal bokeh
@@ -300,6 +351,13 @@ pub struct ViewDepthOfFieldBindGroupLayouts {
     ///
     /// This will only be present if bokeh is in use.
     dual_input: Option<BindGroupLayout>,
+
+    /// The bind group layout for the aperture pass when it samples a bokeh
+    /// texture, which takes the bokeh texture as an extra input.
+    ///
+    /// This will only be present if the aperture mode with a bokeh texture is
+    /// in use.
+    bokeh_texture: Option<BindGroupLayout>,
 }
 
 /// Information needed to specialize the pipeline corresponding to a pass of the
@@ -321,6 +379,7 @@ impl ViewNode for DepthOfFieldNode {
         Read<ViewDepthOfFieldBindGroupLayouts>,
         Read<DynamicUniformIndex<DepthOfFieldUniform>>,
         Option<Read<AuxiliaryDepthOfFieldTexture>>,
+        Read<DepthOfFieldSettings>,
     );
 
     fn run<'w>(
@@ -335,16 +394,19 @@ impl ViewNode for DepthOfFieldNode {
             view_bind_group_layouts,
             dof_settings_uniform_index,
             auxiliary_dof_texture,
+            dof_settings,
         ): QueryItem<'w, Self::ViewQuery>,
         world: &'w World,
     ) -> Result<(), NodeRunError> {
         let pipeline_cache = world.resource::<PipelineCache>();
         let view_uniforms = world.resource::<ViewUniforms>();
         let global_bind_group = world.resource::<DepthOfFieldGlobalBindGroup>();
+        let images = world.resource::<RenderAssets<GpuImage>>();
 
-        // We can be in either Gaussian blur or bokeh mode here. Both modes are
-        // similar, consisting of two passes each. We factor out the information
-        // specific to each pass into
+        // We can be in Gaussian blur, bokeh or aperture mode here. The first
+        // two modes are similar, consisting of two passes each, while the
+        // aperture mode consists of a single pass. We factor out the
+        // information specific to each pass into
         // [`DepthOfFieldPipelines::pipeline_render_info`].
         for pipeline_render_info in view_pipelines.pipeline_render_info().iter() {
             let (Some(render_pipeline), Some(view_uniforms_binding), Some(global_bind_group)) = (
@@ -379,6 +441,26 @@ impl ViewNode for DepthOfFieldNode {
                         &auxiliary_dof_texture.default_view,
                     )),
                 )
+            } else if pipeline_render_info.uses_bokeh_texture {
+                let (Some(bokeh_texture), Some(bokeh_texture_bind_group_layout)) = (
+                    dof_settings
+                        .bokeh_texture
+                        .as_ref()
+                        .and_then(|bokeh_texture| images.get(bokeh_texture)),
+                    view_bind_group_layouts.bokeh_texture.as_ref(),
+                ) else {
+                    return Ok(());
+                };
+                render_context.render_device().create_bind_group(
+                    Some(pipeline_render_info.view_bind_group_label),
+                    bokeh_texture_bind_group_layout,
+                    &BindGroupEntries::sequential((
+                        view_uniforms_binding,
+                        view_depth_texture.view(),
+                        postprocess.source,
+                        &bokeh_texture.texture_view,
+                    )),
+                )
             } else {
                 render_context.render_device().create_bind_group(
                     Some(pipeline_render_info.view_bind_group_label),
@@ -453,6 +535,9 @@ impl Default for DepthOfFieldSettings {
             max_circle_of_confusion_diameter: 64.0,
             max_depth: f32::INFINITY,
             mode: DepthOfFieldMode::Bokeh,
+            aperture_blade_count: 6,
+            aperture_rotation: 0.0,
+            bokeh_texture: None,
         }
     }
 }
@@ -468,6 +553,9 @@ impl DepthOfFieldSettings {
     ///
     /// All fields of the returned [`DepthOfFieldSettings`] other than
     /// `focal_length` and `aperture_f_stops` are set to their default values.
+    ///
+    /// To keep both effects in sync as the parameters change, add the
+    /// [`PhysicalCameraParameters`] to the camera as a component instead.
     pub fn from_physical_camera(camera: &PhysicalCameraParameters) -> DepthOfFieldSettings {
         DepthOfFieldSettings {
             sensor_height: camera.sensor_height,
@@ -520,6 +608,12 @@ pub fn prepare_depth_of_field_view_bind_group_layouts(
     render_device: Res<RenderDevice>,
 ) {
     for (view, dof_settings) in view_targets.iter() {
+        let depth_texture = if *msaa != Msaa::Off {
+            texture_depth_2d_multisampled()
+        } else {
+            texture_depth_2d()
+        };
+
         // Create the bind group layout for the passes that take one input.
         let single_input = render_device.create_bind_group_layout(
             Some("depth of field bind group layout (single input)"),
@@ -527,11 +621,7 @@ pub fn prepare_depth_of_field_view_bind_group_layouts(
                 ShaderStages::FRAGMENT,
                 (
                     uniform_buffer::<ViewUniform>(true),
-                    if *msaa != Msaa::Off {
-                        texture_depth_2d_multisampled()
-                    } else {
-                        texture_depth_2d()
-                    },
+                    depth_texture,
                     texture_2d(TextureSampleType::Float { filterable: true }),
                 ),
             ),
@@ -540,18 +630,14 @@ pub fn prepare_depth_of_field_view_bind_group_layouts(
         // If needed, create the bind group layout for the second bokeh pass,
         // which takes two inputs. We only need to do this if bokeh is in use.
         let dual_input = match dof_settings.mode {
-            DepthOfFieldMode::Gaussian => None,
+            DepthOfFieldMode::Gaussian | DepthOfFieldMode::Aperture => None,
             DepthOfFieldMode::Bokeh => Some(render_device.create_bind_group_layout(
                 Some("depth of field bind group layout (dual input)"),
                 &BindGroupLayoutEntries::sequential(
                     ShaderStages::FRAGMENT,
                     (
                         uniform_buffer::<ViewUniform>(true),
-                        if *msaa != Msaa::Off {
-                            texture_depth_2d_multisampled()
-                        } else {
-                            texture_depth_2d()
-                        },
+                        depth_texture,
                         texture_2d(TextureSampleType::Float { filterable: true }),
                         texture_2d(TextureSampleType::Float { filterable: true }),
                     ),
@@ -559,11 +645,31 @@ pub fn prepare_depth_of_field_view_bind_group_layouts(
             )),
         };
 
+        // Likewise, the aperture pass only needs an extra input if it samples a
+        // bokeh texture.
+        let bokeh_texture = (dof_settings.mode == DepthOfFieldMode::Aperture
+            && dof_settings.bokeh_texture.is_some())
+        .then(|| {
+            render_device.create_bind_group_layout(
+                Some("depth of field bind group layout (bokeh texture)"),
+                &BindGroupLayoutEntries::sequential(
+                    ShaderStages::FRAGMENT,
+                    (
+                        uniform_buffer::<ViewUniform>(true),
+                        depth_texture,
+                        texture_2d(TextureSampleType::Float { filterable: true }),
+                        texture_2d(TextureSampleType::Float { filterable: true }),
+                    ),
+                ),
+            )
+        });
+
         commands
             .entity(view)
             .insert(ViewDepthOfFieldBindGroupLayouts {
                 single_input,
                 dual_input,
+                bokeh_texture,
             });
     }
 }
@@ -654,6 +760,7 @@ pub fn prepare_depth_of_field_pipelines(
         &DepthOfFieldSettings,
         &ViewDepthOfFieldBindGroupLayouts,
     )>,
+    images: Res<RenderAssets<GpuImage>>,
 ) {
     for (entity, view, dof_settings, view_bind_group_layouts) in view_targets.iter() {
         let dof_pipeline = DepthOfFieldPipeline {
@@ -676,6 +783,7 @@ pub fn prepare_depth_of_field_pipelines(
                             DepthOfFieldPipelineKey {
                                 hdr,
                                 multisample,
+                                bokeh_texture: false,
                                 pass: DofPass::GaussianHorizontal,
                             },
                         ),
@@ -685,6 +793,7 @@ pub fn prepare_depth_of_field_pipelines(
                             DepthOfFieldPipelineKey {
                                 hdr,
                                 multisample,
+                                bokeh_texture: false,
                                 pass: DofPass::GaussianVertical,
                             },
                         ),
@@ -701,6 +810,7 @@ pub fn prepare_depth_of_field_pipelines(
                             DepthOfFieldPipelineKey {
                                 hdr,
                                 multisample,
+                                bokeh_texture: false,
                                 pass: DofPass::BokehPass0,
                             },
                         ),
@@ -710,11 +820,36 @@ pub fn prepare_depth_of_field_pipelines(
                             DepthOfFieldPipelineKey {
                                 hdr,
                                 multisample,
+                                bokeh_texture: false,
                                 pass: DofPass::BokehPass1,
                             },
                         ),
                     });
             }
+
+            DepthOfFieldMode::Aperture => {
+                // Fall back to the polygonal aperture until the bokeh texture
+                // has loaded.
+                let bokeh_texture = dof_settings
+                    .bokeh_texture
+                    .as_ref()
+                    .is_some_and(|bokeh_texture| images.get(bokeh_texture).is_some());
+                commands
+                    .entity(entity)
+                    .insert(DepthOfFieldPipelines::Aperture {
+                        pipeline: pipelines.specialize(
+                            &pipeline_cache,
+                            &dof_pipeline,
+                            DepthOfFieldPipelineKey {
+                                hdr,
+                                multisample,
+                                bokeh_texture,
+                                pass: DofPass::Aperture,
+                            },
+                        ),
+                        bokeh_texture,
+                    });
+            }
         }
     }
 }
@@ -741,6 +876,25 @@ impl SpecializedRenderPipeline for DepthOfFieldPipeline {
                 // Gaussian blurs take only a single input and output.
                 layout.push(self.view_bind_group_layouts.single_input.clone());
             }
+            DofPass::Aperture if key.bokeh_texture => {
+                // The aperture pass takes the bokeh texture alongside its
+                // input.
+                let bokeh_texture_bind_group_layout = self
+                    .view_bind_group_layouts
+                    .bokeh_texture
+                    .as_ref()
+                    .expect(
+                        "Bokeh texture depth of field bind group should have been created by now",
+                    )
+                    .clone();
+                layout.push(bokeh_texture_bind_group_layout);
+                shader_defs.push("BOKEH_TEXTURE".into());
+            }
+            DofPass::Aperture => {
+                // Without a bokeh texture, the aperture pass takes only a
+                // single input and output.
+                layout.push(self.view_bind_group_layouts.single_input.clone());
+            }
             DofPass::BokehPass0 => {
                 // The first bokeh pass takes one input and produces two outputs.
                 layout.push(self.view_bind_group_layouts.single_input.clone());
@@ -783,6 +937,7 @@ impl SpecializedRenderPipeline for DepthOfFieldPipeline {
                     DofPass::GaussianVertical => "gaussian_vertical".into(),
                     DofPass::BokehPass0 => "bokeh_pass_0".into(),
                     DofPass::BokehPass1 => "bokeh_pass_1".into(),
+                    DofPass::Aperture => "aperture".into(),
                 },
                 targets,
             }),
@@ -793,7 +948,14 @@ impl SpecializedRenderPipeline for DepthOfFieldPipeline {
 /// Extracts all [`DepthOfFieldSettings`] components into the render world.
 fn extract_depth_of_field_settings(
     mut commands: Commands,
-    mut query: Extract<Query<(Entity, &DepthOfFieldSettings, &Projection)>>,
+    mut query: Extract<
+        Query<(
+            Entity,
+            &DepthOfFieldSettings,
+            &Projection,
+            Option<&PhysicalCameraParameters>,
+        )>,
+    >,
 ) {
     if !DEPTH_TEXTURE_SAMPLING_SUPPORTED {
         info_once!(
@@ -802,28 +964,36 @@ fn extract_depth_of_field_settings(
         return;
     }
 
-    for (entity, dof_settings, projection) in query.iter_mut() {
+    for (entity, dof_settings, projection, physical_camera_parameters) in query.iter_mut() {
         // Depth of field is nonsensical without a perspective projection.
         let Projection::Perspective(ref perspective_projection) = *projection else {
             continue;
         };
 
-        let focal_length =
-            calculate_focal_length(dof_settings.sensor_height, perspective_projection.fov);
+        // A physical camera on the view takes precedence, so that the exposure
+        // and the depth of field are computed from the same lens.
+        let (sensor_height, aperture_f_stops) = match physical_camera_parameters {
+            Some(physical_camera_parameters) => (
+                physical_camera_parameters.sensor_height,
+                physical_camera_parameters.aperture_f_stops,
+            ),
+            None => (dof_settings.sensor_height, dof_settings.aperture_f_stops),
+        };
+
+        let focal_length = calculate_focal_length(sensor_height, perspective_projection.fov);
 
         // Convert `DepthOfFieldSettings` to `DepthOfFieldUniform`.
         commands.get_or_spawn(entity).insert((
-            *dof_settings,
+            dof_settings.clone(),
             DepthOfFieldUniform {
                 focal_distance: dof_settings.focal_distance,
                 focal_length,
-                coc_scale_factor: focal_length * focal_length
-                    / (dof_settings.sensor_height * dof_settings.aperture_f_stops),
+                coc_scale_factor: focal_length * focal_length / (sensor_height * aperture_f_stops),
                 max_circle_of_confusion_diameter: dof_settings.max_circle_of_confusion_diameter,
                 max_depth: dof_settings.max_depth,
-                pad_a: 0,
-                pad_b: 0,
-                pad_c: 0,
+                aperture_blade_count: dof_settings.aperture_blade_count,
+                aperture_rotation: dof_settings.aperture_rotation,
+                pad: 0,
             },
         ));
     }
@@ -837,20 +1007,21 @@ pub fn calculate_focal_length(sensor_height: f32, fov: f32) -> f32 {
 }
 
 impl DepthOfFieldPipelines {
-    /// Populates the information that the `DepthOfFieldNode` needs for the two
+    /// Populates the information that the `DepthOfFieldNode` needs for the
     /// depth of field render passes.
-    fn pipeline_render_info(&self) -> [DepthOfFieldPipelineRenderInfo; 2] {
+    fn pipeline_render_info(&self) -> SmallVec<[DepthOfFieldPipelineRenderInfo; 2]> {
         match *self {
             DepthOfFieldPipelines::Gaussian {
                 horizontal: horizontal_pipeline,
                 vertical: vertical_pipeline,
-            } => [
+            } => smallvec![
                 DepthOfFieldPipelineRenderInfo {
                     pass_label: "depth of field pass (horizontal Gaussian)",
                     view_bind_group_label: "depth of field view bind group (horizontal Gaussian)",
                     pipeline: horizontal_pipeline,
                     is_dual_input: false,
                     is_dual_output: false,
+                    uses_bokeh_texture: false,
                 },
                 DepthOfFieldPipelineRenderInfo {
                     pass_label: "depth of field pass (vertical Gaussian)",
@@ -858,19 +1029,21 @@ impl DepthOfFieldPipelines {
                     pipeline: vertical_pipeline,
                     is_dual_input: false,
                     is_dual_output: false,
+                    uses_bokeh_texture: false,
                 },
             ],
 
             DepthOfFieldPipelines::Bokeh {
                 pass_0: pass_0_pipeline,
                 pass_1: pass_1_pipeline,
-            } => [
+            } => smallvec![
                 DepthOfFieldPipelineRenderInfo {
                     pass_label: "depth of field pass (bokeh pass 0)",
                     view_bind_group_label: "depth of field view bind group (bokeh pass 0)",
                     pipeline: pass_0_pipeline,
                     is_dual_input: false,
                     is_dual_output: true,
+                    uses_bokeh_texture: false,
                 },
                 DepthOfFieldPipelineRenderInfo {
                     pass_label: "depth of field pass (bokeh pass 1)",
@@ -878,8 +1051,21 @@ impl DepthOfFieldPipelines {
                     pipeline: pass_1_pipeline,
                     is_dual_input: true,
                     is_dual_output: false,
+                    uses_bokeh_texture: false,
                 },
             ],
+
+            DepthOfFieldPipelines::Aperture {
+                pipeline,
+                bokeh_texture,
+            } => smallvec![DepthOfFieldPipelineRenderInfo {
+                pass_label: "depth of field pass (aperture)",
+                view_bind_group_label: "depth of field view bind group (aperture)",
+                pipeline,
+                is_dual_input: false,
+                is_dual_output: false,
+                uses_bokeh_texture: bokeh_texture,
+            }],
         }
     }
 }
@@ -0,0 +1,329 @@
+//! An integration point for temporal upscalers implemented outside of Bevy, such as DLSS,
+//! `XeSS` or FSR 2.
+//!
+//! Adding [`ExternalUpscaler`] to a 3D camera renders its main passes at a lower resolution, with a
+//! jittered projection, and leaves the upscaling to a render graph node added by the crate that
+//! wraps the upscaler. The node goes between [`Node3d::StartMainPassUpscaling`] and
+//! [`Node3d::EndMainPassUpscaling`], and reads its inputs and output through the
+//! [`ExternalUpscalerInputs`] query:
+//!
+//! ```no_run
+//! # use bevy_core_pipeline::{
+//! #     core_3d::graph::{Core3d, Node3d},
+//! #     external_upscaler::ExternalUpscalerInputs,
+//! # };
+//! # use bevy_ecs::{query::QueryItem, world::World};
+//! # use bevy_render::{
+//! #     render_graph::{
+//! #         NodeRunError, RenderGraphApp, RenderGraphContext, RenderLabel, ViewNode, ViewNodeRunner,
+//! #     },
+//! #     renderer::RenderContext,
+//! #     RenderApp,
+//! # };
+//! #[derive(Default)]
+//! struct MyUpscalerNode;
+//!
+//! impl ViewNode for MyUpscalerNode {
+//!     type ViewQuery = ExternalUpscalerInputs;
+//!
+//!     fn run(
+//!         &self,
+//!         _graph: &mut RenderGraphContext,
+//!         render_context: &mut RenderContext,
+//!         inputs: QueryItem<Self::ViewQuery>,
+//!         _world: &World,
+//!     ) -> Result<(), NodeRunError> {
+//!         let color = inputs.color();
+//!         let depth = inputs.depth();
+//!         let Some(motion_vectors) = inputs.motion_vectors() else {
+//!             return Ok(());
+//!         };
+//!         // Upscale `color.source` to `color.destination`, with the render and output sizes,
+//!         // the jitter, and the reset flag of `inputs.upscaler`.
+//!         Ok(())
+//!     }
+//! }
+//!
+//! #[derive(Debug, Hash, PartialEq, Eq, Clone, RenderLabel)]
+//! struct MyUpscaler;
+//!
+//! # let mut app = bevy_app::App::new();
+//! let render_app = app.sub_app_mut(RenderApp);
+//! render_app
+//!     .add_render_graph_node::<ViewNodeRunner<MyUpscalerNode>>(Core3d, MyUpscaler)
+//!     .add_render_graph_edges(
+//!         Core3d,
+//!         (Node3d::StartMainPassUpscaling, MyUpscaler, Node3d::EndMainPassUpscaling),
+//!     );
+//! ```
+//!
+//! [`Node3d::StartMainPassUpscaling`]: crate::core_3d::graph::Node3d::StartMainPassUpscaling
+//! [`Node3d::EndMainPassUpscaling`]: crate::core_3d::graph::Node3d::EndMainPassUpscaling
+
+use crate::{auto_exposure::AutoExposureBuffers, core_3d::Camera3d, prepass::ViewPrepassTextures};
+use bevy_app::prelude::*;
+use bevy_core::FrameCount;
+use bevy_ecs::{prelude::*, query::QueryData};
+use bevy_math::{UVec2, Vec2};
+use bevy_reflect::{std_traits::ReflectDefault, Reflect};
+use bevy_render::{
+    camera::{Camera, ExtractedCamera, MainPassResolutionOverride, MipBias, TemporalJitter},
+    render_resource::{Buffer, TextureUsages, TextureView},
+    view::{ExtractedView, PostProcessWrite, ViewDepthTexture, ViewTarget},
+    ExtractSchedule, MainWorld, Render, RenderApp, RenderSet,
+};
+
+/// Renders the main passes of a 3D camera at a lower resolution, for an upscaler implemented
+/// outside of Bevy to upscale them.
+///
+/// See the [module documentation](self) for how to plug an upscaler in. Upscalers usually need
+/// motion vectors, so the camera should also have a
+/// [`MotionVectorPrepass`](crate::prepass::MotionVectorPrepass) and a
+/// [`DepthPrepass`](crate::prepass::DepthPrepass).
+///
+/// Bevy jitters the projection of the camera with a Halton sequence, and adds a [`MipBias`] of
+/// `log2(render_scale) - 1.0` to the camera when it doesn't have one, as temporal upscalers
+/// expect.
+///
+/// The effects that read the depth or the motion vectors of the main passes after they are
+/// upscaled, such as motion blur, TAA and depth of field, don't support the lower resolution and
+/// shouldn't be used with an external upscaler.
+#[derive(Component, Reflect, Clone, Debug)]
+#[reflect(Component, Default)]
+pub struct ExternalUpscaler {
+    /// The resolution of the main passes, relative to the size of the viewport of the camera.
+    ///
+    /// Values of 1.0 and above render at full resolution, for upscalers that only anti-alias.
+    ///
+    /// The default value is 0.67.
+    pub render_scale: f32,
+    /// The number of jitter offsets the projection cycles through.
+    ///
+    /// When `None`, it's `ceil(8.0 / render_scale²)`, as recommended by FSR 2, so that each output
+    /// pixel is covered by about eight samples.
+    ///
+    /// The default value is `None`.
+    pub jitter_phase_count: Option<u32>,
+    /// Set to true to tell the upscaler to discard its history, such as after camera cuts.
+    ///
+    /// After setting this to true, it will automatically be toggled back to false at the end of
+    /// the frame.
+    ///
+    /// The default value is `true`.
+    pub reset: bool,
+}
+
+impl Default for ExternalUpscaler {
+    fn default() -> Self {
+        ExternalUpscaler {
+            render_scale: 0.67,
+            jitter_phase_count: None,
+            reset: true,
+        }
+    }
+}
+
+/// The state of an [`ExternalUpscaler`] for the current frame, in the render world.
+#[derive(Component, Clone, Debug)]
+pub struct ViewExternalUpscaler {
+    /// The resolution of the main passes, in pixels.
+    ///
+    /// The main passes are rendered to the top left corner of the viewport.
+    pub render_size: UVec2,
+    /// The size of the viewport that the upscaler outputs to, in pixels.
+    pub output_size: UVec2,
+    /// The top left corner of the viewport in the textures of the view, in pixels.
+    pub origin: UVec2,
+    /// The jitter of the projection this frame, in pixels of the main passes.
+    ///
+    /// The jitter moves the scene by `-jitter` pixels: a pixel of the main passes holds the
+    /// scene `jitter` pixels away from its center.
+    pub jitter: Vec2,
+    /// The number of jitter offsets the projection cycles through.
+    pub jitter_phase_count: u32,
+    /// Whether the upscaler should discard its history this frame.
+    pub reset: bool,
+}
+
+/// The inputs and output of an external upscaler, for the [`ViewNode`] of the upscaler.
+///
+/// [`ViewNode`]: bevy_render::render_graph::ViewNode
+#[derive(QueryData)]
+pub struct ExternalUpscalerInputs {
+    pub entity: Entity,
+    pub upscaler: &'static ViewExternalUpscaler,
+    pub camera: &'static ExtractedCamera,
+    pub target: &'static ViewTarget,
+    pub depth_texture: &'static ViewDepthTexture,
+    pub prepass_textures: &'static ViewPrepassTextures,
+}
+
+impl ExternalUpscalerInputsItem<'_> {
+    /// Returns the main texture of the view, as the color rendered by the main passes in
+    /// `source`, and the texture to write the upscaled color to in `destination`.
+    ///
+    /// The color hasn't been tonemapped yet. The upscaler has to fill the whole viewport of
+    /// `destination`, since it becomes the main texture of the view. Like
+    /// [`ViewTarget::post_process_write`], each call swaps the main texture, so this must only be
+    /// called once per frame.
+    pub fn color(&self) -> PostProcessWrite<'_> {
+        self.target.post_process_write()
+    }
+
+    /// Returns the depth texture of the main passes.
+    pub fn depth(&self) -> &TextureView {
+        self.depth_texture.view()
+    }
+
+    /// Returns the motion vectors of the main passes, if the camera has a
+    /// [`MotionVectorPrepass`](crate::prepass::MotionVectorPrepass).
+    ///
+    /// The motion vectors are in UV units of the viewport, from the current frame to the previous
+    /// one, and aren't jittered.
+    pub fn motion_vectors(&self) -> Option<&TextureView> {
+        self.prepass_textures
+            .motion_vectors
+            .as_ref()
+            .map(|motion_vectors| &motion_vectors.texture.default_view)
+    }
+
+    /// Returns the exposure of the camera, which the color is multiplied by.
+    ///
+    /// This doesn't include the exposure of the color grading, nor the one of
+    /// [`AutoExposureSettings`](crate::auto_exposure::AutoExposureSettings), which are only
+    /// applied by the tonemapping, after the upscaling. See [`Self::auto_exposure`].
+    pub fn exposure(&self) -> f32 {
+        self.camera.exposure
+    }
+
+    /// Returns the storage buffer holding the exposure compensation computed by
+    /// [`AutoExposureSettings`](crate::auto_exposure::AutoExposureSettings) for this camera, if
+    /// it has any.
+    ///
+    /// The buffer holds a single `f32`, in EV, which the tonemapping adds to the exposure of the
+    /// color grading. Since auto exposure runs after the upscaling, the buffer holds the value of
+    /// the previous frame, which is what upscalers expect as their exposure input, as in
+    /// `exposure() * exp2(color_grading.exposure + auto_exposure)`.
+    pub fn auto_exposure<'w>(&self, world: &'w World) -> Option<&'w Buffer> {
+        world
+            .get_resource::<AutoExposureBuffers>()?
+            .buffers
+            .get(&self.entity)?
+            .state
+            .buffer()
+    }
+}
+
+/// Adds support for [`ExternalUpscaler`].
+///
+/// This is added by [`CorePipelinePlugin`](crate::CorePipelinePlugin), so crates that implement
+/// upscalers only have to add their render graph node.
+pub struct ExternalUpscalerPlugin;
+
+impl Plugin for ExternalUpscalerPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<ExternalUpscaler>();
+
+        let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+        render_app
+            .add_systems(ExtractSchedule, extract_external_upscalers)
+            .add_systems(
+                Render,
+                (
+                    prepare_external_upscaler_views.in_set(RenderSet::ManageViews),
+                    configure_external_upscaler_depth_textures.in_set(RenderSet::ManageViews),
+                ),
+            );
+    }
+}
+
+fn extract_external_upscalers(mut commands: Commands, mut main_world: ResMut<MainWorld>) {
+    let mut cameras_3d =
+        main_world.query_filtered::<(Entity, &Camera, &mut ExternalUpscaler), With<Camera3d>>();
+
+    for (entity, camera, mut upscaler) in cameras_3d.iter_mut(&mut main_world) {
+        let Some(viewport_size) = camera.physical_viewport_size() else {
+            continue;
+        };
+        if !camera.is_active || upscaler.render_scale <= 0.0 {
+            continue;
+        }
+
+        let render_size = (viewport_size.as_vec2() * upscaler.render_scale.min(1.0))
+            .round()
+            .as_uvec2()
+            .max(UVec2::ONE);
+        commands
+            .get_or_spawn(entity)
+            .insert((upscaler.clone(), MainPassResolutionOverride(render_size)));
+        upscaler.reset = false;
+    }
+}
+
+/// Jitters the views with an [`ExternalUpscaler`], and prepares their [`ViewExternalUpscaler`].
+fn prepare_external_upscaler_views(
+    mut commands: Commands,
+    frame_count: Res<FrameCount>,
+    views: Query<(
+        Entity,
+        &ExtractedView,
+        &ExternalUpscaler,
+        &MainPassResolutionOverride,
+        Option<&MipBias>,
+    )>,
+) {
+    for (entity, view, upscaler, resolution_override, mip_bias) in &views {
+        let render_scale = upscaler.render_scale.min(1.0);
+        let jitter_phase_count = upscaler
+            .jitter_phase_count
+            .unwrap_or_else(|| (8.0 / (render_scale * render_scale)).ceil() as u32)
+            .max(1);
+
+        // Halton sequence (2, 3) - 0.5, skipping i = 0
+        let index = frame_count.0 % jitter_phase_count + 1;
+        let jitter = Vec2::new(halton(index, 2), halton(index, 3)) - 0.5;
+
+        let mut entity_commands = commands.entity(entity);
+        entity_commands.insert((
+            TemporalJitter { offset: jitter },
+            ViewExternalUpscaler {
+                render_size: resolution_override.0,
+                output_size: UVec2::new(view.viewport.z, view.viewport.w),
+                origin: UVec2::new(view.viewport.x, view.viewport.y),
+                jitter,
+                jitter_phase_count,
+                reset: upscaler.reset,
+            },
+        ));
+
+        if mip_bias.is_none() {
+            entity_commands.insert(MipBias(render_scale.log2() - 1.0));
+        }
+    }
+}
+
+/// Returns the element of the Halton sequence of the given `base` at `index`.
+fn halton(mut index: u32, base: u32) -> f32 {
+    let mut fraction = 1.0;
+    let mut result = 0.0;
+    while index > 0 {
+        fraction /= base as f32;
+        result += fraction * (index % base) as f32;
+        index /= base;
+    }
+    result
+}
+
+/// Makes the depth textures of the cameras with [`ExternalUpscaler`] readable, so that the
+/// upscalers can read them.
+fn configure_external_upscaler_depth_textures(
+    mut view_targets: Query<&mut Camera3d, With<ExternalUpscaler>>,
+) {
+    for mut camera_3d in view_targets.iter_mut() {
+        let mut depth_texture_usages = TextureUsages::from(camera_3d.depth_texture_usages);
+        depth_texture_usages |= TextureUsages::TEXTURE_BINDING;
+        camera_3d.depth_texture_usages = depth_texture_usages.into();
+    }
+}
@@ -0,0 +1,245 @@
+// AMD FidelityFX Super Resolution 1.0 (FSR)
+//
+// Adapted from the reference implementation in `ffx_fsr1.h`, under the MIT license:
+// https://github.com/GPUOpen-Effects/FidelityFX-FSR
+//
+// Copyright (c) 2021 Advanced Micro Devices, Inc. All rights reserved.
+//
+// The `easu` pass upscales the low resolution image of the main passes, found in the top left
+// corner of the viewport, to the whole viewport, and the `rcas` pass sharpens the result.
+//
+// FSR expects a tonemapped image in the 0 to 1 range. The image of HDR views hasn't been
+// tonemapped yet, so `easu` applies a reversible tonemapping to its input, which keeps the bright
+// pixels from dominating the filters, `rcas` works on the tonemapped upscaled image, and reverses
+// the tonemapping of its output. LDR views were tonemapped by the main passes already.
+// https://gpuopen.com/learn/optimized-reversible-tonemapper-for-resolve
+
+#import bevy_core_pipeline::fullscreen_vertex_shader::FullscreenVertexOutput
+
+struct FsrSettings {
+    origin: vec2<u32>,
+    input_size: vec2<u32>,
+    output_size: vec2<u32>,
+    sharpness: f32,
+}
+
+@group(0) @binding(0) var input_texture: texture_2d<f32>;
+@group(0) @binding(1) var<uniform> settings: FsrSettings;
+
+// The limit of the negative lobe of RCAS, which keeps it from sharpening beyond what the
+// neighborhood of the pixel allows.
+const RCAS_LIMIT: f32 = 0.1875;
+
+fn max3(x: vec3<f32>) -> f32 {
+    return max(x.r, max(x.g, x.b));
+}
+
+fn tonemap(color: vec3<f32>) -> vec3<f32> {
+    return color / (1.0 + max3(color));
+}
+
+fn reverse_tonemap(color: vec3<f32>) -> vec3<f32> {
+    return color / max(1.0 - max3(color), 1e-4);
+}
+
+// Loads a pixel of the input, clamped to the rectangle that holds the input image.
+fn load(coords: vec2<i32>, min_coords: vec2<i32>, max_coords: vec2<i32>) -> vec3<f32> {
+    return textureLoad(input_texture, clamp(coords, min_coords, max_coords), 0).rgb;
+}
+
+// Loads a pixel of the low resolution image, tonemapped if it hasn't been yet.
+fn load_easu(coords: vec2<i32>, min_coords: vec2<i32>, max_coords: vec2<i32>) -> vec3<f32> {
+    let color = max(load(coords, min_coords, max_coords), vec3(0.0));
+#ifdef TONEMAP
+    return tonemap(color);
+#else
+    return color;
+#endif
+}
+
+// An approximation of the luma, times two.
+fn luma(color: vec3<f32>) -> f32 {
+    return color.b * 0.5 + (color.r * 0.5 + color.g);
+}
+
+// Accumulates the direction and length of the edge at one of the four pixels around the
+// position, weighted by its bilinear weight. The pixel is `c`, and its neighbors are laid out as:
+//
+//    a
+//  b c d
+//    e
+fn easu_set(
+    direction: ptr<function, vec2<f32>>,
+    edge_length: ptr<function, f32>,
+    weight: f32,
+    a: f32,
+    b: f32,
+    c: f32,
+    d: f32,
+    e: f32,
+) {
+    // The direction is the gradient across the pixel, and the length is how much of the local
+    // contrast the gradient accounts for, which is high for edges and low for noise.
+    let direction_x = d - b;
+    let length_x = saturate(abs(direction_x) / max(max(abs(d - c), abs(c - b)), 1e-5));
+    let direction_y = e - a;
+    let length_y = saturate(abs(direction_y) / max(max(abs(e - c), abs(c - a)), 1e-5));
+
+    *direction += vec2(direction_x, direction_y) * weight;
+    *edge_length += (length_x * length_x + length_y * length_y) * weight;
+}
+
+// Accumulates a tap of the filter, a Lanczos-like kernel stretched along the edge.
+fn easu_tap(
+    color_sum: ptr<function, vec3<f32>>,
+    weight_sum: ptr<function, f32>,
+    offset: vec2<f32>,
+    direction: vec2<f32>,
+    kernel_length: vec2<f32>,
+    lobe: f32,
+    clip: f32,
+    color: vec3<f32>,
+) {
+    // Rotate the offset into the frame of the edge, and scale it.
+    let v = vec2(
+        offset.x * direction.x + offset.y * direction.y,
+        offset.x * -direction.y + offset.y * direction.x,
+    ) * kernel_length;
+
+    // An approximation of the windowed Lanczos kernel, from the squared distance.
+    let distance_squared = min(dot(v, v), clip);
+    let base = 0.4 * distance_squared - 1.0;
+    let window = lobe * distance_squared - 1.0;
+    let weight = (1.5625 * base * base - 0.5625) * (window * window);
+
+    *color_sum += color * weight;
+    *weight_sum += weight;
+}
+
+// Edge adaptive spatial upsampling, with a 12 tap kernel around the position in the input:
+//
+//    b c
+//  e f g h
+//  i j k l
+//    n o
+@fragment
+fn easu(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
+    let origin = vec2<i32>(settings.origin);
+    let max_coords = origin + vec2<i32>(settings.input_size) - 1;
+
+    // The position in the input, relative to the center of the pixel `f`.
+    let scale = vec2<f32>(settings.input_size) / vec2<f32>(settings.output_size);
+    let position = (in.position.xy - vec2<f32>(settings.origin)) * scale - 0.5;
+    let base = floor(position);
+    let pp = position - base;
+    let f_coords = origin + vec2<i32>(base);
+
+    let b = load_easu(f_coords + vec2(0, -1), origin, max_coords);
+    let c = load_easu(f_coords + vec2(1, -1), origin, max_coords);
+    let e = load_easu(f_coords + vec2(-1, 0), origin, max_coords);
+    let f = load_easu(f_coords, origin, max_coords);
+    let g = load_easu(f_coords + vec2(1, 0), origin, max_coords);
+    let h = load_easu(f_coords + vec2(2, 0), origin, max_coords);
+    let i = load_easu(f_coords + vec2(-1, 1), origin, max_coords);
+    let j = load_easu(f_coords + vec2(0, 1), origin, max_coords);
+    let k = load_easu(f_coords + vec2(1, 1), origin, max_coords);
+    let l = load_easu(f_coords + vec2(2, 1), origin, max_coords);
+    let n = load_easu(f_coords + vec2(0, 2), origin, max_coords);
+    let o = load_easu(f_coords + vec2(1, 2), origin, max_coords);
+
+    let b_luma = luma(b);
+    let c_luma = luma(c);
+    let e_luma = luma(e);
+    let f_luma = luma(f);
+    let g_luma = luma(g);
+    let h_luma = luma(h);
+    let i_luma = luma(i);
+    let j_luma = luma(j);
+    let k_luma = luma(k);
+    let l_luma = luma(l);
+    let n_luma = luma(n);
+    let o_luma = luma(o);
+
+    // Find the direction and length of the edge from the four pixels around the position.
+    var direction = vec2(0.0);
+    var edge_length = 0.0;
+    easu_set(&direction, &edge_length, (1.0 - pp.x) * (1.0 - pp.y), b_luma, e_luma, f_luma, g_luma, j_luma);
+    easu_set(&direction, &edge_length, pp.x * (1.0 - pp.y), c_luma, f_luma, g_luma, h_luma, k_luma);
+    easu_set(&direction, &edge_length, (1.0 - pp.x) * pp.y, f_luma, i_luma, j_luma, k_luma, n_luma);
+    easu_set(&direction, &edge_length, pp.x * pp.y, g_luma, j_luma, k_luma, l_luma, o_luma);
+
+    // Normalize the direction, falling back to the horizontal axis when there is no edge.
+    let direction_length_squared = dot(direction, direction);
+    if direction_length_squared < 1.0 / 32768.0 {
+        direction = vec2(1.0, 0.0);
+    } else {
+        direction *= inverseSqrt(direction_length_squared);
+    }
+
+    // Shape the kernel: stretch it along diagonal edges, and narrow it across strong edges.
+    edge_length = edge_length * 0.5;
+    edge_length *= edge_length;
+    let stretch = dot(direction, direction) / max(abs(direction.x), abs(direction.y));
+    let kernel_length = vec2(1.0 + (stretch - 1.0) * edge_length, 1.0 - 0.5 * edge_length);
+    let lobe = 0.5 + ((1.0 / 4.0 - 0.04) - 0.5) * edge_length;
+    let clip = 1.0 / lobe;
+
+    var color_sum = vec3(0.0);
+    var weight_sum = 0.0;
+    easu_tap(&color_sum, &weight_sum, vec2(0.0, -1.0) - pp, direction, kernel_length, lobe, clip, b);
+    easu_tap(&color_sum, &weight_sum, vec2(1.0, -1.0) - pp, direction, kernel_length, lobe, clip, c);
+    easu_tap(&color_sum, &weight_sum, vec2(-1.0, 1.0) - pp, direction, kernel_length, lobe, clip, i);
+    easu_tap(&color_sum, &weight_sum, vec2(0.0, 1.0) - pp, direction, kernel_length, lobe, clip, j);
+    easu_tap(&color_sum, &weight_sum, vec2(0.0, 0.0) - pp, direction, kernel_length, lobe, clip, f);
+    easu_tap(&color_sum, &weight_sum, vec2(-1.0, 0.0) - pp, direction, kernel_length, lobe, clip, e);
+    easu_tap(&color_sum, &weight_sum, vec2(1.0, 1.0) - pp, direction, kernel_length, lobe, clip, k);
+    easu_tap(&color_sum, &weight_sum, vec2(2.0, 1.0) - pp, direction, kernel_length, lobe, clip, l);
+    easu_tap(&color_sum, &weight_sum, vec2(2.0, 0.0) - pp, direction, kernel_length, lobe, clip, h);
+    easu_tap(&color_sum, &weight_sum, vec2(1.0, 0.0) - pp, direction, kernel_length, lobe, clip, g);
+    easu_tap(&color_sum, &weight_sum, vec2(1.0, 2.0) - pp, direction, kernel_length, lobe, clip, o);
+    easu_tap(&color_sum, &weight_sum, vec2(0.0, 2.0) - pp, direction, kernel_length, lobe, clip, n);
+
+    // Remove the ringing of the negative lobes by clamping to the four nearest pixels.
+    let min_color = min(min(f, g), min(j, k));
+    let max_color = max(max(f, g), max(j, k));
+    let color = clamp(color_sum / weight_sum, min_color, max_color);
+
+    // Stays tonemapped for `rcas`
+    return vec4(color, 1.0);
+}
+
+// Robust contrast adaptive sharpening, with a 5 tap cross around the pixel `e`:
+//
+//    b
+//  d e f
+//    h
+@fragment
+fn rcas(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
+    let origin = vec2<i32>(settings.origin);
+    let max_coords = origin + vec2<i32>(settings.output_size) - 1;
+    let coords = vec2<i32>(floor(in.position.xy));
+
+    let b = load(coords + vec2(0, -1), origin, max_coords);
+    let d = load(coords + vec2(-1, 0), origin, max_coords);
+    let e_sample = textureLoad(input_texture, coords, 0);
+    let e = e_sample.rgb;
+    let f = load(coords + vec2(1, 0), origin, max_coords);
+    let h = load(coords + vec2(0, 1), origin, max_coords);
+
+    // The negative lobe that keeps the result within the range of the neighborhood, which is
+    // what makes RCAS robust to clipping.
+    let min_color = min(min(b, d), min(f, h));
+    let max_color = max(max(b, d), max(f, h));
+    let hit_min = min(min_color, e) / max(4.0 * max_color, vec3(1e-5));
+    let hit_max = (1.0 - max(max_color, e)) / (4.0 * min_color - 4.0);
+    let lobe_rgb = max(-hit_min, hit_max);
+    let lobe = max(-RCAS_LIMIT, min(max3(lobe_rgb), 0.0)) * settings.sharpness;
+
+    let color = (lobe * (b + d + f + h) + e) / (4.0 * lobe + 1.0);
+
+#ifdef TONEMAP
+    return vec4(reverse_tonemap(color), e_sample.a);
+#else
+    return vec4(color, e_sample.a);
+#endif
+}
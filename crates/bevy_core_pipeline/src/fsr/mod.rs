@@ -0,0 +1,287 @@
+use crate::{
+    core_3d::{
+        graph::{Core3d, Node3d},
+        Camera3d,
+    },
+    fullscreen_vertex_shader::fullscreen_shader_vertex_state,
+};
+use bevy_app::prelude::*;
+use bevy_asset::{load_internal_asset, Handle};
+use bevy_ecs::{prelude::*, query::QueryItem};
+use bevy_math::UVec2;
+use bevy_reflect::{std_traits::ReflectDefault, Reflect};
+use bevy_render::{
+    camera::{Camera, MainPassResolutionOverride, MipBias},
+    extract_component::{ExtractComponent, ExtractComponentPlugin, UniformComponentPlugin},
+    render_graph::{RenderGraphApp, ViewNodeRunner},
+    render_resource::{
+        binding_types::{texture_2d, uniform_buffer},
+        *,
+    },
+    renderer::RenderDevice,
+    texture::BevyDefault,
+    view::{ExtractedView, ViewTarget},
+    Render, RenderApp, RenderSet,
+};
+
+mod node;
+
+pub use node::FsrNode;
+
+/// Renders the main passes of a 3D camera at a lower resolution, and upscales them with AMD
+/// `FidelityFX` Super Resolution 1.0 (FSR).
+///
+/// FSR is a spatial upscaler made of two passes: edge adaptive spatial upsampling (EASU), which
+/// reconstructs the edges of the low resolution image at the full resolution, and robust contrast
+/// adaptive sharpening (RCAS), which restores the detail lost to the upscaling. It gives sharper
+/// results than the bilinear filtering of the final blit, for a fraction of the cost of rendering
+/// the scene at full resolution.
+///
+/// The scene is upscaled right after the main passes, so that the post-processing effects and the
+/// UI are drawn at full resolution. FSR expects a tonemapped image, so on HDR cameras, whose image
+/// is only tonemapped by the post-processing, it works on a reversibly tonemapped copy of it, as
+/// TAA does. The effects that read the depth or the motion vectors of the
+/// main passes after they end, such as TAA, motion blur, and depth of field, don't support the
+/// lower resolution and shouldn't be used with FSR.
+///
+/// A [`MipBias`] of `log2(render_scale)` is added to the camera when it doesn't have one, so that
+/// the textures of the scene keep the sharpness they would have at full resolution.
+#[derive(Component, Reflect, Clone, Debug)]
+#[reflect(Component, Default)]
+pub struct Fsr {
+    /// The resolution of the main passes, relative to the size of the viewport of the camera.
+    ///
+    /// AMD recommends 0.77 for its "Ultra Quality" mode, 0.67 for "Quality", 0.59 for "Balanced",
+    /// and 0.5 for "Performance". Values of 1.0 and above disable the upscaling.
+    ///
+    /// The default value is 0.67.
+    pub render_scale: f32,
+    /// The amount of sharpening applied by RCAS, in stops: 0.0 is the sharpest, and each stop
+    /// halves the sharpening.
+    ///
+    /// The default value is 0.2.
+    pub sharpness: f32,
+}
+
+impl Default for Fsr {
+    fn default() -> Self {
+        Fsr {
+            render_scale: 0.67,
+            sharpness: 0.2,
+        }
+    }
+}
+
+impl ExtractComponent for Fsr {
+    type QueryData = (&'static Self, &'static Camera);
+    type QueryFilter = With<Camera3d>;
+    type Out = (Self, MainPassResolutionOverride);
+
+    fn extract_component((fsr, camera): QueryItem<Self::QueryData>) -> Option<Self::Out> {
+        if !(fsr.render_scale > 0.0 && fsr.render_scale < 1.0) {
+            return None;
+        }
+        let viewport_size = camera.physical_viewport_size()?;
+        let render_size = (viewport_size.as_vec2() * fsr.render_scale)
+            .round()
+            .as_uvec2()
+            .max(UVec2::ONE);
+        Some((fsr.clone(), MainPassResolutionOverride(render_size)))
+    }
+}
+
+/// The uniform struct prepared from [`Fsr`] attached to a [`Camera`].
+/// Will be available for use in the FSR shader.
+#[doc(hidden)]
+#[derive(Component, ShaderType, Clone)]
+pub struct FsrUniform {
+    /// The top left corner of the viewport, where both the low resolution image and the upscaled
+    /// image start.
+    origin: UVec2,
+    input_size: UVec2,
+    output_size: UVec2,
+    /// The sharpness of RCAS, converted from stops to a linear amount.
+    sharpness: f32,
+}
+
+const FSR_SHADER_HANDLE: Handle<Shader> = Handle::weak_from_u128(2769415108342867153);
+
+/// Adds support for upscaling 3D cameras with [`Fsr`].
+pub struct FsrPlugin;
+
+impl Plugin for FsrPlugin {
+    fn build(&self, app: &mut App) {
+        load_internal_asset!(app, FSR_SHADER_HANDLE, "fsr.wgsl", Shader::from_wgsl);
+
+        app.register_type::<Fsr>();
+        app.add_plugins((
+            ExtractComponentPlugin::<Fsr>::default(),
+            UniformComponentPlugin::<FsrUniform>::default(),
+        ));
+
+        let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+        render_app
+            .init_resource::<SpecializedRenderPipelines<FsrPipeline>>()
+            .add_systems(
+                Render,
+                (
+                    prepare_fsr_views.in_set(RenderSet::ManageViews),
+                    prepare_fsr_pipelines.in_set(RenderSet::Prepare),
+                ),
+            )
+            .add_render_graph_node::<ViewNodeRunner<FsrNode>>(Core3d, Node3d::Fsr)
+            .add_render_graph_edges(
+                Core3d,
+                (
+                    Node3d::StartMainPassUpscaling,
+                    Node3d::Fsr,
+                    Node3d::EndMainPassUpscaling,
+                ),
+            );
+    }
+
+    fn finish(&self, app: &mut App) {
+        let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+        render_app.init_resource::<FsrPipeline>();
+    }
+}
+
+#[derive(Resource)]
+pub struct FsrPipeline {
+    bind_group_layout: BindGroupLayout,
+}
+
+impl FromWorld for FsrPipeline {
+    fn from_world(render_world: &mut World) -> Self {
+        let render_device = render_world.resource::<RenderDevice>();
+        let bind_group_layout = render_device.create_bind_group_layout(
+            "fsr_bind_group_layout",
+            &BindGroupLayoutEntries::sequential(
+                ShaderStages::FRAGMENT,
+                (
+                    // The low resolution image for EASU, and the upscaled image for RCAS
+                    texture_2d(TextureSampleType::Float { filterable: false }),
+                    uniform_buffer::<FsrUniform>(true),
+                ),
+            ),
+        );
+
+        FsrPipeline { bind_group_layout }
+    }
+}
+
+/// The two passes of [`Fsr`].
+#[derive(PartialEq, Eq, Hash, Clone, Copy)]
+pub enum FsrPass {
+    /// Edge adaptive spatial upsampling, which upscales the image.
+    Easu,
+    /// Robust contrast adaptive sharpening, which sharpens the upscaled image.
+    Rcas,
+}
+
+#[derive(PartialEq, Eq, Hash, Clone, Copy)]
+pub struct FsrPipelineKey {
+    hdr: bool,
+    pass: FsrPass,
+}
+
+impl SpecializedRenderPipeline for FsrPipeline {
+    type Key = FsrPipelineKey;
+
+    fn specialize(&self, key: Self::Key) -> RenderPipelineDescriptor {
+        let (label, entry_point) = match key.pass {
+            FsrPass::Easu => ("fsr_easu", "easu"),
+            FsrPass::Rcas => ("fsr_rcas", "rcas"),
+        };
+
+        let mut shader_defs = vec![];
+
+        let format = if key.hdr {
+            shader_defs.push("TONEMAP".into());
+            ViewTarget::TEXTURE_FORMAT_HDR
+        } else {
+            TextureFormat::bevy_default()
+        };
+
+        RenderPipelineDescriptor {
+            label: Some(label.into()),
+            layout: vec![self.bind_group_layout.clone()],
+            vertex: fullscreen_shader_vertex_state(),
+            fragment: Some(FragmentState {
+                shader: FSR_SHADER_HANDLE,
+                shader_defs,
+                entry_point: entry_point.into(),
+                targets: vec![Some(ColorTargetState {
+                    format,
+                    blend: None,
+                    write_mask: ColorWrites::ALL,
+                })],
+            }),
+            primitive: PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: MultisampleState::default(),
+            push_constant_ranges: Vec::new(),
+        }
+    }
+}
+
+/// Prepares the [`FsrUniform`], and adds the default [`MipBias`] to the views without one.
+fn prepare_fsr_views(
+    mut commands: Commands,
+    views: Query<(
+        Entity,
+        &ExtractedView,
+        &Fsr,
+        &MainPassResolutionOverride,
+        Option<&MipBias>,
+    )>,
+) {
+    for (entity, view, fsr, resolution_override, mip_bias) in &views {
+        let mut entity_commands = commands.entity(entity);
+        entity_commands.insert(FsrUniform {
+            origin: UVec2::new(view.viewport.x, view.viewport.y),
+            input_size: resolution_override.0,
+            output_size: UVec2::new(view.viewport.z, view.viewport.w),
+            sharpness: (-fsr.sharpness.max(0.0)).exp2(),
+        });
+
+        if mip_bias.is_none() {
+            entity_commands.insert(MipBias(fsr.render_scale.log2()));
+        }
+    }
+}
+
+fn prepare_fsr_pipelines(
+    mut commands: Commands,
+    pipeline_cache: Res<PipelineCache>,
+    mut pipelines: ResMut<SpecializedRenderPipelines<FsrPipeline>>,
+    fsr_pipeline: Res<FsrPipeline>,
+    views: Query<(Entity, &ExtractedView), With<FsrUniform>>,
+) {
+    for (entity, view) in &views {
+        let [easu, rcas] = [FsrPass::Easu, FsrPass::Rcas].map(|pass| {
+            pipelines.specialize(
+                &pipeline_cache,
+                &fsr_pipeline,
+                FsrPipelineKey {
+                    hdr: view.hdr,
+                    pass,
+                },
+            )
+        });
+
+        commands
+            .entity(entity)
+            .insert(ViewFsrPipelines { easu, rcas });
+    }
+}
+
+#[derive(Component)]
+pub struct ViewFsrPipelines {
+    easu: CachedRenderPipelineId,
+    rcas: CachedRenderPipelineId,
+}
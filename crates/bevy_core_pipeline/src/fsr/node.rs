@@ -0,0 +1,85 @@
+use crate::fsr::{FsrPipeline, FsrUniform, ViewFsrPipelines};
+use bevy_ecs::{prelude::*, query::QueryItem};
+use bevy_render::{
+    camera::ExtractedCamera,
+    extract_component::{ComponentUniforms, DynamicUniformIndex},
+    render_graph::{NodeRunError, RenderGraphContext, ViewNode},
+    render_resource::{
+        BindGroupEntries, Operations, PipelineCache, RenderPassColorAttachment,
+        RenderPassDescriptor,
+    },
+    renderer::RenderContext,
+    view::ViewTarget,
+};
+
+/// Upscales the low resolution image of the main passes to the whole viewport with EASU, and
+/// sharpens it with RCAS.
+#[derive(Default)]
+pub struct FsrNode;
+
+impl ViewNode for FsrNode {
+    type ViewQuery = (
+        &'static ExtractedCamera,
+        &'static ViewTarget,
+        &'static ViewFsrPipelines,
+        &'static DynamicUniformIndex<FsrUniform>,
+    );
+
+    fn run(
+        &self,
+        _graph: &mut RenderGraphContext,
+        render_context: &mut RenderContext,
+        (camera, target, pipelines, uniform_index): QueryItem<Self::ViewQuery>,
+        world: &World,
+    ) -> Result<(), NodeRunError> {
+        let pipeline_cache = world.resource::<PipelineCache>();
+        let fsr_pipeline = world.resource::<FsrPipeline>();
+        let uniforms = world.resource::<ComponentUniforms<FsrUniform>>();
+
+        let Some(uniforms) = uniforms.binding() else {
+            return Ok(());
+        };
+
+        let (Some(easu_pipeline), Some(rcas_pipeline)) = (
+            pipeline_cache.get_render_pipeline(pipelines.easu),
+            pipeline_cache.get_render_pipeline(pipelines.rcas),
+        ) else {
+            return Ok(());
+        };
+
+        for (label, pipeline) in [
+            ("fsr_easu_pass", easu_pipeline),
+            ("fsr_rcas_pass", rcas_pipeline),
+        ] {
+            let post_process = target.post_process_write();
+
+            let bind_group = render_context.render_device().create_bind_group(
+                "fsr_bind_group",
+                &fsr_pipeline.bind_group_layout,
+                &BindGroupEntries::sequential((post_process.source, uniforms.clone())),
+            );
+
+            let pass_descriptor = RenderPassDescriptor {
+                label: Some(label),
+                color_attachments: &[Some(RenderPassColorAttachment {
+                    view: post_process.destination,
+                    resolve_target: None,
+                    ops: Operations::default(),
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            };
+
+            let mut render_pass = render_context.begin_tracked_render_pass(pass_descriptor);
+            if let Some(viewport) = camera.viewport.as_ref() {
+                render_pass.set_camera_viewport(viewport);
+            }
+            render_pass.set_render_pipeline(pipeline);
+            render_pass.set_bind_group(0, &bind_group, &[uniform_index.index()]);
+            render_pass.draw(0..3, 0..1);
+        }
+
+        Ok(())
+    }
+}
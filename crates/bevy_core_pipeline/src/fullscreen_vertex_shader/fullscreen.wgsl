@@ -24,11 +24,44 @@ struct FullscreenVertexOutput {
 // The top-left has UV 0,0, the bottom-left has 0,2, and the top-right has 2,0.
 // This means that the UV gets interpolated to 1,1 at the bottom-right corner
 // of the clip-space rectangle that is at 1,-1 in clip space.
-@vertex
-fn fullscreen_vertex_shader(@builtin(vertex_index) vertex_index: u32) -> FullscreenVertexOutput {
+fn fullscreen_triangle(vertex_index: u32) -> FullscreenVertexOutput {
     // See the explanation above for how this works
     let uv = vec2<f32>(f32(vertex_index >> 1u), f32(vertex_index & 1u)) * 2.0;
     let clip_position = vec4<f32>(uv * vec2<f32>(2.0, -2.0) + vec2<f32>(-1.0, 1.0), 0.0, 1.0);
 
     return FullscreenVertexOutput(clip_position, uv);
 }
+
+@vertex
+fn fullscreen_vertex_shader(@builtin(vertex_index) vertex_index: u32) -> FullscreenVertexOutput {
+    return fullscreen_triangle(vertex_index);
+}
+
+// Same as `fullscreen_vertex_shader`, but the V coordinate goes from 1 at the top to 0 at the
+// bottom, for targets that use the opposite convention.
+@vertex
+fn fullscreen_vertex_shader_flipped_uv(@builtin(vertex_index) vertex_index: u32) -> FullscreenVertexOutput {
+    var out = fullscreen_triangle(vertex_index);
+    out.uv.y = 1.0 - out.uv.y;
+    return out;
+}
+
+struct FullscreenInstancedVertexOutput {
+    @builtin(position)
+    position: vec4<f32>,
+    @location(0)
+    uv: vec2<f32>,
+    @location(1) @interpolate(flat)
+    instance_index: u32,
+};
+
+// Same as `fullscreen_vertex_shader`, but also passes the instance index to the fragment
+// shader, e.g. to select which layer of an array texture each instance reads.
+@vertex
+fn fullscreen_vertex_shader_instanced(
+    @builtin(vertex_index) vertex_index: u32,
+    @builtin(instance_index) instance_index: u32,
+) -> FullscreenInstancedVertexOutput {
+    let out = fullscreen_triangle(vertex_index);
+    return FullscreenInstancedVertexOutput(out.position, out.uv, instance_index);
+}
@@ -6,10 +6,10 @@ pub const FULLSCREEN_SHADER_HANDLE: Handle<Shader> = Handle::weak_from_u128(7837
 /// uses the [`FULLSCREEN_SHADER_HANDLE`] to output a
 /// ```wgsl
 /// struct FullscreenVertexOutput {
-///     [[builtin(position)]]
-///     position: vec4<f32>;
-///     [[location(0)]]
-///     uv: vec2<f32>;
+///     @builtin(position)
+///     position: vec4<f32>,
+///     @location(0)
+///     uv: vec2<f32>,
 /// };
 /// ```
 /// from the vertex shader.
@@ -22,3 +22,41 @@ pub fn fullscreen_shader_vertex_state() -> VertexState {
         buffers: Vec::new(),
     }
 }
+
+/// Same as [`fullscreen_shader_vertex_state`], but the V coordinate of the `uv` output is flipped,
+/// going from 1 at the top of the screen to 0 at the bottom.
+///
+/// This is useful when blitting into targets that use the opposite UV convention.
+/// The draw call should render one triangle: `render_pass.draw(0..3, 0..1);`
+pub fn fullscreen_shader_vertex_state_flipped_uv() -> VertexState {
+    VertexState {
+        entry_point: "fullscreen_vertex_shader_flipped_uv".into(),
+        ..fullscreen_shader_vertex_state()
+    }
+}
+
+/// uses the [`FULLSCREEN_SHADER_HANDLE`] to output a
+/// ```wgsl
+/// struct FullscreenInstancedVertexOutput {
+///     @builtin(position)
+///     position: vec4<f32>,
+///     @location(0)
+///     uv: vec2<f32>,
+///     @location(1) @interpolate(flat)
+///     instance_index: u32,
+/// };
+/// ```
+/// from the vertex shader.
+///
+/// Each instance draws the same fullscreen triangle over the same render target, so the instance
+/// index can only select what the fragment shader reads, e.g. a layer of an array texture, and
+/// the instances are combined with blending: `render_pass.draw(0..3, 0..instance_count);`
+///
+/// Rendering to several array layers still takes one render pass per layer, as a render pass
+/// can only target a single layer without multiview.
+pub fn fullscreen_shader_vertex_state_instanced() -> VertexState {
+    VertexState {
+        entry_point: "fullscreen_vertex_shader_instanced".into(),
+        ..fullscreen_shader_vertex_state()
+    }
+}
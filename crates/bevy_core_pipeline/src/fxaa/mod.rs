@@ -198,6 +198,23 @@ impl SpecializedRenderPipeline for FxaaPipeline {
     }
 }
 
+impl PrewarmRenderPipeline for FxaaPipeline {
+    fn warm_keys(&self) -> Vec<Self::Key> {
+        let fxaa = Fxaa::default();
+        [
+            ViewTarget::TEXTURE_FORMAT_HDR,
+            TextureFormat::bevy_default(),
+        ]
+        .into_iter()
+        .map(|texture_format| FxaaPipelineKey {
+            edge_threshold: fxaa.edge_threshold,
+            edge_threshold_min: fxaa.edge_threshold_min,
+            texture_format,
+        })
+        .collect()
+    }
+}
+
 pub fn prepare_fxaa_pipelines(
     mut commands: Commands,
     pipeline_cache: Res<PipelineCache>,
@@ -0,0 +1,418 @@
+//! Renders some of the transparent meshes of a 3D camera at half resolution, and composites
+//! them over the main pass.
+//!
+//! Large transparent meshes that overlap a lot, such as particles, smoke, or foliage cards, are
+//! usually limited by how many pixels the GPU can shade and blend. Rendering them at half
+//! resolution divides that cost by four, and is hard to notice for soft effects.
+//!
+//! See [`HalfResolutionTransparency`] and [`RenderAtHalfResolution`].
+
+use std::ops::Range;
+
+use crate::{
+    bilateral_upsample::{
+        BilateralUpsample, BilateralUpsamplePipeline, BilateralUpsamplePipelineKey,
+        BilateralUpsampleSettings, BilateralUpsampleUniforms, DownsampleDepth,
+        DownsampleDepthPipeline, DownsampleDepthPipelineKey,
+    },
+    core_3d::{
+        graph::{Core3d, Node3d},
+        Camera3d, CORE_3D_DEPTH_FORMAT, DEPTH_TEXTURE_SAMPLING_SUPPORTED,
+    },
+};
+use bevy_app::prelude::*;
+use bevy_ecs::{entity::EntityHashSet, prelude::*, query::QueryItem};
+use bevy_math::FloatOrd;
+use bevy_reflect::{std_traits::ReflectDefault, Reflect};
+use bevy_render::{
+    camera::ExtractedCamera,
+    extract_component::{ExtractComponent, ExtractComponentPlugin},
+    prelude::{Camera, Msaa},
+    render_graph::{RenderGraphApp, ViewNodeRunner},
+    render_phase::{
+        sort_phase_system, CachedRenderPipelinePhaseItem, DrawFunctionId, DrawFunctions, PhaseItem,
+        PhaseItemExtraIndex, SortedPhaseItem, ViewSortedRenderPhases,
+    },
+    render_resource::*,
+    renderer::RenderDevice,
+    texture::{BevyDefault, CachedTexture, TextureCache},
+    view::{prepare_view_targets, ExtractedView, ViewTarget},
+    Extract, ExtractSchedule, Render, RenderApp, RenderSet,
+};
+
+mod node;
+
+pub use node::HalfResolutionTransparencyNode;
+
+/// Renders the transparent meshes marked with [`RenderAtHalfResolution`] into a half resolution
+/// texture, instead of the main texture of this 3D camera.
+///
+/// The half resolution texture is composited over the main texture after the main transparent
+/// pass, with a [bilateral upsample](crate::bilateral_upsample). Each pixel is upsampled from the
+/// four nearest half resolution pixels, keeping only the ones at a depth close to the depth of the
+/// pixel, so that the edges of the opaque meshes in front of the transparent ones stay sharp.
+///
+/// Marked meshes are blended against a transparent black texture, so alpha blended, premultiplied,
+/// and additive materials look the same as at full resolution, but multiplicative materials
+/// don't. Marked meshes are also always composited over the full resolution transparent meshes,
+/// whatever their distance to the camera.
+///
+/// This isn't supported on WebGL 2, where marked meshes are rendered at full resolution.
+#[derive(Component, Reflect, Clone, Debug)]
+#[reflect(Component, Default)]
+pub struct HalfResolutionTransparency {
+    /// The largest relative difference between the depth of a pixel and the depth of a half
+    /// resolution pixel for them to be blended together when upsampling.
+    ///
+    /// The half resolution pixels further away than that are left out, and when all four nearest
+    /// ones are, the pixel takes the color of the one with the closest depth instead. Lower values
+    /// give sharper edges, and higher values smoother gradients.
+    ///
+    /// The default value is 0.1.
+    pub depth_threshold: f32,
+}
+
+impl Default for HalfResolutionTransparency {
+    fn default() -> Self {
+        HalfResolutionTransparency {
+            depth_threshold: 0.1,
+        }
+    }
+}
+
+/// Renders the transparent mesh of this entity at half resolution for the cameras that have
+/// [`HalfResolutionTransparency`].
+///
+/// This has no effect on opaque and alpha masked meshes, and on cameras without
+/// [`HalfResolutionTransparency`].
+#[derive(Component, Reflect, Clone, Copy, Debug, Default)]
+#[reflect(Component, Default)]
+pub struct RenderAtHalfResolution;
+
+impl ExtractComponent for HalfResolutionTransparency {
+    type QueryData = &'static Self;
+    type QueryFilter = With<Camera3d>;
+    type Out = Self;
+
+    fn extract_component(item: QueryItem<Self::QueryData>) -> Option<Self::Out> {
+        if !DEPTH_TEXTURE_SAMPLING_SUPPORTED {
+            return None;
+        }
+        Some(item.clone())
+    }
+}
+
+/// A transparent mesh rendered at half resolution, for the cameras that have
+/// [`HalfResolutionTransparency`].
+///
+/// This is the same as [`Transparent3d`](crate::core_3d::Transparent3d), in a separate phase.
+pub struct HalfResolutionTransparent3d {
+    pub distance: f32,
+    pub pipeline: CachedRenderPipelineId,
+    pub entity: Entity,
+    pub draw_function: DrawFunctionId,
+    pub batch_range: Range<u32>,
+    pub extra_index: PhaseItemExtraIndex,
+}
+
+impl PhaseItem for HalfResolutionTransparent3d {
+    #[inline]
+    fn entity(&self) -> Entity {
+        self.entity
+    }
+
+    #[inline]
+    fn draw_function(&self) -> DrawFunctionId {
+        self.draw_function
+    }
+
+    #[inline]
+    fn batch_range(&self) -> &Range<u32> {
+        &self.batch_range
+    }
+
+    #[inline]
+    fn batch_range_mut(&mut self) -> &mut Range<u32> {
+        &mut self.batch_range
+    }
+
+    #[inline]
+    fn extra_index(&self) -> PhaseItemExtraIndex {
+        self.extra_index
+    }
+
+    #[inline]
+    fn batch_range_and_extra_index_mut(&mut self) -> (&mut Range<u32>, &mut PhaseItemExtraIndex) {
+        (&mut self.batch_range, &mut self.extra_index)
+    }
+}
+
+impl SortedPhaseItem for HalfResolutionTransparent3d {
+    // NOTE: Values increase towards the camera. Back-to-front ordering for transparent means we need an ascending sort.
+    type SortKey = FloatOrd;
+
+    #[inline]
+    fn sort_key(&self) -> Self::SortKey {
+        FloatOrd(self.distance)
+    }
+
+    #[inline]
+    fn sort(items: &mut [Self]) {
+        radsort::sort_by_key(items, |item| item.distance);
+    }
+}
+
+impl CachedRenderPipelinePhaseItem for HalfResolutionTransparent3d {
+    #[inline]
+    fn cached_pipeline(&self) -> CachedRenderPipelineId {
+        self.pipeline
+    }
+}
+
+/// Adds support for [`HalfResolutionTransparency`].
+///
+/// The phase items are queued by the renderers of the meshes, such as the materials of
+/// `bevy_pbr`.
+pub struct HalfResolutionTransparencyPlugin;
+
+impl Plugin for HalfResolutionTransparencyPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<HalfResolutionTransparency>()
+            .register_type::<RenderAtHalfResolution>();
+        app.add_plugins(ExtractComponentPlugin::<HalfResolutionTransparency>::default());
+
+        let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+        render_app
+            .init_resource::<DrawFunctions<HalfResolutionTransparent3d>>()
+            .init_resource::<ViewSortedRenderPhases<HalfResolutionTransparent3d>>()
+            .add_systems(ExtractSchedule, extract_half_resolution_transparent_phases)
+            .add_systems(
+                Render,
+                (
+                    configure_half_resolution_transparency_depth_textures
+                        .after(prepare_view_targets)
+                        .in_set(RenderSet::ManageViews),
+                    sort_phase_system::<HalfResolutionTransparent3d>.in_set(RenderSet::PhaseSort),
+                    prepare_half_resolution_transparency_pipelines
+                        .in_set(RenderSet::PrepareResources),
+                    prepare_half_resolution_transparency_textures
+                        .in_set(RenderSet::PrepareResources),
+                ),
+            )
+            .add_render_graph_node::<ViewNodeRunner<HalfResolutionTransparencyNode>>(
+                Core3d,
+                Node3d::HalfResolutionTransparentPass,
+            )
+            .add_render_graph_edges(
+                Core3d,
+                (
+                    Node3d::MainTransparentPass,
+                    Node3d::HalfResolutionTransparentPass,
+                    Node3d::EndMainPass,
+                ),
+            );
+    }
+}
+
+/// Creates the [`HalfResolutionTransparent3d`] phase of the cameras with
+/// [`HalfResolutionTransparency`].
+///
+/// The renderers of the meshes queue the marked meshes in this phase when it exists for a view,
+/// and in the [`Transparent3d`](crate::core_3d::Transparent3d) phase otherwise.
+pub fn extract_half_resolution_transparent_phases(
+    mut half_resolution_transparent_phases: ResMut<
+        ViewSortedRenderPhases<HalfResolutionTransparent3d>,
+    >,
+    cameras_3d: Extract<
+        Query<(Entity, &Camera), (With<Camera3d>, With<HalfResolutionTransparency>)>,
+    >,
+    mut live_entities: Local<EntityHashSet>,
+) {
+    live_entities.clear();
+
+    if DEPTH_TEXTURE_SAMPLING_SUPPORTED {
+        for (entity, camera) in &cameras_3d {
+            if !camera.is_active {
+                continue;
+            }
+
+            half_resolution_transparent_phases.insert_or_clear(entity);
+
+            live_entities.insert(entity);
+        }
+    }
+
+    half_resolution_transparent_phases.retain(|entity, _| live_entities.contains(entity));
+}
+
+/// Makes the depth textures of the cameras with [`HalfResolutionTransparency`] readable, since
+/// the half resolution depth is downsampled from them.
+fn configure_half_resolution_transparency_depth_textures(
+    mut view_targets: Query<&mut Camera3d, With<HalfResolutionTransparency>>,
+) {
+    for mut camera_3d in view_targets.iter_mut() {
+        let mut depth_texture_usages = TextureUsages::from(camera_3d.depth_texture_usages);
+        depth_texture_usages |= TextureUsages::TEXTURE_BINDING;
+        camera_3d.depth_texture_usages = depth_texture_usages.into();
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn prepare_half_resolution_transparency_pipelines(
+    mut commands: Commands,
+    pipeline_cache: Res<PipelineCache>,
+    mut downsample_depth_pipelines: ResMut<SpecializedRenderPipelines<DownsampleDepthPipeline>>,
+    downsample_depth_pipeline: Res<DownsampleDepthPipeline>,
+    mut upsample_pipelines: ResMut<SpecializedRenderPipelines<BilateralUpsamplePipeline>>,
+    upsample_pipeline: Res<BilateralUpsamplePipeline>,
+    mut upsample_uniforms: ResMut<BilateralUpsampleUniforms>,
+    msaa: Res<Msaa>,
+    views: Query<(Entity, &ExtractedView, &HalfResolutionTransparency)>,
+) {
+    for (entity, view, half_resolution_transparency) in &views {
+        let downsample_depth = downsample_depth_pipeline.prepare(
+            &pipeline_cache,
+            &mut downsample_depth_pipelines,
+            DownsampleDepthPipelineKey {
+                samples: msaa.samples(),
+            },
+        );
+
+        let texture_format = if view.hdr {
+            ViewTarget::TEXTURE_FORMAT_HDR
+        } else {
+            TextureFormat::bevy_default()
+        };
+        let upsample = upsample_pipeline.prepare(
+            &pipeline_cache,
+            &mut upsample_pipelines,
+            &mut upsample_uniforms,
+            BilateralUpsamplePipelineKey {
+                texture_format,
+                samples: msaa.samples(),
+                multisampled: msaa.samples() > 1,
+                normals: false,
+                // The half resolution color is premultiplied by its coverage, and the alpha of
+                // the main texture is kept as it is
+                blend: Some(BlendState {
+                    color: BlendComponent {
+                        src_factor: BlendFactor::One,
+                        dst_factor: BlendFactor::OneMinusSrcAlpha,
+                        operation: BlendOperation::Add,
+                    },
+                    alpha: BlendComponent {
+                        src_factor: BlendFactor::Zero,
+                        dst_factor: BlendFactor::One,
+                        operation: BlendOperation::Add,
+                    },
+                }),
+            },
+            &BilateralUpsampleSettings {
+                downsample_factor: 2,
+                depth_threshold: half_resolution_transparency.depth_threshold.max(0.0),
+                ..BilateralUpsampleSettings::default()
+            },
+        );
+
+        commands
+            .entity(entity)
+            .insert(ViewHalfResolutionTransparencyPipelines {
+                downsample_depth,
+                upsample,
+            });
+    }
+}
+
+#[derive(Component)]
+pub struct ViewHalfResolutionTransparencyPipelines {
+    downsample_depth: DownsampleDepth,
+    upsample: BilateralUpsample,
+}
+
+fn prepare_half_resolution_transparency_textures(
+    mut commands: Commands,
+    mut texture_cache: ResMut<TextureCache>,
+    render_device: Res<RenderDevice>,
+    msaa: Res<Msaa>,
+    views: Query<(Entity, &ExtractedCamera, &ExtractedView), With<HalfResolutionTransparency>>,
+) {
+    for (entity, camera, view) in &views {
+        let Some(target_size) = camera.physical_target_size else {
+            continue;
+        };
+
+        let size = Extent3d {
+            width: target_size.x.div_ceil(2),
+            height: target_size.y.div_ceil(2),
+            depth_or_array_layers: 1,
+        };
+        let texture_format = if view.hdr {
+            ViewTarget::TEXTURE_FORMAT_HDR
+        } else {
+            TextureFormat::bevy_default()
+        };
+        let multisampled = msaa.samples() > 1;
+
+        let color_descriptor = TextureDescriptor {
+            label: Some("half_resolution_transparency_color_texture"),
+            size,
+            mip_level_count: 1,
+            sample_count: msaa.samples(),
+            dimension: TextureDimension::D2,
+            format: texture_format,
+            usage: if multisampled {
+                TextureUsages::RENDER_ATTACHMENT
+            } else {
+                TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING
+            },
+            view_formats: &[],
+        };
+
+        commands
+            .entity(entity)
+            .insert(HalfResolutionTransparencyTextures {
+                color: texture_cache.get(&render_device, color_descriptor.clone()),
+                resolved_color: multisampled.then(|| {
+                    texture_cache.get(
+                        &render_device,
+                        TextureDescriptor {
+                            label: Some("half_resolution_transparency_resolved_color_texture"),
+                            sample_count: 1,
+                            usage: TextureUsages::RENDER_ATTACHMENT
+                                | TextureUsages::TEXTURE_BINDING,
+                            ..color_descriptor.clone()
+                        },
+                    )
+                }),
+                depth: texture_cache.get(
+                    &render_device,
+                    TextureDescriptor {
+                        label: Some("half_resolution_transparency_depth_texture"),
+                        format: CORE_3D_DEPTH_FORMAT,
+                        usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING,
+                        ..color_descriptor
+                    },
+                ),
+            });
+    }
+}
+
+/// The half resolution textures of [`HalfResolutionTransparency`].
+#[derive(Component)]
+pub struct HalfResolutionTransparencyTextures {
+    /// The color of the half resolution transparent meshes, premultiplied by their coverage.
+    color: CachedTexture,
+    /// The resolved color texture, when MSAA is on.
+    resolved_color: Option<CachedTexture>,
+    /// The farthest depth of the main pass in each 2x2 block of pixels.
+    depth: CachedTexture,
+}
+
+impl HalfResolutionTransparencyTextures {
+    /// Returns the single sampled color texture read by the composite pass.
+    fn sampled_color(&self) -> &CachedTexture {
+        self.resolved_color.as_ref().unwrap_or(&self.color)
+    }
+}
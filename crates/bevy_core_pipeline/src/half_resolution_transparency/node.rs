@@ -0,0 +1,144 @@
+use crate::{
+    bilateral_upsample::{half_resolution_viewport, BilateralUpsampleTextures},
+    half_resolution_transparency::{
+        HalfResolutionTransparencyTextures, HalfResolutionTransparent3d,
+        ViewHalfResolutionTransparencyPipelines,
+    },
+};
+use bevy_color::LinearRgba;
+use bevy_ecs::{prelude::*, query::QueryItem};
+use bevy_render::{
+    camera::{ExtractedCamera, MainPassResolutionOverride, Viewport},
+    diagnostic::RecordDiagnostics,
+    render_graph::{NodeRunError, RenderGraphContext, ViewNode},
+    render_phase::ViewSortedRenderPhases,
+    render_resource::{
+        LoadOp, Operations, RenderPassColorAttachment, RenderPassDepthStencilAttachment,
+        RenderPassDescriptor, StoreOp,
+    },
+    renderer::RenderContext,
+    view::{ViewDepthTexture, ViewTarget},
+};
+#[cfg(feature = "trace")]
+use bevy_utils::tracing::info_span;
+
+/// A [`bevy_render::render_graph::Node`] that runs the [`HalfResolutionTransparent3d`]
+/// [`SortedRenderPhase`](bevy_render::render_phase::SortedRenderPhase) at half resolution, and
+/// composites it over the main texture.
+#[derive(Default)]
+pub struct HalfResolutionTransparencyNode;
+
+impl ViewNode for HalfResolutionTransparencyNode {
+    type ViewQuery = (
+        &'static ExtractedCamera,
+        &'static ViewTarget,
+        &'static ViewDepthTexture,
+        &'static ViewHalfResolutionTransparencyPipelines,
+        &'static HalfResolutionTransparencyTextures,
+        Option<&'static MainPassResolutionOverride>,
+    );
+
+    fn run(
+        &self,
+        graph: &mut RenderGraphContext,
+        render_context: &mut RenderContext,
+        (camera, target, depth, pipelines, textures, resolution_override): QueryItem<
+            Self::ViewQuery,
+        >,
+        world: &World,
+    ) -> Result<(), NodeRunError> {
+        let view_entity = graph.view_entity();
+
+        let Some(half_resolution_phases) =
+            world.get_resource::<ViewSortedRenderPhases<HalfResolutionTransparent3d>>()
+        else {
+            return Ok(());
+        };
+
+        let Some(half_resolution_phase) = half_resolution_phases.get(&view_entity) else {
+            return Ok(());
+        };
+
+        if half_resolution_phase.items.is_empty() {
+            return Ok(());
+        }
+
+        #[cfg(feature = "trace")]
+        let _half_resolution_transparent_pass_3d_span =
+            info_span!("half_resolution_transparent_pass_3d").entered();
+
+        let viewport =
+            Viewport::from_viewport_and_override(camera.viewport.as_ref(), resolution_override);
+        let half_resolution_viewport = viewport.as_ref().map(half_resolution_viewport);
+
+        // Downsample the depth of the main pass, so that the opaque meshes occlude the half
+        // resolution transparent meshes
+        if !pipelines.downsample_depth.render(
+            render_context,
+            world,
+            depth.view(),
+            &textures.depth.default_view,
+            half_resolution_viewport.as_ref(),
+        ) {
+            return Ok(());
+        }
+
+        // Render the half resolution transparent meshes, sorted back-to-front, over transparent
+        // black
+        {
+            let diagnostics = render_context.diagnostic_recorder();
+
+            let mut render_pass = render_context.begin_tracked_render_pass(RenderPassDescriptor {
+                label: Some("half_resolution_transparent_pass_3d"),
+                color_attachments: &[Some(RenderPassColorAttachment {
+                    view: &textures.color.default_view,
+                    resolve_target: textures
+                        .resolved_color
+                        .as_ref()
+                        .map(|texture| &*texture.default_view),
+                    ops: Operations {
+                        load: LoadOp::Clear(LinearRgba::NONE.into()),
+                        store: StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: Some(RenderPassDepthStencilAttachment {
+                    view: &textures.depth.default_view,
+                    depth_ops: Some(Operations {
+                        load: LoadOp::Load,
+                        store: StoreOp::Store,
+                    }),
+                    stencil_ops: None,
+                }),
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+
+            let pass_span =
+                diagnostics.pass_span(&mut render_pass, "half_resolution_transparent_pass_3d");
+
+            if let Some(viewport) = half_resolution_viewport.as_ref() {
+                render_pass.set_camera_viewport(viewport);
+            }
+
+            half_resolution_phase.render(&mut render_pass, world, view_entity);
+
+            pass_span.end(&mut render_pass);
+        }
+
+        // Upsample the half resolution color and blend it over the main texture
+        pipelines.upsample.render(
+            render_context,
+            world,
+            BilateralUpsampleTextures {
+                color: &textures.sampled_color().default_view,
+                low_resolution_depth: &textures.depth.default_view,
+                depth: depth.view(),
+                normals: None,
+            },
+            target.get_color_attachment(),
+            viewport.as_ref(),
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,178 @@
+// Anisotropic Kuwahara filter, with the polynomial sector weights of
+// "Anisotropic Kuwahara Filtering with Polynomial Weighting Functions", by Kyprianidis et al.
+
+#import bevy_core_pipeline::fullscreen_vertex_shader::FullscreenVertexOutput
+
+struct Kuwahara {
+    radius: f32,
+    sharpness: f32,
+    anisotropy: f32,
+}
+
+// The image for the structure tensor and filter passes, and the structure tensor for the blur
+// passes.
+@group(0) @binding(0) var input_texture: texture_2d<f32>;
+@group(0) @binding(1) var tensor_texture: texture_2d<f32>;
+@group(0) @binding(2) var<uniform> kuwahara: Kuwahara;
+
+// The standard deviation of the blur of the structure tensor, in pixels.
+const TENSOR_BLUR_SIGMA: f32 = 2.0;
+// The angle at which the polynomial weight of a sector crosses zero.
+const ZERO_CROSSING: f32 = 0.58;
+// Scales the variance of the sectors before the sharpness is applied.
+const HARDNESS: f32 = 8000.0;
+
+fn load(position: vec2<i32>) -> vec4<f32> {
+    let last = vec2<i32>(textureDimensions(input_texture)) - 1;
+    return textureLoad(input_texture, clamp(position, vec2<i32>(0), last), 0);
+}
+
+// Computes the structure tensor of the image from its Sobel gradients, summed over the color
+// channels, as (gx · gx, gy · gy, gx · gy).
+@fragment
+fn structure_tensor(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
+    let p = vec2<i32>(in.position.xy);
+    let top_left = saturate(load(p + vec2(-1, -1)).rgb);
+    let top = saturate(load(p + vec2(0, -1)).rgb);
+    let top_right = saturate(load(p + vec2(1, -1)).rgb);
+    let left = saturate(load(p + vec2(-1, 0)).rgb);
+    let right = saturate(load(p + vec2(1, 0)).rgb);
+    let bottom_left = saturate(load(p + vec2(-1, 1)).rgb);
+    let bottom = saturate(load(p + vec2(0, 1)).rgb);
+    let bottom_right = saturate(load(p + vec2(1, 1)).rgb);
+
+    let gx = (top_right + 2.0 * right + bottom_right - top_left - 2.0 * left - bottom_left) / 4.0;
+    let gy = (bottom_left + 2.0 * bottom + bottom_right - top_left - 2.0 * top - top_right) / 4.0;
+    return vec4<f32>(dot(gx, gx), dot(gy, gy), dot(gx, gy), 1.0);
+}
+
+fn blur(position: vec2<i32>, direction: vec2<i32>) -> vec4<f32> {
+    let extent = i32(ceil(2.0 * TENSOR_BLUR_SIGMA));
+    var sum = vec4<f32>(0.0);
+    var total_weight = 0.0;
+    for (var i = -extent; i <= extent; i += 1) {
+        let x = f32(i);
+        let weight = exp(-x * x / (2.0 * TENSOR_BLUR_SIGMA * TENSOR_BLUR_SIGMA));
+        sum += load(position + direction * i) * weight;
+        total_weight += weight;
+    }
+    return sum / total_weight;
+}
+
+@fragment
+fn blur_horizontal(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
+    return blur(vec2<i32>(in.position.xy), vec2<i32>(1, 0));
+}
+
+@fragment
+fn blur_vertical(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
+    return blur(vec2<i32>(in.position.xy), vec2<i32>(0, 1));
+}
+
+@fragment
+fn kuwahara_filter(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
+    let position = vec2<i32>(in.position.xy);
+
+    // The orientation of the image is the eigenvector of the smallest eigenvalue of the
+    // structure tensor, which follows the edges, and the anisotropy is how much larger the
+    // other eigenvalue is.
+    let tensor = textureLoad(tensor_texture, position, 0).xyz;
+    let e = tensor.x;
+    let g = tensor.y;
+    let f = tensor.z;
+    let root = sqrt((e - g) * (e - g) + 4.0 * f * f);
+    let lambda1 = 0.5 * (e + g + root);
+    let lambda2 = 0.5 * (e + g - root);
+    let eigenvector = vec2<f32>(lambda1 - e, -f);
+    var tangent = vec2<f32>(0.0, 1.0);
+    if (length(eigenvector) > 0.0) {
+        tangent = normalize(eigenvector);
+    }
+    let normal = vec2<f32>(-tangent.y, tangent.x);
+    var anisotropy = 0.0;
+    if (lambda1 + lambda2 > 0.0) {
+        anisotropy = (lambda1 - lambda2) / (lambda1 + lambda2);
+    }
+
+    // The ellipse is stretched along the tangent, and squashed along the normal.
+    let stretch = 1.0 + anisotropy * kuwahara.anisotropy;
+    let a = kuwahara.radius * clamp(stretch, 0.1, 2.0);
+    let b = kuwahara.radius * clamp(1.0 / stretch, 0.1, 2.0);
+    let extent = vec2<i32>(
+        i32(sqrt(a * a * tangent.x * tangent.x + b * b * tangent.y * tangent.y)),
+        i32(sqrt(a * a * tangent.y * tangent.y + b * b * tangent.x * tangent.x)),
+    );
+
+    let zeta = 2.0 / kuwahara.radius;
+    let sin_zero_crossing = sin(ZERO_CROSSING);
+    let eta = (zeta + cos(ZERO_CROSSING)) / (sin_zero_crossing * sin_zero_crossing);
+
+    // The weighted sum of the colors of each of the 8 sectors, with the sum of the weights in
+    // the alpha channel, and the weighted sum of their squares.
+    var means: array<vec4<f32>, 8>;
+    var squares: array<vec3<f32>, 8>;
+    for (var k = 0; k < 8; k += 1) {
+        means[k] = vec4<f32>(0.0);
+        squares[k] = vec3<f32>(0.0);
+    }
+
+    for (var y = -extent.y; y <= extent.y; y += 1) {
+        for (var x = -extent.x; x <= extent.x; x += 1) {
+            let offset = vec2<f32>(f32(x), f32(y));
+            // The offset mapped to a disc of radius 0.5.
+            var v = vec2<f32>(dot(offset, tangent) / a, dot(offset, normal) / b) * 0.5;
+            if (dot(v, v) > 0.25) {
+                continue;
+            }
+            let color = saturate(load(position + vec2<i32>(x, y)).rgb);
+
+            var weights: array<f32, 8>;
+            var sum = 0.0;
+            var vxx = zeta - eta * v.x * v.x;
+            var vyy = zeta - eta * v.y * v.y;
+            var z = max(0.0, v.y + vxx);
+            weights[0] = z * z;
+            z = max(0.0, -v.x + vyy);
+            weights[2] = z * z;
+            z = max(0.0, -v.y + vxx);
+            weights[4] = z * z;
+            z = max(0.0, v.x + vyy);
+            weights[6] = z * z;
+
+            // The odd sectors are the even ones rotated by 45 degrees.
+            v = sqrt(2.0) / 2.0 * vec2<f32>(v.x - v.y, v.x + v.y);
+            vxx = zeta - eta * v.x * v.x;
+            vyy = zeta - eta * v.y * v.y;
+            z = max(0.0, v.y + vxx);
+            weights[1] = z * z;
+            z = max(0.0, -v.x + vyy);
+            weights[3] = z * z;
+            z = max(0.0, -v.y + vxx);
+            weights[5] = z * z;
+            z = max(0.0, v.x + vyy);
+            weights[7] = z * z;
+
+            for (var k = 0; k < 8; k += 1) {
+                sum += weights[k];
+            }
+            let gaussian = exp(-3.125 * dot(v, v)) / sum;
+            for (var k = 0; k < 8; k += 1) {
+                let weight = weights[k] * gaussian;
+                means[k] += vec4<f32>(color * weight, weight);
+                squares[k] += color * color * weight;
+            }
+        }
+    }
+
+    // Blend the sectors, favoring the ones with the lowest variance.
+    var output = vec4<f32>(0.0);
+    for (var k = 0; k < 8; k += 1) {
+        let mean = means[k].rgb / means[k].w;
+        let variance = abs(squares[k] / means[k].w - mean * mean);
+        let sigma2 = variance.r + variance.g + variance.b;
+        let weight = 1.0 / (1.0 + pow(HARDNESS * sigma2, 0.5 * kuwahara.sharpness));
+        output += vec4<f32>(mean * weight, weight);
+    }
+
+    return vec4<f32>(output.rgb / output.w, load(position).a);
+}
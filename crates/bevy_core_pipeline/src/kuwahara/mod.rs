@@ -0,0 +1,363 @@
+use crate::{
+    core_2d::graph::{Core2d, Node2d},
+    core_3d::graph::{Core3d, Node3d},
+    fullscreen_vertex_shader::fullscreen_shader_vertex_state,
+};
+use bevy_app::prelude::*;
+use bevy_asset::{load_internal_asset, Handle};
+use bevy_ecs::{prelude::*, query::QueryItem};
+use bevy_reflect::{std_traits::ReflectDefault, Reflect};
+use bevy_render::{
+    camera::ExtractedCamera,
+    extract_component::{ExtractComponent, ExtractComponentPlugin, UniformComponentPlugin},
+    prelude::Camera,
+    render_graph::{RenderGraphApp, ViewNodeRunner},
+    render_resource::{
+        binding_types::{texture_2d, uniform_buffer},
+        *,
+    },
+    renderer::RenderDevice,
+    texture::{BevyDefault, CachedTexture, TextureCache},
+    view::{ExtractedView, ViewTarget},
+    Render, RenderApp, RenderSet,
+};
+
+mod node;
+
+pub use node::KuwaharaNode;
+
+/// The largest [`Kuwahara::radius`], which bounds the number of pixels read for each pixel.
+const MAX_RADIUS: f32 = 16.0;
+
+/// The format of the textures holding the structure tensor.
+const TENSOR_TEXTURE_FORMAT: TextureFormat = TextureFormat::Rgba16Float;
+
+/// Gives a 2D or 3D camera a painterly look, by smoothing flat regions into brush strokes that
+/// follow the edges of the image, while keeping the edges themselves sharp.
+///
+/// This is the anisotropic Kuwahara filter: the local orientation of the image is estimated from
+/// its structure tensor, and each pixel is replaced with the average of the most uniform sectors
+/// of an ellipse stretched along that orientation.
+///
+/// The filter is applied after tonemapping, and before the vignette, antialiasing, and
+/// sharpening.
+///
+/// See "Image and Video Abstraction by Anisotropic Kuwahara Filtering", by Kyprianidis et al.
+#[derive(Component, Reflect, Clone, Debug)]
+#[reflect(Component, Default)]
+pub struct Kuwahara {
+    /// The size of the brush strokes, in physical pixels of the render target.
+    ///
+    /// Values below 1 disable the filter, and values above 16 are clamped to 16. The cost of the
+    /// filter grows with the square of the radius.
+    ///
+    /// The default value is 6.0.
+    pub radius: f32,
+    /// How sharply the most uniform sectors are preferred over the others.
+    ///
+    /// Low values blend the sectors together, which blurs the edges, and high values keep the
+    /// edges crisp but can give blocky artifacts.
+    ///
+    /// The default value is 8.0.
+    pub sharpness: f32,
+    /// How much the brush strokes are stretched along the edges of the image.
+    ///
+    /// Zero gives round strokes, like the original Kuwahara filter, whatever the orientation of
+    /// the image.
+    ///
+    /// The default value is 1.0.
+    pub anisotropy: f32,
+}
+
+impl Default for Kuwahara {
+    fn default() -> Self {
+        Kuwahara {
+            radius: 6.0,
+            sharpness: 8.0,
+            anisotropy: 1.0,
+        }
+    }
+}
+
+/// The uniform struct extracted from [`Kuwahara`] attached to a [`Camera`].
+/// Will be available for use in the Kuwahara shader.
+#[doc(hidden)]
+#[derive(Component, ShaderType, Clone)]
+pub struct KuwaharaUniform {
+    radius: f32,
+    sharpness: f32,
+    anisotropy: f32,
+}
+
+impl ExtractComponent for Kuwahara {
+    type QueryData = &'static Self;
+    type QueryFilter = With<Camera>;
+    type Out = KuwaharaUniform;
+
+    fn extract_component(item: QueryItem<Self::QueryData>) -> Option<Self::Out> {
+        if item.radius < 1.0 {
+            return None;
+        }
+        Some(KuwaharaUniform {
+            radius: item.radius.min(MAX_RADIUS),
+            sharpness: item.sharpness.max(0.0),
+            anisotropy: item.anisotropy.max(0.0),
+        })
+    }
+}
+
+const KUWAHARA_SHADER_HANDLE: Handle<Shader> = Handle::weak_from_u128(4198837765020958130);
+
+/// Adds support for the [`Kuwahara`] post-processing effect.
+pub struct KuwaharaPlugin;
+
+impl Plugin for KuwaharaPlugin {
+    fn build(&self, app: &mut App) {
+        load_internal_asset!(
+            app,
+            KUWAHARA_SHADER_HANDLE,
+            "kuwahara.wgsl",
+            Shader::from_wgsl
+        );
+
+        app.register_type::<Kuwahara>();
+        app.add_plugins((
+            ExtractComponentPlugin::<Kuwahara>::default(),
+            UniformComponentPlugin::<KuwaharaUniform>::default(),
+        ));
+
+        let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+        render_app
+            .init_resource::<SpecializedRenderPipelines<KuwaharaPipeline>>()
+            .add_systems(
+                Render,
+                (
+                    prepare_kuwahara_pipelines.in_set(RenderSet::Prepare),
+                    prepare_kuwahara_textures.in_set(RenderSet::PrepareResources),
+                ),
+            )
+            .add_render_graph_node::<ViewNodeRunner<KuwaharaNode>>(Core3d, Node3d::Kuwahara)
+            .add_render_graph_edges(
+                Core3d,
+                (Node3d::Tonemapping, Node3d::Kuwahara, Node3d::Vignette),
+            )
+            .add_render_graph_node::<ViewNodeRunner<KuwaharaNode>>(Core2d, Node2d::Kuwahara)
+            .add_render_graph_edges(
+                Core2d,
+                (Node2d::Tonemapping, Node2d::Kuwahara, Node2d::Vignette),
+            );
+    }
+
+    fn finish(&self, app: &mut App) {
+        let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+        render_app.init_resource::<KuwaharaPipeline>();
+    }
+}
+
+#[derive(Resource)]
+pub struct KuwaharaPipeline {
+    /// Layout with a single texture, for the structure tensor passes
+    tensor_bind_group_layout: BindGroupLayout,
+    /// Layout with the image, the blurred structure tensor, and uniforms, for the filter pass
+    filter_bind_group_layout: BindGroupLayout,
+}
+
+impl FromWorld for KuwaharaPipeline {
+    fn from_world(render_world: &mut World) -> Self {
+        let render_device = render_world.resource::<RenderDevice>();
+        let tensor_bind_group_layout = render_device.create_bind_group_layout(
+            "kuwahara_tensor_bind_group_layout",
+            &BindGroupLayoutEntries::single(
+                ShaderStages::FRAGMENT,
+                texture_2d(TextureSampleType::Float { filterable: false }),
+            ),
+        );
+        let filter_bind_group_layout = render_device.create_bind_group_layout(
+            "kuwahara_filter_bind_group_layout",
+            &BindGroupLayoutEntries::sequential(
+                ShaderStages::FRAGMENT,
+                (
+                    texture_2d(TextureSampleType::Float { filterable: false }),
+                    texture_2d(TextureSampleType::Float { filterable: false }),
+                    uniform_buffer::<KuwaharaUniform>(true),
+                ),
+            ),
+        );
+
+        KuwaharaPipeline {
+            tensor_bind_group_layout,
+            filter_bind_group_layout,
+        }
+    }
+}
+
+/// The passes of [`Kuwahara`], in the order they run.
+#[derive(PartialEq, Eq, Hash, Clone, Copy)]
+pub enum KuwaharaPass {
+    /// Computes the structure tensor of the image from its gradients.
+    StructureTensor,
+    /// Blurs the structure tensor horizontally.
+    BlurHorizontal,
+    /// Blurs the structure tensor vertically.
+    BlurVertical,
+    /// Filters the image along the orientation given by the blurred structure tensor.
+    Filter,
+}
+
+#[derive(PartialEq, Eq, Hash, Clone, Copy)]
+pub struct KuwaharaPipelineKey {
+    texture_format: TextureFormat,
+    pass: KuwaharaPass,
+}
+
+impl SpecializedRenderPipeline for KuwaharaPipeline {
+    type Key = KuwaharaPipelineKey;
+
+    fn specialize(&self, key: Self::Key) -> RenderPipelineDescriptor {
+        let (label, entry_point, layout, format) = match key.pass {
+            KuwaharaPass::StructureTensor => (
+                "kuwahara_structure_tensor",
+                "structure_tensor",
+                &self.tensor_bind_group_layout,
+                TENSOR_TEXTURE_FORMAT,
+            ),
+            KuwaharaPass::BlurHorizontal => (
+                "kuwahara_blur_horizontal",
+                "blur_horizontal",
+                &self.tensor_bind_group_layout,
+                TENSOR_TEXTURE_FORMAT,
+            ),
+            KuwaharaPass::BlurVertical => (
+                "kuwahara_blur_vertical",
+                "blur_vertical",
+                &self.tensor_bind_group_layout,
+                TENSOR_TEXTURE_FORMAT,
+            ),
+            KuwaharaPass::Filter => (
+                "kuwahara_filter",
+                "kuwahara_filter",
+                &self.filter_bind_group_layout,
+                key.texture_format,
+            ),
+        };
+
+        RenderPipelineDescriptor {
+            label: Some(label.into()),
+            layout: vec![layout.clone()],
+            vertex: fullscreen_shader_vertex_state(),
+            fragment: Some(FragmentState {
+                shader: KUWAHARA_SHADER_HANDLE,
+                shader_defs: vec![],
+                entry_point: entry_point.into(),
+                targets: vec![Some(ColorTargetState {
+                    format,
+                    blend: None,
+                    write_mask: ColorWrites::ALL,
+                })],
+            }),
+            primitive: PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: MultisampleState::default(),
+            push_constant_ranges: Vec::new(),
+        }
+    }
+}
+
+fn prepare_kuwahara_pipelines(
+    mut commands: Commands,
+    pipeline_cache: Res<PipelineCache>,
+    mut pipelines: ResMut<SpecializedRenderPipelines<KuwaharaPipeline>>,
+    kuwahara_pipeline: Res<KuwaharaPipeline>,
+    views: Query<(Entity, &ExtractedView), With<KuwaharaUniform>>,
+) {
+    for (entity, view) in &views {
+        let texture_format = if view.hdr {
+            ViewTarget::TEXTURE_FORMAT_HDR
+        } else {
+            TextureFormat::bevy_default()
+        };
+        let [structure_tensor, blur_horizontal, blur_vertical, filter] = [
+            KuwaharaPass::StructureTensor,
+            KuwaharaPass::BlurHorizontal,
+            KuwaharaPass::BlurVertical,
+            KuwaharaPass::Filter,
+        ]
+        .map(|pass| {
+            pipelines.specialize(
+                &pipeline_cache,
+                &kuwahara_pipeline,
+                KuwaharaPipelineKey {
+                    texture_format,
+                    pass,
+                },
+            )
+        });
+
+        commands.entity(entity).insert(ViewKuwaharaPipelines {
+            structure_tensor,
+            blur_horizontal,
+            blur_vertical,
+            filter,
+        });
+    }
+}
+
+#[derive(Component)]
+pub struct ViewKuwaharaPipelines {
+    structure_tensor: CachedRenderPipelineId,
+    blur_horizontal: CachedRenderPipelineId,
+    blur_vertical: CachedRenderPipelineId,
+    filter: CachedRenderPipelineId,
+}
+
+fn prepare_kuwahara_textures(
+    mut commands: Commands,
+    mut texture_cache: ResMut<TextureCache>,
+    render_device: Res<RenderDevice>,
+    views: Query<(Entity, &ExtractedCamera), With<KuwaharaUniform>>,
+) {
+    for (entity, camera) in &views {
+        let Some(target_size) = camera.physical_target_size else {
+            continue;
+        };
+
+        let descriptor = TextureDescriptor {
+            label: Some("kuwahara_tensor_texture"),
+            size: Extent3d {
+                width: target_size.x,
+                height: target_size.y,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format: TENSOR_TEXTURE_FORMAT,
+            usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        };
+
+        commands.entity(entity).insert(KuwaharaTextures {
+            tensor: texture_cache.get(&render_device, descriptor.clone()),
+            blurred_tensor: texture_cache.get(
+                &render_device,
+                TextureDescriptor {
+                    label: Some("kuwahara_blurred_tensor_texture"),
+                    ..descriptor
+                },
+            ),
+        });
+    }
+}
+
+/// The intermediate textures of [`Kuwahara`].
+#[derive(Component)]
+pub struct KuwaharaTextures {
+    /// The structure tensor of the image, and then the fully blurred structure tensor.
+    tensor: CachedTexture,
+    /// The structure tensor after the horizontal blur.
+    blurred_tensor: CachedTexture,
+}
@@ -0,0 +1,143 @@
+use crate::kuwahara::{KuwaharaPipeline, KuwaharaTextures, KuwaharaUniform, ViewKuwaharaPipelines};
+use bevy_ecs::{prelude::*, query::QueryItem};
+use bevy_render::{
+    extract_component::{ComponentUniforms, DynamicUniformIndex},
+    render_graph::{NodeRunError, RenderGraphContext, ViewNode},
+    render_resource::{
+        BindGroup, BindGroupEntries, Operations, PipelineCache, RenderPassColorAttachment,
+        RenderPassDescriptor, RenderPipeline, TextureView,
+    },
+    renderer::RenderContext,
+    view::ViewTarget,
+};
+
+#[derive(Default)]
+pub struct KuwaharaNode;
+
+impl ViewNode for KuwaharaNode {
+    type ViewQuery = (
+        &'static ViewTarget,
+        &'static ViewKuwaharaPipelines,
+        &'static KuwaharaTextures,
+        &'static DynamicUniformIndex<KuwaharaUniform>,
+    );
+
+    fn run(
+        &self,
+        _graph: &mut RenderGraphContext,
+        render_context: &mut RenderContext,
+        (target, pipelines, textures, uniform_index): QueryItem<Self::ViewQuery>,
+        world: &World,
+    ) -> Result<(), NodeRunError> {
+        let pipeline_cache = world.resource::<PipelineCache>();
+        let kuwahara_pipeline = world.resource::<KuwaharaPipeline>();
+        let uniforms = world.resource::<ComponentUniforms<KuwaharaUniform>>();
+
+        let Some(uniforms) = uniforms.binding() else {
+            return Ok(());
+        };
+
+        let (
+            Some(structure_tensor_pipeline),
+            Some(blur_horizontal_pipeline),
+            Some(blur_vertical_pipeline),
+            Some(filter_pipeline),
+        ) = (
+            pipeline_cache.get_render_pipeline(pipelines.structure_tensor),
+            pipeline_cache.get_render_pipeline(pipelines.blur_horizontal),
+            pipeline_cache.get_render_pipeline(pipelines.blur_vertical),
+            pipeline_cache.get_render_pipeline(pipelines.filter),
+        )
+        else {
+            return Ok(());
+        };
+
+        let post_process = target.post_process_write();
+        let tensor = &textures.tensor.default_view;
+        let blurred_tensor = &textures.blurred_tensor.default_view;
+
+        let render_device = render_context.render_device().clone();
+        let tensor_bind_group = |label, texture: &TextureView| {
+            render_device.create_bind_group(
+                label,
+                &kuwahara_pipeline.tensor_bind_group_layout,
+                &BindGroupEntries::single(texture),
+            )
+        };
+        let structure_tensor_bind_group =
+            tensor_bind_group("kuwahara_structure_tensor_bind_group", post_process.source);
+        let blur_horizontal_bind_group =
+            tensor_bind_group("kuwahara_blur_horizontal_bind_group", tensor);
+        let blur_vertical_bind_group =
+            tensor_bind_group("kuwahara_blur_vertical_bind_group", blurred_tensor);
+        let filter_bind_group = render_device.create_bind_group(
+            "kuwahara_filter_bind_group",
+            &kuwahara_pipeline.filter_bind_group_layout,
+            &BindGroupEntries::sequential((post_process.source, tensor, uniforms)),
+        );
+
+        run_pass(
+            render_context,
+            "kuwahara_structure_tensor_pass",
+            structure_tensor_pipeline,
+            &structure_tensor_bind_group,
+            &[],
+            tensor,
+        );
+        run_pass(
+            render_context,
+            "kuwahara_blur_horizontal_pass",
+            blur_horizontal_pipeline,
+            &blur_horizontal_bind_group,
+            &[],
+            blurred_tensor,
+        );
+        run_pass(
+            render_context,
+            "kuwahara_blur_vertical_pass",
+            blur_vertical_pipeline,
+            &blur_vertical_bind_group,
+            &[],
+            tensor,
+        );
+        run_pass(
+            render_context,
+            "kuwahara_filter_pass",
+            filter_pipeline,
+            &filter_bind_group,
+            &[uniform_index.index()],
+            post_process.destination,
+        );
+
+        Ok(())
+    }
+}
+
+fn run_pass(
+    render_context: &mut RenderContext,
+    label: &'static str,
+    pipeline: &RenderPipeline,
+    bind_group: &BindGroup,
+    dynamic_offsets: &[u32],
+    destination: &TextureView,
+) {
+    let pass_descriptor = RenderPassDescriptor {
+        label: Some(label),
+        color_attachments: &[Some(RenderPassColorAttachment {
+            view: destination,
+            resolve_target: None,
+            ops: Operations::default(),
+        })],
+        depth_stencil_attachment: None,
+        timestamp_writes: None,
+        occlusion_query_set: None,
+    };
+
+    let mut render_pass = render_context
+        .command_encoder()
+        .begin_render_pass(&pass_descriptor);
+
+    render_pass.set_pipeline(pipeline);
+    render_pass.set_bind_group(0, bind_group, dynamic_offsets);
+    render_pass.draw(0..3, 0..1);
+}
@@ -8,6 +8,7 @@
 )]
 
 pub mod auto_exposure;
+pub mod bilateral_upsample;
 pub mod blit;
 pub mod bloom;
 pub mod contrast_adaptive_sharpening;
@@ -15,16 +16,25 @@ pub mod core_2d;
 pub mod core_3d;
 pub mod deferred;
 pub mod dof;
+pub mod external_upscaler;
+pub mod fsr;
 pub mod fullscreen_vertex_shader;
 pub mod fxaa;
+pub mod half_resolution_transparency;
+pub mod kuwahara;
 pub mod motion_blur;
 pub mod msaa_writeback;
+pub mod pixelate;
+pub mod post_process_effect;
+pub mod posterize;
 pub mod prepass;
 mod skybox;
 pub mod smaa;
 mod taa;
 pub mod tonemapping;
 pub mod upscaling;
+pub mod vignette;
+pub mod weighted_blended_oit;
 
 pub use skybox::Skybox;
 
@@ -34,7 +44,8 @@ pub use skybox::Skybox;
 pub mod experimental {
     pub mod taa {
         pub use crate::taa::{
-            TemporalAntiAliasBundle, TemporalAntiAliasNode, TemporalAntiAliasPlugin,
+            ResponsiveAntiAliasing, TemporalAntiAliasBundle, TemporalAntiAliasClamping,
+            TemporalAntiAliasHistoryFilter, TemporalAntiAliasNode, TemporalAntiAliasPlugin,
             TemporalAntiAliasSettings,
         };
     }
@@ -49,6 +60,7 @@ pub mod prelude {
 }
 
 use crate::{
+    bilateral_upsample::BilateralUpsamplePlugin,
     blit::BlitPlugin,
     bloom::BloomPlugin,
     contrast_adaptive_sharpening::CASPlugin,
@@ -56,14 +68,26 @@ use crate::{
     core_3d::Core3dPlugin,
     deferred::copy_lighting_id::CopyDeferredLightingIdPlugin,
     dof::DepthOfFieldPlugin,
+    external_upscaler::ExternalUpscalerPlugin,
+    fsr::FsrPlugin,
     fullscreen_vertex_shader::FULLSCREEN_SHADER_HANDLE,
     fxaa::FxaaPlugin,
+    half_resolution_transparency::HalfResolutionTransparencyPlugin,
+    kuwahara::KuwaharaPlugin,
     motion_blur::MotionBlurPlugin,
     msaa_writeback::MsaaWritebackPlugin,
-    prepass::{DeferredPrepass, DepthPrepass, MotionVectorPrepass, NormalPrepass},
+    pixelate::PixelatePlugin,
+    post_process_effect::PostProcessChainPlugin,
+    posterize::PosterizePlugin,
+    prepass::{
+        DeferredPrepass, DepthPrepass, MeshMaskPrepass, MotionVectorPrepass, NormalPrepass,
+        MESH_MASK_SHADER_HANDLE,
+    },
     smaa::SmaaPlugin,
     tonemapping::TonemappingPlugin,
     upscaling::UpscalingPlugin,
+    vignette::VignettePlugin,
+    weighted_blended_oit::WeightedBlendedOitPlugin,
 };
 use bevy_app::{App, Plugin};
 use bevy_asset::load_internal_asset;
@@ -80,10 +104,17 @@ impl Plugin for CorePipelinePlugin {
             "fullscreen_vertex_shader/fullscreen.wgsl",
             Shader::from_wgsl
         );
+        load_internal_asset!(
+            app,
+            MESH_MASK_SHADER_HANDLE,
+            "prepass/mesh_mask.wgsl",
+            Shader::from_wgsl
+        );
 
         app.register_type::<DepthPrepass>()
             .register_type::<NormalPrepass>()
             .register_type::<MotionVectorPrepass>()
+            .register_type::<MeshMaskPrepass>()
             .register_type::<DeferredPrepass>()
             .add_plugins((
                 Core2dPlugin,
@@ -95,10 +126,26 @@ impl Plugin for CorePipelinePlugin {
                 UpscalingPlugin,
                 BloomPlugin,
                 FxaaPlugin,
+                // The contrast adaptive sharpening node is ordered after the SMAA node, so SMAA
+                // has to be added before it
+                SmaaPlugin,
                 CASPlugin,
                 MotionBlurPlugin,
                 DepthOfFieldPlugin,
-                SmaaPlugin,
+                VignettePlugin,
+                // Some of the nodes here are ordered relative to the vignette and posterize nodes,
+                // so they have to be added after them
+                (
+                    BilateralUpsamplePlugin,
+                    HalfResolutionTransparencyPlugin,
+                    WeightedBlendedOitPlugin,
+                    FsrPlugin,
+                    ExternalUpscalerPlugin,
+                    PosterizePlugin,
+                    PixelatePlugin,
+                    KuwaharaPlugin,
+                    PostProcessChainPlugin,
+                ),
             ));
     }
 }
@@ -40,8 +40,9 @@ pub struct MotionBlurBundle {
 ///
 /// Because rendering simulates discrete steps in time, we use per-pixel motion vectors to estimate
 /// the path of objects between frames. This kind of implementation has some artifacts:
-/// - Fast moving objects in front of a stationary object or when in front of empty space, will not
-///   have their edges blurred.
+/// - Fast moving objects only blur past their edges by about two tiles of
+///   [`MOTION_BLUR_TILE_SIZE`](pipeline::MOTION_BLUR_TILE_SIZE) pixels, so very fast motion can
+///   still leave a visible silhouette.
 /// - Transparent objects do not write to depth or motion vectors, so they cannot be blurred.
 ///
 /// Other approaches, such as *A Reconstruction Filter for Plausible Motion Blur* produce more
@@ -103,6 +104,18 @@ pub struct MotionBlur {
     pub _webgl2_padding: bevy_math::Vec2,
 }
 
+/// Add this component to a mesh entity to exclude it from motion blur, e.g. for a first-person
+/// view model that moves along with the camera.
+///
+/// The mesh isn't blurred, and doesn't smear into its surroundings. It still writes its motion
+/// vectors, so other effects that read them, such as TAA, are unaffected.
+///
+/// **This requires the [`MeshMaskPrepass`](crate::prepass::MeshMaskPrepass) on the camera, which
+/// writes the meshes to skip.**
+#[derive(Reflect, Component, Default, Clone, Copy, Debug)]
+#[reflect(Component, Default)]
+pub struct NoMotionBlur;
+
 impl Default for MotionBlur {
     fn default() -> Self {
         Self {
@@ -116,6 +129,8 @@ impl Default for MotionBlur {
 
 pub const MOTION_BLUR_SHADER_HANDLE: Handle<Shader> =
     Handle::weak_from_u128(987457899187986082347921);
+pub const VELOCITY_DILATION_SHADER_HANDLE: Handle<Shader> =
+    Handle::weak_from_u128(4736210917346123083162871);
 
 /// Adds support for per-object motion blur to the app. See [`MotionBlur`] for details.
 pub struct MotionBlurPlugin;
@@ -127,6 +142,12 @@ impl Plugin for MotionBlurPlugin {
             "motion_blur.wgsl",
             Shader::from_wgsl
         );
+        load_internal_asset!(
+            app,
+            VELOCITY_DILATION_SHADER_HANDLE,
+            "velocity_dilation.wgsl",
+            Shader::from_wgsl
+        );
         app.add_plugins((
             ExtractComponentPlugin::<MotionBlur>::default(),
             UniformComponentPlugin::<MotionBlur>::default(),
@@ -140,7 +161,10 @@ impl Plugin for MotionBlurPlugin {
             .init_resource::<SpecializedRenderPipelines<pipeline::MotionBlurPipeline>>()
             .add_systems(
                 Render,
-                pipeline::prepare_motion_blur_pipelines.in_set(RenderSet::Prepare),
+                (
+                    pipeline::prepare_motion_blur_pipelines.in_set(RenderSet::Prepare),
+                    pipeline::prepare_motion_blur_tile_textures.in_set(RenderSet::PrepareResources),
+                ),
             );
 
         render_app
@@ -151,7 +175,7 @@ impl Plugin for MotionBlurPlugin {
             .add_render_graph_edges(
                 Core3d,
                 (
-                    Node3d::EndMainPass,
+                    Node3d::EndMainPassUpscaling,
                     Node3d::MotionBlur,
                     Node3d::Bloom, // we want blurred areas to bloom and tonemap properly.
                 ),
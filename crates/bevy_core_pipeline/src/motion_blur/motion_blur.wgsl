@@ -23,6 +23,33 @@ struct MotionBlur {
 }
 @group(0) @binding(4) var<uniform> settings: MotionBlur;
 @group(0) @binding(5) var<uniform> globals: Globals;
+// The longest motion vector around each tile, see velocity_dilation.wgsl
+@group(0) @binding(6) var neighbor_max: texture_2d<f32>;
+
+#ifdef MESH_MASK
+#import bevy_core_pipeline::mesh_mask::MESH_MASK_NO_MOTION_BLUR_BIT
+#ifdef MULTISAMPLED
+@group(1) @binding(0) var mesh_mask: texture_multisampled_2d<u32>;
+#else
+@group(1) @binding(0) var mesh_mask: texture_2d<u32>;
+#endif
+#endif
+
+const TILE_SIZE: i32 = #{MOTION_BLUR_TILE_SIZE};
+
+// Returns true if the mesh covering this pixel has `NoMotionBlur`.
+fn is_excluded(coords: vec2<i32>, sample_index: i32) -> bool {
+#ifdef MESH_MASK
+#ifdef MULTISAMPLED
+    let mask = textureLoad(mesh_mask, coords, sample_index).r;
+#else
+    let mask = textureLoad(mesh_mask, coords, 0).r;
+#endif
+    return (mask & MESH_MASK_NO_MOTION_BLUR_BIT) != 0u;
+#else
+    return false;
+#endif
+}
 
 @fragment
 fn fragment(
@@ -36,10 +63,16 @@ fn fragment(
 
 #ifdef MULTISAMPLED
     let base_color = textureLoad(screen_texture, frag_coords, i32(sample_index));
+    let mask_sample_index = i32(sample_index);
 #else
     let base_color = textureSample(screen_texture, texture_sampler, in.uv);
+    let mask_sample_index = 0;
 #endif
 
+    if is_excluded(frag_coords, mask_sample_index) {
+        return base_color;
+    }
+
     let shutter_angle = settings.shutter_angle;
 
 #ifdef MULTISAMPLED
@@ -68,7 +101,15 @@ fn fragment(
     // Using a shutter angle larger than 1.0 is non-physical, objects would need to move further
     // than they physically travelled during a frame, which is not possible. Note: we allow values
     // larger than 1.0 because it may be desired for artistic reasons.
-    let exposure_vector = shutter_angle * this_motion_vector;
+    //
+    // A fragment next to an object moving much faster than itself may be covered by that object
+    // while the shutter is open, so it is blurred along the object's motion instead. This lets fast
+    // moving objects blur beyond their silhouette.
+    let neighbor_max_motion = textureLoad(neighbor_max, frag_coords / TILE_SIZE, 0).rg;
+    let is_dilated = length(neighbor_max_motion * texture_size)
+        > 2.0 * length(this_motion_vector * texture_size);
+    let motion_vector = select(this_motion_vector, neighbor_max_motion, is_dilated);
+    let exposure_vector = shutter_angle * motion_vector;
 
     var accumulator: vec4<f32>;
     var weight_total = 0.0;
@@ -88,15 +129,20 @@ fn fragment(
         let sample_coords = vec2<i32>(sample_uv * texture_size);
 
     #ifdef MULTISAMPLED
-        let sample_color = textureLoad(screen_texture, sample_coords, i32(sample_index));
+        var sample_color = textureLoad(screen_texture, sample_coords, i32(sample_index));
     #else
-        let sample_color = textureSample(screen_texture, texture_sampler, sample_uv);
+        var sample_color = textureSample(screen_texture, texture_sampler, sample_uv);
     #endif
     #ifdef MULTISAMPLED
-        let sample_motion = textureLoad(motion_vectors, sample_coords, i32(sample_index)).rg;
+        var sample_motion = textureLoad(motion_vectors, sample_coords, i32(sample_index)).rg;
     #else
-        let sample_motion = textureSample(motion_vectors, texture_sampler, sample_uv).rg;
+        var sample_motion = textureSample(motion_vectors, texture_sampler, sample_uv).rg;
     #endif
+        // Meshes excluded from motion blur are treated as stationary, so they don't smear into
+        // the fragments around them.
+        if is_excluded(sample_coords, mask_sample_index) {
+            sample_motion = vec2(0.0);
+        }
     #ifdef NO_DEPTH_TEXTURE_SUPPORT
         let sample_depth = 0.0;
     #else
@@ -107,6 +153,20 @@ fn fragment(
     #endif
     #endif
 
+        if is_dilated {
+            // The fragment doesn't move along the sampled motion, so it stays visible unless the
+            // sample is in front of it and moved far enough to cover it.
+            let frag_speed = length(step_vector);
+            let sample_speed = length(sample_motion) / 2.0; // Halved because the sample is centered
+            let is_sample_in_front = depth_supported && sample_depth > this_depth;
+            if !is_sample_in_front || sample_speed < frag_speed {
+                sample_color = base_color;
+            }
+            weight_total += 1.0;
+            accumulator += sample_color;
+            continue;
+        }
+
         var weight = 1.0;
         let is_sample_in_fg = !(depth_supported && sample_depth < this_depth && sample_depth > 0.0);
         // If the depth is 0.0, this fragment has no depth written to it and we assume it is in the
@@ -147,7 +207,7 @@ fn fragment(
     }
 
     let has_moved_less_than_a_pixel = 
-        dot(this_motion_vector * texture_size, this_motion_vector * texture_size) < 1.0;
+        dot(motion_vector * texture_size, motion_vector * texture_size) < 1.0;
     // In case no samples were accepted, fall back to base color.
     // We also fall back if motion is small, to not break antialiasing.
     if weight_total <= 0.0 || has_moved_less_than_a_pixel {
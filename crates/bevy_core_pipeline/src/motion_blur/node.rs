@@ -14,7 +14,9 @@ use bevy_render::{
 use crate::prepass::ViewPrepassTextures;
 
 use super::{
-    pipeline::{MotionBlurPipeline, MotionBlurPipelineId},
+    pipeline::{
+        MotionBlurPipeline, MotionBlurPipelineId, MotionBlurTilePipelineIds, MotionBlurTileTextures,
+    },
     MotionBlur,
 };
 
@@ -25,6 +27,8 @@ impl ViewNode for MotionBlurNode {
     type ViewQuery = (
         &'static ViewTarget,
         &'static MotionBlurPipelineId,
+        &'static MotionBlurTilePipelineIds,
+        &'static MotionBlurTileTextures,
         &'static ViewPrepassTextures,
         &'static MotionBlur,
     );
@@ -32,7 +36,7 @@ impl ViewNode for MotionBlurNode {
         &self,
         _graph: &mut RenderGraphContext,
         render_context: &mut RenderContext,
-        (view_target, pipeline_id, prepass_textures, settings): QueryItem<Self::ViewQuery>,
+        (view_target, pipeline_id, tile_pipeline_ids, tile_textures, prepass_textures, settings): QueryItem<Self::ViewQuery>,
         world: &World,
     ) -> Result<(), NodeRunError> {
         if settings.samples == 0 || settings.shutter_angle <= 0.0 {
@@ -42,7 +46,11 @@ impl ViewNode for MotionBlurNode {
         let motion_blur_pipeline = world.resource::<MotionBlurPipeline>();
         let pipeline_cache = world.resource::<PipelineCache>();
         let settings_uniforms = world.resource::<ComponentUniforms<MotionBlur>>();
-        let Some(pipeline) = pipeline_cache.get_render_pipeline(pipeline_id.0) else {
+        let (Some(pipeline), Some(tile_max_pipeline), Some(neighbor_max_pipeline)) = (
+            pipeline_cache.get_render_pipeline(pipeline_id.id),
+            pipeline_cache.get_render_pipeline(tile_pipeline_ids.tile_max),
+            pipeline_cache.get_render_pipeline(tile_pipeline_ids.neighbor_max),
+        ) else {
             return Ok(());
         };
 
@@ -58,15 +66,85 @@ impl ViewNode for MotionBlurNode {
             return Ok(());
         };
 
-        let post_process = view_target.post_process_write();
-
         let msaa = world.resource::<Msaa>();
-        let layout = if msaa.samples() == 1 {
-            &motion_blur_pipeline.layout
+        let (layout, tile_max_layout, mesh_mask_layout) = if msaa.samples() == 1 {
+            (
+                &motion_blur_pipeline.layout,
+                &motion_blur_pipeline.tile_max_layout,
+                &motion_blur_pipeline.mesh_mask_layout,
+            )
+        } else {
+            (
+                &motion_blur_pipeline.layout_msaa,
+                &motion_blur_pipeline.tile_max_layout_msaa,
+                &motion_blur_pipeline.mesh_mask_layout_msaa,
+            )
+        };
+
+        let mesh_mask_bind_group = if pipeline_id.mesh_mask {
+            let Some(mesh_mask) = prepass_textures.mesh_mask_view() else {
+                return Ok(());
+            };
+            Some(render_context.render_device().create_bind_group(
+                "motion_blur_mesh_mask_bind_group",
+                mesh_mask_layout,
+                &BindGroupEntries::single(mesh_mask),
+            ))
         } else {
-            &motion_blur_pipeline.layout_msaa
+            None
         };
 
+        // Dilate the motion vectors, so that fast moving objects also blur over the pixels around
+        // their silhouette, instead of having a hard edge against the background.
+        let tile_max_bind_group = render_context.render_device().create_bind_group(
+            Some("motion_blur_tile_max_bind_group"),
+            tile_max_layout,
+            &BindGroupEntries::single(&prepass_motion_vectors_texture.texture.default_view),
+        );
+        let neighbor_max_bind_group = render_context.render_device().create_bind_group(
+            Some("motion_blur_neighbor_max_bind_group"),
+            &motion_blur_pipeline.neighbor_max_layout,
+            &BindGroupEntries::single(&tile_textures.tile_max.default_view),
+        );
+
+        for (label, pipeline, bind_group, target, mesh_mask_bind_group) in [
+            (
+                "motion_blur_tile_max_pass",
+                tile_max_pipeline,
+                &tile_max_bind_group,
+                &tile_textures.tile_max,
+                mesh_mask_bind_group.as_ref(),
+            ),
+            (
+                "motion_blur_neighbor_max_pass",
+                neighbor_max_pipeline,
+                &neighbor_max_bind_group,
+                &tile_textures.neighbor_max,
+                None,
+            ),
+        ] {
+            let mut render_pass = render_context.begin_tracked_render_pass(RenderPassDescriptor {
+                label: Some(label),
+                color_attachments: &[Some(RenderPassColorAttachment {
+                    view: &target.default_view,
+                    resolve_target: None,
+                    ops: Operations::default(),
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+
+            render_pass.set_render_pipeline(pipeline);
+            render_pass.set_bind_group(0, bind_group, &[]);
+            if let Some(mesh_mask_bind_group) = mesh_mask_bind_group {
+                render_pass.set_bind_group(1, mesh_mask_bind_group, &[]);
+            }
+            render_pass.draw(0..3, 0..1);
+        }
+
+        let post_process = view_target.post_process_write();
+
         let bind_group = render_context.render_device().create_bind_group(
             Some("motion_blur_bind_group"),
             layout,
@@ -77,6 +155,7 @@ impl ViewNode for MotionBlurNode {
                 &motion_blur_pipeline.sampler,
                 settings_binding.clone(),
                 globals_uniforms.clone(),
+                &tile_textures.neighbor_max.default_view,
             )),
         );
 
@@ -94,6 +173,9 @@ impl ViewNode for MotionBlurNode {
 
         render_pass.set_render_pipeline(pipeline);
         render_pass.set_bind_group(0, &bind_group, &[]);
+        if let Some(mesh_mask_bind_group) = &mesh_mask_bind_group {
+            render_pass.set_bind_group(1, mesh_mask_bind_group, &[]);
+        }
         render_pass.draw(0..3, 0..1);
 
         Ok(())
@@ -1,11 +1,12 @@
 use bevy_ecs::{
     component::Component,
     entity::Entity,
-    query::With,
+    query::{Has, With},
     system::{Commands, Query, Res, ResMut, Resource},
     world::FromWorld,
 };
 use bevy_render::{
+    camera::ExtractedCamera,
     globals::GlobalsUniform,
     render_resource::{
         binding_types::{
@@ -13,25 +14,40 @@ use bevy_render::{
             texture_depth_2d_multisampled, uniform_buffer_sized,
         },
         BindGroupLayout, BindGroupLayoutEntries, CachedRenderPipelineId, ColorTargetState,
-        ColorWrites, FragmentState, MultisampleState, PipelineCache, PrimitiveState,
+        ColorWrites, Extent3d, FragmentState, MultisampleState, PipelineCache, PrimitiveState,
         RenderPipelineDescriptor, Sampler, SamplerBindingType, SamplerDescriptor, ShaderDefVal,
         ShaderStages, ShaderType, SpecializedRenderPipeline, SpecializedRenderPipelines,
-        TextureFormat, TextureSampleType,
+        TextureDescriptor, TextureDimension, TextureFormat, TextureSampleType, TextureUsages,
     },
     renderer::RenderDevice,
-    texture::BevyDefault,
+    texture::{BevyDefault, CachedTexture, TextureCache},
     view::{ExtractedView, Msaa, ViewTarget},
 };
 
-use crate::fullscreen_vertex_shader::fullscreen_shader_vertex_state;
+use crate::{
+    fullscreen_vertex_shader::fullscreen_shader_vertex_state,
+    prepass::{MeshMaskPrepass, MOTION_VECTOR_PREPASS_FORMAT},
+};
+
+use super::{MotionBlur, MOTION_BLUR_SHADER_HANDLE, VELOCITY_DILATION_SHADER_HANDLE};
 
-use super::{MotionBlur, MOTION_BLUR_SHADER_HANDLE};
+/// The size in pixels of the tiles that motion vectors are dilated over.
+///
+/// Objects blur up to roughly twice this distance beyond their silhouette.
+pub const MOTION_BLUR_TILE_SIZE: u32 = 16;
 
 #[derive(Resource)]
 pub struct MotionBlurPipeline {
     pub(crate) sampler: Sampler,
     pub(crate) layout: BindGroupLayout,
     pub(crate) layout_msaa: BindGroupLayout,
+    pub(crate) tile_max_layout: BindGroupLayout,
+    pub(crate) tile_max_layout_msaa: BindGroupLayout,
+    pub(crate) neighbor_max_layout: BindGroupLayout,
+    /// Binds the [`MeshMaskPrepass`] texture to the tile max and blur passes, which skip the
+    /// meshes with [`NoMotionBlur`](super::NoMotionBlur).
+    pub(crate) mesh_mask_layout: BindGroupLayout,
+    pub(crate) mesh_mask_layout_msaa: BindGroupLayout,
 }
 
 impl MotionBlurPipeline {
@@ -51,6 +67,8 @@ impl MotionBlurPipeline {
                 uniform_buffer_sized(false, Some(MotionBlur::min_size())),
                 // Globals uniform input
                 uniform_buffer_sized(false, Some(GlobalsUniform::min_size())),
+                // Neighbor max motion vectors
+                texture_2d(TextureSampleType::Float { filterable: false }),
             ),
         );
 
@@ -69,6 +87,8 @@ impl MotionBlurPipeline {
                 uniform_buffer_sized(false, Some(MotionBlur::min_size())),
                 // Globals uniform input
                 uniform_buffer_sized(false, Some(GlobalsUniform::min_size())),
+                // Neighbor max motion vectors
+                texture_2d(TextureSampleType::Float { filterable: false }),
             ),
         );
 
@@ -77,10 +97,55 @@ impl MotionBlurPipeline {
         let layout_msaa =
             render_device.create_bind_group_layout("motion_blur_layout_msaa", mb_layout_msaa);
 
+        let tile_max_layout = render_device.create_bind_group_layout(
+            "motion_blur_tile_max_layout",
+            &BindGroupLayoutEntries::single(
+                ShaderStages::FRAGMENT,
+                // Motion Vectors
+                texture_2d(TextureSampleType::Float { filterable: false }),
+            ),
+        );
+        let tile_max_layout_msaa = render_device.create_bind_group_layout(
+            "motion_blur_tile_max_layout_msaa",
+            &BindGroupLayoutEntries::single(
+                ShaderStages::FRAGMENT,
+                // Motion Vectors
+                texture_2d_multisampled(TextureSampleType::Float { filterable: false }),
+            ),
+        );
+        let neighbor_max_layout = render_device.create_bind_group_layout(
+            "motion_blur_neighbor_max_layout",
+            &BindGroupLayoutEntries::single(
+                ShaderStages::FRAGMENT,
+                // Tile max motion vectors
+                texture_2d(TextureSampleType::Float { filterable: false }),
+            ),
+        );
+
+        let mesh_mask_layout = render_device.create_bind_group_layout(
+            "motion_blur_mesh_mask_layout",
+            &BindGroupLayoutEntries::single(
+                ShaderStages::FRAGMENT,
+                texture_2d(TextureSampleType::Uint),
+            ),
+        );
+        let mesh_mask_layout_msaa = render_device.create_bind_group_layout(
+            "motion_blur_mesh_mask_layout_msaa",
+            &BindGroupLayoutEntries::single(
+                ShaderStages::FRAGMENT,
+                texture_2d_multisampled(TextureSampleType::Uint),
+            ),
+        );
+
         Self {
             sampler,
             layout,
             layout_msaa,
+            tile_max_layout,
+            tile_max_layout_msaa,
+            neighbor_max_layout,
+            mesh_mask_layout,
+            mesh_mask_layout_msaa,
         }
     }
 }
@@ -96,23 +161,81 @@ impl FromWorld for MotionBlurPipeline {
 pub struct MotionBlurPipelineKey {
     hdr: bool,
     samples: u32,
+    mesh_mask: bool,
+    pass: MotionBlurPass,
+}
+
+/// Identifies a specific motion blur render pass.
+#[derive(PartialEq, Eq, Hash, Clone, Copy)]
+enum MotionBlurPass {
+    /// Reduces the motion vectors to the longest one in each tile.
+    TileMax,
+    /// Reduces the tiles to the longest motion vector in their neighborhood.
+    NeighborMax,
+    /// Blurs the view target.
+    Blur,
 }
 
 impl SpecializedRenderPipeline for MotionBlurPipeline {
     type Key = MotionBlurPipelineKey;
 
     fn specialize(&self, key: Self::Key) -> RenderPipelineDescriptor {
-        let layout = match key.samples {
-            1 => vec![self.layout.clone()],
-            _ => vec![self.layout_msaa.clone()],
-        };
-
-        let mut shader_defs = vec![];
+        let mut shader_defs = vec![ShaderDefVal::UInt(
+            "MOTION_BLUR_TILE_SIZE".into(),
+            MOTION_BLUR_TILE_SIZE,
+        )];
 
         if key.samples > 1 {
             shader_defs.push(ShaderDefVal::from("MULTISAMPLED"));
         }
 
+        // The velocity dilation passes render to the tile textures.
+        let (label, layout, shader, entry_point, format) = match key.pass {
+            MotionBlurPass::TileMax => {
+                shader_defs.push("TILE_MAX".into());
+                (
+                    "motion_blur_tile_max_pipeline",
+                    match key.samples {
+                        1 => self.tile_max_layout.clone(),
+                        _ => self.tile_max_layout_msaa.clone(),
+                    },
+                    VELOCITY_DILATION_SHADER_HANDLE,
+                    "tile_max",
+                    MOTION_VECTOR_PREPASS_FORMAT,
+                )
+            }
+            MotionBlurPass::NeighborMax => (
+                "motion_blur_neighbor_max_pipeline",
+                self.neighbor_max_layout.clone(),
+                VELOCITY_DILATION_SHADER_HANDLE,
+                "neighbor_max",
+                MOTION_VECTOR_PREPASS_FORMAT,
+            ),
+            MotionBlurPass::Blur => (
+                "motion_blur_pipeline",
+                match key.samples {
+                    1 => self.layout.clone(),
+                    _ => self.layout_msaa.clone(),
+                },
+                MOTION_BLUR_SHADER_HANDLE,
+                "fragment",
+                if key.hdr {
+                    ViewTarget::TEXTURE_FORMAT_HDR
+                } else {
+                    TextureFormat::bevy_default()
+                },
+            ),
+        };
+
+        let mut layout = vec![layout];
+        if key.mesh_mask && key.pass != MotionBlurPass::NeighborMax {
+            shader_defs.push("MESH_MASK".into());
+            layout.push(match key.samples {
+                1 => self.mesh_mask_layout.clone(),
+                _ => self.mesh_mask_layout_msaa.clone(),
+            });
+        }
+
         #[cfg(all(feature = "webgl", target_arch = "wasm32", not(feature = "webgpu")))]
         {
             shader_defs.push("NO_DEPTH_TEXTURE_SUPPORT".into());
@@ -120,19 +243,15 @@ impl SpecializedRenderPipeline for MotionBlurPipeline {
         }
 
         RenderPipelineDescriptor {
-            label: Some("motion_blur_pipeline".into()),
+            label: Some(label.into()),
             layout,
             vertex: fullscreen_shader_vertex_state(),
             fragment: Some(FragmentState {
-                shader: MOTION_BLUR_SHADER_HANDLE,
+                shader,
                 shader_defs,
-                entry_point: "fragment".into(),
+                entry_point: entry_point.into(),
                 targets: vec![Some(ColorTargetState {
-                    format: if key.hdr {
-                        ViewTarget::TEXTURE_FORMAT_HDR
-                    } else {
-                        TextureFormat::bevy_default()
-                    },
+                    format,
                     blend: None,
                     write_mask: ColorWrites::ALL,
                 })],
@@ -146,7 +265,25 @@ impl SpecializedRenderPipeline for MotionBlurPipeline {
 }
 
 #[derive(Component)]
-pub struct MotionBlurPipelineId(pub CachedRenderPipelineId);
+pub struct MotionBlurPipelineId {
+    pub id: CachedRenderPipelineId,
+    /// Whether the tile max and blur passes read the [`MeshMaskPrepass`] texture.
+    pub mesh_mask: bool,
+}
+
+/// The pipelines of the velocity dilation passes that run before the motion blur.
+#[derive(Component)]
+pub struct MotionBlurTilePipelineIds {
+    pub tile_max: CachedRenderPipelineId,
+    pub neighbor_max: CachedRenderPipelineId,
+}
+
+/// The textures holding the longest motion vector in each tile, and in its neighborhood.
+#[derive(Component)]
+pub struct MotionBlurTileTextures {
+    pub tile_max: CachedTexture,
+    pub neighbor_max: CachedTexture,
+}
 
 pub(crate) fn prepare_motion_blur_pipelines(
     mut commands: Commands,
@@ -154,20 +291,71 @@ pub(crate) fn prepare_motion_blur_pipelines(
     mut pipelines: ResMut<SpecializedRenderPipelines<MotionBlurPipeline>>,
     pipeline: Res<MotionBlurPipeline>,
     msaa: Res<Msaa>,
-    views: Query<(Entity, &ExtractedView), With<MotionBlur>>,
+    views: Query<(Entity, &ExtractedView, Has<MeshMaskPrepass>), With<MotionBlur>>,
 ) {
-    for (entity, view) in &views {
-        let pipeline_id = pipelines.specialize(
-            &pipeline_cache,
-            &pipeline,
-            MotionBlurPipelineKey {
-                hdr: view.hdr,
-                samples: msaa.samples(),
+    for (entity, view, mesh_mask) in &views {
+        let mut specialize = |pass| {
+            pipelines.specialize(
+                &pipeline_cache,
+                &pipeline,
+                MotionBlurPipelineKey {
+                    hdr: view.hdr,
+                    samples: msaa.samples(),
+                    mesh_mask,
+                    pass,
+                },
+            )
+        };
+
+        let pipeline_id = specialize(MotionBlurPass::Blur);
+        let tile_pipeline_ids = MotionBlurTilePipelineIds {
+            tile_max: specialize(MotionBlurPass::TileMax),
+            neighbor_max: specialize(MotionBlurPass::NeighborMax),
+        };
+
+        commands.entity(entity).insert((
+            MotionBlurPipelineId {
+                id: pipeline_id,
+                mesh_mask,
             },
-        );
+            tile_pipeline_ids,
+        ));
+    }
+}
+
+pub(crate) fn prepare_motion_blur_tile_textures(
+    mut commands: Commands,
+    mut texture_cache: ResMut<TextureCache>,
+    render_device: Res<RenderDevice>,
+    views: Query<(Entity, &ExtractedCamera), With<MotionBlur>>,
+) {
+    for (entity, camera) in &views {
+        let Some(physical_target_size) = camera.physical_target_size else {
+            continue;
+        };
+
+        let size = (physical_target_size + MOTION_BLUR_TILE_SIZE - 1) / MOTION_BLUR_TILE_SIZE;
+        let mut texture_descriptor = TextureDescriptor {
+            label: Some("motion_blur_tile_max_texture"),
+            size: Extent3d {
+                width: size.x,
+                height: size.y,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format: MOTION_VECTOR_PREPASS_FORMAT,
+            usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        };
+        let tile_max = texture_cache.get(&render_device, texture_descriptor.clone());
+        texture_descriptor.label = Some("motion_blur_neighbor_max_texture");
+        let neighbor_max = texture_cache.get(&render_device, texture_descriptor);
 
-        commands
-            .entity(entity)
-            .insert(MotionBlurPipelineId(pipeline_id));
+        commands.entity(entity).insert(MotionBlurTileTextures {
+            tile_max,
+            neighbor_max,
+        });
     }
 }
@@ -0,0 +1,82 @@
+// Velocity dilation for motion blur, following the tile-max and neighbor-max passes of [RFPMB].
+//
+// The motion vectors are first reduced to the longest one in each tile of `TILE_SIZE` pixels, and
+// then to the longest one among each tile and its eight neighbors. The motion blur pass uses the
+// latter to find the motion of objects that may cover a fragment without covering it in the
+// prepass, so that fast moving objects blur beyond their silhouette.
+//
+// References:
+// * [RFPMB] - A Reconstruction Filter for Plausible Motion Blur - https://casual-effects.com/research/McGuire2012Blur/index.html
+
+#import bevy_core_pipeline::fullscreen_vertex_shader::FullscreenVertexOutput
+
+const TILE_SIZE: u32 = #{MOTION_BLUR_TILE_SIZE}u;
+
+#ifdef TILE_MAX
+#ifdef MULTISAMPLED
+@group(0) @binding(0) var motion_vectors: texture_multisampled_2d<f32>;
+#else
+@group(0) @binding(0) var motion_vectors: texture_2d<f32>;
+#endif
+#else
+@group(0) @binding(0) var tile_max_texture: texture_2d<f32>;
+#endif
+
+#ifdef MESH_MASK
+#import bevy_core_pipeline::mesh_mask::MESH_MASK_NO_MOTION_BLUR_BIT
+#ifdef MULTISAMPLED
+@group(1) @binding(0) var mesh_mask: texture_multisampled_2d<u32>;
+#else
+@group(1) @binding(0) var mesh_mask: texture_2d<u32>;
+#endif
+#endif
+
+// Returns the longer of two motion vectors, which are in UV units, on a texture of `size` pixels.
+fn longest(a: vec2<f32>, b: vec2<f32>, size: vec2<f32>) -> vec2<f32> {
+    let a_pixels = a * size;
+    let b_pixels = b * size;
+    return select(a, b, dot(b_pixels, b_pixels) > dot(a_pixels, a_pixels));
+}
+
+#ifdef TILE_MAX
+@fragment
+fn tile_max(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
+    let size = textureDimensions(motion_vectors);
+    let tile_origin = vec2<u32>(in.position.xy) * TILE_SIZE;
+
+    var result = vec2<f32>(0.0);
+    for (var y = 0u; y < TILE_SIZE; y++) {
+        for (var x = 0u; x < TILE_SIZE; x++) {
+            let coords = min(tile_origin + vec2<u32>(x, y), size - 1u);
+#ifdef MESH_MASK
+            // Meshes excluded from motion blur don't blur over their neighbors either.
+            if (textureLoad(mesh_mask, coords, 0).r & MESH_MASK_NO_MOTION_BLUR_BIT) != 0u {
+                continue;
+            }
+#endif
+            // With MSAA, the first sample is representative enough of the pixel.
+            let motion_vector = textureLoad(motion_vectors, coords, 0).rg;
+            result = longest(result, motion_vector, vec2<f32>(size));
+        }
+    }
+
+    return vec4<f32>(result, 0.0, 0.0);
+}
+#else
+@fragment
+fn neighbor_max(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
+    let size = vec2<i32>(textureDimensions(tile_max_texture));
+    let tile = vec2<i32>(in.position.xy);
+
+    var result = vec2<f32>(0.0);
+    for (var y = -1; y <= 1; y++) {
+        for (var x = -1; x <= 1; x++) {
+            let coords = clamp(tile + vec2<i32>(x, y), vec2<i32>(0), size - 1);
+            let motion_vector = textureLoad(tile_max_texture, coords, 0).rg;
+            result = longest(result, motion_vector, vec2<f32>(size));
+        }
+    }
+
+    return vec4<f32>(result, 0.0, 0.0);
+}
+#endif
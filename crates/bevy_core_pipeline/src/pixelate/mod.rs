@@ -0,0 +1,362 @@
+use crate::{
+    core_2d::graph::{Core2d, Node2d},
+    core_3d::graph::{Core3d, Node3d},
+    fullscreen_vertex_shader::fullscreen_shader_vertex_state,
+};
+use bevy_app::prelude::*;
+use bevy_asset::{load_internal_asset, Handle};
+use bevy_ecs::{prelude::*, query::QueryItem};
+use bevy_math::{UVec2, Vec2, Vec3Swizzles};
+use bevy_reflect::{std_traits::ReflectDefault, Reflect};
+use bevy_render::{
+    camera::ExtractedCamera,
+    extract_component::{ExtractComponent, ExtractComponentPlugin, UniformComponentPlugin},
+    prelude::Camera,
+    render_graph::{RenderGraphApp, ViewNodeRunner},
+    render_resource::{
+        binding_types::{texture_2d, uniform_buffer},
+        *,
+    },
+    renderer::RenderDevice,
+    texture::{BevyDefault, CachedTexture, TextureCache},
+    view::{ExtractedView, ViewTarget},
+    Render, RenderApp, RenderSet,
+};
+use bevy_transform::components::{GlobalTransform, Transform};
+
+mod node;
+
+pub use node::PixelateNode;
+
+/// The largest [`Pixelate::pixel_size`], which bounds the number of pixels averaged together.
+const MAX_PIXEL_SIZE: u32 = 16;
+
+/// Renders a 2D or 3D camera with large square pixels, for a retro look.
+///
+/// The scene is averaged down to a single color for each block of [`Pixelate::pixel_size`] by
+/// [`Pixelate::pixel_size`] pixels, so that details smaller than a block don't flicker in and out
+/// the way they do when the scene is point sampled, and each block is then drawn with that color.
+///
+/// Pixelation is applied after tonemapping, antialiasing, and sharpening, and before
+/// [`Posterize`](crate::posterize::Posterize) and upscaling. The UI is drawn afterward, so it
+/// stays at full resolution.
+#[derive(Component, Reflect, Clone, Debug)]
+#[reflect(Component, Default)]
+pub struct Pixelate {
+    /// The width and height of the blocks, in physical pixels of the render target.
+    ///
+    /// One disables the effect, and values above 16 are clamped to 16.
+    ///
+    /// The default value is 4.
+    pub pixel_size: u32,
+    /// Whether the camera is snapped to the grid of blocks.
+    ///
+    /// Without snapping, the blocks shimmer as the camera moves, because each of them covers a
+    /// slightly different part of the scene every frame. With snapping, the scene is rendered
+    /// from the nearest position on the grid, which keeps still objects stable, and the image is
+    /// shifted by the remaining distance, so that the camera still moves smoothly.
+    ///
+    /// Only orthographic projections can be snapped, since the size of a block in the world
+    /// depends on the depth with a perspective projection.
+    ///
+    /// The default value is `false`.
+    pub snap: bool,
+}
+
+impl Default for Pixelate {
+    fn default() -> Self {
+        Pixelate {
+            pixel_size: 4,
+            snap: false,
+        }
+    }
+}
+
+/// The uniform struct prepared from [`Pixelate`] attached to a [`Camera`].
+/// Will be available for use in the pixelate shader.
+#[doc(hidden)]
+#[derive(Component, ShaderType, Clone)]
+pub struct PixelateUniform {
+    /// The top left corner of the viewport, where the grid of blocks starts.
+    origin: UVec2,
+    pixel_size: u32,
+    /// How far the camera was moved to snap it to the grid, in blocks.
+    offset: Vec2,
+}
+
+impl ExtractComponent for Pixelate {
+    type QueryData = &'static Self;
+    type QueryFilter = With<Camera>;
+    type Out = Self;
+
+    fn extract_component(item: QueryItem<Self::QueryData>) -> Option<Self::Out> {
+        if item.pixel_size <= 1 {
+            return None;
+        }
+        Some(item.clone())
+    }
+}
+
+const PIXELATE_SHADER_HANDLE: Handle<Shader> = Handle::weak_from_u128(9520143864261870735);
+
+/// Adds support for the [`Pixelate`] post-processing effect.
+pub struct PixelatePlugin;
+
+impl Plugin for PixelatePlugin {
+    fn build(&self, app: &mut App) {
+        load_internal_asset!(
+            app,
+            PIXELATE_SHADER_HANDLE,
+            "pixelate.wgsl",
+            Shader::from_wgsl
+        );
+
+        app.register_type::<Pixelate>();
+        app.add_plugins((
+            ExtractComponentPlugin::<Pixelate>::default(),
+            UniformComponentPlugin::<PixelateUniform>::default(),
+        ));
+
+        let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+        render_app
+            .init_resource::<SpecializedRenderPipelines<PixelatePipeline>>()
+            .add_systems(
+                Render,
+                (
+                    prepare_pixelate_views.in_set(RenderSet::ManageViews),
+                    prepare_pixelate_pipelines.in_set(RenderSet::Prepare),
+                    prepare_pixelate_textures.in_set(RenderSet::PrepareResources),
+                ),
+            )
+            .add_render_graph_node::<ViewNodeRunner<PixelateNode>>(Core3d, Node3d::Pixelate)
+            .add_render_graph_edges(
+                Core3d,
+                (
+                    Node3d::ContrastAdaptiveSharpening,
+                    Node3d::Pixelate,
+                    Node3d::Posterize,
+                ),
+            )
+            .add_render_graph_node::<ViewNodeRunner<PixelateNode>>(Core2d, Node2d::Pixelate)
+            .add_render_graph_edges(
+                Core2d,
+                (
+                    Node2d::ContrastAdaptiveSharpening,
+                    Node2d::Pixelate,
+                    Node2d::Posterize,
+                ),
+            );
+    }
+
+    fn finish(&self, app: &mut App) {
+        let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+        render_app.init_resource::<PixelatePipeline>();
+    }
+}
+
+#[derive(Resource)]
+pub struct PixelatePipeline {
+    bind_group_layout: BindGroupLayout,
+}
+
+impl FromWorld for PixelatePipeline {
+    fn from_world(render_world: &mut World) -> Self {
+        let render_device = render_world.resource::<RenderDevice>();
+        let bind_group_layout = render_device.create_bind_group_layout(
+            "pixelate_bind_group_layout",
+            &BindGroupLayoutEntries::sequential(
+                ShaderStages::FRAGMENT,
+                (
+                    // The full resolution image when downsampling, and the blocks when upscaling
+                    texture_2d(TextureSampleType::Float { filterable: false }),
+                    uniform_buffer::<PixelateUniform>(true),
+                ),
+            ),
+        );
+
+        PixelatePipeline { bind_group_layout }
+    }
+}
+
+/// The two passes of [`Pixelate`].
+#[derive(PartialEq, Eq, Hash, Clone, Copy)]
+pub enum PixelatePass {
+    /// Averages each block of the image into a single pixel of a low resolution texture.
+    Downsample,
+    /// Draws each pixel of the low resolution texture as a block of the image.
+    Upscale,
+}
+
+#[derive(PartialEq, Eq, Hash, Clone, Copy)]
+pub struct PixelatePipelineKey {
+    texture_format: TextureFormat,
+    pass: PixelatePass,
+}
+
+impl SpecializedRenderPipeline for PixelatePipeline {
+    type Key = PixelatePipelineKey;
+
+    fn specialize(&self, key: Self::Key) -> RenderPipelineDescriptor {
+        let (label, entry_point) = match key.pass {
+            PixelatePass::Downsample => ("pixelate_downsample", "downsample"),
+            PixelatePass::Upscale => ("pixelate_upscale", "upscale"),
+        };
+
+        RenderPipelineDescriptor {
+            label: Some(label.into()),
+            layout: vec![self.bind_group_layout.clone()],
+            vertex: fullscreen_shader_vertex_state(),
+            fragment: Some(FragmentState {
+                shader: PIXELATE_SHADER_HANDLE,
+                shader_defs: vec![],
+                entry_point: entry_point.into(),
+                targets: vec![Some(ColorTargetState {
+                    format: key.texture_format,
+                    blend: None,
+                    write_mask: ColorWrites::ALL,
+                })],
+            }),
+            primitive: PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: MultisampleState::default(),
+            push_constant_ranges: Vec::new(),
+        }
+    }
+}
+
+/// Snaps the views that ask for it to their grid of blocks, and prepares the [`PixelateUniform`].
+///
+/// This runs before the views are used for anything else, so that the whole frame is rendered
+/// from the snapped position.
+fn prepare_pixelate_views(
+    mut commands: Commands,
+    mut views: Query<(Entity, &mut ExtractedView, &Pixelate)>,
+) {
+    for (entity, mut view, pixelate) in &mut views {
+        let pixel_size = pixelate.pixel_size.min(MAX_PIXEL_SIZE);
+
+        let is_orthographic = view.clip_from_view.w_axis.w == 1.0;
+        let offset = if pixelate.snap && is_orthographic && view.clip_from_world.is_none() {
+            snap_to_pixel_grid(&mut view, pixel_size)
+        } else {
+            Vec2::ZERO
+        };
+
+        commands.entity(entity).insert(PixelateUniform {
+            origin: UVec2::new(view.viewport.x, view.viewport.y),
+            pixel_size,
+            offset,
+        });
+    }
+}
+
+/// Moves the camera of an orthographic `view` onto the grid of blocks of `pixel_size` pixels, and
+/// returns how far it was moved along its right and up axes, in blocks.
+fn snap_to_pixel_grid(view: &mut ExtractedView, pixel_size: u32) -> Vec2 {
+    // The size of a block in view space, from its size in clip space.
+    let block = 2.0 * pixel_size as f32
+        / (UVec2::new(view.viewport.z, view.viewport.w).as_vec2()
+            * Vec2::new(view.clip_from_view.x_axis.x, view.clip_from_view.y_axis.y));
+    if !block.is_finite() {
+        return Vec2::ZERO;
+    }
+
+    let (scale, rotation, translation) = view.world_from_view.to_scale_rotation_translation();
+    let block = block * scale.xy();
+
+    // The position of the camera along its own axes.
+    let position = rotation.inverse() * translation;
+    let snapped = (position.xy() / block).round() * block;
+
+    view.world_from_view = GlobalTransform::from(Transform {
+        translation: rotation * snapped.extend(position.z),
+        rotation,
+        scale,
+    });
+
+    (position.xy() - snapped) / block
+}
+
+fn prepare_pixelate_pipelines(
+    mut commands: Commands,
+    pipeline_cache: Res<PipelineCache>,
+    mut pipelines: ResMut<SpecializedRenderPipelines<PixelatePipeline>>,
+    pixelate_pipeline: Res<PixelatePipeline>,
+    views: Query<(Entity, &ExtractedView), With<PixelateUniform>>,
+) {
+    for (entity, view) in &views {
+        let texture_format = if view.hdr {
+            ViewTarget::TEXTURE_FORMAT_HDR
+        } else {
+            TextureFormat::bevy_default()
+        };
+        let [downsample, upscale] = [PixelatePass::Downsample, PixelatePass::Upscale].map(|pass| {
+            pipelines.specialize(
+                &pipeline_cache,
+                &pixelate_pipeline,
+                PixelatePipelineKey {
+                    texture_format,
+                    pass,
+                },
+            )
+        });
+
+        commands.entity(entity).insert(ViewPixelatePipelines {
+            downsample,
+            upscale,
+        });
+    }
+}
+
+#[derive(Component)]
+pub struct ViewPixelatePipelines {
+    downsample: CachedRenderPipelineId,
+    upscale: CachedRenderPipelineId,
+}
+
+fn prepare_pixelate_textures(
+    mut commands: Commands,
+    mut texture_cache: ResMut<TextureCache>,
+    render_device: Res<RenderDevice>,
+    views: Query<(Entity, &ExtractedCamera, &ExtractedView, &PixelateUniform)>,
+) {
+    for (entity, camera, view, uniform) in &views {
+        let Some(target_size) = camera.physical_target_size else {
+            continue;
+        };
+        // One pixel per block, rounded up so that partial blocks at the edges are kept.
+        let size = (target_size + uniform.pixel_size - 1) / uniform.pixel_size;
+
+        let texture = texture_cache.get(
+            &render_device,
+            TextureDescriptor {
+                label: Some("pixelate_texture"),
+                size: Extent3d {
+                    width: size.x,
+                    height: size.y,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: TextureDimension::D2,
+                format: if view.hdr {
+                    ViewTarget::TEXTURE_FORMAT_HDR
+                } else {
+                    TextureFormat::bevy_default()
+                },
+                usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING,
+                view_formats: &[],
+            },
+        );
+
+        commands.entity(entity).insert(PixelateTexture(texture));
+    }
+}
+
+/// The low resolution texture that holds one pixel per block.
+#[derive(Component)]
+pub struct PixelateTexture(CachedTexture);
@@ -0,0 +1,98 @@
+use crate::pixelate::{PixelatePipeline, PixelateTexture, PixelateUniform, ViewPixelatePipelines};
+use bevy_ecs::{prelude::*, query::QueryItem};
+use bevy_render::{
+    extract_component::{ComponentUniforms, DynamicUniformIndex},
+    render_graph::{NodeRunError, RenderGraphContext, ViewNode},
+    render_resource::{
+        BindGroupEntries, Operations, PipelineCache, RenderPassColorAttachment,
+        RenderPassDescriptor,
+    },
+    renderer::RenderContext,
+    view::ViewTarget,
+};
+
+#[derive(Default)]
+pub struct PixelateNode;
+
+impl ViewNode for PixelateNode {
+    type ViewQuery = (
+        &'static ViewTarget,
+        &'static ViewPixelatePipelines,
+        &'static PixelateTexture,
+        &'static DynamicUniformIndex<PixelateUniform>,
+    );
+
+    fn run(
+        &self,
+        _graph: &mut RenderGraphContext,
+        render_context: &mut RenderContext,
+        (target, pipelines, texture, uniform_index): QueryItem<Self::ViewQuery>,
+        world: &World,
+    ) -> Result<(), NodeRunError> {
+        let pipeline_cache = world.resource::<PipelineCache>();
+        let pixelate_pipeline = world.resource::<PixelatePipeline>();
+        let uniforms = world.resource::<ComponentUniforms<PixelateUniform>>();
+
+        let Some(uniforms) = uniforms.binding() else {
+            return Ok(());
+        };
+
+        let (Some(downsample_pipeline), Some(upscale_pipeline)) = (
+            pipeline_cache.get_render_pipeline(pipelines.downsample),
+            pipeline_cache.get_render_pipeline(pipelines.upscale),
+        ) else {
+            return Ok(());
+        };
+
+        let post_process = target.post_process_write();
+        let blocks = &texture.0.default_view;
+
+        let downsample_bind_group = render_context.render_device().create_bind_group(
+            "pixelate_downsample_bind_group",
+            &pixelate_pipeline.bind_group_layout,
+            &BindGroupEntries::sequential((post_process.source, uniforms.clone())),
+        );
+        let upscale_bind_group = render_context.render_device().create_bind_group(
+            "pixelate_upscale_bind_group",
+            &pixelate_pipeline.bind_group_layout,
+            &BindGroupEntries::sequential((blocks, uniforms)),
+        );
+
+        for (label, pipeline, bind_group, destination) in [
+            (
+                "pixelate_downsample_pass",
+                downsample_pipeline,
+                &downsample_bind_group,
+                blocks,
+            ),
+            (
+                "pixelate_upscale_pass",
+                upscale_pipeline,
+                &upscale_bind_group,
+                post_process.destination,
+            ),
+        ] {
+            let pass_descriptor = RenderPassDescriptor {
+                label: Some(label),
+                color_attachments: &[Some(RenderPassColorAttachment {
+                    view: destination,
+                    resolve_target: None,
+                    ops: Operations::default(),
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            };
+
+            let mut render_pass = render_context
+                .command_encoder()
+                .begin_render_pass(&pass_descriptor);
+
+            render_pass.set_pipeline(pipeline);
+            render_pass.set_bind_group(0, bind_group, &[uniform_index.index()]);
+            render_pass.draw(0..3, 0..1);
+        }
+
+        Ok(())
+    }
+}
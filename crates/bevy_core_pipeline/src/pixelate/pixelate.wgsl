@@ -0,0 +1,37 @@
+#import bevy_core_pipeline::fullscreen_vertex_shader::FullscreenVertexOutput
+
+struct Pixelate {
+    origin: vec2<u32>,
+    pixel_size: u32,
+    offset: vec2<f32>,
+}
+
+@group(0) @binding(0) var input_texture: texture_2d<f32>;
+@group(0) @binding(1) var<uniform> pixelate: Pixelate;
+
+// Averages the block of the image covered by each pixel of the low resolution texture, so that
+// details smaller than a block still contribute to its color instead of flickering in and out.
+@fragment
+fn downsample(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
+    let last = vec2<i32>(textureDimensions(input_texture)) - 1;
+    let size = i32(pixelate.pixel_size);
+    let first = vec2<i32>(pixelate.origin) + vec2<i32>(in.position.xy) * size;
+
+    var sum = vec4<f32>(0.0);
+    for (var y = 0; y < size; y += 1) {
+        for (var x = 0; x < size; x += 1) {
+            sum += textureLoad(input_texture, min(first + vec2<i32>(x, y), last), 0);
+        }
+    }
+    return sum / f32(size * size);
+}
+
+// Draws each pixel of the low resolution texture as a block of the image, shifted by how far the
+// camera was moved to snap it to the grid of blocks.
+@fragment
+fn upscale(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
+    let position = (in.position.xy - vec2<f32>(pixelate.origin)) / f32(pixelate.pixel_size)
+        + vec2<f32>(pixelate.offset.x, -pixelate.offset.y);
+    let last = vec2<i32>(textureDimensions(input_texture)) - 1;
+    return textureLoad(input_texture, clamp(vec2<i32>(floor(position)), vec2<i32>(0), last), 0);
+}
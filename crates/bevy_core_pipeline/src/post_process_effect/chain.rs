@@ -0,0 +1,540 @@
+use crate::{
+    core_2d::graph::{Core2d, Node2d},
+    core_3d::graph::{Core3d, Node3d},
+    post_process_effect::{node::run_post_process_effect, PostProcessEffect, PostProcessOrder},
+};
+use bevy_app::prelude::*;
+use bevy_ecs::{prelude::*, query::QueryItem};
+use bevy_render::{
+    camera::ExtractedCamera,
+    extract_component::{ExtractComponent, ExtractComponentPlugin},
+    prelude::Camera,
+    render_graph::{
+        InternedRenderLabel, InternedRenderSubGraph, NodeRunError, RenderGraph, RenderGraphContext,
+        RenderLabel, RenderSubGraph, ViewNode, ViewNodeRunner,
+    },
+    renderer::RenderContext,
+    Render, RenderApp, RenderSet,
+};
+use bevy_utils::{warn_once, HashMap, HashSet};
+use thiserror::Error;
+
+/// Sets the order of the post-processing effects of a camera.
+///
+/// The chain lists [`PostProcessEffectLabel`](super::PostProcessEffectLabel)s of
+/// [`PostProcessEffect`]s, which run in the listed order, and labels of built-in
+/// post-processing nodes, such as [`Node3d::Bloom`] or [`Node3d::Tonemapping`].
+///
+/// The render graph is shared by all the cameras, so the built-in nodes always run in the same
+/// order, and a chain can't reorder them. Instead, they anchor the effects listed around them: an
+/// effect runs right after the built-in node listed before it, or, if the chain lists none
+/// before it, right before the built-in node listed after it. Effects can run between any two
+/// consecutive built-in nodes, e.g. between bloom and depth of field.
+///
+/// ```
+/// # use bevy_core_pipeline::{
+/// #     core_3d::graph::Node3d,
+/// #     post_process_effect::{PostProcessChain, PostProcessEffect, PostProcessEffectLabel},
+/// # };
+/// # use bevy_ecs::component::Component;
+/// # use bevy_render::render_resource::{ShaderRef, ShaderType};
+/// # #[derive(Component, ShaderType, Clone)]
+/// # struct FilmGrain {
+/// #     intensity: f32,
+/// # }
+/// # impl PostProcessEffect for FilmGrain {
+/// #     fn fragment_shader() -> ShaderRef {
+/// #         "shaders/film_grain.wgsl".into()
+/// #     }
+/// # }
+/// let chain = PostProcessChain::default()
+///     .then(Node3d::Bloom)
+///     .then(Node3d::DepthOfField)
+///     .then(Node3d::Tonemapping)
+///     .then(PostProcessEffectLabel::of::<FilmGrain>());
+/// ```
+///
+/// A chain that lists no built-in node runs its effects at the default place of the first one,
+/// given by [`PostProcessEffect::order`].
+///
+/// The chain is checked against the registered effects and the built-in nodes of the render graph
+/// of the camera every frame. If it lists an unknown label, lists a label twice, or lists built-in
+/// nodes in a different order than they run in, a warning is logged and the effects run in their
+/// default order.
+///
+/// Effects that the camera has but the chain doesn't list run at their default place.
+#[derive(Component, Clone, Debug, Default)]
+pub struct PostProcessChain(pub Vec<InternedRenderLabel>);
+
+impl PostProcessChain {
+    /// Appends the node with the given `label` to the chain.
+    pub fn then(mut self, label: impl RenderLabel) -> Self {
+        self.0.push(label.intern());
+        self
+    }
+
+    /// Checks the chain against the [`PostProcessEffects`] and the built-in post-processing nodes
+    /// of the render `graph`, and returns where each effect runs.
+    pub fn validate(
+        &self,
+        graph: InternedRenderSubGraph,
+        effects: &PostProcessEffects,
+    ) -> Result<ViewPostProcessChain, PostProcessChainError> {
+        let Some(nodes) = effects.built_in_nodes.get(&graph) else {
+            return Err(PostProcessChainError::UnsupportedGraph(graph));
+        };
+
+        let mut seen = HashSet::default();
+        // The last built-in node, as its index in `nodes`.
+        let mut last_node: Option<(usize, InternedRenderLabel)> = None;
+        // The effects listed since the last built-in node, or since the start of the chain.
+        let mut pending = Vec::new();
+        let mut chain = ViewPostProcessChain::default();
+
+        for &label in &self.0 {
+            if !seen.insert(label) {
+                return Err(PostProcessChainError::Duplicate(label));
+            }
+
+            if let Some(index) = nodes.iter().position(|node| *node == label) {
+                match last_node {
+                    Some((last_index, previous)) if last_index > index => {
+                        return Err(PostProcessChainError::OutOfOrder { label, previous });
+                    }
+                    Some((_, previous)) => chain.push(previous, &mut pending),
+                    // The effects listed before the first built-in node run right before it
+                    None => {
+                        if let Some(index) = index.checked_sub(1) {
+                            chain.push(nodes[index], &mut pending);
+                        } else if let Some(&first) = pending.first() {
+                            return Err(PostProcessChainError::OutOfOrder {
+                                label,
+                                previous: first,
+                            });
+                        }
+                    }
+                }
+                last_node = Some((index, label));
+            } else if effects.effects.contains_key(&label) {
+                pending.push(label);
+            } else {
+                return Err(PostProcessChainError::Unregistered(label));
+            }
+        }
+
+        let Some(&first) = pending.first() else {
+            return Ok(chain);
+        };
+        let after = match last_node {
+            Some((_, last)) => Some(last),
+            None => default_place(nodes, effects.effects[&first].1),
+        };
+        if let Some(after) = after {
+            chain.push(after, &mut pending);
+        }
+
+        Ok(chain)
+    }
+}
+
+impl ExtractComponent for PostProcessChain {
+    type QueryData = &'static Self;
+    type QueryFilter = With<Camera>;
+    type Out = Self;
+
+    fn extract_component(item: QueryItem<Self::QueryData>) -> Option<Self::Out> {
+        Some(item.clone())
+    }
+}
+
+/// The built-in nodes of the 3D render graph that a [`PostProcessChain`] can list, in the order
+/// they run, starting with the node that ends the main passes, and the node that ends the
+/// post-processing.
+fn core_3d_nodes() -> (Vec<InternedRenderLabel>, InternedRenderLabel) {
+    let nodes = vec![
+        Node3d::EndMainPassUpscaling.intern(),
+        Node3d::MotionBlur.intern(),
+        Node3d::Taa.intern(),
+        Node3d::Bloom.intern(),
+        Node3d::DepthOfField.intern(),
+        Node3d::Tonemapping.intern(),
+        Node3d::Kuwahara.intern(),
+        Node3d::Vignette.intern(),
+        Node3d::Fxaa.intern(),
+        Node3d::Smaa.intern(),
+        Node3d::ContrastAdaptiveSharpening.intern(),
+        Node3d::Pixelate.intern(),
+        Node3d::Posterize.intern(),
+    ];
+    (nodes, Node3d::EndMainPassPostProcessing.intern())
+}
+
+/// The built-in nodes of the 2D render graph that a [`PostProcessChain`] can list, in the order
+/// they run, starting with the node that ends the main passes, and the node that ends the
+/// post-processing.
+fn core_2d_nodes() -> (Vec<InternedRenderLabel>, InternedRenderLabel) {
+    let nodes = vec![
+        Node2d::EndMainPass.intern(),
+        Node2d::Bloom.intern(),
+        Node2d::Tonemapping.intern(),
+        Node2d::Kuwahara.intern(),
+        Node2d::Vignette.intern(),
+        Node2d::Fxaa.intern(),
+        Node2d::Smaa.intern(),
+        Node2d::ContrastAdaptiveSharpening.intern(),
+        Node2d::Pixelate.intern(),
+        Node2d::Posterize.intern(),
+    ];
+    (nodes, Node2d::EndMainPassPostProcessing.intern())
+}
+
+/// Returns the built-in node that effects with the given `order` run right after by default.
+fn default_place(
+    nodes: &[InternedRenderLabel],
+    order: PostProcessOrder,
+) -> Option<InternedRenderLabel> {
+    let tonemapping = nodes.iter().position(|node| {
+        *node == Node3d::Tonemapping.intern() || *node == Node2d::Tonemapping.intern()
+    });
+    let index = match order {
+        PostProcessOrder::BeforeTonemapping => tonemapping?.checked_sub(1)?,
+        PostProcessOrder::AfterTonemapping => tonemapping?,
+        PostProcessOrder::BeforeUpscaling => nodes.len().checked_sub(1)?,
+    };
+    Some(nodes[index])
+}
+
+/// An error in a [`PostProcessChain`].
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum PostProcessChainError {
+    #[error(
+        "{0:?} is neither a registered post-processing effect nor a built-in post-processing node"
+    )]
+    Unregistered(InternedRenderLabel),
+    #[error("{0:?} is listed more than once")]
+    Duplicate(InternedRenderLabel),
+    #[error("{label:?} can't run after {previous:?}")]
+    OutOfOrder {
+        label: InternedRenderLabel,
+        previous: InternedRenderLabel,
+    },
+    #[error("Post-processing chains aren't supported by the {0:?} render graph")]
+    UnsupportedGraph(InternedRenderSubGraph),
+}
+
+/// Renders a [`PostProcessEffect`] for a view.
+pub type RunPostProcessEffect = fn(&mut RenderContext, &World, Entity);
+
+/// The [`PostProcessEffect`]s registered in the render world, by the label of their node, and the
+/// built-in post-processing nodes that [`PostProcessChain`]s can list.
+#[derive(Resource, Default)]
+pub struct PostProcessEffects {
+    effects: HashMap<InternedRenderLabel, (RunPostProcessEffect, PostProcessOrder)>,
+    /// The built-in nodes of each render graph, in the order they run, skipping the ones of the
+    /// plugins that weren't added.
+    built_in_nodes: HashMap<InternedRenderSubGraph, Vec<InternedRenderLabel>>,
+}
+
+impl PostProcessEffects {
+    /// Registers the effect `E`, so that [`PostProcessChain`]s can list it.
+    pub fn register<E: PostProcessEffect>(&mut self) {
+        self.effects.insert(
+            super::PostProcessEffectLabel::of::<E>().intern(),
+            (run_post_process_effect::<E>, E::order()),
+        );
+    }
+}
+
+/// The effects of the [`PostProcessChain`] of a view, by the built-in node they run right after.
+#[derive(Component, Clone, Debug, Default, PartialEq, Eq)]
+pub struct ViewPostProcessChain {
+    /// The effects that run right after each built-in node, in order.
+    pub after: HashMap<InternedRenderLabel, Vec<InternedRenderLabel>>,
+}
+
+impl ViewPostProcessChain {
+    /// Returns whether the chain runs the effect with the given `label`.
+    pub fn contains(&self, label: InternedRenderLabel) -> bool {
+        self.after.values().any(|effects| effects.contains(&label))
+    }
+
+    fn push(&mut self, after: InternedRenderLabel, effects: &mut Vec<InternedRenderLabel>) {
+        if !effects.is_empty() {
+            self.after.entry(after).or_default().append(effects);
+        }
+    }
+}
+
+/// The render graph label of the node that runs the effects of the [`PostProcessChain`]s that
+/// run right after the given built-in node.
+#[derive(Debug, Hash, PartialEq, Eq, Clone, RenderLabel)]
+pub struct PostProcessChainLabel(pub InternedRenderLabel);
+
+/// Adds support for [`PostProcessChain`].
+pub struct PostProcessChainPlugin;
+
+impl Plugin for PostProcessChainPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(ExtractComponentPlugin::<PostProcessChain>::default());
+
+        let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+        render_app
+            .init_resource::<PostProcessEffects>()
+            .add_systems(
+                Render,
+                prepare_post_process_chains.in_set(RenderSet::Prepare),
+            );
+    }
+
+    // The nodes of the chains are added once all the plugins have added their built-in nodes
+    fn finish(&self, app: &mut App) {
+        let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+        let world = render_app.world_mut();
+
+        for (graph, (nodes, end)) in [
+            (Core3d.intern(), core_3d_nodes()),
+            (Core2d.intern(), core_2d_nodes()),
+        ] {
+            let Some(sub_graph) = world.resource::<RenderGraph>().get_sub_graph(graph) else {
+                continue;
+            };
+            let nodes: Vec<_> = nodes
+                .into_iter()
+                .filter(|node| sub_graph.get_node_state(*node).is_ok())
+                .collect();
+
+            // One node between each built-in node and the next one
+            for (index, &node) in nodes.iter().enumerate() {
+                let next = nodes.get(index + 1).copied().unwrap_or(end);
+                let chain_node = ViewNodeRunner::new(PostProcessChainNode(node), world);
+                let mut render_graph = world.resource_mut::<RenderGraph>();
+                let Some(sub_graph) = render_graph.get_sub_graph_mut(graph) else {
+                    continue;
+                };
+                sub_graph.add_node(PostProcessChainLabel(node), chain_node);
+                sub_graph.add_node_edges((node, PostProcessChainLabel(node), next));
+            }
+
+            world
+                .resource_mut::<PostProcessEffects>()
+                .built_in_nodes
+                .insert(graph, nodes);
+        }
+    }
+}
+
+fn prepare_post_process_chains(
+    mut commands: Commands,
+    effects: Res<PostProcessEffects>,
+    views: Query<(Entity, &ExtractedCamera, &PostProcessChain)>,
+) {
+    for (entity, camera, chain) in &views {
+        match chain.validate(camera.render_graph, &effects) {
+            Ok(chain) => {
+                commands.entity(entity).insert(chain);
+            }
+            Err(err) => {
+                warn_once!("Invalid post-processing chain on {entity:?}: {err}");
+            }
+        }
+    }
+}
+
+/// Runs the effects of the [`PostProcessChain`] of a view that run right after a built-in node.
+pub struct PostProcessChainNode(InternedRenderLabel);
+
+impl ViewNode for PostProcessChainNode {
+    type ViewQuery = &'static ViewPostProcessChain;
+
+    fn run(
+        &self,
+        graph: &mut RenderGraphContext,
+        render_context: &mut RenderContext,
+        chain: QueryItem<Self::ViewQuery>,
+        world: &World,
+    ) -> Result<(), NodeRunError> {
+        let Some(labels) = chain.after.get(&self.0) else {
+            return Ok(());
+        };
+        let effects = world.resource::<PostProcessEffects>();
+        for label in labels {
+            if let Some((run, _)) = effects.effects.get(label) {
+                run(render_context, world, graph.view_entity());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Hash, PartialEq, Eq, Clone, RenderLabel)]
+    enum TestEffect {
+        Grain,
+        Outline,
+        Tint,
+    }
+
+    fn run(_: &mut RenderContext, _: &World, _: Entity) {}
+
+    fn effects() -> PostProcessEffects {
+        let mut effects = PostProcessEffects::default();
+        for (effect, order) in [
+            (TestEffect::Grain, PostProcessOrder::AfterTonemapping),
+            (TestEffect::Outline, PostProcessOrder::BeforeTonemapping),
+            (TestEffect::Tint, PostProcessOrder::BeforeUpscaling),
+        ] {
+            effects
+                .effects
+                .insert(effect.intern(), (run as RunPostProcessEffect, order));
+        }
+        // As if the TAA plugin wasn't added
+        let (nodes, _) = core_3d_nodes();
+        effects.built_in_nodes.insert(
+            Core3d.intern(),
+            nodes
+                .into_iter()
+                .filter(|node| *node != Node3d::Taa.intern())
+                .collect(),
+        );
+        effects
+    }
+
+    fn validate(chain: PostProcessChain) -> Result<ViewPostProcessChain, PostProcessChainError> {
+        chain.validate(Core3d.intern(), &effects())
+    }
+
+    fn after(entries: impl IntoIterator<Item = (Node3d, Vec<TestEffect>)>) -> ViewPostProcessChain {
+        ViewPostProcessChain {
+            after: entries
+                .into_iter()
+                .map(|(node, effects)| {
+                    (
+                        node.intern(),
+                        effects.into_iter().map(|effect| effect.intern()).collect(),
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn effects_run_after_the_previous_built_in_node() {
+        let chain = PostProcessChain::default()
+            .then(Node3d::Bloom)
+            .then(TestEffect::Outline)
+            .then(Node3d::DepthOfField)
+            .then(Node3d::Tonemapping)
+            .then(TestEffect::Tint)
+            .then(TestEffect::Grain);
+        assert_eq!(
+            validate(chain),
+            Ok(after([
+                (Node3d::Bloom, vec![TestEffect::Outline]),
+                (
+                    Node3d::Tonemapping,
+                    vec![TestEffect::Tint, TestEffect::Grain]
+                ),
+            ]))
+        );
+    }
+
+    #[test]
+    fn leading_effects_run_before_the_next_built_in_node() {
+        let chain = PostProcessChain::default()
+            .then(TestEffect::Grain)
+            .then(Node3d::Bloom)
+            .then(TestEffect::Tint);
+        assert_eq!(
+            validate(chain),
+            Ok(after([
+                (Node3d::MotionBlur, vec![TestEffect::Grain]),
+                (Node3d::Bloom, vec![TestEffect::Tint]),
+            ]))
+        );
+    }
+
+    #[test]
+    fn effects_without_built_in_nodes_run_at_the_default_place_of_the_first() {
+        let chain = PostProcessChain::default()
+            .then(TestEffect::Outline)
+            .then(TestEffect::Grain);
+        assert_eq!(
+            validate(chain),
+            Ok(after([(
+                Node3d::DepthOfField,
+                vec![TestEffect::Outline, TestEffect::Grain]
+            )]))
+        );
+        let chain = PostProcessChain::default().then(TestEffect::Tint);
+        assert_eq!(
+            validate(chain),
+            Ok(after([(Node3d::Posterize, vec![TestEffect::Tint])]))
+        );
+        assert_eq!(
+            validate(PostProcessChain::default()),
+            Ok(ViewPostProcessChain::default())
+        );
+    }
+
+    #[test]
+    fn built_in_nodes_keep_their_order() {
+        let chain = PostProcessChain::default()
+            .then(Node3d::Tonemapping)
+            .then(TestEffect::Grain)
+            .then(Node3d::Bloom);
+        assert_eq!(
+            validate(chain),
+            Err(PostProcessChainError::OutOfOrder {
+                label: Node3d::Bloom.intern(),
+                previous: Node3d::Tonemapping.intern(),
+            })
+        );
+        let chain = PostProcessChain::default()
+            .then(TestEffect::Grain)
+            .then(Node3d::EndMainPassUpscaling);
+        assert_eq!(
+            validate(chain),
+            Err(PostProcessChainError::OutOfOrder {
+                label: Node3d::EndMainPassUpscaling.intern(),
+                previous: TestEffect::Grain.intern(),
+            })
+        );
+    }
+
+    #[test]
+    fn invalid_labels() {
+        let chain = PostProcessChain::default()
+            .then(TestEffect::Grain)
+            .then(Node3d::Tonemapping)
+            .then(TestEffect::Grain);
+        assert_eq!(
+            validate(chain),
+            Err(PostProcessChainError::Duplicate(TestEffect::Grain.intern()))
+        );
+        // The node of a plugin that wasn't added
+        let chain = PostProcessChain::default().then(Node3d::Taa);
+        assert_eq!(
+            validate(chain),
+            Err(PostProcessChainError::Unregistered(Node3d::Taa.intern()))
+        );
+        let chain = PostProcessChain::default().then(Node3d::MainOpaquePass);
+        assert_eq!(
+            validate(chain),
+            Err(PostProcessChainError::Unregistered(
+                Node3d::MainOpaquePass.intern()
+            ))
+        );
+        assert_eq!(
+            PostProcessChain::default().validate(Core2d.intern(), &effects()),
+            Err(PostProcessChainError::UnsupportedGraph(Core2d.intern()))
+        );
+    }
+}
@@ -0,0 +1,323 @@
+//! A way to write custom post-processing effects with only a fragment shader and a settings
+//! component, without writing a render graph node and a pipeline.
+//!
+//! See [`PostProcessEffect`].
+
+use std::{any::type_name, marker::PhantomData};
+
+use crate::{
+    core_2d::graph::{Core2d, Node2d},
+    core_3d::graph::{Core3d, Node3d},
+    fullscreen_vertex_shader::fullscreen_shader_vertex_state,
+};
+use bevy_app::prelude::*;
+use bevy_asset::{AssetServer, Handle};
+use bevy_ecs::prelude::*;
+use bevy_render::{
+    extract_component::UniformComponentPlugin,
+    prelude::Camera,
+    render_graph::{RenderGraphApp, RenderLabel, ViewNodeRunner},
+    render_resource::{
+        binding_types::{sampler, texture_2d, uniform_buffer},
+        encase::internal::WriteInto,
+        *,
+    },
+    renderer::RenderDevice,
+    texture::BevyDefault,
+    view::{ExtractedView, ViewTarget},
+    Extract, ExtractSchedule, Render, RenderApp, RenderSet,
+};
+
+mod chain;
+mod node;
+
+pub use chain::{
+    PostProcessChain, PostProcessChainError, PostProcessChainLabel, PostProcessChainNode,
+    PostProcessChainPlugin, PostProcessEffects, RunPostProcessEffect, ViewPostProcessChain,
+};
+pub use node::PostProcessEffectNode;
+
+/// A custom fullscreen post-processing effect, applied to the cameras that have it as a
+/// component.
+///
+/// The type implementing this trait is both the component that enables the effect on a camera
+/// and the uniform given to the shader, so it derives [`Component`] and [`ShaderType`]. Adding a
+/// [`PostProcessEffectPlugin`] for it creates everything else: the extraction of the component,
+/// its uniform buffer, the bind group, the pipeline, and the render graph node of the effect, for
+/// both 2D and 3D cameras.
+///
+/// The fragment shader reads the image rendered so far, and returns the new color of each pixel.
+/// It has the following bindings:
+///
+/// ```wgsl
+/// #import bevy_core_pipeline::fullscreen_vertex_shader::FullscreenVertexOutput
+///
+/// @group(0) @binding(0) var screen_texture: texture_2d<f32>;
+/// @group(0) @binding(1) var screen_sampler: sampler;
+/// @group(0) @binding(2) var<uniform> settings: MyEffect;
+///
+/// @fragment
+/// fn fragment(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
+///     return textureSample(screen_texture, screen_sampler, in.uv) * settings.tint;
+/// }
+/// ```
+///
+/// And the effect itself is a component:
+///
+/// ```
+/// # use bevy_core_pipeline::post_process_effect::{
+/// #     PostProcessEffect, PostProcessEffectPlugin, PostProcessOrder,
+/// # };
+/// # use bevy_ecs::component::Component;
+/// # use bevy_math::Vec4;
+/// # use bevy_render::render_resource::{ShaderRef, ShaderType};
+/// #[derive(Component, ShaderType, Clone)]
+/// struct MyEffect {
+///     tint: Vec4,
+/// }
+///
+/// impl PostProcessEffect for MyEffect {
+///     fn fragment_shader() -> ShaderRef {
+///         "shaders/my_effect.wgsl".into()
+///     }
+///
+///     fn order() -> PostProcessOrder {
+///         PostProcessOrder::AfterTonemapping
+///     }
+/// }
+///
+/// # let mut app = bevy_app::App::new();
+/// app.add_plugins(PostProcessEffectPlugin::<MyEffect>::default());
+/// ```
+///
+/// Each frame, the component is extracted from the cameras and written to the uniform buffer, so
+/// changing it changes the effect.
+pub trait PostProcessEffect: Component + ShaderType + WriteInto + Clone {
+    /// Returns the fragment shader of the effect, with a `fragment` entry point.
+    ///
+    /// [`ShaderRef::Default`] isn't supported, since there is no default effect.
+    fn fragment_shader() -> ShaderRef;
+
+    /// Returns where the effect runs relative to the built-in post-processing effects.
+    ///
+    /// Effects with the same order run in an unspecified order. Use a [`PostProcessChain`] to
+    /// order them for a camera.
+    fn order() -> PostProcessOrder {
+        PostProcessOrder::default()
+    }
+}
+
+/// Where a [`PostProcessEffect`] runs relative to the built-in post-processing effects.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum PostProcessOrder {
+    /// After bloom and depth of field, and before tonemapping.
+    ///
+    /// The effect reads the linear HDR image if the camera has HDR enabled, which can have values
+    /// above 1.0.
+    BeforeTonemapping,
+    /// After tonemapping, and before the other built-in effects, such as antialiasing.
+    #[default]
+    AfterTonemapping,
+    /// After all the built-in effects, just before upscaling to the render target and the UI.
+    BeforeUpscaling,
+}
+
+/// The render graph label of the node of a [`PostProcessEffect`], in both the 2D and 3D render
+/// graphs.
+#[derive(Debug, Hash, PartialEq, Eq, Clone, RenderLabel)]
+pub struct PostProcessEffectLabel(&'static str);
+
+impl PostProcessEffectLabel {
+    /// Returns the label of the node of the effect `E`.
+    pub fn of<E: PostProcessEffect>() -> Self {
+        PostProcessEffectLabel(type_name::<E>())
+    }
+}
+
+/// Adds support for the [`PostProcessEffect`] `E`.
+pub struct PostProcessEffectPlugin<E: PostProcessEffect>(PhantomData<fn() -> E>);
+
+impl<E: PostProcessEffect> Default for PostProcessEffectPlugin<E> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<E: PostProcessEffect> Plugin for PostProcessEffectPlugin<E> {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(UniformComponentPlugin::<E>::default());
+
+        let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+
+        render_app
+            .world_mut()
+            .get_resource_or_insert_with(PostProcessEffects::default)
+            .register::<E>();
+
+        let label = PostProcessEffectLabel::of::<E>();
+        render_app
+            .init_resource::<SpecializedRenderPipelines<PostProcessEffectPipeline<E>>>()
+            .add_systems(ExtractSchedule, extract_post_process_effects::<E>)
+            .add_systems(
+                Render,
+                prepare_post_process_effect_pipelines::<E>.in_set(RenderSet::Prepare),
+            )
+            .add_render_graph_node::<ViewNodeRunner<PostProcessEffectNode<E>>>(
+                Core3d,
+                label.clone(),
+            )
+            .add_render_graph_node::<ViewNodeRunner<PostProcessEffectNode<E>>>(
+                Core2d,
+                label.clone(),
+            );
+
+        match E::order() {
+            PostProcessOrder::BeforeTonemapping => render_app
+                .add_render_graph_edges(
+                    Core3d,
+                    (Node3d::DepthOfField, label.clone(), Node3d::Tonemapping),
+                )
+                .add_render_graph_edges(Core2d, (Node2d::Bloom, label, Node2d::Tonemapping)),
+            PostProcessOrder::AfterTonemapping => render_app
+                .add_render_graph_edges(
+                    Core3d,
+                    (Node3d::Tonemapping, label.clone(), Node3d::Kuwahara),
+                )
+                .add_render_graph_edges(Core2d, (Node2d::Tonemapping, label, Node2d::Kuwahara)),
+            PostProcessOrder::BeforeUpscaling => render_app
+                .add_render_graph_edges(
+                    Core3d,
+                    (
+                        Node3d::Posterize,
+                        label.clone(),
+                        Node3d::EndMainPassPostProcessing,
+                    ),
+                )
+                .add_render_graph_edges(
+                    Core2d,
+                    (Node2d::Posterize, label, Node2d::EndMainPassPostProcessing),
+                ),
+        };
+    }
+
+    fn finish(&self, app: &mut App) {
+        let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+        render_app.init_resource::<PostProcessEffectPipeline<E>>();
+    }
+}
+
+fn extract_post_process_effects<E: PostProcessEffect>(
+    mut commands: Commands,
+    mut previous_len: Local<usize>,
+    query: Extract<Query<(Entity, &E), With<Camera>>>,
+) {
+    let mut values = Vec::with_capacity(*previous_len);
+    for (entity, effect) in &query {
+        values.push((entity, effect.clone()));
+    }
+    *previous_len = values.len();
+    commands.insert_or_spawn_batch(values);
+}
+
+#[derive(Resource)]
+pub struct PostProcessEffectPipeline<E: PostProcessEffect> {
+    texture_bind_group: BindGroupLayout,
+    sampler: Sampler,
+    shader: Handle<Shader>,
+    marker: PhantomData<fn() -> E>,
+}
+
+impl<E: PostProcessEffect> FromWorld for PostProcessEffectPipeline<E> {
+    fn from_world(render_world: &mut World) -> Self {
+        let shader = match E::fragment_shader() {
+            ShaderRef::Default => panic!(
+                "The post-processing effect {} has no fragment shader",
+                type_name::<E>()
+            ),
+            ShaderRef::Handle(handle) => handle,
+            ShaderRef::Path(path) => render_world.resource::<AssetServer>().load(path),
+        };
+
+        let render_device = render_world.resource::<RenderDevice>();
+        let texture_bind_group = render_device.create_bind_group_layout(
+            "post_process_effect_texture_bind_group_layout",
+            &BindGroupLayoutEntries::sequential(
+                ShaderStages::FRAGMENT,
+                (
+                    texture_2d(TextureSampleType::Float { filterable: true }),
+                    sampler(SamplerBindingType::Filtering),
+                    uniform_buffer::<E>(true),
+                ),
+            ),
+        );
+
+        let sampler = render_device.create_sampler(&SamplerDescriptor::default());
+
+        PostProcessEffectPipeline {
+            texture_bind_group,
+            sampler,
+            shader,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<E: PostProcessEffect> SpecializedRenderPipeline for PostProcessEffectPipeline<E> {
+    type Key = TextureFormat;
+
+    fn specialize(&self, texture_format: Self::Key) -> RenderPipelineDescriptor {
+        RenderPipelineDescriptor {
+            label: Some(format!("post_process_effect_pipeline<{}>", type_name::<E>()).into()),
+            layout: vec![self.texture_bind_group.clone()],
+            vertex: fullscreen_shader_vertex_state(),
+            fragment: Some(FragmentState {
+                shader: self.shader.clone(),
+                shader_defs: vec![],
+                entry_point: "fragment".into(),
+                targets: vec![Some(ColorTargetState {
+                    format: texture_format,
+                    blend: None,
+                    write_mask: ColorWrites::ALL,
+                })],
+            }),
+            primitive: PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: MultisampleState::default(),
+            push_constant_ranges: Vec::new(),
+        }
+    }
+}
+
+fn prepare_post_process_effect_pipelines<E: PostProcessEffect>(
+    mut commands: Commands,
+    pipeline_cache: Res<PipelineCache>,
+    mut pipelines: ResMut<SpecializedRenderPipelines<PostProcessEffectPipeline<E>>>,
+    effect_pipeline: Res<PostProcessEffectPipeline<E>>,
+    views: Query<(Entity, &ExtractedView), With<E>>,
+) {
+    for (entity, view) in &views {
+        let pipeline_id = pipelines.specialize(
+            &pipeline_cache,
+            &effect_pipeline,
+            if view.hdr {
+                ViewTarget::TEXTURE_FORMAT_HDR
+            } else {
+                TextureFormat::bevy_default()
+            },
+        );
+
+        commands
+            .entity(entity)
+            .insert(ViewPostProcessEffectPipeline::<E>(pipeline_id, PhantomData));
+    }
+}
+
+#[derive(Component)]
+pub struct ViewPostProcessEffectPipeline<E: PostProcessEffect>(
+    CachedRenderPipelineId,
+    PhantomData<fn() -> E>,
+);
@@ -0,0 +1,107 @@
+use std::marker::PhantomData;
+
+use crate::post_process_effect::{
+    PostProcessEffect, PostProcessEffectLabel, PostProcessEffectPipeline, ViewPostProcessChain,
+    ViewPostProcessEffectPipeline,
+};
+use bevy_ecs::{prelude::*, query::QueryItem};
+use bevy_render::{
+    extract_component::{ComponentUniforms, DynamicUniformIndex},
+    render_graph::{NodeRunError, RenderGraphContext, RenderLabel, ViewNode},
+    render_resource::{
+        BindGroupEntries, Operations, PipelineCache, RenderPassColorAttachment,
+        RenderPassDescriptor,
+    },
+    renderer::RenderContext,
+    view::ViewTarget,
+};
+
+/// The render graph node of a [`PostProcessEffect`].
+///
+/// The node skips the views whose [`PostProcessChain`](super::PostProcessChain) lists the
+/// effect, since the chain runs it instead.
+pub struct PostProcessEffectNode<E: PostProcessEffect>(PhantomData<fn() -> E>);
+
+impl<E: PostProcessEffect> FromWorld for PostProcessEffectNode<E> {
+    fn from_world(_world: &mut World) -> Self {
+        PostProcessEffectNode(PhantomData)
+    }
+}
+
+impl<E: PostProcessEffect> ViewNode for PostProcessEffectNode<E> {
+    type ViewQuery = Option<&'static ViewPostProcessChain>;
+
+    fn run(
+        &self,
+        graph: &mut RenderGraphContext,
+        render_context: &mut RenderContext,
+        chain: QueryItem<Self::ViewQuery>,
+        world: &World,
+    ) -> Result<(), NodeRunError> {
+        if chain.is_some_and(|chain| chain.contains(PostProcessEffectLabel::of::<E>().intern())) {
+            return Ok(());
+        }
+
+        run_post_process_effect::<E>(render_context, world, graph.view_entity());
+
+        Ok(())
+    }
+}
+
+/// Renders the [`PostProcessEffect`] `E` for the `view`, if it has it.
+pub(super) fn run_post_process_effect<E: PostProcessEffect>(
+    render_context: &mut RenderContext,
+    world: &World,
+    view: Entity,
+) {
+    let Some(view) = world.get_entity(view) else {
+        return;
+    };
+    let (Some(target), Some(pipeline), Some(uniform_index)) = (
+        view.get::<ViewTarget>(),
+        view.get::<ViewPostProcessEffectPipeline<E>>(),
+        view.get::<DynamicUniformIndex<E>>(),
+    ) else {
+        return;
+    };
+
+    let pipeline_cache = world.resource::<PipelineCache>();
+    let effect_pipeline = world.resource::<PostProcessEffectPipeline<E>>();
+    let uniforms = world.resource::<ComponentUniforms<E>>();
+
+    let Some(uniforms) = uniforms.binding() else {
+        return;
+    };
+
+    let Some(pipeline) = pipeline_cache.get_render_pipeline(pipeline.0) else {
+        return;
+    };
+
+    let post_process = target.post_process_write();
+
+    let bind_group = render_context.render_device().create_bind_group(
+        "post_process_effect_bind_group",
+        &effect_pipeline.texture_bind_group,
+        &BindGroupEntries::sequential((post_process.source, &effect_pipeline.sampler, uniforms)),
+    );
+
+    let pass_descriptor = RenderPassDescriptor {
+        label: Some("post_process_effect_pass"),
+        color_attachments: &[Some(RenderPassColorAttachment {
+            view: post_process.destination,
+            resolve_target: None,
+            ops: Operations::default(),
+        })],
+        depth_stencil_attachment: None,
+        timestamp_writes: None,
+        occlusion_query_set: None,
+    };
+
+    let mut render_pass = render_context
+        .command_encoder()
+        .begin_render_pass(&pass_descriptor);
+
+    render_pass.set_pipeline(pipeline);
+    render_pass.set_bind_group(0, &bind_group, &[uniform_index.index()]);
+    render_pass.draw(0..3, 0..1);
+}
@@ -0,0 +1,253 @@
+use crate::{
+    core_2d::graph::{Core2d, Node2d},
+    core_3d::graph::{Core3d, Node3d},
+    fullscreen_vertex_shader::fullscreen_shader_vertex_state,
+};
+use bevy_app::prelude::*;
+use bevy_asset::{load_internal_asset, Handle};
+use bevy_ecs::{prelude::*, query::QueryItem};
+use bevy_math::{UVec3, Vec3};
+use bevy_reflect::{std_traits::ReflectDefault, Reflect};
+use bevy_render::{
+    extract_component::{ExtractComponent, ExtractComponentPlugin, UniformComponentPlugin},
+    prelude::Camera,
+    render_graph::{RenderGraphApp, ViewNodeRunner},
+    render_resource::{
+        binding_types::{sampler, texture_2d, uniform_buffer},
+        *,
+    },
+    renderer::RenderDevice,
+    texture::BevyDefault,
+    view::{ExtractedView, ViewTarget},
+    Render, RenderApp, RenderSet,
+};
+
+mod node;
+
+pub use node::PosterizeNode;
+
+/// Reduces the number of colors of a 2D or 3D camera, for a retro look.
+///
+/// Each channel keeps only [`Posterize::bits`] bits of precision, and the banding between the
+/// remaining colors is broken up with an ordered [`PosterizeDither`] pattern.
+///
+/// Posterization is applied after tonemapping, antialiasing, and sharpening, so that they don't
+/// blur the dithering pattern, and before upscaling and the UI.
+#[derive(Component, Reflect, Clone, Debug)]
+#[reflect(Component, Default)]
+pub struct Posterize {
+    /// The number of bits kept for the red, green, and blue channels of the display color.
+    ///
+    /// Each channel is clamped between 1 and 8 bits.
+    ///
+    /// The default value is 5 bits for every channel.
+    pub bits: UVec3,
+    /// How the banding between the remaining colors is broken up.
+    ///
+    /// The default value is [`PosterizeDither::Bayer`].
+    pub dither: PosterizeDither,
+}
+
+impl Default for Posterize {
+    fn default() -> Self {
+        Posterize {
+            bits: UVec3::splat(5),
+            dither: PosterizeDither::Bayer,
+        }
+    }
+}
+
+/// The dithering pattern of [`Posterize`].
+#[derive(Component, Reflect, Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[reflect(Component, Default)]
+pub enum PosterizeDither {
+    /// Colors are rounded to the nearest remaining color, leaving visible bands.
+    None,
+    /// An 8x8 Bayer matrix, which gives the crosshatched look of old consoles and PCs.
+    #[default]
+    Bayer,
+    /// A blue noise pattern, which is less regular than [`PosterizeDither::Bayer`] and has no
+    /// visible structure.
+    BlueNoise,
+}
+
+/// The uniform struct extracted from [`Posterize`] attached to a [`Camera`].
+/// Will be available for use in the posterize shader.
+#[doc(hidden)]
+#[derive(Component, ShaderType, Clone)]
+pub struct PosterizeUniform {
+    /// The highest value of each channel once quantized, `2^bits - 1`.
+    levels: Vec3,
+}
+
+impl ExtractComponent for Posterize {
+    type QueryData = &'static Self;
+    type QueryFilter = With<Camera>;
+    type Out = (PosterizeDither, PosterizeUniform);
+
+    fn extract_component(item: QueryItem<Self::QueryData>) -> Option<Self::Out> {
+        let bits = item.bits.clamp(UVec3::ONE, UVec3::splat(8));
+        Some((
+            item.dither,
+            PosterizeUniform {
+                levels: ((UVec3::ONE << bits) - 1).as_vec3(),
+            },
+        ))
+    }
+}
+
+const POSTERIZE_SHADER_HANDLE: Handle<Shader> = Handle::weak_from_u128(17088927778460743492);
+
+/// Adds support for the [`Posterize`] post-processing effect.
+pub struct PosterizePlugin;
+
+impl Plugin for PosterizePlugin {
+    fn build(&self, app: &mut App) {
+        load_internal_asset!(
+            app,
+            POSTERIZE_SHADER_HANDLE,
+            "posterize.wgsl",
+            Shader::from_wgsl
+        );
+
+        app.register_type::<Posterize>();
+        app.add_plugins((
+            ExtractComponentPlugin::<Posterize>::default(),
+            UniformComponentPlugin::<PosterizeUniform>::default(),
+        ));
+
+        let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+        render_app
+            .init_resource::<SpecializedRenderPipelines<PosterizePipeline>>()
+            .add_systems(
+                Render,
+                prepare_posterize_pipelines.in_set(RenderSet::Prepare),
+            )
+            .add_render_graph_node::<ViewNodeRunner<PosterizeNode>>(Core3d, Node3d::Posterize)
+            .add_render_graph_edges(
+                Core3d,
+                (
+                    Node3d::ContrastAdaptiveSharpening,
+                    Node3d::Posterize,
+                    Node3d::EndMainPassPostProcessing,
+                ),
+            )
+            .add_render_graph_node::<ViewNodeRunner<PosterizeNode>>(Core2d, Node2d::Posterize)
+            .add_render_graph_edges(
+                Core2d,
+                (
+                    Node2d::ContrastAdaptiveSharpening,
+                    Node2d::Posterize,
+                    Node2d::EndMainPassPostProcessing,
+                ),
+            );
+    }
+
+    fn finish(&self, app: &mut App) {
+        let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+        render_app.init_resource::<PosterizePipeline>();
+    }
+}
+
+#[derive(Resource)]
+pub struct PosterizePipeline {
+    texture_bind_group: BindGroupLayout,
+    sampler: Sampler,
+}
+
+impl FromWorld for PosterizePipeline {
+    fn from_world(render_world: &mut World) -> Self {
+        let render_device = render_world.resource::<RenderDevice>();
+        let texture_bind_group = render_device.create_bind_group_layout(
+            "posterize_texture_bind_group_layout",
+            &BindGroupLayoutEntries::sequential(
+                ShaderStages::FRAGMENT,
+                (
+                    texture_2d(TextureSampleType::Float { filterable: true }),
+                    sampler(SamplerBindingType::Filtering),
+                    uniform_buffer::<PosterizeUniform>(true),
+                ),
+            ),
+        );
+
+        let sampler = render_device.create_sampler(&SamplerDescriptor::default());
+
+        PosterizePipeline {
+            texture_bind_group,
+            sampler,
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Hash, Clone, Copy)]
+pub struct PosterizePipelineKey {
+    texture_format: TextureFormat,
+    dither: PosterizeDither,
+}
+
+impl SpecializedRenderPipeline for PosterizePipeline {
+    type Key = PosterizePipelineKey;
+
+    fn specialize(&self, key: Self::Key) -> RenderPipelineDescriptor {
+        let mut shader_defs = vec![];
+        match key.dither {
+            PosterizeDither::None => {}
+            PosterizeDither::Bayer => shader_defs.push("DITHER_BAYER".into()),
+            PosterizeDither::BlueNoise => shader_defs.push("DITHER_BLUE_NOISE".into()),
+        }
+
+        RenderPipelineDescriptor {
+            label: Some("posterize".into()),
+            layout: vec![self.texture_bind_group.clone()],
+            vertex: fullscreen_shader_vertex_state(),
+            fragment: Some(FragmentState {
+                shader: POSTERIZE_SHADER_HANDLE,
+                shader_defs,
+                entry_point: "fragment".into(),
+                targets: vec![Some(ColorTargetState {
+                    format: key.texture_format,
+                    blend: None,
+                    write_mask: ColorWrites::ALL,
+                })],
+            }),
+            primitive: PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: MultisampleState::default(),
+            push_constant_ranges: Vec::new(),
+        }
+    }
+}
+
+fn prepare_posterize_pipelines(
+    mut commands: Commands,
+    pipeline_cache: Res<PipelineCache>,
+    mut pipelines: ResMut<SpecializedRenderPipelines<PosterizePipeline>>,
+    posterize_pipeline: Res<PosterizePipeline>,
+    views: Query<(Entity, &ExtractedView, &PosterizeDither), With<PosterizeUniform>>,
+) {
+    for (entity, view, dither) in &views {
+        let pipeline_id = pipelines.specialize(
+            &pipeline_cache,
+            &posterize_pipeline,
+            PosterizePipelineKey {
+                texture_format: if view.hdr {
+                    ViewTarget::TEXTURE_FORMAT_HDR
+                } else {
+                    TextureFormat::bevy_default()
+                },
+                dither: *dither,
+            },
+        );
+
+        commands
+            .entity(entity)
+            .insert(ViewPosterizePipeline(pipeline_id));
+    }
+}
+
+#[derive(Component)]
+pub struct ViewPosterizePipeline(CachedRenderPipelineId);
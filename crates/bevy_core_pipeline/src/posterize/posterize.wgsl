@@ -0,0 +1,74 @@
+#import bevy_core_pipeline::fullscreen_vertex_shader::FullscreenVertexOutput
+#import bevy_render::maths::powsafe
+
+struct Posterize {
+    levels: vec3<f32>,
+}
+
+@group(0) @binding(0) var screen_texture: texture_2d<f32>;
+@group(0) @binding(1) var screen_sampler: sampler;
+@group(0) @binding(2) var<uniform> posterize: Posterize;
+
+// Threshold of an 8x8 Bayer matrix, between 0.0 and 1.0.
+//
+// The index is built by interleaving the bits of `x ^ y` and `y`, starting from the least
+// significant ones, so that neighboring pixels get thresholds that are as far apart as possible.
+fn bayer_threshold(position: vec2<u32>) -> f32 {
+    let x = position.x ^ position.y;
+    let y = position.y;
+    var index = 0u;
+    for (var bit = 0u; bit < 3u; bit += 1u) {
+        index = (index << 2u) | (((x >> bit) & 1u) << 1u) | ((y >> bit) & 1u);
+    }
+    return (f32(index) + 0.5) / 64.0;
+}
+
+// Index of the pixel along a Hilbert curve that covers a 64x64 tile.
+// https://www.shadertoy.com/view/3tB3z3
+fn hilbert_index(position: vec2<u32>) -> u32 {
+    var x = position.x % 64u;
+    var y = position.y % 64u;
+    var index = 0u;
+    for (var level = 32u; level > 0u; level /= 2u) {
+        let region_x = u32((x & level) > 0u);
+        let region_y = u32((y & level) > 0u);
+        index += level * level * ((3u * region_x) ^ region_y);
+        if (region_y == 0u) {
+            if (region_x == 1u) {
+                x = 63u - x;
+                y = 63u - y;
+            }
+            let t = x;
+            x = y;
+            y = t;
+        }
+    }
+    return index;
+}
+
+// Threshold with a blue noise distribution, between 0.0 and 1.0, from the R1 sequence laid
+// along a Hilbert curve.
+// http://extremelearning.com.au/unreasonable-effectiveness-of-quasirandom-sequences
+fn blue_noise_threshold(position: vec2<u32>) -> f32 {
+    return fract(0.5 + f32(hilbert_index(position)) * 0.6180339887498949);
+}
+
+@fragment
+fn fragment(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
+    let color = textureSample(screen_texture, screen_sampler, in.uv);
+    let position = vec2<u32>(in.position.xy);
+
+#ifdef DITHER_BAYER
+    let threshold = bayer_threshold(position % 8u);
+#else ifdef DITHER_BLUE_NOISE
+    let threshold = blue_noise_threshold(position);
+#else
+    let threshold = 0.5;
+#endif
+
+    // Quantize the display color, like a limited palette would, and convert it back to linear
+    // space, as the output texture expects.
+    let display = powsafe(saturate(color.rgb), 1.0 / 2.2);
+    let quantized = min(floor(display * posterize.levels + threshold), posterize.levels) / posterize.levels;
+    return vec4<f32>(powsafe(quantized, 2.2), color.a);
+}
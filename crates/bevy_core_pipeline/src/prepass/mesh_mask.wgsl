@@ -0,0 +1,10 @@
+#define_import_path bevy_core_pipeline::mesh_mask
+
+// The bits of the texture written by the `MeshMaskPrepass`.
+
+// [2^0, 2^3) - the bloom layer of the mesh, see `BloomSettings::layer_intensity`
+const MESH_MASK_BLOOM_LAYER_BITS: u32 = 7u;
+// 2^3 - the mesh has `ResponsiveAntiAliasing`
+const MESH_MASK_RESPONSIVE_ANTI_ALIASING_BIT: u32 = 8u;
+// 2^4 - the mesh has `NoMotionBlur`
+const MESH_MASK_NO_MOTION_BLUR_BIT: u32 = 16u;
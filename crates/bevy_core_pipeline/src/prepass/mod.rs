@@ -8,6 +8,7 @@
 //! [`DepthPrepass`]
 //! [`NormalPrepass`]
 //! [`MotionVectorPrepass`]
+//! [`MeshMaskPrepass`]
 //!
 //! The textures are automatically added to the default mesh view bindings. You can also get the raw textures
 //! by querying the [`ViewPrepassTextures`] component on any camera with a prepass component.
@@ -29,7 +30,7 @@ pub mod node;
 
 use std::ops::Range;
 
-use bevy_asset::AssetId;
+use bevy_asset::{AssetId, Handle};
 use bevy_ecs::prelude::*;
 use bevy_math::Mat4;
 use bevy_reflect::Reflect;
@@ -41,7 +42,7 @@ use bevy_render::{
     },
     render_resource::{
         BindGroupId, CachedRenderPipelineId, ColorTargetState, ColorWrites, DynamicUniformBuffer,
-        Extent3d, ShaderType, TextureFormat, TextureView,
+        Extent3d, Shader, ShaderType, TextureFormat, TextureView,
     },
     texture::ColorAttachment,
 };
@@ -50,6 +51,11 @@ use crate::deferred::{DEFERRED_LIGHTING_PASS_ID_FORMAT, DEFERRED_PREPASS_FORMAT}
 
 pub const NORMAL_PREPASS_FORMAT: TextureFormat = TextureFormat::Rgb10a2Unorm;
 pub const MOTION_VECTOR_PREPASS_FORMAT: TextureFormat = TextureFormat::Rg16Float;
+pub const MESH_MASK_PREPASS_FORMAT: TextureFormat = TextureFormat::R8Uint;
+
+/// Defines the bits of the [`MeshMaskPrepass`] texture, under the `bevy_core_pipeline::mesh_mask`
+/// import path.
+pub const MESH_MASK_SHADER_HANDLE: Handle<Shader> = Handle::weak_from_u128(4176209539820158734);
 
 /// If added to a [`crate::prelude::Camera3d`] then depth values will be copied to a separate texture available to the main pass.
 #[derive(Component, Default, Reflect, Clone)]
@@ -64,6 +70,20 @@ pub struct NormalPrepass;
 #[derive(Component, Default, Reflect, Clone)]
 pub struct MotionVectorPrepass;
 
+/// If added to a [`crate::prelude::Camera3d`] then a few flags of each mesh will be written to a separate texture,
+/// letting post-processing effects treat meshes differently.
+///
+/// Each pixel of the [`MESH_MASK_PREPASS_FORMAT`] texture holds:
+/// * the bloom layer of the mesh in its lowest 3 bits, see
+///   [`BloomSettings::layer_intensity`](crate::bloom::BloomSettings::layer_intensity)
+/// * whether the mesh has [`ResponsiveAntiAliasing`](crate::experimental::taa::ResponsiveAntiAliasing)
+///   in bit 3
+/// * whether the mesh has [`NoMotionBlur`](crate::motion_blur::NoMotionBlur) in bit 4
+///
+/// Pixels not covered by an opaque or alpha-masked mesh are 0.
+#[derive(Component, Default, Reflect, Clone)]
+pub struct MeshMaskPrepass;
+
 /// If added to a [`crate::prelude::Camera3d`] then deferred materials will be rendered to the deferred gbuffer texture and will be available to subsequent passes.
 /// Note the default deferred lighting plugin also requires `DepthPrepass` to work correctly.
 #[derive(Component, Default, Reflect)]
@@ -105,6 +125,9 @@ pub struct ViewPrepassTextures {
     /// A texture that specifies the deferred lighting pass id for a material.
     /// Exists only if [`DeferredPrepass`] is added to the `ViewTarget`
     pub deferred_lighting_pass_id: Option<ColorAttachment>,
+    /// The mesh flags generated by the prepass.
+    /// Exists only if [`MeshMaskPrepass`] is added to the `ViewTarget`
+    pub mesh_mask: Option<ColorAttachment>,
     /// The size of the textures.
     pub size: Extent3d,
 }
@@ -127,6 +150,10 @@ impl ViewPrepassTextures {
     pub fn deferred_view(&self) -> Option<&TextureView> {
         self.deferred.as_ref().map(|t| &t.texture.default_view)
     }
+
+    pub fn mesh_mask_view(&self) -> Option<&TextureView> {
+        self.mesh_mask.as_ref().map(|t| &t.texture.default_view)
+    }
 }
 
 /// Opaque phase of the 3D prepass.
@@ -297,6 +324,7 @@ pub fn prepass_target_descriptors(
     normal_prepass: bool,
     motion_vector_prepass: bool,
     deferred_prepass: bool,
+    mesh_mask_prepass: bool,
 ) -> Vec<Option<ColorTargetState>> {
     vec![
         normal_prepass.then_some(ColorTargetState {
@@ -319,5 +347,10 @@ pub fn prepass_target_descriptors(
             blend: None,
             write_mask: ColorWrites::ALL,
         }),
+        mesh_mask_prepass.then_some(ColorTargetState {
+            format: MESH_MASK_PREPASS_FORMAT,
+            blend: None,
+            write_mask: ColorWrites::ALL,
+        }),
     ]
 }
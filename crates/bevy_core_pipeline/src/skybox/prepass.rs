@@ -25,8 +25,8 @@ use bevy_utils::prelude::default;
 use crate::{
     core_3d::CORE_3D_DEPTH_FORMAT,
     prepass::{
-        prepass_target_descriptors, MotionVectorPrepass, NormalPrepass, PreviousViewData,
-        PreviousViewUniforms,
+        prepass_target_descriptors, MeshMaskPrepass, MotionVectorPrepass, NormalPrepass,
+        PreviousViewData, PreviousViewUniforms,
     },
     Skybox,
 };
@@ -48,6 +48,7 @@ pub struct SkyboxPrepassPipeline {
 pub struct SkyboxPrepassPipelineKey {
     samples: u32,
     normal_prepass: bool,
+    mesh_mask_prepass: bool,
 }
 
 /// Stores the ID for a camera's specialized pipeline, so it can be retrieved from the
@@ -105,7 +106,13 @@ impl SpecializedRenderPipeline for SkyboxPrepassPipeline {
                 shader: SKYBOX_PREPASS_SHADER_HANDLE,
                 shader_defs: vec![],
                 entry_point: "fragment".into(),
-                targets: prepass_target_descriptors(key.normal_prepass, true, false),
+                // The skybox only writes motion vectors, and leaves the cleared mesh mask alone
+                targets: prepass_target_descriptors(
+                    key.normal_prepass,
+                    true,
+                    false,
+                    key.mesh_mask_prepass,
+                ),
             }),
         }
     }
@@ -118,12 +125,16 @@ pub fn prepare_skybox_prepass_pipelines(
     mut pipelines: ResMut<SpecializedRenderPipelines<SkyboxPrepassPipeline>>,
     msaa: Res<Msaa>,
     pipeline: Res<SkyboxPrepassPipeline>,
-    views: Query<(Entity, Has<NormalPrepass>), (With<Skybox>, With<MotionVectorPrepass>)>,
+    views: Query<
+        (Entity, Has<NormalPrepass>, Has<MeshMaskPrepass>),
+        (With<Skybox>, With<MotionVectorPrepass>),
+    >,
 ) {
-    for (entity, normal_prepass) in &views {
+    for (entity, normal_prepass, mesh_mask_prepass) in &views {
         let pipeline_key = SkyboxPrepassPipelineKey {
             samples: msaa.samples(),
             normal_prepass,
+            mesh_mask_prepass,
         };
 
         let render_skybox_prepass_pipeline =
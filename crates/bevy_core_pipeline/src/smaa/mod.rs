@@ -17,10 +17,12 @@
 //! likely want to turn the default MSAA off by inserting the
 //! [`bevy_render::Msaa::Off`] resource into the [`App`].
 //!
+//! The temporal variant, SMAA T2x, can be selected with [`SmaaPreset::T2x`].
+//!
 //! Those who have used SMAA in other engines should be aware that Bevy doesn't
 //! yet support the following more advanced features of SMAA:
 //!
-//! * The temporal variant.
+//! * The spatial multisampling variants (S2x and 4x).
 //!
 //! * Depth- and chroma-based edge detection.
 //!
@@ -32,20 +34,21 @@
 
 use bevy_app::{App, Plugin};
 use bevy_asset::{load_internal_asset, load_internal_binary_asset, Handle};
+use bevy_core::FrameCount;
 use bevy_derive::{Deref, DerefMut};
 use bevy_ecs::{
     component::Component,
-    entity::Entity,
-    query::{QueryItem, With},
+    entity::{Entity, EntityHashMap},
+    query::{Has, QueryItem, With},
     reflect::ReflectComponent,
     schedule::IntoSystemConfigs as _,
-    system::{lifetimeless::Read, Commands, Query, Res, ResMut, Resource},
+    system::{lifetimeless::Read, Commands, Local, Query, Res, ResMut, Resource},
     world::{FromWorld, World},
 };
-use bevy_math::{vec4, Vec4};
+use bevy_math::{vec2, vec4, UVec4, Vec2, Vec4};
 use bevy_reflect::{std_traits::ReflectDefault, Reflect};
 use bevy_render::{
-    camera::ExtractedCamera,
+    camera::{ExtractedCamera, TemporalJitter},
     extract_component::{ExtractComponent, ExtractComponentPlugin},
     render_asset::{RenderAssetUsages, RenderAssets},
     render_graph::{
@@ -77,6 +80,7 @@ use bevy_utils::prelude::default;
 use crate::{
     core_2d::graph::{Core2d, Node2d},
     core_3d::graph::{Core3d, Node3d},
+    prepass::{MotionVectorPrepass, ViewPrepassTextures},
 };
 
 /// The handle of the `smaa.wgsl` shader.
@@ -100,6 +104,14 @@ pub struct SmaaSettings {
     pub preset: SmaaPreset,
 }
 
+impl SmaaSettings {
+    /// Returns true if these settings select SMAA T2x and the view has what it
+    /// needs: camera jitter and motion vectors.
+    fn is_temporal(&self, temporal_jitter: bool, motion_vector_prepass: bool) -> bool {
+        self.preset == SmaaPreset::T2x && temporal_jitter && motion_vector_prepass
+    }
+}
+
 /// A preset quality level for SMAA.
 ///
 /// Higher values are slower but result in a higher-quality image.
@@ -122,11 +134,34 @@ pub enum SmaaPreset {
 
     /// Thirty-two search steps, 8 diagonal search steps, and corner detection.
     Ultra,
+
+    /// The temporal variant, SMAA T2x: the *high* preset, applied to frames
+    /// that are alternately offset by a quarter of a pixel, with each frame
+    /// blended with the previous one.
+    ///
+    /// This antialiases subpixel features and shimmering edges better than
+    /// the spatial presets, with less ghosting and blur than TAA.
+    ///
+    /// The camera needs the [`TemporalJitter`] and [`MotionVectorPrepass`]
+    /// components, otherwise this behaves like [`SmaaPreset::High`]. Don't
+    /// combine it with TAA, which jitters the camera on its own.
+    T2x,
 }
 
+/// The camera jitter of SMAA T2x on even and odd frames, in pixels.
+///
+/// These match the `@SUBSAMPLE_INDICES` table in `smaa.wgsl`, once converted
+/// to the conventions of [`TemporalJitter`].
+const SMAA_T2X_JITTERS: [Vec2; 2] = [vec2(-0.25, -0.25), vec2(0.25, 0.25)];
+
+/// The subsample indices that select the area texture matching each entry of
+/// [`SMAA_T2X_JITTERS`].
+const SMAA_T2X_SUBSAMPLE_INDICES: [Vec4; 2] = [vec4(1.0, 1.0, 1.0, 0.0), vec4(2.0, 2.0, 2.0, 0.0)];
+
 /// A render world resource that holds all render pipeline data needed for SMAA.
 ///
-/// There are three separate passes, so we need three separate pipelines.
+/// There are three separate passes, so we need three separate pipelines, plus
+/// a fourth for the temporal resolve of SMAA T2x.
 #[derive(Resource)]
 pub struct SmaaPipelines {
     /// Pass 1: Edge detection.
@@ -135,6 +170,8 @@ pub struct SmaaPipelines {
     blending_weight_calculation: SmaaBlendingWeightCalculationPipeline,
     /// Pass 3: Neighborhood blending.
     neighborhood_blending: SmaaNeighborhoodBlendingPipeline,
+    /// Pass 4 (T2x only): Temporal resolve.
+    resolve: SmaaResolvePipeline,
 }
 
 /// The pipeline data for phase 1 of SMAA: edge detection.
@@ -159,6 +196,17 @@ struct SmaaNeighborhoodBlendingPipeline {
     postprocess_bind_group_layout: BindGroupLayout,
     /// The bind group layout for data specific to this pass.
     neighborhood_blending_bind_group_layout: BindGroupLayout,
+    /// The bind group layout for the velocity texture that SMAA T2x packs into
+    /// the alpha channel of the output.
+    reprojection_bind_group_layout: BindGroupLayout,
+}
+
+/// The pipeline data for phase 4 of SMAA T2x: temporal resolve.
+struct SmaaResolvePipeline {
+    /// The bind group layout common to all passes.
+    postprocess_bind_group_layout: BindGroupLayout,
+    /// The bind group layout for data specific to this pass.
+    resolve_bind_group_layout: BindGroupLayout,
 }
 
 /// A unique identifier for a set of SMAA pipelines.
@@ -168,6 +216,8 @@ pub struct SmaaNeighborhoodBlendingPipelineKey {
     texture_format: TextureFormat,
     /// The quality preset.
     preset: SmaaPreset,
+    /// Whether the velocity is packed into the alpha channel for SMAA T2x.
+    reprojection: bool,
 }
 
 /// A render world component that holds the pipeline IDs for the SMAA passes.
@@ -182,6 +232,9 @@ pub struct ViewSmaaPipelines {
     blending_weight_calculation_pipeline_id: CachedRenderPipelineId,
     /// The pipeline ID for neighborhood blending (phase 3).
     neighborhood_blending_pipeline_id: CachedRenderPipelineId,
+    /// The pipeline ID for the temporal resolve (phase 4), if SMAA T2x is in
+    /// use.
+    resolve_pipeline_id: Option<CachedRenderPipelineId>,
 }
 
 /// The render graph node that performs subpixel morphological antialiasing
@@ -191,10 +244,10 @@ pub struct SmaaNode;
 
 /// Values supplied to the GPU for SMAA.
 ///
-/// Currently, this just contains the render target metrics and values derived
-/// from them. These could be computed by the shader itself, but the original
-/// SMAA HLSL code supplied them in a uniform, so we do the same for
-/// consistency.
+/// This contains the render target metrics and values derived from them, as
+/// well as the per-frame values of SMAA T2x. The metrics could be computed by
+/// the shader itself, but the original SMAA HLSL code supplied them in a
+/// uniform, so we do the same for consistency.
 #[derive(Clone, Copy, ShaderType)]
 pub struct SmaaInfoUniform {
     /// Information about the width and height of the framebuffer.
@@ -207,6 +260,16 @@ pub struct SmaaInfoUniform {
     ///
     /// * *w*: The pixel height of the framebuffer.
     pub rt_metrics: Vec4,
+
+    /// The subsample indices that select the area texture matching the
+    /// current camera jitter. Zero unless SMAA T2x is in use.
+    pub subsample_indices: Vec4,
+
+    /// The largest weight that the temporal resolve of SMAA T2x gives to the
+    /// previous frame.
+    ///
+    /// This is 0.5, or zero when the previous frame isn't available.
+    pub history_weight: f32,
 }
 
 /// A render world component that stores the offset of each [`SmaaInfoUniform`]
@@ -248,6 +311,19 @@ pub struct SmaaTextures {
     pub blend_texture: CachedTexture,
 }
 
+/// A render world component that holds the outputs of the neighborhood
+/// blending pass of the current and previous frames, which the temporal
+/// resolve of SMAA T2x blends together.
+///
+/// This is stored on each view that uses SMAA T2x.
+#[derive(Component)]
+pub struct SmaaHistoryTextures {
+    /// The texture that neighborhood blending writes to in this frame.
+    pub write: CachedTexture,
+    /// The texture that neighborhood blending wrote to in the previous frame.
+    pub read: CachedTexture,
+}
+
 /// A render world component that stores the bind groups necessary to perform
 /// SMAA.
 ///
@@ -260,6 +336,11 @@ pub struct SmaaBindGroups {
     pub blending_weight_calculation_bind_group: BindGroup,
     /// The bind group for the final pass (neighborhood blending).
     pub neighborhood_blending_bind_group: BindGroup,
+    /// The bind group for the velocity texture of the neighborhood blending
+    /// pass, if SMAA T2x is in use.
+    pub reprojection_bind_group: Option<BindGroup>,
+    /// The bind group for the temporal resolve pass, if SMAA T2x is in use.
+    pub resolve_bind_group: Option<BindGroup>,
 }
 
 /// Stores the specialized render pipelines for SMAA.
@@ -278,6 +359,9 @@ pub struct SmaaSpecializedRenderPipelines {
     /// Specialized render pipelines for the third phase (neighborhood
     /// blending).
     neighborhood_blending: SpecializedRenderPipelines<SmaaNeighborhoodBlendingPipeline>,
+
+    /// Specialized render pipelines for the fourth phase (temporal resolve).
+    resolve: SpecializedRenderPipelines<SmaaResolvePipeline>,
 }
 
 impl Plugin for SmaaPlugin {
@@ -334,6 +418,7 @@ impl Plugin for SmaaPlugin {
             .add_systems(
                 Render,
                 (
+                    prepare_smaa_jitter.in_set(RenderSet::ManageViews),
                     prepare_smaa_pipelines.in_set(RenderSet::Prepare),
                     prepare_smaa_uniforms.in_set(RenderSet::PrepareResources),
                     prepare_smaa_textures.in_set(RenderSet::PrepareResources),
@@ -419,6 +504,29 @@ impl FromWorld for SmaaPipelines {
             ),
         );
 
+        // Create the reprojection bind group layout (pass 3 of T2x, bind group 2).
+        let reprojection_bind_group_layout = render_device.create_bind_group_layout(
+            "SMAA reprojection bind group layout",
+            &BindGroupLayoutEntries::single(
+                ShaderStages::FRAGMENT,
+                texture_2d(TextureSampleType::Float { filterable: true }), // velocity texture
+            ),
+        );
+
+        // Create the resolve bind group layout (pass 4 of T2x, bind group 1).
+        let resolve_bind_group_layout = render_device.create_bind_group_layout(
+            "SMAA resolve bind group layout",
+            &BindGroupLayoutEntries::sequential(
+                ShaderStages::FRAGMENT,
+                (
+                    texture_2d(TextureSampleType::Float { filterable: true }), // current color texture
+                    texture_2d(TextureSampleType::Float { filterable: true }), // previous color texture
+                    texture_2d(TextureSampleType::Float { filterable: true }), // velocity texture
+                    sampler(SamplerBindingType::Filtering),
+                ),
+            ),
+        );
+
         SmaaPipelines {
             edge_detection: SmaaEdgeDetectionPipeline {
                 postprocess_bind_group_layout: postprocess_bind_group_layout.clone(),
@@ -429,8 +537,13 @@ impl FromWorld for SmaaPipelines {
                 blending_weight_calculation_bind_group_layout,
             },
             neighborhood_blending: SmaaNeighborhoodBlendingPipeline {
-                postprocess_bind_group_layout,
+                postprocess_bind_group_layout: postprocess_bind_group_layout.clone(),
                 neighborhood_blending_bind_group_layout,
+                reprojection_bind_group_layout,
+            },
+            resolve: SmaaResolvePipeline {
+                postprocess_bind_group_layout,
+                resolve_bind_group_layout,
             },
         }
     }
@@ -557,14 +670,22 @@ impl SpecializedRenderPipeline for SmaaNeighborhoodBlendingPipeline {
     type Key = SmaaNeighborhoodBlendingPipelineKey;
 
     fn specialize(&self, key: Self::Key) -> RenderPipelineDescriptor {
-        let shader_defs = vec!["SMAA_NEIGHBORHOOD_BLENDING".into(), key.preset.shader_def()];
+        let mut shader_defs = vec!["SMAA_NEIGHBORHOOD_BLENDING".into(), key.preset.shader_def()];
+        let mut layout = vec![
+            self.postprocess_bind_group_layout.clone(),
+            self.neighborhood_blending_bind_group_layout.clone(),
+        ];
+
+        // SMAA T2x packs the velocity into the alpha channel, for the resolve
+        // pass.
+        if key.reprojection {
+            shader_defs.push("SMAA_REPROJECTION".into());
+            layout.push(self.reprojection_bind_group_layout.clone());
+        }
 
         RenderPipelineDescriptor {
             label: Some("SMAA neighborhood blending".into()),
-            layout: vec![
-                self.postprocess_bind_group_layout.clone(),
-                self.neighborhood_blending_bind_group_layout.clone(),
-            ],
+            layout,
             vertex: VertexState {
                 shader: SMAA_SHADER_HANDLE,
                 shader_defs: shader_defs.clone(),
@@ -589,16 +710,80 @@ impl SpecializedRenderPipeline for SmaaNeighborhoodBlendingPipeline {
     }
 }
 
+// Phase 4 (T2x only): temporal resolve.
+impl SpecializedRenderPipeline for SmaaResolvePipeline {
+    type Key = TextureFormat;
+
+    fn specialize(&self, texture_format: Self::Key) -> RenderPipelineDescriptor {
+        let shader_defs = vec!["SMAA_RESOLVE".into(), SmaaPreset::T2x.shader_def()];
+
+        RenderPipelineDescriptor {
+            label: Some("SMAA resolve".into()),
+            layout: vec![
+                self.postprocess_bind_group_layout.clone(),
+                self.resolve_bind_group_layout.clone(),
+            ],
+            vertex: VertexState {
+                shader: SMAA_SHADER_HANDLE,
+                shader_defs: shader_defs.clone(),
+                entry_point: "resolve_vertex_main".into(),
+                buffers: vec![],
+            },
+            fragment: Some(FragmentState {
+                shader: SMAA_SHADER_HANDLE,
+                shader_defs,
+                entry_point: "resolve_fragment_main".into(),
+                targets: vec![Some(ColorTargetState {
+                    format: texture_format,
+                    blend: None,
+                    write_mask: ColorWrites::ALL,
+                })],
+            }),
+            push_constant_ranges: vec![],
+            primitive: PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: MultisampleState::default(),
+        }
+    }
+}
+
+/// A system, part of the render app, that jitters the cameras that use SMAA
+/// T2x by alternating quarter pixel offsets.
+fn prepare_smaa_jitter(
+    frame_count: Res<FrameCount>,
+    mut view_targets: Query<(&SmaaSettings, &mut TemporalJitter)>,
+) {
+    for (settings, mut jitter) in &mut view_targets {
+        if settings.preset == SmaaPreset::T2x {
+            jitter.offset = SMAA_T2X_JITTERS[frame_count.0 as usize % 2];
+        }
+    }
+}
+
 /// A system, part of the render app, that specializes the three pipelines
-/// needed for SMAA according to each view's SMAA settings.
+/// needed for SMAA according to each view's SMAA settings, and the resolve
+/// pipeline for views that use SMAA T2x.
 fn prepare_smaa_pipelines(
     mut commands: Commands,
     pipeline_cache: Res<PipelineCache>,
     mut specialized_render_pipelines: ResMut<SmaaSpecializedRenderPipelines>,
     smaa_pipelines: Res<SmaaPipelines>,
-    view_targets: Query<(Entity, &ExtractedView, &SmaaSettings)>,
+    view_targets: Query<(
+        Entity,
+        &ExtractedView,
+        &SmaaSettings,
+        Has<TemporalJitter>,
+        Has<MotionVectorPrepass>,
+    )>,
 ) {
-    for (entity, view, settings) in &view_targets {
+    for (entity, view, settings, temporal_jitter, motion_vector_prepass) in &view_targets {
+        let temporal = settings.is_temporal(temporal_jitter, motion_vector_prepass);
+        let texture_format = if view.hdr {
+            ViewTarget::TEXTURE_FORMAT_HDR
+        } else {
+            TextureFormat::bevy_default()
+        };
+
         let edge_detection_pipeline_id = specialized_render_pipelines.edge_detection.specialize(
             &pipeline_cache,
             &smaa_pipelines.edge_detection,
@@ -619,34 +804,65 @@ fn prepare_smaa_pipelines(
                 &pipeline_cache,
                 &smaa_pipelines.neighborhood_blending,
                 SmaaNeighborhoodBlendingPipelineKey {
-                    texture_format: if view.hdr {
-                        ViewTarget::TEXTURE_FORMAT_HDR
-                    } else {
-                        TextureFormat::bevy_default()
-                    },
+                    texture_format,
                     preset: settings.preset,
+                    reprojection: temporal,
                 },
             );
 
+        let resolve_pipeline_id = temporal.then(|| {
+            specialized_render_pipelines.resolve.specialize(
+                &pipeline_cache,
+                &smaa_pipelines.resolve,
+                texture_format,
+            )
+        });
+
         commands.entity(entity).insert(ViewSmaaPipelines {
             edge_detection_pipeline_id,
             blending_weight_calculation_pipeline_id,
             neighborhood_blending_pipeline_id,
+            resolve_pipeline_id,
         });
     }
 }
 
 /// A system, part of the render app, that builds the [`SmaaInfoUniform`] data
 /// for each view with SMAA enabled and writes the resulting data to GPU memory.
+#[allow(clippy::too_many_arguments)]
 fn prepare_smaa_uniforms(
     mut commands: Commands,
     render_device: Res<RenderDevice>,
     render_queue: Res<RenderQueue>,
-    view_targets: Query<(Entity, &ExtractedView), With<SmaaSettings>>,
+    frame_count: Res<FrameCount>,
+    view_targets: Query<(
+        Entity,
+        &ExtractedView,
+        &SmaaSettings,
+        Has<TemporalJitter>,
+        Has<MotionVectorPrepass>,
+    )>,
     mut smaa_info_buffer: ResMut<SmaaInfoUniformBuffer>,
+    mut previous_temporal_viewports: Local<EntityHashMap<UVec4>>,
+    mut temporal_viewports: Local<EntityHashMap<UVec4>>,
 ) {
     smaa_info_buffer.clear();
-    for (entity, view) in &view_targets {
+    temporal_viewports.clear();
+    for (entity, view, settings, temporal_jitter, motion_vector_prepass) in &view_targets {
+        let (subsample_indices, history_weight) =
+            if settings.is_temporal(temporal_jitter, motion_vector_prepass) {
+                // The history of views that just started using SMAA T2x, or
+                // were resized, isn't usable.
+                temporal_viewports.insert(entity, view.viewport);
+                let has_history = previous_temporal_viewports.get(&entity) == Some(&view.viewport);
+                (
+                    SMAA_T2X_SUBSAMPLE_INDICES[frame_count.0 as usize % 2],
+                    if has_history { 0.5 } else { 0.0 },
+                )
+            } else {
+                (Vec4::ZERO, 0.0)
+            };
+
         let offset = smaa_info_buffer.push(&SmaaInfoUniform {
             rt_metrics: vec4(
                 1.0 / view.viewport.z as f32,
@@ -654,6 +870,8 @@ fn prepare_smaa_uniforms(
                 view.viewport.z as f32,
                 view.viewport.w as f32,
             ),
+            subsample_indices,
+            history_weight,
         });
         commands
             .entity(entity)
@@ -661,6 +879,7 @@ fn prepare_smaa_uniforms(
     }
 
     smaa_info_buffer.write_buffer(&render_device, &render_queue);
+    std::mem::swap(&mut *previous_temporal_viewports, &mut *temporal_viewports);
 }
 
 /// A system, part of the render app, that builds the intermediate textures for
@@ -668,14 +887,23 @@ fn prepare_smaa_uniforms(
 ///
 /// Phase 1 (edge detection) needs a two-channel RG texture and an 8-bit stencil
 /// texture; phase 2 (blend weight calculation) needs a four-channel RGBA
-/// texture.
+/// texture. SMAA T2x additionally needs two history textures in the format of
+/// the view, which alternate between being written and read every frame.
 fn prepare_smaa_textures(
     mut commands: Commands,
     render_device: Res<RenderDevice>,
     mut texture_cache: ResMut<TextureCache>,
-    view_targets: Query<(Entity, &ExtractedCamera), (With<ExtractedView>, With<SmaaSettings>)>,
+    frame_count: Res<FrameCount>,
+    view_targets: Query<(
+        Entity,
+        &ExtractedCamera,
+        &ExtractedView,
+        &SmaaSettings,
+        Has<TemporalJitter>,
+        Has<MotionVectorPrepass>,
+    )>,
 ) {
-    for (entity, camera) in &view_targets {
+    for (entity, camera, view, settings, temporal_jitter, motion_vector_prepass) in &view_targets {
         let Some(texture_size) = camera.physical_target_size else {
             continue;
         };
@@ -737,6 +965,40 @@ fn prepare_smaa_textures(
             edge_detection_stencil_texture,
             blend_texture,
         });
+
+        if !settings.is_temporal(temporal_jitter, motion_vector_prepass) {
+            commands.entity(entity).remove::<SmaaHistoryTextures>();
+            continue;
+        }
+
+        // Create the two history textures for SMAA T2x.
+        let mut history_texture_descriptor = TextureDescriptor {
+            label: Some("SMAA history 1 texture"),
+            size: texture_size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format: if view.hdr {
+                ViewTarget::TEXTURE_FORMAT_HDR
+            } else {
+                TextureFormat::bevy_default()
+            },
+            usage: TextureUsages::TEXTURE_BINDING | TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        };
+        let history_1_texture =
+            texture_cache.get(&render_device, history_texture_descriptor.clone());
+        history_texture_descriptor.label = Some("SMAA history 2 texture");
+        let history_2_texture = texture_cache.get(&render_device, history_texture_descriptor);
+
+        let (write, read) = if frame_count.0 % 2 == 0 {
+            (history_1_texture, history_2_texture)
+        } else {
+            (history_2_texture, history_1_texture)
+        };
+        commands
+            .entity(entity)
+            .insert(SmaaHistoryTextures { write, read });
     }
 }
 
@@ -747,7 +1009,15 @@ fn prepare_smaa_bind_groups(
     render_device: Res<RenderDevice>,
     smaa_pipelines: Res<SmaaPipelines>,
     images: Res<RenderAssets<GpuImage>>,
-    view_targets: Query<(Entity, &SmaaTextures), (With<ExtractedView>, With<SmaaSettings>)>,
+    view_targets: Query<
+        (
+            Entity,
+            &SmaaTextures,
+            Option<&SmaaHistoryTextures>,
+            Option<&ViewPrepassTextures>,
+        ),
+        (With<ExtractedView>, With<SmaaSettings>),
+    >,
 ) {
     // Fetch the two lookup textures. These are bundled in this library.
     let (Some(search_texture), Some(area_texture)) = (
@@ -757,7 +1027,7 @@ fn prepare_smaa_bind_groups(
         return;
     };
 
-    for (entity, smaa_textures) in &view_targets {
+    for (entity, smaa_textures, history_textures, prepass_textures) in &view_targets {
         // We use the same sampler settings for all textures, so we can build
         // only one and reuse it.
         let sampler = render_device.create_sampler(&SamplerDescriptor {
@@ -770,6 +1040,11 @@ fn prepare_smaa_bind_groups(
             ..default()
         });
 
+        // SMAA T2x reads the motion vectors in the last two passes.
+        let temporal_textures = history_textures.zip(
+            prepass_textures.and_then(|prepass_textures| prepass_textures.motion_vectors_view()),
+        );
+
         commands.entity(entity).insert(SmaaBindGroups {
             edge_detection_bind_group: render_device.create_bind_group(
                 Some("SMAA edge detection bind group"),
@@ -800,6 +1075,27 @@ fn prepare_smaa_bind_groups(
                     &sampler,
                 )),
             ),
+            reprojection_bind_group: temporal_textures.map(|(_, motion_vectors)| {
+                render_device.create_bind_group(
+                    Some("SMAA reprojection bind group"),
+                    &smaa_pipelines
+                        .neighborhood_blending
+                        .reprojection_bind_group_layout,
+                    &BindGroupEntries::single(motion_vectors),
+                )
+            }),
+            resolve_bind_group: temporal_textures.map(|(history_textures, motion_vectors)| {
+                render_device.create_bind_group(
+                    Some("SMAA resolve bind group"),
+                    &smaa_pipelines.resolve.resolve_bind_group_layout,
+                    &BindGroupEntries::sequential((
+                        &history_textures.write.default_view,
+                        &history_textures.read.default_view,
+                        motion_vectors,
+                        &sampler,
+                    )),
+                )
+            }),
         });
     }
 }
@@ -811,6 +1107,7 @@ impl ViewNode for SmaaNode {
         Read<SmaaInfoUniformOffset>,
         Read<SmaaTextures>,
         Read<SmaaBindGroups>,
+        Option<Read<SmaaHistoryTextures>>,
     );
 
     fn run<'w>(
@@ -823,6 +1120,7 @@ impl ViewNode for SmaaNode {
             view_smaa_uniform_offset,
             smaa_textures,
             view_smaa_bind_groups,
+            history_textures,
         ): QueryItem<'w, Self::ViewQuery>,
         world: &'w World,
     ) -> Result<(), NodeRunError> {
@@ -845,6 +1143,35 @@ impl ViewNode for SmaaNode {
             return Ok(());
         };
 
+        // SMAA T2x renders the neighborhood blending pass to the history
+        // texture instead, and blends it with the previous frame in a fourth
+        // pass.
+        let resolve = match view_pipelines.resolve_pipeline_id {
+            Some(resolve_pipeline_id) => {
+                let (
+                    Some(resolve_pipeline),
+                    Some(history_textures),
+                    Some(reprojection_bind_group),
+                    Some(resolve_bind_group),
+                ) = (
+                    pipeline_cache.get_render_pipeline(resolve_pipeline_id),
+                    history_textures,
+                    &view_smaa_bind_groups.reprojection_bind_group,
+                    &view_smaa_bind_groups.resolve_bind_group,
+                )
+                else {
+                    return Ok(());
+                };
+                Some((
+                    resolve_pipeline,
+                    history_textures,
+                    reprojection_bind_group,
+                    resolve_bind_group,
+                ))
+            }
+            None => None,
+        };
+
         // Fetch the framebuffer textures.
         let postprocess = view_target.post_process_write();
         let (source, destination) = (postprocess.source, postprocess.destination);
@@ -881,10 +1208,28 @@ impl ViewNode for SmaaNode {
             smaa_info_uniform_buffer,
             view_smaa_uniform_offset,
             neighborhood_blending_pipeline,
+            resolve.map(|(_, _, reprojection_bind_group, _)| reprojection_bind_group),
             source,
-            destination,
+            match resolve {
+                Some((_, history_textures, _, _)) => &history_textures.write.default_view,
+                None => destination,
+            },
         );
 
+        // Stage 4: Temporal resolve pass.
+        if let Some((resolve_pipeline, _, _, resolve_bind_group)) = resolve {
+            perform_resolve(
+                render_context,
+                smaa_pipelines,
+                smaa_info_uniform_buffer,
+                view_smaa_uniform_offset,
+                resolve_pipeline,
+                resolve_bind_group,
+                source,
+                destination,
+            );
+        }
+
         Ok(())
     }
 }
@@ -1004,10 +1349,11 @@ fn perform_blending_weight_calculation(
     render_pass.draw(0..3, 0..1);
 }
 
-/// Performs blending weight calculation (phase 3).
+/// Performs neighborhood blending (phase 3).
 ///
 /// This runs as part of the [`SmaaNode`]. It reads from the blend weight
-/// texture. It's the only phase that writes to the postprocessing destination.
+/// texture. It writes to the postprocessing destination, or to the history
+/// texture if SMAA T2x is in use.
 #[allow(clippy::too_many_arguments)]
 fn perform_neighborhood_blending(
     render_context: &mut RenderContext,
@@ -1016,6 +1362,7 @@ fn perform_neighborhood_blending(
     smaa_info_uniform_buffer: &SmaaInfoUniformBuffer,
     view_smaa_uniform_offset: &SmaaInfoUniformOffset,
     neighborhood_blending_pipeline: &RenderPipeline,
+    reprojection_bind_group: Option<&BindGroup>,
     source: &TextureView,
     destination: &TextureView,
 ) {
@@ -1053,9 +1400,54 @@ fn perform_neighborhood_blending(
         &view_smaa_bind_groups.neighborhood_blending_bind_group,
         &[],
     );
+    if let Some(reprojection_bind_group) = reprojection_bind_group {
+        neighborhood_blending_render_pass.set_bind_group(2, reprojection_bind_group, &[]);
+    }
     neighborhood_blending_render_pass.draw(0..3, 0..1);
 }
 
+/// Performs the temporal resolve (phase 4), if SMAA T2x is in use.
+///
+/// This runs as part of the [`SmaaNode`]. It blends the output of phase 3 with
+/// that of the previous frame, and writes to the postprocessing destination.
+#[allow(clippy::too_many_arguments)]
+fn perform_resolve(
+    render_context: &mut RenderContext,
+    smaa_pipelines: &SmaaPipelines,
+    smaa_info_uniform_buffer: &SmaaInfoUniformBuffer,
+    view_smaa_uniform_offset: &SmaaInfoUniformOffset,
+    resolve_pipeline: &RenderPipeline,
+    resolve_bind_group: &BindGroup,
+    source: &TextureView,
+    destination: &TextureView,
+) {
+    let postprocess_bind_group = render_context.render_device().create_bind_group(
+        None,
+        &smaa_pipelines.resolve.postprocess_bind_group_layout,
+        &BindGroupEntries::sequential((source, &**smaa_info_uniform_buffer)),
+    );
+
+    let pass_descriptor = RenderPassDescriptor {
+        label: Some("SMAA resolve pass"),
+        color_attachments: &[Some(RenderPassColorAttachment {
+            view: destination,
+            resolve_target: None,
+            ops: default(),
+        })],
+        depth_stencil_attachment: None,
+        timestamp_writes: None,
+        occlusion_query_set: None,
+    };
+
+    let mut resolve_render_pass = render_context
+        .command_encoder()
+        .begin_render_pass(&pass_descriptor);
+    resolve_render_pass.set_pipeline(resolve_pipeline);
+    resolve_render_pass.set_bind_group(0, &postprocess_bind_group, &[**view_smaa_uniform_offset]);
+    resolve_render_pass.set_bind_group(1, resolve_bind_group, &[]);
+    resolve_render_pass.draw(0..3, 0..1);
+}
+
 impl SmaaPreset {
     /// Returns the `#define` in the shader corresponding to this quality
     /// preset.
@@ -1063,7 +1455,7 @@ impl SmaaPreset {
         match *self {
             SmaaPreset::Low => "SMAA_PRESET_LOW".into(),
             SmaaPreset::Medium => "SMAA_PRESET_MEDIUM".into(),
-            SmaaPreset::High => "SMAA_PRESET_HIGH".into(),
+            SmaaPreset::High | SmaaPreset::T2x => "SMAA_PRESET_HIGH".into(),
             SmaaPreset::Ultra => "SMAA_PRESET_ULTRA".into(),
         }
     }
@@ -294,6 +294,8 @@
 
 struct SmaaInfo {
     rt_metrics: vec4<f32>,
+    subsample_indices: vec4<f32>,
+    history_weight: f32,
 }
 
 struct VertexVaryings {
@@ -323,6 +325,11 @@ struct NeighborhoodBlendingVaryings {
     @location(1) tex_coord: vec2<f32>,
 }
 
+struct ResolveVaryings {
+    @builtin(position) position: vec4<f32>,
+    @location(0) tex_coord: vec2<f32>,
+}
+
 @group(0) @binding(0) var color_texture: texture_2d<f32>;
 @group(0) @binding(1) var<uniform> smaa_info: SmaaInfo;
 
@@ -340,8 +347,18 @@ struct NeighborhoodBlendingVaryings {
 #ifdef SMAA_NEIGHBORHOOD_BLENDING
 @group(1) @binding(0) var blend_texture: texture_2d<f32>;
 @group(1) @binding(1) var blend_sampler: sampler;
+#ifdef SMAA_REPROJECTION
+@group(2) @binding(0) var velocity_texture: texture_2d<f32>;
+#endif  // SMAA_REPROJECTION
 #endif  // SMAA_NEIGHBORHOOD_BLENDING
 
+#ifdef SMAA_RESOLVE
+@group(1) @binding(0) var current_color_texture: texture_2d<f32>;
+@group(1) @binding(1) var previous_color_texture: texture_2d<f32>;
+@group(1) @binding(2) var velocity_texture: texture_2d<f32>;
+@group(1) @binding(3) var resolve_sampler: sampler;
+#endif  // SMAA_RESOLVE
+
 //-----------------------------------------------------------------------------
 // SMAA Presets
 
@@ -438,6 +455,19 @@ const SMAA_CORNER_ROUNDING: u32 = 25u;
  */
 const SMAA_LOCAL_CONTRAST_ADAPTATION_FACTOR: f32 = 2.0;
 
+/**
+ * SMAA_REPROJECTION_WEIGHT_SCALE controls the velocity weighting. It allows to
+ * remove ghosting trails behind the moving object, which are not removed by
+ * just using reprojection. Using low values will exhibit ghosting, while using
+ * high values will disable temporal supersampling under motion.
+ *
+ * Behind the scenes, velocity weighting removes temporal supersampling when
+ * the velocity of the subsamples differs (meaning they are different objects).
+ *
+ * Range: [0, 80]
+ */
+const SMAA_REPROJECTION_WEIGHT_SCALE: f32 = 30.0;
+
 //-----------------------------------------------------------------------------
 // Non-Configurable Defines
 
@@ -557,6 +587,19 @@ fn neighborhood_blending_vertex_main(@builtin(vertex_index) vertex_index: u32)
 
 #endif  // SMAA_NEIGHBORHOOD_BLENDING
 
+#ifdef SMAA_RESOLVE
+
+/**
+ * Temporal Resolve Vertex Shader
+ */
+@vertex
+fn resolve_vertex_main(@builtin(vertex_index) vertex_index: u32) -> ResolveVaryings {
+    let varyings = calculate_vertex_varyings(vertex_index);
+    return ResolveVaryings(vec4(varyings.clip_coord, 0.0, 1.0), varyings.tex_coord);
+}
+
+#endif  // SMAA_RESOLVE
+
 //-----------------------------------------------------------------------------
 // Edge Detection Pixel Shaders (First Pass)
 
@@ -954,7 +997,8 @@ fn detect_vertical_corner_pattern(weights: vec2<f32>, tex_coord: vec4<f32>, d: v
 @fragment
 fn blending_weight_calculation_fragment_main(in: BlendingWeightCalculationVaryings)
         -> @location(0) vec4<f32> {
-    let subsample_indices = vec4(0.0);  // Just pass zero for SMAA 1x, see @SUBSAMPLE_INDICES.
+    // Zero for SMAA 1x, and alternating between frames for SMAA T2x, see @SUBSAMPLE_INDICES.
+    let subsample_indices = smaa_info.subsample_indices;
 
     var weights = vec4(0.0);
 
@@ -1072,8 +1116,15 @@ fn neighborhood_blending_fragment_main(in: NeighborhoodBlendingVaryings) -> @loc
 
     // Is there any blending weight with a value greater than 0.0?
     if (dot(a, vec4(1.0)) < 1.0e-5) {
-        let color = textureSampleLevel(color_texture, blend_sampler, in.tex_coord, 0.0);
-        // TODO: Reprojection
+        var color = textureSampleLevel(color_texture, blend_sampler, in.tex_coord, 0.0);
+
+#ifdef SMAA_REPROJECTION
+        let velocity = textureSampleLevel(velocity_texture, blend_sampler, in.tex_coord, 0.0).rg;
+
+        // Pack velocity into the alpha channel:
+        color.a = sqrt(5.0 * length(velocity));
+#endif  // SMAA_REPROJECTION
+
         return color;
     } else {
         let h = max(a.x, a.z) > max(a.y, a.w);  // max(horizontal) > max(vertical)
@@ -1097,10 +1148,50 @@ fn neighborhood_blending_fragment_main(in: NeighborhoodBlendingVaryings) -> @loc
         color += blending_weight.y *
             textureSampleLevel(color_texture, blend_sampler, blending_coord.zw, 0.0);
 
-        // TODO: Reprojection
+#ifdef SMAA_REPROJECTION
+        // Antialias velocity for proper reprojection in a later stage:
+        var velocity = blending_weight.x *
+            textureSampleLevel(velocity_texture, blend_sampler, blending_coord.xy, 0.0).rg;
+        velocity += blending_weight.y *
+            textureSampleLevel(velocity_texture, blend_sampler, blending_coord.zw, 0.0).rg;
+
+        // Pack velocity into the alpha channel:
+        color.a = sqrt(5.0 * length(velocity));
+#endif  // SMAA_REPROJECTION
 
         return color;
     }
 }
 
 #endif  // SMAA_NEIGHBORHOOD_BLENDING
+
+#ifdef SMAA_RESOLVE
+
+//-----------------------------------------------------------------------------
+// Temporal Resolve Pixel Shader (Optional Pass)
+
+@fragment
+fn resolve_fragment_main(in: ResolveVaryings) -> @location(0) vec4<f32> {
+    // Velocity is assumed to be calculated for motion blur, so we need to
+    // inverse it for reprojection:
+    let velocity = -textureSampleLevel(velocity_texture, resolve_sampler, in.tex_coord, 0.0).rg;
+
+    // Fetch current pixel:
+    let current = textureSampleLevel(current_color_texture, resolve_sampler, in.tex_coord, 0.0);
+
+    // Reproject current coordinates and fetch previous pixel:
+    let previous = textureSampleLevel(
+        previous_color_texture, resolve_sampler, in.tex_coord + velocity, 0.0);
+
+    // Attenuate the previous pixel if the velocity is different:
+    let delta = abs(current.a * current.a - previous.a * previous.a) / 5.0;
+    let weight = smaa_info.history_weight *
+        saturate(1.0 - sqrt(delta) * SMAA_REPROJECTION_WEIGHT_SCALE);
+
+    // Blend the pixels according to the calculated weight. The alpha channel
+    // holds the velocity, so the alpha of the frame is restored from the input:
+    let alpha = textureSampleLevel(color_texture, resolve_sampler, in.tex_coord, 0.0).a;
+    return vec4(mix(current.rgb, previous.rgb, weight), alpha);
+}
+
+#endif  // SMAA_RESOLVE
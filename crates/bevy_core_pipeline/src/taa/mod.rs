@@ -2,32 +2,34 @@ use crate::{
     core_3d::graph::{Core3d, Node3d},
     fullscreen_vertex_shader::fullscreen_shader_vertex_state,
     prelude::Camera3d,
-    prepass::{DepthPrepass, MotionVectorPrepass, ViewPrepassTextures},
+    prepass::{DepthPrepass, MeshMaskPrepass, MotionVectorPrepass, ViewPrepassTextures},
 };
 use bevy_app::{App, Plugin};
 use bevy_asset::{load_internal_asset, Handle};
 use bevy_core::FrameCount;
 use bevy_ecs::{
     prelude::{Bundle, Component, Entity},
-    query::{QueryItem, With},
+    query::{Has, QueryItem, With},
+    reflect::ReflectComponent,
     schedule::IntoSystemConfigs,
     system::{Commands, Query, Res, ResMut, Resource},
     world::{FromWorld, World},
 };
-use bevy_math::vec2;
-use bevy_reflect::Reflect;
+use bevy_math::{vec2, UVec2, Vec2};
+use bevy_reflect::{std_traits::ReflectDefault, Reflect};
 use bevy_render::{
-    camera::{ExtractedCamera, MipBias, TemporalJitter},
+    camera::{ExtractedCamera, MainPassResolutionOverride, MipBias, TemporalJitter},
+    extract_component::{ComponentUniforms, DynamicUniformIndex, UniformComponentPlugin},
     prelude::{Camera, Projection},
     render_graph::{NodeRunError, RenderGraphApp, RenderGraphContext, ViewNode, ViewNodeRunner},
     render_resource::{
-        binding_types::{sampler, texture_2d, texture_depth_2d},
+        binding_types::{sampler, texture_2d, texture_depth_2d, uniform_buffer},
         BindGroupEntries, BindGroupLayout, BindGroupLayoutEntries, CachedRenderPipelineId,
         ColorTargetState, ColorWrites, Extent3d, FilterMode, FragmentState, MultisampleState,
         Operations, PipelineCache, PrimitiveState, RenderPassColorAttachment, RenderPassDescriptor,
         RenderPipelineDescriptor, Sampler, SamplerBindingType, SamplerDescriptor, Shader,
-        ShaderStages, SpecializedRenderPipeline, SpecializedRenderPipelines, TextureDescriptor,
-        TextureDimension, TextureFormat, TextureSampleType, TextureUsages,
+        ShaderStages, ShaderType, SpecializedRenderPipeline, SpecializedRenderPipelines,
+        TextureDescriptor, TextureDimension, TextureFormat, TextureSampleType, TextureUsages,
     },
     renderer::{RenderContext, RenderDevice},
     texture::{BevyDefault, CachedTexture, TextureCache},
@@ -47,7 +49,9 @@ impl Plugin for TemporalAntiAliasPlugin {
         load_internal_asset!(app, TAA_SHADER_HANDLE, "taa.wgsl", Shader::from_wgsl);
 
         app.insert_resource(Msaa::Off)
-            .register_type::<TemporalAntiAliasSettings>();
+            .register_type::<TemporalAntiAliasSettings>()
+            .register_type::<ResponsiveAntiAliasing>()
+            .add_plugins(UniformComponentPlugin::<TemporalAntiAliasUniform>::default());
 
         let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
             return;
@@ -67,7 +71,7 @@ impl Plugin for TemporalAntiAliasPlugin {
             .add_render_graph_edges(
                 Core3d,
                 (
-                    Node3d::EndMainPass,
+                    Node3d::EndMainPassUpscaling,
                     Node3d::MotionBlur, // Running before TAA reduces edge artifacts and noise
                     Node3d::Taa,
                     Node3d::Bloom,
@@ -120,7 +124,7 @@ pub struct TemporalAntiAliasBundle {
 ///
 /// Requires that you add [`TemporalAntiAliasPlugin`] to your app,
 /// and add the [`DepthPrepass`], [`MotionVectorPrepass`], and [`TemporalJitter`]
-/// components to your camera.
+/// components to your camera. [`ResponsiveAntiAliasing`] also requires the [`MeshMaskPrepass`].
 ///
 /// [Currently](https://github.com/bevyengine/bevy/issues/8423) cannot be used with [`bevy_render::camera::OrthographicProjection`].
 ///
@@ -132,7 +136,20 @@ pub struct TemporalAntiAliasBundle {
 /// 1. Write particle motion vectors to the motion vectors prepass texture
 /// 2. Render particles after TAA
 ///
-/// If no [`MipBias`] component is attached to the camera, TAA will add a MipBias(-1.0) component.
+/// If no [`MipBias`] component is attached to the camera, TAA will add a MipBias(-1.0) component,
+/// lowered by `log2(render_scale)` when upscaling.
+///
+/// # Upscaling
+///
+/// With a [`TemporalAntiAliasSettings::render_scale`] below 1.0, TAA doubles as a temporal upscaler
+/// (TAAU): the main passes render at a fraction of the resolution of the viewport, and TAA
+/// accumulates the jittered samples of successive frames into a history at the full resolution.
+/// This is typically used to render at 67% to 75% of the native resolution, for a result close to
+/// native rendering.
+///
+/// The effects that read the main texture or the prepass textures before TAA, such as motion blur,
+/// and the ones that read the depth after it, such as depth of field, don't support the lower
+/// resolution and shouldn't be used with upscaling, nor should [`Fsr`](crate::fsr::Fsr).
 #[derive(Component, Reflect, Clone)]
 pub struct TemporalAntiAliasSettings {
     /// Set to true to delete the saved temporal history (past frames).
@@ -143,14 +160,89 @@ pub struct TemporalAntiAliasSettings {
     /// After setting this to true, it will automatically be toggled
     /// back to false at the end of the frame.
     pub reset: bool,
+
+    /// How the history is constrained to the colors of the current frame's neighborhood.
+    pub clamping: TemporalAntiAliasClamping,
+
+    /// How the history is resampled when it is reprojected.
+    pub history_filter: TemporalAntiAliasHistoryFilter,
+
+    /// The resolution of the main passes, relative to the size of the viewport of the camera.
+    ///
+    /// Values below 1.0 turn on temporal upscaling. See the [upscaling](Self#upscaling) section.
+    ///
+    /// The default value is 1.0.
+    pub render_scale: f32,
 }
 
 impl Default for TemporalAntiAliasSettings {
     fn default() -> Self {
-        Self { reset: true }
+        Self {
+            reset: true,
+            clamping: TemporalAntiAliasClamping::default(),
+            history_filter: TemporalAntiAliasHistoryFilter::default(),
+            render_scale: 1.0,
+        }
+    }
+}
+
+impl TemporalAntiAliasSettings {
+    /// Returns true if the render scale is in the range that turns on temporal upscaling.
+    fn is_upscaling(&self) -> bool {
+        self.render_scale > 0.0 && self.render_scale < 1.0
     }
 }
 
+/// How TAA constrains the history to the 3x3 neighborhood of each pixel in the current frame,
+/// which rejects history that is no longer valid.
+///
+/// Tighter constraints reduce ghosting, looser ones reduce flickering.
+#[derive(Reflect, Clone, Copy, Default, PartialEq, Eq, Hash, Debug)]
+pub enum TemporalAntiAliasClamping {
+    /// Clamps each RGB channel of the history to the range of the neighborhood.
+    ///
+    /// The cheapest and loosest mode. Ghosts the most, but flickers the least.
+    MinMaxClamp,
+    /// Clips the history towards the center of the bounding box of the neighborhood, in `YCoCg`
+    /// space.
+    ///
+    /// Keeps the hue of the history intact, which leaves less discolored ghosting than
+    /// [`TemporalAntiAliasClamping::MinMaxClamp`].
+    YCoCgClip,
+    /// Clips the history towards the mean of the neighborhood, to a box one standard deviation
+    /// wide, in `YCoCg` space.
+    ///
+    /// The tightest mode, and the least prone to ghosting.
+    #[default]
+    VarianceClip,
+}
+
+/// How TAA resamples the history when it is reprojected.
+#[derive(Reflect, Clone, Copy, Default, PartialEq, Eq, Hash, Debug)]
+pub enum TemporalAntiAliasHistoryFilter {
+    /// A single bilinear sample. Cheaper, but blurs the history, and so the image, over time.
+    Bilinear,
+    /// A 5-sample Catmull-Rom filter. Keeps the image sharp, but may add ringing around high
+    /// contrast edges.
+    #[default]
+    CatmullRom,
+}
+
+/// Marker component for meshes that temporal anti-aliasing should rely less on the history for.
+///
+/// TAA blends less of the past frames into pixels covered by these meshes, which keeps content
+/// that lacks accurate motion vectors, such as alpha-tested foliage, animated textures or
+/// in-world UI, from smearing, at the cost of more aliasing on it.
+///
+/// TAA finds the pixels of these meshes in the texture written by the [`MeshMaskPrepass`], which
+/// must be added to the camera for this to take effect. It isn't part of the
+/// [`TemporalAntiAliasBundle`], so that cameras not using this marker don't pay for the extra
+/// prepass target. Like the other prepasses, it only covers opaque and alpha-masked meshes,
+/// forward or deferred.
+#[derive(Reflect, Component, Default, Clone, Copy, Debug)]
+#[reflect(Component, Default)]
+pub struct ResponsiveAntiAliasing;
+
 /// Render [`bevy_render::render_graph::Node`] used by temporal anti-aliasing.
 #[derive(Default)]
 pub struct TemporalAntiAliasNode;
@@ -162,30 +254,51 @@ impl ViewNode for TemporalAntiAliasNode {
         &'static TemporalAntiAliasHistoryTextures,
         &'static ViewPrepassTextures,
         &'static TemporalAntiAliasPipelineId,
+        &'static DynamicUniformIndex<TemporalAntiAliasUniform>,
     );
 
     fn run(
         &self,
         _graph: &mut RenderGraphContext,
         render_context: &mut RenderContext,
-        (camera, view_target, taa_history_textures, prepass_textures, taa_pipeline_id): QueryItem<
-            Self::ViewQuery,
-        >,
+        (
+            camera,
+            view_target,
+            taa_history_textures,
+            prepass_textures,
+            taa_pipeline_id,
+            uniform_index,
+        ): QueryItem<Self::ViewQuery>,
         world: &World,
     ) -> Result<(), NodeRunError> {
-        let (Some(pipelines), Some(pipeline_cache)) = (
+        let (Some(pipelines), Some(pipeline_cache), Some(uniforms)) = (
             world.get_resource::<TaaPipeline>(),
             world.get_resource::<PipelineCache>(),
+            world
+                .get_resource::<ComponentUniforms<TemporalAntiAliasUniform>>()
+                .and_then(|uniforms| uniforms.binding()),
         ) else {
             return Ok(());
         };
         let (Some(taa_pipeline), Some(prepass_motion_vectors_texture), Some(prepass_depth_texture)) = (
-            pipeline_cache.get_render_pipeline(taa_pipeline_id.0),
+            pipeline_cache.get_render_pipeline(taa_pipeline_id.id),
             &prepass_textures.motion_vectors,
             &prepass_textures.depth,
         ) else {
             return Ok(());
         };
+        let mesh_mask_bind_group = if taa_pipeline_id.mesh_mask {
+            let Some(mesh_mask) = prepass_textures.mesh_mask_view() else {
+                return Ok(());
+            };
+            Some(render_context.render_device().create_bind_group(
+                "taa_mesh_mask_bind_group",
+                &pipelines.mesh_mask_bind_group_layout,
+                &BindGroupEntries::single(mesh_mask),
+            ))
+        } else {
+            None
+        };
         let view_target = view_target.post_process_write();
 
         let taa_bind_group = render_context.render_device().create_bind_group(
@@ -198,6 +311,7 @@ impl ViewNode for TemporalAntiAliasNode {
                 &prepass_depth_texture.texture.default_view,
                 &pipelines.nearest_sampler,
                 &pipelines.linear_sampler,
+                uniforms,
             )),
         );
 
@@ -221,7 +335,10 @@ impl ViewNode for TemporalAntiAliasNode {
                 occlusion_query_set: None,
             });
             taa_pass.set_render_pipeline(taa_pipeline);
-            taa_pass.set_bind_group(0, &taa_bind_group, &[]);
+            taa_pass.set_bind_group(0, &taa_bind_group, &[uniform_index.index()]);
+            if let Some(mesh_mask_bind_group) = &mesh_mask_bind_group {
+                taa_pass.set_bind_group(1, mesh_mask_bind_group, &[]);
+            }
             if let Some(viewport) = camera.viewport.as_ref() {
                 taa_pass.set_camera_viewport(viewport);
             }
@@ -235,6 +352,7 @@ impl ViewNode for TemporalAntiAliasNode {
 #[derive(Resource)]
 struct TaaPipeline {
     taa_bind_group_layout: BindGroupLayout,
+    mesh_mask_bind_group_layout: BindGroupLayout,
     nearest_sampler: Sampler,
     linear_sampler: Sampler,
 }
@@ -273,12 +391,23 @@ impl FromWorld for TaaPipeline {
                     sampler(SamplerBindingType::NonFiltering),
                     // Linear sampler
                     sampler(SamplerBindingType::Filtering),
+                    // TAA settings
+                    uniform_buffer::<TemporalAntiAliasUniform>(true),
                 ),
             ),
         );
 
+        let mesh_mask_bind_group_layout = render_device.create_bind_group_layout(
+            "taa_mesh_mask_bind_group_layout",
+            &BindGroupLayoutEntries::single(
+                ShaderStages::FRAGMENT,
+                texture_2d(TextureSampleType::Uint),
+            ),
+        );
+
         TaaPipeline {
             taa_bind_group_layout,
+            mesh_mask_bind_group_layout,
             nearest_sampler,
             linear_sampler,
         }
@@ -289,6 +418,10 @@ impl FromWorld for TaaPipeline {
 struct TaaPipelineKey {
     hdr: bool,
     reset: bool,
+    clamping: TemporalAntiAliasClamping,
+    history_filter: TemporalAntiAliasHistoryFilter,
+    upscale: bool,
+    mesh_mask: bool,
 }
 
 impl SpecializedRenderPipeline for TaaPipeline {
@@ -308,9 +441,29 @@ impl SpecializedRenderPipeline for TaaPipeline {
             shader_defs.push("RESET".into());
         }
 
+        match key.clamping {
+            TemporalAntiAliasClamping::MinMaxClamp => shader_defs.push("CLAMP_MIN_MAX".into()),
+            TemporalAntiAliasClamping::YCoCgClip => shader_defs.push("CLIP_YCOCG".into()),
+            TemporalAntiAliasClamping::VarianceClip => {}
+        }
+
+        if key.history_filter == TemporalAntiAliasHistoryFilter::Bilinear {
+            shader_defs.push("HISTORY_FILTER_BILINEAR".into());
+        }
+
+        if key.upscale {
+            shader_defs.push("UPSCALE".into());
+        }
+
+        let mut layout = vec![self.taa_bind_group_layout.clone()];
+        if key.mesh_mask {
+            shader_defs.push("MESH_MASK".into());
+            layout.push(self.mesh_mask_bind_group_layout.clone());
+        }
+
         RenderPipelineDescriptor {
             label: Some("taa_pipeline".into()),
-            layout: vec![self.taa_bind_group_layout.clone()],
+            layout,
             vertex: fullscreen_shader_vertex_state(),
             fragment: Some(FragmentState {
                 shader: TAA_SHADER_HANDLE,
@@ -351,18 +504,45 @@ fn extract_taa_settings(mut commands: Commands, mut main_world: ResMut<MainWorld
     {
         let has_perspective_projection = matches!(camera_projection, Projection::Perspective(_));
         if camera.is_active && has_perspective_projection {
-            commands.get_or_spawn(entity).insert(taa_settings.clone());
+            let mut entity_commands = commands.get_or_spawn(entity);
+            entity_commands.insert(taa_settings.clone());
             taa_settings.reset = false;
+
+            if !taa_settings.is_upscaling() {
+                continue;
+            }
+            if let Some(viewport_size) = camera.physical_viewport_size() {
+                let render_size = (viewport_size.as_vec2() * taa_settings.render_scale)
+                    .round()
+                    .as_uvec2()
+                    .max(UVec2::ONE);
+                entity_commands.insert(MainPassResolutionOverride(render_size));
+            }
         }
     }
 }
 
+/// The uniform struct prepared for each view with [`TemporalAntiAliasSettings`].
+/// Will be available for use in the TAA shader.
+#[doc(hidden)]
+#[derive(Component, ShaderType, Clone)]
+pub struct TemporalAntiAliasUniform {
+    /// The resolution of the main passes, in pixels.
+    input_size: Vec2,
+    /// The jitter of the main passes this frame, in pixels of the main passes.
+    jitter: Vec2,
+}
+
 fn prepare_taa_jitter_and_mip_bias(
     frame_count: Res<FrameCount>,
-    mut query: Query<
-        (Entity, &mut TemporalJitter, Option<&MipBias>),
-        With<TemporalAntiAliasSettings>,
-    >,
+    mut query: Query<(
+        Entity,
+        &ExtractedView,
+        &TemporalAntiAliasSettings,
+        &mut TemporalJitter,
+        Option<&MipBias>,
+        Option<&MainPassResolutionOverride>,
+    )>,
     mut commands: Commands,
 ) {
     // Halton sequence (2, 3) - 0.5, skipping i = 0
@@ -379,11 +559,25 @@ fn prepare_taa_jitter_and_mip_bias(
 
     let offset = halton_sequence[frame_count.0 as usize % halton_sequence.len()];
 
-    for (entity, mut jitter, mip_bias) in &mut query {
+    for (entity, view, taa_settings, mut jitter, mip_bias, resolution_override) in &mut query {
         jitter.offset = offset;
 
+        let mut entity_commands = commands.entity(entity);
+        let input_size = resolution_override
+            .map(|resolution_override| resolution_override.0)
+            .unwrap_or(UVec2::new(view.viewport.z, view.viewport.w));
+        entity_commands.insert(TemporalAntiAliasUniform {
+            input_size: input_size.as_vec2(),
+            jitter: offset,
+        });
+
         if mip_bias.is_none() {
-            commands.entity(entity).insert(MipBias(-1.0));
+            let render_scale = if taa_settings.is_upscaling() {
+                taa_settings.render_scale
+            } else {
+                1.0
+            };
+            entity_commands.insert(MipBias(render_scale.log2() - 1.0));
         }
     }
 }
@@ -446,19 +640,32 @@ fn prepare_taa_history_textures(
 }
 
 #[derive(Component)]
-pub struct TemporalAntiAliasPipelineId(CachedRenderPipelineId);
+pub struct TemporalAntiAliasPipelineId {
+    id: CachedRenderPipelineId,
+    /// Whether the pipeline reads the [`MeshMaskPrepass`] texture
+    mesh_mask: bool,
+}
 
 fn prepare_taa_pipelines(
     mut commands: Commands,
     pipeline_cache: Res<PipelineCache>,
     mut pipelines: ResMut<SpecializedRenderPipelines<TaaPipeline>>,
     pipeline: Res<TaaPipeline>,
-    views: Query<(Entity, &ExtractedView, &TemporalAntiAliasSettings)>,
+    views: Query<(
+        Entity,
+        &ExtractedView,
+        &TemporalAntiAliasSettings,
+        Has<MeshMaskPrepass>,
+    )>,
 ) {
-    for (entity, view, taa_settings) in &views {
+    for (entity, view, taa_settings, mesh_mask_prepass) in &views {
         let mut pipeline_key = TaaPipelineKey {
             hdr: view.hdr,
             reset: taa_settings.reset,
+            clamping: taa_settings.clamping,
+            history_filter: taa_settings.history_filter,
+            upscale: taa_settings.is_upscaling(),
+            mesh_mask: mesh_mask_prepass,
         };
         let pipeline_id = pipelines.specialize(&pipeline_cache, &pipeline, pipeline_key.clone());
 
@@ -468,8 +675,9 @@ fn prepare_taa_pipelines(
             pipelines.specialize(&pipeline_cache, &pipeline, pipeline_key);
         }
 
-        commands
-            .entity(entity)
-            .insert(TemporalAntiAliasPipelineId(pipeline_id));
+        commands.entity(entity).insert(TemporalAntiAliasPipelineId {
+            id: pipeline_id,
+            mesh_mask: mesh_mask_prepass,
+        });
     }
 }
@@ -4,11 +4,23 @@
 // http://leiy.cc/publications/TAA/TAA_EG2020_Talk.pdf
 // https://advances.realtimerendering.com/s2014/index.html#_HIGH-QUALITY_TEMPORAL_SUPERSAMPLING
 
+#ifdef MESH_MASK
+#import bevy_core_pipeline::mesh_mask::MESH_MASK_RESPONSIVE_ANTI_ALIASING_BIT
+#endif
+
 // Controls how much to blend between the current and past samples
 // Lower numbers = less of the current sample and more of the past sample = more smoothing
 // Values chosen empirically
 const DEFAULT_HISTORY_BLEND_RATE: f32 = 0.1; // Default blend rate to use when no confidence in history
 const MIN_HISTORY_BLEND_RATE: f32 = 0.015; // Minimum blend rate allowed, to ensure at least some of the current sample is used
+const RESPONSIVE_HISTORY_BLEND_RATE: f32 = 0.5; // Minimum blend rate for pixels of meshes with `ResponsiveAntiAliasing`
+
+struct TaaSettings {
+    // The resolution of the main passes, in pixels
+    input_size: vec2<f32>,
+    // The jitter of the main passes, in input pixels
+    jitter: vec2<f32>,
+}
 
 @group(0) @binding(0) var view_target: texture_2d<f32>;
 @group(0) @binding(1) var history: texture_2d<f32>;
@@ -16,6 +28,10 @@ const MIN_HISTORY_BLEND_RATE: f32 = 0.015; // Minimum blend rate allowed, to ens
 @group(0) @binding(3) var depth: texture_depth_2d;
 @group(0) @binding(4) var nearest_sampler: sampler;
 @group(0) @binding(5) var linear_sampler: sampler;
+@group(0) @binding(6) var<uniform> settings: TaaSettings;
+#ifdef MESH_MASK
+@group(1) @binding(0) var mesh_mask: texture_2d<u32>;
+#endif
 
 struct Output {
     @location(0) view_target: vec4<f32>,
@@ -66,21 +82,64 @@ fn sample_history(u: f32, v: f32) -> vec3<f32> {
     return textureSample(history, linear_sampler, vec2(u, v)).rgb;
 }
 
+// The color space the neighborhood is constrained in: RGB for min/max clamping, YCoCg otherwise
+fn to_neighborhood_space(rgb: vec3<f32>) -> vec3<f32> {
+#ifdef CLAMP_MIN_MAX
+    return rgb;
+#else
+    return RGB_to_YCoCg(rgb);
+#endif
+}
+
+fn from_neighborhood_space(color: vec3<f32>) -> vec3<f32> {
+#ifdef CLAMP_MIN_MAX
+    return color;
+#else
+    return YCoCg_to_RGB(color);
+#endif
+}
+
+// Keeps the samples of the view target within the part of it that the main passes rendered to
+fn clamp_input_uv(uv: vec2<f32>) -> vec2<f32> {
+#ifdef UPSCALE
+    let texel_size = 1.0 / vec2<f32>(textureDimensions(view_target));
+    return clamp(uv, 0.5 * texel_size, (settings.input_size - 0.5) * texel_size);
+#else
+    return uv;
+#endif
+}
+
 fn sample_view_target(uv: vec2<f32>) -> vec3<f32> {
-    var sample = textureSample(view_target, nearest_sampler, uv).rgb;
+    var sample = textureSample(view_target, nearest_sampler, clamp_input_uv(uv)).rgb;
 #ifdef TONEMAP
     sample = tonemap(sample);
 #endif
-    return RGB_to_YCoCg(sample);
+    return to_neighborhood_space(sample);
 }
 
 @fragment
 fn taa(@location(0) uv: vec2<f32>) -> Output {
     let texture_size = vec2<f32>(textureDimensions(view_target));
     let texel_size = 1.0 / texture_size;
+    let history_size = vec2<f32>(textureDimensions(history));
+
+#ifdef UPSCALE
+    // The input is rendered at a lower resolution, in the top left corner of the view target,
+    // and jittered by a fraction of an input pixel every frame. Use the input pixel whose
+    // jittered sample lands closest to this output pixel, and remember how far it landed, so that
+    // it can be weighted against the history.
+    // The projection is jittered by `jitter` pixels, which moves the scene by `-jitter` pixels,
+    // so an input pixel samples the scene `jitter` pixels away from its center.
+    let input_position = uv * settings.input_size;
+    let input_pixel = floor(input_position - settings.jitter);
+    let input_uv = (input_pixel + 0.5) * texel_size;
+    let sample_offset = (input_pixel + 0.5 + settings.jitter - input_position) * history_size / settings.input_size;
+#else
+    let input_uv = uv;
+#endif
 
     // Fetch the current sample
-    let original_color = textureSample(view_target, nearest_sampler, uv);
+    let original_color = textureSample(view_target, nearest_sampler, input_uv);
     var current_color = original_color.rgb;
 #ifdef TONEMAP
     current_color = tonemap(current_color);
@@ -90,14 +149,14 @@ fn taa(@location(0) uv: vec2<f32>) -> Output {
     // Pick the closest motion_vector from 5 samples (reduces aliasing on the edges of moving entities)
     // https://advances.realtimerendering.com/s2014/index.html#_HIGH-QUALITY_TEMPORAL_SUPERSAMPLING, slide 27
     let offset = texel_size * 2.0;
-    let d_uv_tl = uv + vec2(-offset.x, offset.y);
-    let d_uv_tr = uv + vec2(offset.x, offset.y);
-    let d_uv_bl = uv + vec2(-offset.x, -offset.y);
-    let d_uv_br = uv + vec2(offset.x, -offset.y);
-    var closest_uv = uv;
+    let d_uv_tl = clamp_input_uv(input_uv + vec2(-offset.x, offset.y));
+    let d_uv_tr = clamp_input_uv(input_uv + vec2(offset.x, offset.y));
+    let d_uv_bl = clamp_input_uv(input_uv + vec2(-offset.x, -offset.y));
+    let d_uv_br = clamp_input_uv(input_uv + vec2(offset.x, -offset.y));
+    var closest_uv = input_uv;
     let d_tl = textureSample(depth, nearest_sampler, d_uv_tl);
     let d_tr = textureSample(depth, nearest_sampler, d_uv_tr);
-    var closest_depth = textureSample(depth, nearest_sampler, uv);
+    var closest_depth = textureSample(depth, nearest_sampler, input_uv);
     let d_bl = textureSample(depth, nearest_sampler, d_uv_bl);
     let d_br = textureSample(depth, nearest_sampler, d_uv_br);
     if d_tl > closest_depth {
@@ -118,12 +177,16 @@ fn taa(@location(0) uv: vec2<f32>) -> Output {
     let closest_motion_vector = textureSample(motion_vectors, nearest_sampler, closest_uv).rg;
 
     // Reproject to find the equivalent sample from the past
+    let history_uv = uv - closest_motion_vector;
+#ifdef HISTORY_FILTER_BILINEAR
+    var history_color = sample_history(history_uv.x, history_uv.y);
+#else
     // Uses 5-sample Catmull-Rom filtering (reduces blurriness)
     // Catmull-Rom filtering: https://gist.github.com/TheRealMJP/c83b8c0f46b63f3a88a5986f4fa982b1
     // Ignoring corners: https://www.activision.com/cdn/research/Dynamic_Temporal_Antialiasing_and_Upsampling_in_Call_of_Duty_v4.pdf#page=68
     // Technically we should renormalize the weights since we're skipping the corners, but it's basically the same result
-    let history_uv = uv - closest_motion_vector;
-    let sample_position = history_uv * texture_size;
+    let history_texel_size = 1.0 / history_size;
+    let sample_position = history_uv * history_size;
     let texel_center = floor(sample_position - 0.5) + 0.5;
     let f = sample_position - texel_center;
     let w0 = f * (-0.5 + f * (1.0 - 0.5 * f));
@@ -131,39 +194,50 @@ fn taa(@location(0) uv: vec2<f32>) -> Output {
     let w2 = f * (0.5 + f * (2.0 - 1.5 * f));
     let w3 = f * f * (-0.5 + 0.5 * f);
     let w12 = w1 + w2;
-    let texel_position_0 = (texel_center - 1.0) * texel_size;
-    let texel_position_3 = (texel_center + 2.0) * texel_size;
-    let texel_position_12 = (texel_center + (w2 / w12)) * texel_size;
+    let texel_position_0 = (texel_center - 1.0) * history_texel_size;
+    let texel_position_3 = (texel_center + 2.0) * history_texel_size;
+    let texel_position_12 = (texel_center + (w2 / w12)) * history_texel_size;
     var history_color = sample_history(texel_position_12.x, texel_position_0.y) * w12.x * w0.y;
     history_color += sample_history(texel_position_0.x, texel_position_12.y) * w0.x * w12.y;
     history_color += sample_history(texel_position_12.x, texel_position_12.y) * w12.x * w12.y;
     history_color += sample_history(texel_position_3.x, texel_position_12.y) * w3.x * w12.y;
     history_color += sample_history(texel_position_12.x, texel_position_3.y) * w12.x * w3.y;
+#endif
 
-    // Constrain past sample with 3x3 YCoCg variance clipping (reduces ghosting)
+    // Constrain past sample to the 3x3 neighborhood of the current sample (reduces ghosting)
     // YCoCg: https://advances.realtimerendering.com/s2014/index.html#_HIGH-QUALITY_TEMPORAL_SUPERSAMPLING, slide 33
     // Variance clipping: https://developer.download.nvidia.com/gameworks/events/GDC2016/msalvi_temporal_supersampling.pdf
-    let s_tl = sample_view_target(uv + vec2(-texel_size.x,  texel_size.y));
-    let s_tm = sample_view_target(uv + vec2( 0.0,           texel_size.y));
-    let s_tr = sample_view_target(uv + vec2( texel_size.x,  texel_size.y));
-    let s_ml = sample_view_target(uv + vec2(-texel_size.x,  0.0));
-    let s_mm = RGB_to_YCoCg(current_color);
-    let s_mr = sample_view_target(uv + vec2( texel_size.x,  0.0));
-    let s_bl = sample_view_target(uv + vec2(-texel_size.x, -texel_size.y));
-    let s_bm = sample_view_target(uv + vec2( 0.0,          -texel_size.y));
-    let s_br = sample_view_target(uv + vec2( texel_size.x, -texel_size.y));
+    let s_tl = sample_view_target(input_uv + vec2(-texel_size.x,  texel_size.y));
+    let s_tm = sample_view_target(input_uv + vec2( 0.0,           texel_size.y));
+    let s_tr = sample_view_target(input_uv + vec2( texel_size.x,  texel_size.y));
+    let s_ml = sample_view_target(input_uv + vec2(-texel_size.x,  0.0));
+    let s_mm = to_neighborhood_space(current_color);
+    let s_mr = sample_view_target(input_uv + vec2( texel_size.x,  0.0));
+    let s_bl = sample_view_target(input_uv + vec2(-texel_size.x, -texel_size.y));
+    let s_bm = sample_view_target(input_uv + vec2( 0.0,          -texel_size.y));
+    let s_br = sample_view_target(input_uv + vec2( texel_size.x, -texel_size.y));
+    history_color = to_neighborhood_space(history_color);
+#ifdef CLAMP_MIN_MAX
+    let aabb_min = min(min(min(min(s_tl, s_tm), min(s_tr, s_ml)), min(min(s_mm, s_mr), min(s_bl, s_bm))), s_br);
+    let aabb_max = max(max(max(max(s_tl, s_tm), max(s_tr, s_ml)), max(max(s_mm, s_mr), max(s_bl, s_bm))), s_br);
+    history_color = clamp(history_color, aabb_min, aabb_max);
+#else ifdef CLIP_YCOCG
+    let aabb_min = min(min(min(min(s_tl, s_tm), min(s_tr, s_ml)), min(min(s_mm, s_mr), min(s_bl, s_bm))), s_br);
+    let aabb_max = max(max(max(max(s_tl, s_tm), max(s_tr, s_ml)), max(max(s_mm, s_mr), max(s_bl, s_bm))), s_br);
+    history_color = clip_towards_aabb_center(history_color, s_mm, aabb_min, aabb_max);
+#else
     let moment_1 = s_tl + s_tm + s_tr + s_ml + s_mm + s_mr + s_bl + s_bm + s_br;
     let moment_2 = (s_tl * s_tl) + (s_tm * s_tm) + (s_tr * s_tr) + (s_ml * s_ml) + (s_mm * s_mm) + (s_mr * s_mr) + (s_bl * s_bl) + (s_bm * s_bm) + (s_br * s_br);
     let mean = moment_1 / 9.0;
     let variance = (moment_2 / 9.0) - (mean * mean);
     let std_deviation = sqrt(max(variance, vec3(0.0)));
-    history_color = RGB_to_YCoCg(history_color);
     history_color = clip_towards_aabb_center(history_color, s_mm, mean - std_deviation, mean + std_deviation);
-    history_color = YCoCg_to_RGB(history_color);
+#endif
+    history_color = from_neighborhood_space(history_color);
 
     // How confident we are that the history is representative of the current frame
     var history_confidence = textureSample(history, nearest_sampler, uv).a;
-    let pixel_motion_vector = abs(closest_motion_vector) * texture_size;
+    let pixel_motion_vector = abs(closest_motion_vector) * history_size;
     if pixel_motion_vector.x < 0.01 && pixel_motion_vector.y < 0.01 {
         // Increment when pixels are not moving
         history_confidence += 10.0;
@@ -177,6 +251,24 @@ fn taa(@location(0) uv: vec2<f32>) -> Output {
     // https://hhoppe.com/supersample.pdf, section 4.1
     var current_color_factor = clamp(1.0 / history_confidence, MIN_HISTORY_BLEND_RATE, DEFAULT_HISTORY_BLEND_RATE);
 
+#ifdef MESH_MASK
+    // Rely less on the history of pixels of meshes with `ResponsiveAntiAliasing`, and keep their
+    // confidence from building up
+    let mask_coords = vec2<i32>(input_uv * vec2<f32>(textureDimensions(mesh_mask)));
+    let mask = textureLoad(mesh_mask, mask_coords, 0).r;
+    if (mask & MESH_MASK_RESPONSIVE_ANTI_ALIASING_BIT) != 0u {
+        current_color_factor = max(current_color_factor, RESPONSIVE_HISTORY_BLEND_RATE);
+        history_confidence = 1.0;
+    }
+#endif
+
+#ifdef UPSCALE
+    // Weight the current sample by how close it landed to this output pixel, with a Gaussian
+    // approximation of a Blackman-Harris window, so that the history gathers the details that
+    // fall between the input pixels
+    current_color_factor *= exp(-2.29 * dot(sample_offset, sample_offset));
+#endif
+
     // Reject history when motion vectors point off screen
     if any(saturate(history_uv) != history_uv) {
         current_color_factor = 1.0;
@@ -0,0 +1,697 @@
+use std::{
+    fmt::Write,
+    path::Path,
+    sync::{Arc, Mutex, OnceLock, PoisonError},
+};
+
+use bevy_app::prelude::*;
+use bevy_asset::{load_internal_asset, Handle};
+use bevy_ecs::{entity::EntityHashMap, prelude::*};
+use bevy_reflect::{std_traits::ReflectDefault, Reflect};
+use bevy_render::{
+    camera::Camera,
+    extract_component::{ExtractComponent, ExtractComponentPlugin},
+    render_asset::{RenderAssetUsages, RenderAssets},
+    render_resource::{
+        binding_types::{texture_storage_3d, uniform_buffer},
+        *,
+    },
+    renderer::{RenderDevice, RenderQueue},
+    texture::{
+        FallbackImage, GpuImage, Image, ImageAddressMode, ImageFilterMode, ImageSampler,
+        ImageSamplerDescriptor, TextureFormatPixelInfo,
+    },
+    view::{ColorGrading, ExtractedView, ViewUniform, ViewUniformOffset, ViewUniforms},
+    Extract, ExtractSchedule, Render, RenderApp, RenderSet,
+};
+use bevy_utils::{
+    default,
+    tracing::{error, info, warn},
+    warn_once,
+};
+use thiserror::Error;
+
+use crate::auto_exposure::AutoExposureSettings;
+
+use super::{
+    get_lut_bind_group_layout_entries, get_lut_bindings, prepare_view_tonemapping_pipelines,
+    tonemapping_shader_defs, CustomTonemappingCurves, Tonemapping, TonemappingLuts,
+    TonemappingPipelineKeyFlags,
+};
+
+const COLOR_GRADING_LUT_SHADER_HANDLE: Handle<Shader> = Handle::weak_from_u128(7383446518135661610);
+
+/// The format of the baked LUT, which has to be both filterable and usable as a storage texture.
+const COLOR_GRADING_LUT_FORMAT: TextureFormat = TextureFormat::Rgba16Float;
+
+/// Bakes the [`ColorGrading`] and [`Tonemapping`] of an HDR camera into a 3D LUT, which the
+/// tonemapping pass samples instead of evaluating them for every pixel.
+///
+/// The LUT is baked again by a compute shader whenever the color grading or tonemapping
+/// changes, so it costs nothing on the other frames. Like [`Tonemapping::Lut`], it is indexed
+/// by the stimulus encoded with `x / (x + 1)`, and it can be exported with
+/// [`ColorGradingLutExporter`] and used on another camera or in another tool.
+///
+/// The LUT is only used by the tonemapping pass, so it has no effect on cameras without
+/// [`Camera::hdr`], which tonemap in their main pass.
+///
+/// **Baking requires compute shaders and is not compatible with WebGL2, nor with
+/// [`AutoExposureSettings`], whose exposure changes on the GPU every frame. Cameras with both
+/// aren't baked, and tonemap without a LUT.**
+#[derive(Component, Clone, Debug, Reflect, ExtractComponent)]
+#[extract_component_filter(With<Camera>)]
+#[reflect(Component, Default)]
+pub struct ColorGradingLut {
+    /// The number of texels along each edge of the LUT.
+    ///
+    /// Larger LUTs are more accurate, but take more memory and longer to bake.
+    ///
+    /// The default value is 32.
+    pub size: u32,
+}
+
+impl Default for ColorGradingLut {
+    fn default() -> Self {
+        Self { size: 32 }
+    }
+}
+
+/// The baked [`ColorGradingLut`] of a view, which replaces the tonemapping LUT in the
+/// tonemapping pass.
+///
+/// This is only added to a view once its LUT has been baked.
+#[derive(Component)]
+pub struct ViewColorGradingLut {
+    pub texture_view: TextureView,
+    pub sampler: Sampler,
+}
+
+pub type ColorGradingLutFn = Box<dyn FnOnce(Image) + Send + Sync>;
+
+/// A resource which allows for exporting the baked [`ColorGradingLut`] of a camera.
+#[derive(Resource, Default)]
+pub struct ColorGradingLutExporter {
+    // this is in a mutex to enable extraction with only an immutable reference
+    callbacks: Mutex<EntityHashMap<ColorGradingLutFn>>,
+}
+
+#[derive(Error, Debug)]
+#[error("An export of the color grading LUT of this camera has already been requested.")]
+pub struct ColorGradingLutExportAlreadyRequestedError;
+
+impl ColorGradingLutExporter {
+    /// Signals the renderer to export the [`ColorGradingLut`] of this camera, once it is baked.
+    ///
+    /// The given callback will be called on the render thread with a 3D [`Image`] that can be
+    /// added to the assets and used with [`Tonemapping::Lut`]. The LUT is read back
+    /// asynchronously, so the callback is only called a few frames after the LUT is baked.
+    pub fn export(
+        &mut self,
+        camera: Entity,
+        callback: impl FnOnce(Image) + Send + Sync + 'static,
+    ) -> Result<(), ColorGradingLutExportAlreadyRequestedError> {
+        self.callbacks
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner)
+            .try_insert(camera, Box::new(callback))
+            .map(|_| ())
+            .map_err(|_| ColorGradingLutExportAlreadyRequestedError)
+    }
+
+    /// Signals the renderer to export the [`ColorGradingLut`] of this camera, once it is baked.
+    ///
+    /// The LUT will eventually be saved to the given path as a `.cube` file, which can be loaded
+    /// back with [`CubeLutLoader`](super::CubeLutLoader). Since the LUT is indexed by the stimulus
+    /// encoded with `x / (x + 1)`, the file starts with a 1D shaper LUT applying that encoding
+    /// to stimuli up to 64.0, and brighter stimuli are clamped.
+    pub fn save_to_disk(
+        &mut self,
+        camera: Entity,
+        path: impl AsRef<Path>,
+    ) -> Result<(), ColorGradingLutExportAlreadyRequestedError> {
+        let path = path.as_ref().to_owned();
+        self.export(camera, move |image| {
+            match std::fs::write(&path, cube_lut_from_image(&image)) {
+                Ok(()) => info!("Color grading LUT saved to {}", path.display()),
+                Err(e) => error!("Cannot save color grading LUT, IO error: {e}"),
+            }
+        })
+    }
+}
+
+/// Adds support for [`ColorGradingLut`].
+pub struct ColorGradingLutPlugin;
+
+impl Plugin for ColorGradingLutPlugin {
+    fn build(&self, app: &mut App) {
+        load_internal_asset!(
+            app,
+            COLOR_GRADING_LUT_SHADER_HANDLE,
+            "color_grading_lut.wgsl",
+            Shader::from_wgsl
+        );
+
+        app.register_type::<ColorGradingLut>()
+            .init_resource::<ColorGradingLutExporter>()
+            .add_plugins(ExtractComponentPlugin::<ColorGradingLut>::default());
+
+        let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+        render_app
+            .init_resource::<SpecializedComputePipelines<ColorGradingLutPipeline>>()
+            .init_resource::<BakedColorGradingLuts>()
+            .add_systems(ExtractSchedule, extract_color_grading_lut_exports)
+            .add_systems(
+                Render,
+                (
+                    prepare_color_grading_luts
+                        .in_set(RenderSet::Prepare)
+                        .before(prepare_view_tonemapping_pipelines),
+                    bake_color_grading_luts.in_set(RenderSet::PrepareBindGroups),
+                ),
+            );
+    }
+
+    fn finish(&self, app: &mut App) {
+        let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+        render_app.init_resource::<ColorGradingLutPipeline>();
+    }
+}
+
+#[derive(Resource)]
+pub struct ColorGradingLutPipeline {
+    layout: BindGroupLayout,
+    sampler: Sampler,
+}
+
+impl FromWorld for ColorGradingLutPipeline {
+    fn from_world(render_world: &mut World) -> Self {
+        let lut_layout_entries = get_lut_bind_group_layout_entries();
+        let entries = DynamicBindGroupLayoutEntries::new_with_indices(
+            ShaderStages::COMPUTE,
+            (
+                (0, uniform_buffer::<ViewUniform>(true)),
+                (
+                    1,
+                    texture_storage_3d(COLOR_GRADING_LUT_FORMAT, StorageTextureAccess::WriteOnly),
+                ),
+                (3, lut_layout_entries[0]),
+                (4, lut_layout_entries[1]),
+            ),
+        );
+
+        let render_device = render_world.resource::<RenderDevice>();
+        let layout =
+            render_device.create_bind_group_layout("color_grading_lut_bind_group_layout", &entries);
+
+        let sampler = render_device.create_sampler(&SamplerDescriptor {
+            label: Some("color_grading_lut_sampler"),
+            mag_filter: FilterMode::Linear,
+            min_filter: FilterMode::Linear,
+            ..default()
+        });
+
+        ColorGradingLutPipeline { layout, sampler }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ColorGradingLutPipelineKey {
+    tonemapping: Tonemapping,
+    custom_tonemapping_curve: Option<u32>,
+    flags: TonemappingPipelineKeyFlags,
+}
+
+impl SpecializedComputePipeline for ColorGradingLutPipeline {
+    type Key = ColorGradingLutPipelineKey;
+
+    fn specialize(&self, key: Self::Key) -> ComputePipelineDescriptor {
+        let mut shader_defs = vec![
+            ShaderDefVal::UInt("TONEMAPPING_LUT_TEXTURE_BINDING_INDEX".into(), 3),
+            ShaderDefVal::UInt("TONEMAPPING_LUT_SAMPLER_BINDING_INDEX".into(), 4),
+        ];
+        shader_defs.extend(tonemapping_shader_defs(
+            &key.tonemapping,
+            key.custom_tonemapping_curve,
+            key.flags,
+        ));
+
+        ComputePipelineDescriptor {
+            label: Some("color_grading_lut_pipeline".into()),
+            layout: vec![self.layout.clone()],
+            push_constant_ranges: vec![],
+            shader: COLOR_GRADING_LUT_SHADER_HANDLE,
+            shader_defs,
+            entry_point: "bake".into(),
+        }
+    }
+}
+
+/// The LUTs of the views with a [`ColorGradingLut`].
+///
+/// They are kept from frame to frame, instead of being taken from the
+/// [`TextureCache`](bevy_render::texture::TextureCache), so that they only need to be baked
+/// again when something changes.
+#[derive(Resource, Default)]
+pub struct BakedColorGradingLuts {
+    luts: EntityHashMap<BakedColorGradingLut>,
+    exports: EntityHashMap<ColorGradingLutFn>,
+    readbacks: Vec<ColorGradingLutReadback>,
+}
+
+struct BakedColorGradingLut {
+    texture: Texture,
+    texture_view: TextureView,
+    size: u32,
+    pipeline: CachedComputePipelineId,
+    color_grading: ColorGrading,
+    tonemapping: Tonemapping,
+    /// The color grading and tonemapping that the LUT was last baked with, along with the
+    /// tonemapping LUT that was bound, which changes once a LUT image is loaded.
+    baked: Option<(ColorGrading, Tonemapping, TextureViewId)>,
+}
+
+/// An exported LUT being copied to a buffer, which is read once the GPU has mapped it.
+struct ColorGradingLutReadback {
+    buffer: Buffer,
+    size: u32,
+    callback: ColorGradingLutFn,
+    /// Set by the callback of [`BufferSlice::map_async`], which runs once the copy is done.
+    mapped: Arc<OnceLock<Result<(), BufferAsyncError>>>,
+}
+
+fn extract_color_grading_lut_exports(
+    mut baked_luts: ResMut<BakedColorGradingLuts>,
+    exporter: Extract<Res<ColorGradingLutExporter>>,
+) {
+    let mut callbacks = exporter
+        .callbacks
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner);
+    baked_luts.exports.extend(callbacks.drain());
+}
+
+#[allow(clippy::too_many_arguments)]
+fn prepare_color_grading_luts(
+    mut commands: Commands,
+    render_device: Res<RenderDevice>,
+    pipeline_cache: Res<PipelineCache>,
+    mut pipelines: ResMut<SpecializedComputePipelines<ColorGradingLutPipeline>>,
+    pipeline: Res<ColorGradingLutPipeline>,
+    mut baked_luts: ResMut<BakedColorGradingLuts>,
+    custom_tonemapping_curves: Res<CustomTonemappingCurves>,
+    views: Query<(
+        Entity,
+        &ExtractedView,
+        &Tonemapping,
+        &ColorGradingLut,
+        Has<AutoExposureSettings>,
+    )>,
+) {
+    baked_luts.luts.retain(|entity, _| {
+        views
+            .get(*entity)
+            .is_ok_and(|(_, view, tonemapping, _, auto_exposure)| {
+                view.hdr && tonemapping.is_enabled() && !auto_exposure
+            })
+    });
+
+    for (entity, view, tonemapping, lut, auto_exposure) in &views {
+        if !view.hdr || !tonemapping.is_enabled() {
+            continue;
+        }
+        if auto_exposure {
+            // The baked LUT would ignore the exposure computed on the GPU.
+            warn_once!(
+                "ColorGradingLut is not compatible with AutoExposureSettings, \
+                tonemapping without it"
+            );
+            continue;
+        }
+
+        let key = ColorGradingLutPipelineKey {
+            tonemapping: tonemapping.pipeline_key(),
+            custom_tonemapping_curve: custom_tonemapping_curves.slot(tonemapping),
+            flags: TonemappingPipelineKeyFlags::from_color_grading(&view.color_grading),
+        };
+        let pipeline_id = pipelines.specialize(&pipeline_cache, &pipeline, key);
+
+        let size = lut
+            .size
+            .clamp(2, render_device.limits().max_texture_dimension_3d);
+        let baked_lut = baked_luts
+            .luts
+            .entry(entity)
+            .or_insert_with(|| create_baked_lut(&render_device, size));
+        if baked_lut.size != size {
+            *baked_lut = create_baked_lut(&render_device, size);
+        }
+        baked_lut.pipeline = pipeline_id;
+        baked_lut.color_grading = view.color_grading.clone();
+        baked_lut.tonemapping = tonemapping.clone();
+
+        if baked_lut.baked.is_some() {
+            commands.entity(entity).insert(ViewColorGradingLut {
+                texture_view: baked_lut.texture_view.clone(),
+                sampler: pipeline.sampler.clone(),
+            });
+        }
+    }
+}
+
+fn create_baked_lut(render_device: &RenderDevice, size: u32) -> BakedColorGradingLut {
+    let texture = render_device.create_texture(&TextureDescriptor {
+        label: Some("color_grading_lut"),
+        size: Extent3d {
+            width: size,
+            height: size,
+            depth_or_array_layers: size,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: TextureDimension::D3,
+        format: COLOR_GRADING_LUT_FORMAT,
+        usage: TextureUsages::STORAGE_BINDING
+            | TextureUsages::TEXTURE_BINDING
+            | TextureUsages::COPY_SRC,
+        view_formats: &[],
+    });
+    let texture_view = texture.create_view(&TextureViewDescriptor::default());
+
+    BakedColorGradingLut {
+        texture,
+        texture_view,
+        size,
+        pipeline: CachedComputePipelineId::INVALID,
+        color_grading: default(),
+        tonemapping: Tonemapping::None,
+        baked: None,
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn bake_color_grading_luts(
+    render_device: Res<RenderDevice>,
+    render_queue: Res<RenderQueue>,
+    pipeline_cache: Res<PipelineCache>,
+    pipeline: Res<ColorGradingLutPipeline>,
+    mut baked_luts: ResMut<BakedColorGradingLuts>,
+    view_uniforms: Res<ViewUniforms>,
+    views: Query<&ViewUniformOffset>,
+    gpu_images: Res<RenderAssets<GpuImage>>,
+    tonemapping_luts: Res<TonemappingLuts>,
+    fallback_image: Res<FallbackImage>,
+) {
+    let BakedColorGradingLuts {
+        luts,
+        exports,
+        readbacks,
+    } = &mut *baked_luts;
+    finish_color_grading_lut_readbacks(readbacks);
+
+    let Some(view_uniforms) = view_uniforms.uniforms.binding() else {
+        return;
+    };
+
+    let mut command_encoder = render_device.create_command_encoder(&CommandEncoderDescriptor {
+        label: Some("color_grading_lut_command_encoder"),
+    });
+    let mut baked_any = false;
+    let mut new_readbacks = Vec::new();
+
+    for (entity, lut) in luts.iter_mut() {
+        let Ok(view_uniform_offset) = views.get(*entity) else {
+            continue;
+        };
+
+        let lut_bindings = get_lut_bindings(
+            &gpu_images,
+            &tonemapping_luts,
+            &lut.tonemapping,
+            &fallback_image,
+        );
+        let baked = (
+            lut.color_grading.clone(),
+            lut.tonemapping.clone(),
+            lut_bindings.0.id(),
+        );
+
+        if lut.baked.as_ref() != Some(&baked) {
+            let Some(compute_pipeline) = pipeline_cache.get_compute_pipeline(lut.pipeline) else {
+                continue;
+            };
+
+            let bind_group = render_device.create_bind_group(
+                "color_grading_lut_bind_group",
+                &pipeline.layout,
+                &BindGroupEntries::with_indices((
+                    (0, view_uniforms.clone()),
+                    (1, &lut.texture_view),
+                    (3, lut_bindings.0),
+                    (4, lut_bindings.1),
+                )),
+            );
+
+            let mut compute_pass = command_encoder.begin_compute_pass(&ComputePassDescriptor {
+                label: Some("color_grading_lut_pass"),
+                timestamp_writes: None,
+            });
+            compute_pass.set_pipeline(compute_pipeline);
+            compute_pass.set_bind_group(0, &bind_group, &[view_uniform_offset.offset]);
+            let workgroups = lut.size.div_ceil(4);
+            compute_pass.dispatch_workgroups(workgroups, workgroups, workgroups);
+            drop(compute_pass);
+
+            lut.baked = Some(baked);
+            baked_any = true;
+        }
+
+        if let Some(callback) = exports.remove(entity) {
+            let buffer = copy_lut_to_buffer(&render_device, &mut command_encoder, lut);
+            new_readbacks.push((buffer, lut.size, callback));
+        }
+    }
+
+    // Exports of LUTs that aren't baked yet are kept for the next frames.
+    exports.retain(|entity, _| {
+        let has_lut = luts.contains_key(entity);
+        if !has_lut {
+            warn!("Camera {entity:?} has no ColorGradingLut of an HDR camera to export");
+        }
+        has_lut
+    });
+
+    if !baked_any && new_readbacks.is_empty() {
+        return;
+    }
+    render_queue.submit([command_encoder.finish()]);
+
+    // The buffers are mapped once the GPU is done with the copies, which the renderer polls for
+    // every frame, so the exports are finished on a later frame instead of waiting for it here.
+    for (buffer, size, callback) in new_readbacks {
+        let mapped = Arc::new(OnceLock::new());
+        let mapped_clone = mapped.clone();
+        buffer.slice(..).map_async(MapMode::Read, move |result| {
+            let _ = mapped_clone.set(result);
+        });
+        readbacks.push(ColorGradingLutReadback {
+            buffer,
+            size,
+            callback,
+            mapped,
+        });
+    }
+}
+
+/// Calls the callbacks of the exported LUTs whose buffers have been mapped since the last frame.
+fn finish_color_grading_lut_readbacks(readbacks: &mut Vec<ColorGradingLutReadback>) {
+    for readback in std::mem::take(readbacks) {
+        match readback.mapped.get() {
+            None => readbacks.push(readback),
+            Some(Err(e)) => error!("Failed to read back the color grading LUT: {e}"),
+            Some(Ok(())) => {
+                let size = readback.size;
+                // Remove the padding that aligns the rows to `COPY_BYTES_PER_ROW_ALIGNMENT`.
+                let (row_bytes, padded_row_bytes) = lut_row_bytes(size);
+                let data = readback
+                    .buffer
+                    .slice(..)
+                    .get_mapped_range()
+                    .chunks(padded_row_bytes)
+                    .flat_map(|row| &row[..row_bytes])
+                    .copied()
+                    .collect();
+
+                let mut image = Image::new(
+                    Extent3d {
+                        width: size,
+                        height: size,
+                        depth_or_array_layers: size,
+                    },
+                    TextureDimension::D3,
+                    data,
+                    COLOR_GRADING_LUT_FORMAT,
+                    RenderAssetUsages::default(),
+                );
+                image.sampler = ImageSampler::Descriptor(ImageSamplerDescriptor {
+                    label: Some("Tonemapping LUT sampler".to_string()),
+                    address_mode_u: ImageAddressMode::ClampToEdge,
+                    address_mode_v: ImageAddressMode::ClampToEdge,
+                    address_mode_w: ImageAddressMode::ClampToEdge,
+                    mag_filter: ImageFilterMode::Linear,
+                    min_filter: ImageFilterMode::Linear,
+                    mipmap_filter: ImageFilterMode::Linear,
+                    ..default()
+                });
+                (readback.callback)(image);
+            }
+        }
+    }
+}
+
+fn copy_lut_to_buffer(
+    render_device: &RenderDevice,
+    command_encoder: &mut CommandEncoder,
+    lut: &BakedColorGradingLut,
+) -> Buffer {
+    let (_, padded_row_bytes) = lut_row_bytes(lut.size);
+
+    let buffer = render_device.create_buffer(&BufferDescriptor {
+        label: Some("color_grading_lut_readback_buffer"),
+        size: (padded_row_bytes * (lut.size * lut.size) as usize) as u64,
+        usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+    command_encoder.copy_texture_to_buffer(
+        lut.texture.as_image_copy(),
+        ImageCopyBuffer {
+            buffer: &buffer,
+            layout: ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(padded_row_bytes as u32),
+                rows_per_image: Some(lut.size),
+            },
+        },
+        Extent3d {
+            width: lut.size,
+            height: lut.size,
+            depth_or_array_layers: lut.size,
+        },
+    );
+    buffer
+}
+
+/// Returns the size in bytes of a row of a LUT, without and with the padding needed to copy it to
+/// a buffer.
+fn lut_row_bytes(size: u32) -> (usize, usize) {
+    let row_bytes = size as usize * COLOR_GRADING_LUT_FORMAT.pixel_size();
+    (row_bytes, RenderDevice::align_copy_bytes_per_row(row_bytes))
+}
+
+/// The number of entries of the shaper LUT of the exported `.cube` files.
+const CUBE_SHAPER_SIZE: usize = 4096;
+
+/// The brightest stimulus the shaper LUT of the exported `.cube` files covers.
+///
+/// The entries of the shaper are evenly spaced, so this is kept low enough for them to follow
+/// the `x / (x + 1)` curve closely near black.
+const CUBE_SHAPER_INPUT_MAX: f32 = 64.0;
+
+/// Writes an exported [`ColorGradingLut`] in the `.cube` format.
+///
+/// The 3D LUT is indexed by the stimulus encoded with `x / (x + 1)` rather than by the linear
+/// stimulus that `.cube` readers expect, so it is preceded by a 1D shaper LUT which encodes
+/// the stimulus.
+fn cube_lut_from_image(image: &Image) -> String {
+    let mut cube = String::new();
+    let _ = writeln!(cube, "TITLE \"Bevy color grading\"");
+    let _ = writeln!(cube, "LUT_1D_SIZE {CUBE_SHAPER_SIZE}");
+    let _ = writeln!(cube, "LUT_1D_INPUT_RANGE 0.0 {CUBE_SHAPER_INPUT_MAX:.1}");
+    let _ = writeln!(cube, "LUT_3D_SIZE {}", image.width());
+    for index in 0..CUBE_SHAPER_SIZE {
+        let stimulus = index as f32 / (CUBE_SHAPER_SIZE - 1) as f32 * CUBE_SHAPER_INPUT_MAX;
+        let encoded = stimulus / (stimulus + 1.0);
+        let _ = writeln!(cube, "{encoded:.6} {encoded:.6} {encoded:.6}");
+    }
+    // Texels are stored with red changing fastest, which is also the order of a `.cube` file.
+    for texel in image.data.chunks_exact(8) {
+        let [red, green, blue] = [0, 2, 4]
+            .map(|offset| f32_from_f16(u16::from_le_bytes([texel[offset], texel[offset + 1]])));
+        let _ = writeln!(cube, "{red:.6} {green:.6} {blue:.6}");
+    }
+    cube
+}
+
+fn f32_from_f16(bits: u16) -> f32 {
+    let sign = if bits & 0x8000 != 0 { -1.0 } else { 1.0 };
+    let exponent = ((bits >> 10) & 0x1f) as i32;
+    let mantissa = (bits & 0x3ff) as f32 / 1024.0;
+    sign * match exponent {
+        0 => mantissa * 2f32.powi(-14),
+        0x1f if mantissa == 0.0 => f32::INFINITY,
+        0x1f => f32::NAN,
+        _ => (1.0 + mantissa) * 2f32.powi(exponent - 15),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn f32_from_f16_values() {
+        assert_eq!(f32_from_f16(0x0000), 0.0);
+        assert_eq!(f32_from_f16(0x3c00), 1.0);
+        assert_eq!(f32_from_f16(0xc000), -2.0);
+        assert_eq!(f32_from_f16(0x3555), 0.333_251_95);
+        assert_eq!(f32_from_f16(0x7bff), 65504.0);
+        // The smallest subnormal.
+        assert_eq!(f32_from_f16(0x0001), 2f32.powi(-24));
+        assert_eq!(f32_from_f16(0x7c00), f32::INFINITY);
+        assert_eq!(f32_from_f16(0xfc00), f32::NEG_INFINITY);
+        assert!(f32_from_f16(0x7e00).is_nan());
+    }
+
+    #[test]
+    fn cube_lut_layout() {
+        // A 2x2x2 LUT whose red channel holds the index of each texel.
+        let data = [
+            0x0000, 0x3c00, 0x4000, 0x4200, 0x4400, 0x4500, 0x4600, 0x4700u16,
+        ]
+        .into_iter()
+        .flat_map(|red| [red, 0x3c00, 0x4000, 0x3c00])
+        .flat_map(u16::to_le_bytes)
+        .collect();
+        let image = Image::new(
+            Extent3d {
+                width: 2,
+                height: 2,
+                depth_or_array_layers: 2,
+            },
+            TextureDimension::D3,
+            data,
+            COLOR_GRADING_LUT_FORMAT,
+            RenderAssetUsages::default(),
+        );
+
+        let cube = cube_lut_from_image(&image);
+        let mut lines = cube.lines();
+        assert_eq!(lines.next(), Some("TITLE \"Bevy color grading\""));
+        assert_eq!(lines.next(), Some("LUT_1D_SIZE 4096"));
+        assert_eq!(lines.next(), Some("LUT_1D_INPUT_RANGE 0.0 64.0"));
+        assert_eq!(lines.next(), Some("LUT_3D_SIZE 2"));
+
+        let shaper: Vec<_> = lines.by_ref().take(CUBE_SHAPER_SIZE).collect();
+        assert_eq!(shaper[0], "0.000000 0.000000 0.000000");
+        assert_eq!(shaper[CUBE_SHAPER_SIZE - 1], "0.984615 0.984615 0.984615");
+
+        let texels: Vec<_> = lines.collect();
+        assert_eq!(texels.len(), 8);
+        for (index, texel) in texels.iter().enumerate() {
+            assert_eq!(*texel, format!("{:.6} 1.000000 2.000000", index as f32));
+        }
+    }
+}
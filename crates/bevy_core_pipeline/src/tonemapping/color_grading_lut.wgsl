@@ -0,0 +1,25 @@
+// Bakes the color grading and tonemapping of a view into a 3D LUT, which the
+// tonemapping pass then samples instead of evaluating them for every pixel.
+
+#import bevy_render::view::View
+#import bevy_core_pipeline::tonemapping::tone_mapping
+
+@group(0) @binding(0) var<uniform> view: View;
+@group(0) @binding(1) var baked_lut: texture_storage_3d<rgba16float, write>;
+
+@compute @workgroup_size(4, 4, 4)
+fn bake(@builtin(global_invocation_id) texel: vec3<u32>) {
+    let size = textureDimensions(baked_lut);
+    if (any(texel >= size)) {
+        return;
+    }
+
+    // The LUT is indexed by the stimulus encoded with `x / (x + 1)`. The last
+    // texels, which would be infinitely bright, are clamped to a stimulus of
+    // 1000 instead.
+    let encoded = vec3<f32>(texel) / vec3<f32>(size - 1u);
+    let stimulus = encoded / max(1.0 - encoded, vec3(0.001));
+
+    let color = tone_mapping(vec4(stimulus, 1.0), view.color_grading).rgb;
+    textureStore(baked_lut, texel, vec4(color, 1.0));
+}
@@ -0,0 +1,472 @@
+use bevy_asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext};
+use bevy_render::{
+    render_asset::RenderAssetUsages,
+    render_resource::{Extent3d, TextureDimension, TextureFormat},
+    texture::{Image, ImageAddressMode, ImageFilterMode, ImageSampler, ImageSamplerDescriptor},
+};
+use bevy_utils::default;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Loads 3D LUTs in the `.cube` format, for use with [`Tonemapping::Lut`](super::Tonemapping::Lut).
+///
+/// The input of a `.cube` LUT is the linear stimulus within its domain, which is `[0, 1]` unless
+/// the file sets `DOMAIN_MIN`/`DOMAIN_MAX` or `LUT_3D_INPUT_RANGE`. A 1D shaper LUT set with
+/// `LUT_1D_SIZE` (and `LUT_1D_INPUT_RANGE`), as written by Resolve, is applied to the stimulus
+/// before the 3D LUT. Since [`Tonemapping::Lut`](super::Tonemapping::Lut) is indexed by the
+/// stimulus encoded with `x / (x + 1)`, the LUT is resampled into that encoding when it's loaded,
+/// at the same size. Stimuli outside of the domain are clamped to it.
+#[derive(Clone, Default)]
+pub struct CubeLutLoader;
+
+#[derive(Serialize, Deserialize, Default, Debug)]
+pub struct CubeLutLoaderSettings {
+    pub asset_usage: RenderAssetUsages,
+}
+
+#[non_exhaustive]
+#[derive(Debug, Error)]
+pub enum CubeLutLoaderError {
+    #[error("Could not load LUT: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Could not read LUT: {0}")]
+    Utf8(#[from] std::str::Utf8Error),
+    #[error("Invalid LUT on line {0}")]
+    InvalidLine(usize),
+    #[error("Unsupported LUT keyword on line {0}: {1}")]
+    Unsupported(usize, String),
+    #[error("The LUT has no LUT_3D_SIZE")]
+    MissingSize,
+    #[error("The LUT should have {expected} entries, but has {found}")]
+    WrongEntryCount { expected: usize, found: usize },
+}
+
+impl AssetLoader for CubeLutLoader {
+    type Asset = Image;
+    type Settings = CubeLutLoaderSettings;
+    type Error = CubeLutLoaderError;
+    async fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader<'_>,
+        settings: &'a Self::Settings,
+        _load_context: &'a mut LoadContext<'_>,
+    ) -> Result<Image, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let lut = CubeLut::parse(std::str::from_utf8(&bytes)?)?;
+
+        let mut image = Image::new(
+            Extent3d {
+                width: lut.size,
+                height: lut.size,
+                depth_or_array_layers: lut.size,
+            },
+            TextureDimension::D3,
+            lut.resample(),
+            TextureFormat::Rgb9e5Ufloat,
+            settings.asset_usage,
+        );
+        image.sampler = ImageSampler::Descriptor(ImageSamplerDescriptor {
+            label: Some("Tonemapping LUT sampler".to_string()),
+            address_mode_u: ImageAddressMode::ClampToEdge,
+            address_mode_v: ImageAddressMode::ClampToEdge,
+            address_mode_w: ImageAddressMode::ClampToEdge,
+            mag_filter: ImageFilterMode::Linear,
+            min_filter: ImageFilterMode::Linear,
+            mipmap_filter: ImageFilterMode::Linear,
+            ..default()
+        });
+        Ok(image)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["cube"]
+    }
+}
+
+/// The contents of a `.cube` file.
+#[derive(Debug, PartialEq)]
+struct CubeLut {
+    size: u32,
+    /// The entries of the 3D LUT, red changing fastest.
+    entries: Vec<[f32; 3]>,
+    domain_min: [f32; 3],
+    domain_max: [f32; 3],
+    shaper: Option<CubeLutShaper>,
+}
+
+/// A 1D LUT applied to each channel of the stimulus, whose output indexes the 3D LUT.
+#[derive(Debug, PartialEq)]
+struct CubeLutShaper {
+    entries: Vec<[f32; 3]>,
+    input_min: f32,
+    input_max: f32,
+}
+
+impl CubeLut {
+    fn parse(text: &str) -> Result<Self, CubeLutLoaderError> {
+        let mut size = None;
+        let mut shaper_size = None;
+        let mut domain_min = [0.0; 3];
+        let mut domain_max = [1.0; 3];
+        let mut shaper_input = (0.0, 1.0);
+        let mut entries = Vec::new();
+        for (index, line) in text.lines().enumerate() {
+            let line_number = index + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let mut words = line.split_whitespace();
+            let Some(first) = words.next() else {
+                continue;
+            };
+            let mut numbers = || {
+                words
+                    .by_ref()
+                    .map(str::parse::<f32>)
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|_| CubeLutLoaderError::InvalidLine(line_number))
+            };
+
+            if let Ok(red) = first.parse::<f32>() {
+                let [green, blue] = numbers()?[..] else {
+                    return Err(CubeLutLoaderError::InvalidLine(line_number));
+                };
+                entries.push([red, green, blue]);
+                continue;
+            }
+
+            match first {
+                "TITLE" => {}
+                "LUT_3D_SIZE" | "LUT_1D_SIZE" => {
+                    let parsed = words
+                        .next()
+                        .and_then(|word| word.parse::<u32>().ok())
+                        .filter(|size| match first {
+                            "LUT_3D_SIZE" => (2..=256).contains(size),
+                            _ => (2..=65536).contains(size),
+                        });
+                    if parsed.is_none() {
+                        return Err(CubeLutLoaderError::InvalidLine(line_number));
+                    }
+                    match first {
+                        "LUT_3D_SIZE" => size = parsed,
+                        _ => shaper_size = parsed,
+                    }
+                }
+                "DOMAIN_MIN" | "DOMAIN_MAX" => {
+                    let [red, green, blue] = numbers()?[..] else {
+                        return Err(CubeLutLoaderError::InvalidLine(line_number));
+                    };
+                    match first {
+                        "DOMAIN_MIN" => domain_min = [red, green, blue],
+                        _ => domain_max = [red, green, blue],
+                    }
+                }
+                "LUT_3D_INPUT_RANGE" | "LUT_1D_INPUT_RANGE" => {
+                    let [min, max] = numbers()?[..] else {
+                        return Err(CubeLutLoaderError::InvalidLine(line_number));
+                    };
+                    match first {
+                        "LUT_3D_INPUT_RANGE" => {
+                            domain_min = [min; 3];
+                            domain_max = [max; 3];
+                        }
+                        _ => shaper_input = (min, max),
+                    }
+                }
+                _ => {
+                    return Err(CubeLutLoaderError::Unsupported(
+                        line_number,
+                        first.to_string(),
+                    ))
+                }
+            }
+        }
+
+        let size = size.ok_or(CubeLutLoaderError::MissingSize)?;
+        let shaper_size = shaper_size.unwrap_or(0) as usize;
+        let expected = shaper_size + size.pow(3) as usize;
+        if entries.len() != expected {
+            return Err(CubeLutLoaderError::WrongEntryCount {
+                expected,
+                found: entries.len(),
+            });
+        }
+
+        // The shaper comes first in the file.
+        let shaper = (shaper_size > 0).then(|| CubeLutShaper {
+            entries: entries.drain(..shaper_size).collect(),
+            input_min: shaper_input.0,
+            input_max: shaper_input.1,
+        });
+        Ok(CubeLut {
+            size,
+            entries,
+            domain_min,
+            domain_max,
+            shaper,
+        })
+    }
+
+    /// Samples the LUT at each texel of a LUT of the same size indexed by the stimulus encoded
+    /// with `x / (x + 1)`, and packs the results into [`TextureFormat::Rgb9e5Ufloat`] texels.
+    fn resample(&self) -> Vec<u8> {
+        let size = self.size as usize;
+        let mut data = Vec::with_capacity(size.pow(3) * 4);
+        for blue in 0..size {
+            for green in 0..size {
+                for red in 0..size {
+                    let stimulus = [red, green, blue].map(|index| {
+                        let encoded = index as f32 / (size - 1) as f32;
+                        // The last texel is reached by an infinite stimulus, which is clamped to
+                        // the domain like any stimulus outside of it.
+                        encoded / (1.0 - encoded)
+                    });
+                    data.extend_from_slice(&rgb9e5_from_rgb(self.sample(stimulus)).to_le_bytes());
+                }
+            }
+        }
+        data
+    }
+
+    /// Applies the LUT to a linear stimulus, interpolating between its entries.
+    fn sample(&self, stimulus: [f32; 3]) -> [f32; 3] {
+        let input = match &self.shaper {
+            Some(shaper) => [0, 1, 2].map(|channel| {
+                let position =
+                    (stimulus[channel] - shaper.input_min) / (shaper.input_max - shaper.input_min);
+                let (low, high, t) = lerp_indices(position, shaper.entries.len());
+                lerp(
+                    shaper.entries[low][channel],
+                    shaper.entries[high][channel],
+                    t,
+                )
+            }),
+            None => stimulus,
+        };
+
+        let size = self.size as usize;
+        let [red, green, blue] = [0, 1, 2].map(|channel| {
+            let position = (input[channel] - self.domain_min[channel])
+                / (self.domain_max[channel] - self.domain_min[channel]);
+            lerp_indices(position, size)
+        });
+        let entry = |red: usize, green: usize, blue: usize| {
+            self.entries[red + green * size + blue * size * size]
+        };
+
+        [0, 1, 2].map(|channel| {
+            let along_red = |green: usize, blue: usize| {
+                lerp(
+                    entry(red.0, green, blue)[channel],
+                    entry(red.1, green, blue)[channel],
+                    red.2,
+                )
+            };
+            let along_green =
+                |blue: usize| lerp(along_red(green.0, blue), along_red(green.1, blue), green.2);
+            lerp(along_green(blue.0), along_green(blue.1), blue.2)
+        })
+    }
+}
+
+/// Returns the two entries of a LUT with `count` entries around a position in `[0, 1]`, clamping
+/// it to that range, and how far the position is between them.
+fn lerp_indices(position: f32, count: usize) -> (usize, usize, f32) {
+    // An empty domain divides zero by zero.
+    let position = if position.is_nan() {
+        0.0
+    } else {
+        position.clamp(0.0, 1.0) * (count - 1) as f32
+    };
+    let low = (position.floor() as usize).min(count - 2);
+    (low, low + 1, position - low as f32)
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+/// Packs a color into the shared exponent format used by [`TextureFormat::Rgb9e5Ufloat`], which
+/// is filterable everywhere, unlike 32-bit float formats.
+fn rgb9e5_from_rgb(rgb: [f32; 3]) -> u32 {
+    const MANTISSA_BITS: i32 = 9;
+    const EXPONENT_BIAS: i32 = 15;
+    const MAX_EXPONENT: i32 = 31;
+    const MAX_VALUE: f32 = (511.0 / 512.0) * (1 << (MAX_EXPONENT - EXPONENT_BIAS)) as f32;
+
+    let [red, green, blue] = rgb.map(|channel| channel.clamp(0.0, MAX_VALUE));
+    let max_channel = red.max(green).max(blue);
+
+    let mut exponent =
+        (max_channel.log2().floor() as i32).max(-EXPONENT_BIAS - 1) + 1 + EXPONENT_BIAS;
+    let mut scale = 2f32.powi(exponent - EXPONENT_BIAS - MANTISSA_BITS);
+    if (max_channel / scale).round() as u32 == 1 << MANTISSA_BITS {
+        exponent += 1;
+        scale *= 2.0;
+    }
+
+    let [red, green, blue] = [red, green, blue].map(|channel| (channel / scale).round() as u32);
+    ((exponent as u32) << 27) | (blue << 18) | (green << 9) | red
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{rgb9e5_from_rgb, CubeLut, CubeLutLoaderError, CubeLutShaper};
+
+    const IDENTITY: &str = "
+        # An identity LUT
+        TITLE \"Identity\"
+        LUT_3D_SIZE 2
+
+        0 0 0
+        1 0 0
+        0 1 0
+        1 1 0
+        0 0 1
+        1 0 1
+        0 1 1
+        1 1 1
+    ";
+
+    fn identity_entries() -> Vec<[f32; 3]> {
+        (0..8)
+            .map(|index| [index & 1, (index >> 1) & 1, (index >> 2) & 1].map(|bit| bit as f32))
+            .collect()
+    }
+
+    fn assert_close(a: [f32; 3], b: [f32; 3]) {
+        assert!(
+            a.iter().zip(b).all(|(a, b)| (a - b).abs() < 1e-5),
+            "{a:?} != {b:?}"
+        );
+    }
+
+    #[test]
+    fn parse_identity() {
+        assert_eq!(
+            CubeLut::parse(IDENTITY).unwrap(),
+            CubeLut {
+                size: 2,
+                entries: identity_entries(),
+                domain_min: [0.0; 3],
+                domain_max: [1.0; 3],
+                shaper: None,
+            }
+        );
+    }
+
+    #[test]
+    fn parse_domain_and_shaper() {
+        let lut = CubeLut::parse(&format!(
+            "DOMAIN_MIN 0 0.5 0\nDOMAIN_MAX 2 2 4\nLUT_1D_SIZE 2\nLUT_1D_INPUT_RANGE 0 8\n\
+             0 0 0\n1 1 1\n{IDENTITY}"
+        ))
+        .unwrap();
+        assert_eq!(lut.domain_min, [0.0, 0.5, 0.0]);
+        assert_eq!(lut.domain_max, [2.0, 2.0, 4.0]);
+        assert_eq!(
+            lut.shaper,
+            Some(CubeLutShaper {
+                entries: vec![[0.0; 3], [1.0; 3]],
+                input_min: 0.0,
+                input_max: 8.0,
+            })
+        );
+        assert_eq!(lut.entries, identity_entries());
+
+        let lut = CubeLut::parse(&format!("LUT_3D_INPUT_RANGE -1 3\n{IDENTITY}")).unwrap();
+        assert_eq!(lut.domain_min, [-1.0; 3]);
+        assert_eq!(lut.domain_max, [3.0; 3]);
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert!(matches!(
+            CubeLut::parse("0 0 0"),
+            Err(CubeLutLoaderError::MissingSize)
+        ));
+        assert!(matches!(
+            CubeLut::parse("LUT_3D_SIZE 1"),
+            Err(CubeLutLoaderError::InvalidLine(1))
+        ));
+        assert!(matches!(
+            CubeLut::parse("LUT_3D_SIZE 2\n0 0"),
+            Err(CubeLutLoaderError::InvalidLine(2))
+        ));
+        assert!(matches!(
+            CubeLut::parse("LUT_3D_SIZE 2\nDOMAIN_MIN 0 0"),
+            Err(CubeLutLoaderError::InvalidLine(2))
+        ));
+        assert!(matches!(
+            CubeLut::parse("LUT_3D_SIZE 2\n0 0 0"),
+            Err(CubeLutLoaderError::WrongEntryCount {
+                expected: 8,
+                found: 1
+            })
+        ));
+        assert!(matches!(
+            CubeLut::parse("LUT_3D_SIZE 2\nLUT_3D_GAMMA 2.2"),
+            Err(CubeLutLoaderError::Unsupported(2, keyword)) if keyword == "LUT_3D_GAMMA"
+        ));
+    }
+
+    #[test]
+    fn sample_honors_domain_and_shaper() {
+        let mut lut = CubeLut::parse(IDENTITY).unwrap();
+        assert_close(lut.sample([0.25, 0.5, 0.75]), [0.25, 0.5, 0.75]);
+        // Stimuli outside of the domain are clamped to it.
+        assert_close(lut.sample([2.0, 0.0, f32::INFINITY]), [1.0, 0.0, 1.0]);
+
+        lut.domain_max = [2.0, 4.0, 8.0];
+        assert_close(lut.sample([1.0, 1.0, 1.0]), [0.5, 0.25, 0.125]);
+
+        lut.domain_max = [1.0; 3];
+        lut.shaper = Some(CubeLutShaper {
+            entries: vec![[0.0; 3], [1.0; 3]],
+            input_min: 0.0,
+            input_max: 4.0,
+        });
+        assert_close(lut.sample([1.0, 2.0, 4.0]), [0.25, 0.5, 1.0]);
+    }
+
+    #[test]
+    fn resample_encodes_stimulus() {
+        let mut lut = CubeLut::parse(IDENTITY).unwrap();
+        lut.size = 3;
+        lut.entries = (0..27)
+            .map(|index| [index % 3, index / 3 % 3, index / 9].map(|i| i as f32 / 2.0))
+            .collect();
+        lut.domain_max = [2.0; 3];
+
+        let texels: Vec<u32> = lut
+            .resample()
+            .chunks_exact(4)
+            .map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap()))
+            .collect();
+        assert_eq!(texels.len(), 27);
+        // The middle texel is reached by a stimulus of 1, halfway through the domain.
+        assert_eq!(texels[13], rgb9e5_from_rgb([0.5; 3]));
+        assert_eq!(texels[0], rgb9e5_from_rgb([0.0; 3]));
+        assert_eq!(texels[26], rgb9e5_from_rgb([1.0; 3]));
+    }
+
+    #[test]
+    fn rgb9e5() {
+        assert_eq!(rgb9e5_from_rgb([0.0; 3]), 0);
+        // 1.0 is 256 / 2^8, so the exponent is 8 + 15 - 9 + 1 = 16 with a mantissa of 256.
+        assert_eq!(rgb9e5_from_rgb([1.0, 0.0, 0.0]), (16 << 27) | 256);
+        assert_eq!(
+            rgb9e5_from_rgb([1.0, 0.5, 0.25]),
+            (16 << 27) | (64 << 18) | (128 << 9) | 256
+        );
+        // Negative values are clamped to zero, and large ones to the largest value.
+        assert_eq!(rgb9e5_from_rgb([-1.0, 1.0, 0.0]), (16 << 27) | (256 << 9));
+        assert_eq!(rgb9e5_from_rgb([f32::MAX; 3]), u32::MAX);
+        // Rounding up to the next power of two bumps the exponent.
+        assert_eq!(rgb9e5_from_rgb([1.0 - 1e-4, 0.0, 0.0]), (16 << 27) | 256);
+    }
+}
@@ -1,6 +1,6 @@
 use crate::fullscreen_vertex_shader::fullscreen_shader_vertex_state;
 use bevy_app::prelude::*;
-use bevy_asset::{load_internal_asset, Assets, Handle};
+use bevy_asset::{load_internal_asset, AssetApp, AssetEvent, AssetId, Assets, Handle};
 use bevy_ecs::prelude::*;
 use bevy_reflect::Reflect;
 use bevy_render::extract_component::{ExtractComponent, ExtractComponentPlugin};
@@ -11,16 +11,22 @@ use bevy_render::render_resource::binding_types::{
 };
 use bevy_render::renderer::RenderDevice;
 use bevy_render::texture::{CompressedImageFormats, GpuImage, Image, ImageSampler, ImageType};
-use bevy_render::view::{ExtractedView, ViewTarget, ViewUniform};
+use bevy_render::view::{ColorGrading, ExtractedView, ViewTarget, ViewUniform};
 use bevy_render::{camera::Camera, texture::FallbackImage};
 use bevy_render::{render_resource::*, Render, RenderApp, RenderSet};
-#[cfg(not(feature = "tonemapping_luts"))]
-use bevy_utils::tracing::error;
+use bevy_utils::{tracing::error, HashSet};
 use bitflags::bitflags;
 
+mod color_grading_lut;
+mod cube_lut_loader;
 mod node;
 
 use bevy_utils::default;
+pub use color_grading_lut::{
+    ColorGradingLut, ColorGradingLutExportAlreadyRequestedError, ColorGradingLutExporter,
+    ColorGradingLutPlugin, ViewColorGradingLut,
+};
+pub use cube_lut_loader::{CubeLutLoader, CubeLutLoaderError};
 pub use node::TonemappingNode;
 
 const TONEMAPPING_SHADER_HANDLE: Handle<Shader> = Handle::weak_from_u128(17015368199668024512);
@@ -31,6 +37,9 @@ const TONEMAPPING_SHARED_SHADER_HANDLE: Handle<Shader> =
 const TONEMAPPING_LUT_BINDINGS_SHADER_HANDLE: Handle<Shader> =
     Handle::weak_from_u128(8392056472189465073);
 
+const CUSTOM_TONEMAPPING_SHADER_HANDLE: Handle<Shader> =
+    Handle::weak_from_u128(6175390249181725032);
+
 /// 3D LUT (look up table) textures used for tonemapping
 #[derive(Resource, Clone, ExtractResource)]
 pub struct TonemappingLuts {
@@ -61,6 +70,12 @@ impl Plugin for TonemappingPlugin {
             "lut_bindings.wgsl",
             Shader::from_wgsl
         );
+        load_internal_asset!(
+            app,
+            CUSTOM_TONEMAPPING_SHADER_HANDLE,
+            "custom_tonemapping.wgsl",
+            Shader::from_wgsl
+        );
 
         if !app.world().is_resource_added::<TonemappingLuts>() {
             let mut images = app.world_mut().resource_mut::<Assets<Image>>();
@@ -96,7 +111,8 @@ impl Plugin for TonemappingPlugin {
             app.insert_resource(tonemapping_luts);
         }
 
-        app.add_plugins(ExtractResourcePlugin::<TonemappingLuts>::default());
+        app.add_plugins(ExtractResourcePlugin::<TonemappingLuts>::default())
+            .init_asset_loader::<CubeLutLoader>();
 
         app.register_type::<Tonemapping>();
         app.register_type::<DebandDither>();
@@ -104,7 +120,11 @@ impl Plugin for TonemappingPlugin {
         app.add_plugins((
             ExtractComponentPlugin::<Tonemapping>::default(),
             ExtractComponentPlugin::<DebandDither>::default(),
-        ));
+            ColorGradingLutPlugin,
+        ))
+        .init_resource::<CustomTonemappingCurves>()
+        .add_plugins(ExtractResourcePlugin::<CustomTonemappingCurves>::default())
+        .add_systems(PostUpdate, update_custom_tonemapping_curves);
 
         let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
             return;
@@ -125,6 +145,124 @@ impl Plugin for TonemappingPlugin {
     }
 }
 
+/// The maximum number of different [`Tonemapping::Custom`] curves that can be in use at the same
+/// time.
+pub const MAX_CUSTOM_TONEMAPPING_CURVES: usize = 8;
+
+/// The [`Tonemapping::Custom`] curves in use, each of which has a slot in the module imported by
+/// `tonemapping_shared.wgsl`.
+///
+/// Shader imports must be available whether or not the shader defs that guard them are set, so
+/// the module always exists. Each curve is spliced into it under its own
+/// `CUSTOM_TONEMAPPING_CURVE_{slot}` shader def, which the pipelines of a view set according to
+/// the curve of that view, so that each camera can use a different curve.
+#[derive(Resource, Clone, Default, ExtractResource)]
+pub struct CustomTonemappingCurves {
+    slots: [Option<AssetId<Shader>>; MAX_CUSTOM_TONEMAPPING_CURVES],
+}
+
+impl CustomTonemappingCurves {
+    /// Returns the slot of the curve of the given tonemapping, if it is a [`Tonemapping::Custom`]
+    /// curve that has one.
+    pub fn slot(&self, tonemapping: &Tonemapping) -> Option<u32> {
+        let Tonemapping::Custom(shader) = tonemapping else {
+            return None;
+        };
+        self.slots
+            .iter()
+            .position(|slot| *slot == Some(shader.id()))
+            .map(|slot| slot as u32)
+    }
+}
+
+/// Returns the shader def that selects the curve in the given slot of the
+/// [`CustomTonemappingCurves`].
+pub fn custom_tonemapping_curve_shader_def(slot: u32) -> ShaderDefVal {
+    format!("CUSTOM_TONEMAPPING_CURVE_{slot}").into()
+}
+
+/// Gives a slot to each [`Tonemapping::Custom`] curve in use, and splices their sources into the
+/// module imported by `tonemapping_shared.wgsl`.
+fn update_custom_tonemapping_curves(
+    tonemappings: Query<&Tonemapping>,
+    mut shader_events: EventReader<AssetEvent<Shader>>,
+    mut shaders: ResMut<Assets<Shader>>,
+    mut curves: ResMut<CustomTonemappingCurves>,
+) {
+    let mut changed = false;
+    for event in shader_events.read() {
+        changed |= curves
+            .slots
+            .iter()
+            .flatten()
+            .any(|id| event.is_loaded_with_dependencies(*id) || event.is_modified(*id));
+    }
+
+    let in_use: HashSet<_> = tonemappings
+        .iter()
+        .filter_map(|tonemapping| match tonemapping {
+            Tonemapping::Custom(shader) => Some(shader.id()),
+            _ => None,
+        })
+        .collect();
+    // Freed slots keep their source until they are reused, so that freeing them doesn't cause
+    // every shader that tonemaps to be compiled again.
+    for slot in &mut curves.slots {
+        if slot.is_some_and(|id| !in_use.contains(&id)) {
+            *slot = None;
+        }
+    }
+    for id in in_use {
+        if curves.slots.contains(&Some(id)) {
+            continue;
+        }
+        let Some(slot) = curves.slots.iter_mut().find(|slot| slot.is_none()) else {
+            error!(
+                "At most {MAX_CUSTOM_TONEMAPPING_CURVES} different Tonemapping::Custom curves \
+                can be in use at the same time"
+            );
+            break;
+        };
+        *slot = Some(id);
+        changed = true;
+    }
+
+    if !changed {
+        return;
+    }
+
+    let mut source = String::from("#define_import_path bevy_core_pipeline::custom_tonemapping\n");
+    let mut first = true;
+    for (slot, id) in curves.slots.iter().enumerate() {
+        // Curves that aren't loaded yet use the placeholder until they are
+        let Some(shader) = id.and_then(|id| shaders.get(id)) else {
+            continue;
+        };
+        let Source::Wgsl(curve) = &shader.source else {
+            error!("Tonemapping::Custom requires a WGSL shader");
+            continue;
+        };
+        let directive = if first { "#ifdef" } else { "#else ifdef" };
+        source.push_str(&format!(
+            "{directive} CUSTOM_TONEMAPPING_CURVE_{slot}\n{curve}\n"
+        ));
+        first = false;
+    }
+    let placeholder = include_str!("custom_tonemapping.wgsl")
+        .split_once('\n')
+        .map_or("", |(_, placeholder)| placeholder);
+    if first {
+        source.push_str(placeholder);
+    } else {
+        source.push_str(&format!("#else\n{placeholder}\n#endif\n"));
+    }
+
+    shaders.insert(
+        &CUSTOM_TONEMAPPING_SHADER_HANDLE,
+        Shader::from_wgsl(source, "custom_tonemapping.wgsl"),
+    );
+}
+
 #[derive(Resource)]
 pub struct TonemappingPipeline {
     texture_bind_group: BindGroupLayout,
@@ -132,9 +270,7 @@ pub struct TonemappingPipeline {
 }
 
 /// Optionally enables a tonemapping shader that attempts to map linear input stimulus into a perceptually uniform image for a given [`Camera`] entity.
-#[derive(
-    Component, Debug, Hash, Clone, Copy, Reflect, Default, ExtractComponent, PartialEq, Eq,
-)]
+#[derive(Component, Debug, Hash, Clone, Reflect, Default, ExtractComponent, PartialEq, Eq)]
 #[extract_component_filter(With<Camera>)]
 #[reflect(Component)]
 pub enum Tonemapping {
@@ -181,12 +317,48 @@ pub enum Tonemapping {
     /// Somewhat neutral. Suffers from hue shifting. Brights desaturate across the spectrum.
     /// NOTE: Requires the `tonemapping_luts` cargo feature.
     BlenderFilmic,
+    /// A user supplied 3D LUT, e.g. for film emulation.
+    ///
+    /// The LUT is indexed by the linear stimulus encoded with `x / (x + 1)`, the same way as
+    /// [`Tonemapping::TonyMcMapface`], and can be of any size. It can be loaded from a `.ktx2` file
+    /// with a 3D texture, or from a `.cube` file with [`CubeLutLoader`], which resamples it into
+    /// that encoding.
+    ///
+    /// The image should use a linear, clamp-to-edge sampler.
+    Lut(Handle<Image>),
+    /// A user supplied tonemapping curve.
+    ///
+    /// The WGSL shader must define a `custom_tonemapping` function that maps the linear stimulus
+    /// to display values, and no import path. It runs after exposure and color grading, and
+    /// before the post saturation adjustment.
+    ///
+    /// ```wgsl
+    /// fn custom_tonemapping(color: vec3<f32>) -> vec3<f32> {
+    ///     return color / (color + 1.0);
+    /// }
+    /// ```
+    ///
+    /// Each camera can use a different curve, up to [`MAX_CUSTOM_TONEMAPPING_CURVES`] at the same
+    /// time.
+    Custom(Handle<Shader>),
 }
 
 impl Tonemapping {
     pub fn is_enabled(&self) -> bool {
         *self != Tonemapping::None
     }
+
+    /// Returns this tonemapping without its asset handles, for use in pipeline keys.
+    ///
+    /// The LUT is bound at draw time and the curve is selected by its slot, so views using
+    /// different assets can share the same pipeline.
+    fn pipeline_key(&self) -> Self {
+        match self {
+            Tonemapping::Lut(_) => Tonemapping::Lut(Handle::default()),
+            Tonemapping::Custom(_) => Tonemapping::Custom(Handle::default()),
+            tonemapping => tonemapping.clone(),
+        }
+    }
 }
 
 bitflags! {
@@ -202,13 +374,41 @@ bitflags! {
         /// Saturation/contrast/gamma/gain/lift for one or more sections
         /// (shadows, midtones, highlights) need to be adjusted.
         const SECTIONAL_COLOR_GRADING   = 0x04;
+        /// The color grading and tonemapping were baked into a
+        /// [`ColorGradingLut`], which is sampled instead.
+        const BAKED_LUT                 = 0x08;
+    }
+}
+
+impl TonemappingPipelineKeyFlags {
+    /// Returns the flags for the steps of the given color grading that need to
+    /// run.
+    pub fn from_color_grading(color_grading: &ColorGrading) -> Self {
+        let mut flags = TonemappingPipelineKeyFlags::empty();
+        flags.set(
+            TonemappingPipelineKeyFlags::HUE_ROTATE,
+            color_grading.global.hue != 0.0,
+        );
+        flags.set(
+            TonemappingPipelineKeyFlags::WHITE_BALANCE,
+            color_grading.global.temperature != 0.0 || color_grading.global.tint != 0.0,
+        );
+        flags.set(
+            TonemappingPipelineKeyFlags::SECTIONAL_COLOR_GRADING,
+            color_grading
+                .all_sections()
+                .any(|section| *section != default()),
+        );
+        flags
     }
 }
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct TonemappingPipelineKey {
     deband_dither: DebandDither,
     tonemapping: Tonemapping,
+    /// The slot of the [`Tonemapping::Custom`] curve in the [`CustomTonemappingCurves`].
+    custom_tonemapping_curve: Option<u32>,
     flags: TonemappingPipelineKeyFlags,
 }
 
@@ -231,61 +431,12 @@ impl SpecializedRenderPipeline for TonemappingPipeline {
             shader_defs.push("DEBAND_DITHER".into());
         }
 
-        // Define shader flags depending on the color grading options in use.
-        if key.flags.contains(TonemappingPipelineKeyFlags::HUE_ROTATE) {
-            shader_defs.push("HUE_ROTATE".into());
-        }
-        if key
-            .flags
-            .contains(TonemappingPipelineKeyFlags::WHITE_BALANCE)
-        {
-            shader_defs.push("WHITE_BALANCE".into());
-        }
-        if key
-            .flags
-            .contains(TonemappingPipelineKeyFlags::SECTIONAL_COLOR_GRADING)
-        {
-            shader_defs.push("SECTIONAL_COLOR_GRADING".into());
-        }
+        shader_defs.extend(tonemapping_shader_defs(
+            &key.tonemapping,
+            key.custom_tonemapping_curve,
+            key.flags,
+        ));
 
-        match key.tonemapping {
-            Tonemapping::None => shader_defs.push("TONEMAP_METHOD_NONE".into()),
-            Tonemapping::Reinhard => shader_defs.push("TONEMAP_METHOD_REINHARD".into()),
-            Tonemapping::ReinhardLuminance => {
-                shader_defs.push("TONEMAP_METHOD_REINHARD_LUMINANCE".into());
-            }
-            Tonemapping::AcesFitted => shader_defs.push("TONEMAP_METHOD_ACES_FITTED".into()),
-            Tonemapping::AgX => {
-                #[cfg(not(feature = "tonemapping_luts"))]
-                error!(
-                    "AgX tonemapping requires the `tonemapping_luts` feature.
-                    Either enable the `tonemapping_luts` feature for bevy in `Cargo.toml` (recommended),
-                    or use a different `Tonemapping` method in your `Camera2dBundle`/`Camera3dBundle`."
-                );
-                shader_defs.push("TONEMAP_METHOD_AGX".into());
-            }
-            Tonemapping::SomewhatBoringDisplayTransform => {
-                shader_defs.push("TONEMAP_METHOD_SOMEWHAT_BORING_DISPLAY_TRANSFORM".into());
-            }
-            Tonemapping::TonyMcMapface => {
-                #[cfg(not(feature = "tonemapping_luts"))]
-                error!(
-                    "TonyMcMapFace tonemapping requires the `tonemapping_luts` feature.
-                    Either enable the `tonemapping_luts` feature for bevy in `Cargo.toml` (recommended),
-                    or use a different `Tonemapping` method in your `Camera2dBundle`/`Camera3dBundle`."
-                );
-                shader_defs.push("TONEMAP_METHOD_TONY_MC_MAPFACE".into());
-            }
-            Tonemapping::BlenderFilmic => {
-                #[cfg(not(feature = "tonemapping_luts"))]
-                error!(
-                    "BlenderFilmic tonemapping requires the `tonemapping_luts` feature.
-                    Either enable the `tonemapping_luts` feature for bevy in `Cargo.toml` (recommended),
-                    or use a different `Tonemapping` method in your `Camera2dBundle`/`Camera3dBundle`."
-                );
-                shader_defs.push("TONEMAP_METHOD_BLENDER_FILMIC".into());
-            }
-        }
         RenderPipelineDescriptor {
             label: Some("tonemapping pipeline".into()),
             layout: vec![self.texture_bind_group.clone()],
@@ -308,6 +459,110 @@ impl SpecializedRenderPipeline for TonemappingPipeline {
     }
 }
 
+impl PrewarmRenderPipeline for TonemappingPipeline {
+    /// Returns the keys of the built-in curves available in this build, with and without
+    /// debanding, for views without color grading.
+    fn warm_keys(&self) -> Vec<Self::Key> {
+        let curves = [
+            Tonemapping::None,
+            Tonemapping::Reinhard,
+            Tonemapping::ReinhardLuminance,
+            Tonemapping::AcesFitted,
+            Tonemapping::SomewhatBoringDisplayTransform,
+            #[cfg(feature = "tonemapping_luts")]
+            Tonemapping::AgX,
+            #[cfg(feature = "tonemapping_luts")]
+            Tonemapping::TonyMcMapface,
+            #[cfg(feature = "tonemapping_luts")]
+            Tonemapping::BlenderFilmic,
+        ];
+
+        let mut keys = Vec::new();
+        for tonemapping in curves {
+            for deband_dither in [DebandDither::Disabled, DebandDither::Enabled] {
+                keys.push(TonemappingPipelineKey {
+                    deband_dither,
+                    tonemapping: tonemapping.clone(),
+                    custom_tonemapping_curve: None,
+                    flags: TonemappingPipelineKeyFlags::empty(),
+                });
+            }
+        }
+        keys
+    }
+}
+
+/// Returns the shader defs that select the given tonemapping curve and color
+/// grading steps in `tonemapping_shared.wgsl`.
+fn tonemapping_shader_defs(
+    tonemapping: &Tonemapping,
+    custom_tonemapping_curve: Option<u32>,
+    flags: TonemappingPipelineKeyFlags,
+) -> Vec<ShaderDefVal> {
+    let mut shader_defs = Vec::new();
+
+    // Define shader flags depending on the color grading options in use.
+    if flags.contains(TonemappingPipelineKeyFlags::HUE_ROTATE) {
+        shader_defs.push("HUE_ROTATE".into());
+    }
+    if flags.contains(TonemappingPipelineKeyFlags::WHITE_BALANCE) {
+        shader_defs.push("WHITE_BALANCE".into());
+    }
+    if flags.contains(TonemappingPipelineKeyFlags::SECTIONAL_COLOR_GRADING) {
+        shader_defs.push("SECTIONAL_COLOR_GRADING".into());
+    }
+    if flags.contains(TonemappingPipelineKeyFlags::BAKED_LUT) {
+        shader_defs.push("BAKED_COLOR_GRADING_LUT".into());
+    }
+
+    match tonemapping {
+        Tonemapping::None => shader_defs.push("TONEMAP_METHOD_NONE".into()),
+        Tonemapping::Reinhard => shader_defs.push("TONEMAP_METHOD_REINHARD".into()),
+        Tonemapping::ReinhardLuminance => {
+            shader_defs.push("TONEMAP_METHOD_REINHARD_LUMINANCE".into());
+        }
+        Tonemapping::AcesFitted => shader_defs.push("TONEMAP_METHOD_ACES_FITTED".into()),
+        Tonemapping::AgX => {
+            #[cfg(not(feature = "tonemapping_luts"))]
+            error!(
+                    "AgX tonemapping requires the `tonemapping_luts` feature.
+                    Either enable the `tonemapping_luts` feature for bevy in `Cargo.toml` (recommended),
+                    or use a different `Tonemapping` method in your `Camera2dBundle`/`Camera3dBundle`."
+                );
+            shader_defs.push("TONEMAP_METHOD_AGX".into());
+        }
+        Tonemapping::SomewhatBoringDisplayTransform => {
+            shader_defs.push("TONEMAP_METHOD_SOMEWHAT_BORING_DISPLAY_TRANSFORM".into());
+        }
+        Tonemapping::TonyMcMapface => {
+            #[cfg(not(feature = "tonemapping_luts"))]
+            error!(
+                    "TonyMcMapFace tonemapping requires the `tonemapping_luts` feature.
+                    Either enable the `tonemapping_luts` feature for bevy in `Cargo.toml` (recommended),
+                    or use a different `Tonemapping` method in your `Camera2dBundle`/`Camera3dBundle`."
+                );
+            shader_defs.push("TONEMAP_METHOD_TONY_MC_MAPFACE".into());
+        }
+        Tonemapping::BlenderFilmic => {
+            #[cfg(not(feature = "tonemapping_luts"))]
+            error!(
+                    "BlenderFilmic tonemapping requires the `tonemapping_luts` feature.
+                    Either enable the `tonemapping_luts` feature for bevy in `Cargo.toml` (recommended),
+                    or use a different `Tonemapping` method in your `Camera2dBundle`/`Camera3dBundle`."
+                );
+            shader_defs.push("TONEMAP_METHOD_BLENDER_FILMIC".into());
+        }
+        Tonemapping::Lut(_) => shader_defs.push("TONEMAP_METHOD_LUT".into()),
+        Tonemapping::Custom(_) => {
+            shader_defs.push("TONEMAP_METHOD_CUSTOM".into());
+            if let Some(slot) = custom_tonemapping_curve {
+                shader_defs.push(custom_tonemapping_curve_shader_def(slot));
+            }
+        }
+    }
+    shader_defs
+}
+
 impl FromWorld for TonemappingPipeline {
     fn from_world(render_world: &mut World) -> Self {
         let mut entries = DynamicBindGroupLayoutEntries::new_with_indices(
@@ -346,37 +601,31 @@ pub fn prepare_view_tonemapping_pipelines(
     pipeline_cache: Res<PipelineCache>,
     mut pipelines: ResMut<SpecializedRenderPipelines<TonemappingPipeline>>,
     upscaling_pipeline: Res<TonemappingPipeline>,
+    custom_tonemapping_curves: Res<CustomTonemappingCurves>,
     view_targets: Query<
         (
             Entity,
             &ExtractedView,
             Option<&Tonemapping>,
             Option<&DebandDither>,
+            Has<ViewColorGradingLut>,
         ),
         With<ViewTarget>,
     >,
 ) {
-    for (entity, view, tonemapping, dither) in view_targets.iter() {
+    for (entity, view, tonemapping, dither, baked_lut) in view_targets.iter() {
         // As an optimization, we omit parts of the shader that are unneeded.
-        let mut flags = TonemappingPipelineKeyFlags::empty();
-        flags.set(
-            TonemappingPipelineKeyFlags::HUE_ROTATE,
-            view.color_grading.global.hue != 0.0,
-        );
-        flags.set(
-            TonemappingPipelineKeyFlags::WHITE_BALANCE,
-            view.color_grading.global.temperature != 0.0 || view.color_grading.global.tint != 0.0,
-        );
-        flags.set(
-            TonemappingPipelineKeyFlags::SECTIONAL_COLOR_GRADING,
-            view.color_grading
-                .all_sections()
-                .any(|section| *section != default()),
-        );
+        let flags = if baked_lut {
+            TonemappingPipelineKeyFlags::BAKED_LUT
+        } else {
+            TonemappingPipelineKeyFlags::from_color_grading(&view.color_grading)
+        };
 
+        let tonemapping = tonemapping.cloned().unwrap_or(Tonemapping::None);
         let key = TonemappingPipelineKey {
             deband_dither: *dither.unwrap_or(&DebandDither::Disabled),
-            tonemapping: *tonemapping.unwrap_or(&Tonemapping::None),
+            custom_tonemapping_curve: custom_tonemapping_curves.slot(&tonemapping),
+            tonemapping: tonemapping.pipeline_key(),
             flags,
         };
         let pipeline = pipelines.specialize(&pipeline_cache, &upscaling_pipeline, key);
@@ -411,9 +660,11 @@ pub fn get_lut_bindings<'a>(
         | Tonemapping::ReinhardLuminance
         | Tonemapping::AcesFitted
         | Tonemapping::AgX
-        | Tonemapping::SomewhatBoringDisplayTransform => &tonemapping_luts.agx,
+        | Tonemapping::SomewhatBoringDisplayTransform
+        | Tonemapping::Custom(_) => &tonemapping_luts.agx,
         Tonemapping::TonyMcMapface => &tonemapping_luts.tony_mc_mapface,
         Tonemapping::BlenderFilmic => &tonemapping_luts.blender_filmic,
+        Tonemapping::Lut(lut) => lut,
     };
     let lut_image = images.get(image).unwrap_or(&fallback_image.d3);
     (&lut_image.texture_view, &lut_image.sampler)
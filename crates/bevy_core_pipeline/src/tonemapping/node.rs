@@ -1,6 +1,8 @@
 use std::sync::Mutex;
 
-use crate::tonemapping::{TonemappingLuts, TonemappingPipeline, ViewTonemappingPipeline};
+use crate::tonemapping::{
+    TonemappingLuts, TonemappingPipeline, ViewColorGradingLut, ViewTonemappingPipeline,
+};
 
 use bevy_ecs::{prelude::*, query::QueryItem};
 use bevy_render::{
@@ -29,13 +31,14 @@ impl ViewNode for TonemappingNode {
         &'static ViewTarget,
         &'static ViewTonemappingPipeline,
         &'static Tonemapping,
+        Option<&'static ViewColorGradingLut>,
     );
 
     fn run(
         &self,
         _graph: &mut RenderGraphContext,
         render_context: &mut RenderContext,
-        (view_uniform_offset, target, view_tonemapping_pipeline, tonemapping): QueryItem<
+        (view_uniform_offset, target, view_tonemapping_pipeline, tonemapping, baked_lut): QueryItem<
             Self::ViewQuery,
         >,
         world: &World,
@@ -72,25 +75,30 @@ impl ViewNode for TonemappingNode {
             true
         };
         if tonemapping_changed {
-            *last_tonemapping = Some(*tonemapping);
+            *last_tonemapping = Some(tonemapping.clone());
         }
 
+        // A baked color grading LUT replaces the tonemapping LUT.
+        let lut_bindings = match baked_lut {
+            Some(baked_lut) => (&baked_lut.texture_view, &baked_lut.sampler),
+            None => {
+                let tonemapping_luts = world.resource::<TonemappingLuts>();
+                get_lut_bindings(gpu_images, tonemapping_luts, tonemapping, fallback_image)
+            }
+        };
+
         let mut cached_bind_group = self.cached_bind_group.lock().unwrap();
         let bind_group = match &mut *cached_bind_group {
             Some((buffer_id, texture_id, lut_id, bind_group))
                 if view_uniforms_id == *buffer_id
                     && source.id() == *texture_id
+                    && *lut_id == lut_bindings.0.id()
                     && *lut_id != fallback_image.d3.texture_view.id()
                     && !tonemapping_changed =>
             {
                 bind_group
             }
             cached_bind_group => {
-                let tonemapping_luts = world.resource::<TonemappingLuts>();
-
-                let lut_bindings =
-                    get_lut_bindings(gpu_images, tonemapping_luts, tonemapping, fallback_image);
-
                 let bind_group = render_context.render_device().create_bind_group(
                     None,
                     &tonemapping_pipeline.texture_bind_group,
@@ -20,7 +20,16 @@
 fn fragment(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
     let hdr_color = textureSample(hdr_texture, hdr_sampler, in.uv);
 
+#ifdef BAKED_COLOR_GRADING_LUT
+    // The color grading and tonemapping were baked into the LUT, which is
+    // indexed by the stimulus encoded the same way as for Tony McMapface.
+    let stimulus = max(hdr_color.rgb, vec3(0.0));
+    let dims = vec3<f32>(textureDimensions(dt_lut_texture));
+    let uv = (stimulus / (stimulus + 1.0)) * ((dims - 1.0) / dims) + 0.5 / dims;
+    var output_rgb = textureSampleLevel(dt_lut_texture, dt_lut_sampler, uv, 0.0).rgb;
+#else
     var output_rgb = tone_mapping(hdr_color, view.color_grading).rgb;
+#endif
 
 #ifdef DEBAND_DITHER
     output_rgb = powsafe(output_rgb.rgb, 1.0 / 2.2);
@@ -11,6 +11,10 @@
     dt_lut_sampler,
 }
 
+#ifdef TONEMAP_METHOD_CUSTOM
+#import bevy_core_pipeline::custom_tonemapping::custom_tonemapping
+#endif
+
 // Half the size of the crossfade region between shadows and midtones and
 // between midtones and highlights. This value, 0.1, corresponds to 10% of the
 // gamut on either side of the cutoff point.
@@ -29,6 +33,8 @@ fn sample_current_lut(p: vec3<f32>) -> vec3<f32> {
     return textureSampleLevel(dt_lut_texture, dt_lut_sampler, p, 0.0).rgb;
 #else ifdef TONEMAP_METHOD_BLENDER_FILMIC
     return textureSampleLevel(dt_lut_texture, dt_lut_sampler, p, 0.0).rgb;
+#else ifdef TONEMAP_METHOD_LUT
+    return textureSampleLevel(dt_lut_texture, dt_lut_sampler, p, 0.0).rgb;
 #else
     return vec3(1.0, 0.0, 1.0);
  #endif
@@ -104,6 +110,22 @@ fn sample_tony_mc_mapface_lut(stimulus: vec3<f32>) -> vec3<f32> {
     return sample_current_lut(saturate(uv)).rgb;
 }
 
+// ------------------------------------------
+// --------------- Custom LUT ---------------
+// ------------------------------------------
+
+// Samples a user supplied LUT of any size, indexed by the stimulus encoded the same way as for
+// Tony McMapface.
+fn sample_custom_lut(stimulus: vec3<f32>) -> vec3<f32> {
+#ifdef TONEMAP_METHOD_LUT
+    let dims = vec3<f32>(textureDimensions(dt_lut_texture));
+    let uv = (stimulus / (stimulus + 1.0)) * ((dims - 1.0) / dims) + 0.5 / dims;
+    return sample_current_lut(saturate(uv));
+#else
+    return vec3(1.0, 0.0, 1.0);
+#endif
+}
+
 // ---------------------------------
 // ---------- ACES Fitted ----------
 // ---------------------------------
@@ -310,6 +332,13 @@ fn sectional_color_grading(
         levels.z = 1.0;
     }
 
+    // Apply the per-channel slope, offset, and power of the [ASC CDL]. This
+    // comes before the saturation adjustment, as in the CDL itself.
+    let slope = (*color_grading).slope * levels;
+    let offset = (*color_grading).offset * levels;
+    let power = (*color_grading).power * levels;
+    color = pow(max(color * slope + offset, vec3(0.0)), power);
+
     // Calculate contrast/saturation/gamma/gain/lift.
     let contrast = dot(levels, (*color_grading).contrast);
     let saturation = dot(levels, (*color_grading).saturation);
@@ -386,6 +415,10 @@ fn tone_mapping(in: vec4<f32>, in_color_grading: ColorGrading) -> vec4<f32> {
     color = sample_tony_mc_mapface_lut(color);
 #else ifdef TONEMAP_METHOD_BLENDER_FILMIC
     color = sample_blender_filmic_lut(color.rgb);
+#else ifdef TONEMAP_METHOD_LUT
+    color = sample_custom_lut(color.rgb);
+#else ifdef TONEMAP_METHOD_CUSTOM
+    color = custom_tonemapping(color.rgb);
 #endif
 
     // Perceptual post tonemapping grading
@@ -0,0 +1,237 @@
+use crate::{
+    core_2d::graph::{Core2d, Node2d},
+    core_3d::graph::{Core3d, Node3d},
+    fullscreen_vertex_shader::fullscreen_shader_vertex_state,
+};
+use bevy_app::prelude::*;
+use bevy_asset::{load_internal_asset, Handle};
+use bevy_color::{Color, LinearRgba};
+use bevy_ecs::{prelude::*, query::QueryItem};
+use bevy_math::Vec3;
+use bevy_reflect::{std_traits::ReflectDefault, Reflect};
+use bevy_render::{
+    extract_component::{ExtractComponent, ExtractComponentPlugin, UniformComponentPlugin},
+    prelude::Camera,
+    render_graph::{RenderGraphApp, ViewNodeRunner},
+    render_resource::{
+        binding_types::{sampler, texture_2d, uniform_buffer},
+        *,
+    },
+    renderer::RenderDevice,
+    texture::BevyDefault,
+    view::{ExtractedView, ViewTarget},
+    Render, RenderApp, RenderSet,
+};
+
+mod node;
+
+pub use node::VignetteNode;
+
+/// Darkens or tints the edges of the screen of a 2D or 3D camera, the way the lens of a real
+/// camera does.
+///
+/// The vignette is applied after tonemapping, and before antialiasing and sharpening, so a
+/// fullscreen material isn't needed for it.
+#[derive(Component, Reflect, Clone, Debug)]
+#[reflect(Component, Default)]
+pub struct Vignette {
+    /// How strongly the edges of the screen are tinted with [`Vignette::color`].
+    ///
+    /// Zero disables the vignette, and one tints the edges with the full color.
+    ///
+    /// The default value is 0.5.
+    pub intensity: f32,
+    /// How far the vignette fades in from the edges of the screen toward the center.
+    ///
+    /// Zero gives a sharp edge, and one fades all the way from the center.
+    ///
+    /// The default value is 0.6.
+    pub smoothness: f32,
+    /// The shape of the vignette.
+    ///
+    /// One gives a circle, whatever the aspect ratio of the screen, and zero a rounded
+    /// rectangle that follows the edges of the screen.
+    ///
+    /// The default value is 1.0.
+    pub roundness: f32,
+    /// The color that the edges of the screen are multiplied by.
+    ///
+    /// The default value is black.
+    pub color: Color,
+}
+
+impl Default for Vignette {
+    fn default() -> Self {
+        Vignette {
+            intensity: 0.5,
+            smoothness: 0.6,
+            roundness: 1.0,
+            color: Color::BLACK,
+        }
+    }
+}
+
+/// The uniform struct extracted from [`Vignette`] attached to a [`Camera`].
+/// Will be available for use in the vignette shader.
+#[doc(hidden)]
+#[derive(Component, ShaderType, Clone)]
+pub struct VignetteUniform {
+    color: Vec3,
+    intensity: f32,
+    smoothness: f32,
+    roundness: f32,
+}
+
+impl ExtractComponent for Vignette {
+    type QueryData = &'static Self;
+    type QueryFilter = With<Camera>;
+    type Out = VignetteUniform;
+
+    fn extract_component(item: QueryItem<Self::QueryData>) -> Option<Self::Out> {
+        if item.intensity <= 0.0 {
+            return None;
+        }
+        let color = LinearRgba::from(item.color);
+        Some(VignetteUniform {
+            color: Vec3::new(color.red, color.green, color.blue),
+            intensity: item.intensity.min(1.0),
+            // `smoothstep` is undefined for an empty range
+            smoothness: item.smoothness.clamp(0.001, 1.0),
+            roundness: item.roundness.clamp(0.0, 1.0),
+        })
+    }
+}
+
+const VIGNETTE_SHADER_HANDLE: Handle<Shader> = Handle::weak_from_u128(6621632404696532764);
+
+/// Adds support for the [`Vignette`] post-processing effect.
+pub struct VignettePlugin;
+
+impl Plugin for VignettePlugin {
+    fn build(&self, app: &mut App) {
+        load_internal_asset!(
+            app,
+            VIGNETTE_SHADER_HANDLE,
+            "vignette.wgsl",
+            Shader::from_wgsl
+        );
+
+        app.register_type::<Vignette>();
+        app.add_plugins((
+            ExtractComponentPlugin::<Vignette>::default(),
+            UniformComponentPlugin::<VignetteUniform>::default(),
+        ));
+
+        let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+        render_app
+            .init_resource::<SpecializedRenderPipelines<VignettePipeline>>()
+            .add_systems(
+                Render,
+                prepare_vignette_pipelines.in_set(RenderSet::Prepare),
+            )
+            .add_render_graph_node::<ViewNodeRunner<VignetteNode>>(Core3d, Node3d::Vignette)
+            .add_render_graph_edges(
+                Core3d,
+                (Node3d::Tonemapping, Node3d::Vignette, Node3d::Fxaa),
+            )
+            .add_render_graph_edge(Core3d, Node3d::Vignette, Node3d::Smaa)
+            .add_render_graph_node::<ViewNodeRunner<VignetteNode>>(Core2d, Node2d::Vignette)
+            .add_render_graph_edges(
+                Core2d,
+                (Node2d::Tonemapping, Node2d::Vignette, Node2d::Fxaa),
+            )
+            .add_render_graph_edge(Core2d, Node2d::Vignette, Node2d::Smaa);
+    }
+
+    fn finish(&self, app: &mut App) {
+        let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+        render_app.init_resource::<VignettePipeline>();
+    }
+}
+
+#[derive(Resource)]
+pub struct VignettePipeline {
+    texture_bind_group: BindGroupLayout,
+    sampler: Sampler,
+}
+
+impl FromWorld for VignettePipeline {
+    fn from_world(render_world: &mut World) -> Self {
+        let render_device = render_world.resource::<RenderDevice>();
+        let texture_bind_group = render_device.create_bind_group_layout(
+            "vignette_texture_bind_group_layout",
+            &BindGroupLayoutEntries::sequential(
+                ShaderStages::FRAGMENT,
+                (
+                    texture_2d(TextureSampleType::Float { filterable: true }),
+                    sampler(SamplerBindingType::Filtering),
+                    uniform_buffer::<VignetteUniform>(true),
+                ),
+            ),
+        );
+
+        let sampler = render_device.create_sampler(&SamplerDescriptor::default());
+
+        VignettePipeline {
+            texture_bind_group,
+            sampler,
+        }
+    }
+}
+
+impl SpecializedRenderPipeline for VignettePipeline {
+    type Key = TextureFormat;
+
+    fn specialize(&self, texture_format: Self::Key) -> RenderPipelineDescriptor {
+        RenderPipelineDescriptor {
+            label: Some("vignette".into()),
+            layout: vec![self.texture_bind_group.clone()],
+            vertex: fullscreen_shader_vertex_state(),
+            fragment: Some(FragmentState {
+                shader: VIGNETTE_SHADER_HANDLE,
+                shader_defs: vec![],
+                entry_point: "fragment".into(),
+                targets: vec![Some(ColorTargetState {
+                    format: texture_format,
+                    blend: None,
+                    write_mask: ColorWrites::ALL,
+                })],
+            }),
+            primitive: PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: MultisampleState::default(),
+            push_constant_ranges: Vec::new(),
+        }
+    }
+}
+
+fn prepare_vignette_pipelines(
+    mut commands: Commands,
+    pipeline_cache: Res<PipelineCache>,
+    mut pipelines: ResMut<SpecializedRenderPipelines<VignettePipeline>>,
+    vignette_pipeline: Res<VignettePipeline>,
+    views: Query<(Entity, &ExtractedView), With<VignetteUniform>>,
+) {
+    for (entity, view) in &views {
+        let pipeline_id = pipelines.specialize(
+            &pipeline_cache,
+            &vignette_pipeline,
+            if view.hdr {
+                ViewTarget::TEXTURE_FORMAT_HDR
+            } else {
+                TextureFormat::bevy_default()
+            },
+        );
+
+        commands
+            .entity(entity)
+            .insert(ViewVignettePipeline(pipeline_id));
+    }
+}
+
+#[derive(Component)]
+pub struct ViewVignettePipeline(CachedRenderPipelineId);
@@ -0,0 +1,97 @@
+use std::sync::Mutex;
+
+use crate::vignette::{ViewVignettePipeline, VignettePipeline, VignetteUniform};
+use bevy_ecs::{prelude::*, query::QueryItem};
+use bevy_render::{
+    extract_component::{ComponentUniforms, DynamicUniformIndex},
+    render_graph::{NodeRunError, RenderGraphContext, ViewNode},
+    render_resource::{
+        BindGroup, BindGroupEntries, BufferId, Operations, PipelineCache,
+        RenderPassColorAttachment, RenderPassDescriptor, TextureViewId,
+    },
+    renderer::RenderContext,
+    view::ViewTarget,
+};
+
+#[derive(Default)]
+pub struct VignetteNode {
+    cached_bind_group: Mutex<Option<(BufferId, TextureViewId, BindGroup)>>,
+}
+
+impl ViewNode for VignetteNode {
+    type ViewQuery = (
+        &'static ViewTarget,
+        &'static ViewVignettePipeline,
+        &'static DynamicUniformIndex<VignetteUniform>,
+    );
+
+    fn run(
+        &self,
+        _graph: &mut RenderGraphContext,
+        render_context: &mut RenderContext,
+        (target, pipeline, uniform_index): QueryItem<Self::ViewQuery>,
+        world: &World,
+    ) -> Result<(), NodeRunError> {
+        let pipeline_cache = world.resource::<PipelineCache>();
+        let vignette_pipeline = world.resource::<VignettePipeline>();
+        let uniforms = world.resource::<ComponentUniforms<VignetteUniform>>();
+
+        let Some(uniforms_buffer) = uniforms.buffer() else {
+            return Ok(());
+        };
+        let uniforms_id = uniforms_buffer.id();
+        let Some(uniforms) = uniforms.binding() else {
+            return Ok(());
+        };
+
+        let Some(pipeline) = pipeline_cache.get_render_pipeline(pipeline.0) else {
+            return Ok(());
+        };
+
+        let post_process = target.post_process_write();
+        let source = post_process.source;
+        let destination = post_process.destination;
+
+        let mut cached_bind_group = self.cached_bind_group.lock().unwrap();
+        let bind_group = match &mut *cached_bind_group {
+            Some((buffer_id, texture_id, bind_group))
+                if source.id() == *texture_id && uniforms_id == *buffer_id =>
+            {
+                bind_group
+            }
+            cached_bind_group => {
+                let bind_group = render_context.render_device().create_bind_group(
+                    "vignette_bind_group",
+                    &vignette_pipeline.texture_bind_group,
+                    &BindGroupEntries::sequential((source, &vignette_pipeline.sampler, uniforms)),
+                );
+
+                let (_, _, bind_group) =
+                    cached_bind_group.insert((uniforms_id, source.id(), bind_group));
+                bind_group
+            }
+        };
+
+        let pass_descriptor = RenderPassDescriptor {
+            label: Some("vignette_pass"),
+            color_attachments: &[Some(RenderPassColorAttachment {
+                view: destination,
+                resolve_target: None,
+                ops: Operations::default(),
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        };
+
+        let mut render_pass = render_context
+            .command_encoder()
+            .begin_render_pass(&pass_descriptor);
+
+        render_pass.set_pipeline(pipeline);
+        render_pass.set_bind_group(0, bind_group, &[uniform_index.index()]);
+        render_pass.draw(0..3, 0..1);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,35 @@
+#import bevy_core_pipeline::fullscreen_vertex_shader::FullscreenVertexOutput
+
+struct Vignette {
+    color: vec3<f32>,
+    intensity: f32,
+    smoothness: f32,
+    roundness: f32,
+}
+
+@group(0) @binding(0) var screen_texture: texture_2d<f32>;
+@group(0) @binding(1) var screen_sampler: sampler;
+@group(0) @binding(2) var<uniform> vignette: Vignette;
+
+// The exponent of the superellipse that gives the vignette its shape when the roundness is zero.
+// A superellipse with an exponent of 2.0 is an ellipse, and it approaches a rectangle as the
+// exponent grows.
+const SQUARE_EXPONENT: f32 = 8.0;
+
+@fragment
+fn fragment(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
+    let color = textureSample(screen_texture, screen_sampler, in.uv);
+
+    // Offset from the center of the screen, 1.0 at the middle of each edge.
+    var offset = max(abs(in.uv * 2.0 - 1.0), vec2<f32>(1e-5));
+
+    // A round vignette is corrected for the aspect ratio to stay circular, a square one follows
+    // the edges of the screen.
+    let size = vec2<f32>(textureDimensions(screen_texture));
+    offset.x *= mix(1.0, size.x / size.y, vignette.roundness);
+    let exponent = mix(SQUARE_EXPONENT, 2.0, vignette.roundness);
+    let distance = pow(dot(pow(offset, vec2<f32>(exponent)), vec2<f32>(1.0)), 1.0 / exponent);
+
+    let amount = vignette.intensity * smoothstep(1.0 - vignette.smoothness, 1.0, distance);
+    return vec4<f32>(color.rgb * mix(vec3<f32>(1.0), vignette.color, amount), color.a);
+}
@@ -0,0 +1,418 @@
+//! Weighted blended order-independent transparency (OIT), a cheaper way of rendering many
+//! overlapping transparent meshes than sorting them.
+//!
+//! See [`WeightedBlendedOit`].
+
+use std::ops::Range;
+
+use crate::{
+    core_3d::{
+        graph::{Core3d, Node3d},
+        Camera3d,
+    },
+    fullscreen_vertex_shader::fullscreen_shader_vertex_state,
+};
+use bevy_app::prelude::*;
+use bevy_asset::{load_internal_asset, Handle};
+use bevy_ecs::{entity::EntityHashSet, prelude::*};
+use bevy_math::FloatOrd;
+use bevy_reflect::{std_traits::ReflectDefault, Reflect};
+use bevy_render::{
+    camera::ExtractedCamera,
+    extract_component::{ExtractComponent, ExtractComponentPlugin},
+    prelude::{Camera, Msaa},
+    render_graph::{RenderGraphApp, ViewNodeRunner},
+    render_phase::{
+        CachedRenderPipelinePhaseItem, DrawFunctionId, DrawFunctions, PhaseItem,
+        PhaseItemExtraIndex, SortedPhaseItem, ViewSortedRenderPhases,
+    },
+    render_resource::{binding_types::texture_2d, *},
+    renderer::RenderDevice,
+    texture::{BevyDefault, CachedTexture, TextureCache},
+    view::{ExtractedView, ViewTarget},
+    Extract, ExtractSchedule, Render, RenderApp, RenderSet,
+};
+
+mod node;
+
+pub use node::WeightedBlendedOitNode;
+
+/// Renders the alpha blended transparent meshes of this 3D camera with weighted blended
+/// order-independent transparency, instead of sorting them.
+///
+/// Each transparent fragment is accumulated into two textures, with a weight that decreases with
+/// its distance to the camera, and the result is composited over the main texture before the
+/// other transparent meshes are rendered. This doesn't need any sorting, and its memory cost
+/// doesn't depend on the number of overlapping fragments, which makes it well suited to scenes
+/// with many overlapping low-opacity meshes, such as particles. In exchange, it is only an
+/// approximation: overlapping fragments with a high opacity blend together instead of the front
+/// one hiding the ones behind it.
+///
+/// Only the materials with `AlphaMode::Blend` and `AlphaMode::Premultiplied` are rendered this
+/// way. The meshes with `AlphaMode::Add` and `AlphaMode::Multiply`, which can't be
+/// expressed as a weighted average, and the meshes rendered by other renderers, such as gizmos,
+/// are still sorted and rendered by the main transparent pass, over the weighted blended ones.
+/// Marking meshes with
+/// [`RenderAtHalfResolution`](crate::half_resolution_transparency::RenderAtHalfResolution) has
+/// no effect on these cameras.
+///
+/// Materials with a custom fragment shader have to write the revealage of the fragment to their
+/// second output when `WEIGHTED_BLENDED_OIT` is defined, which the
+/// `bevy_core_pipeline::weighted_blended_oit` shader import helps with.
+#[derive(Component, Reflect, Clone, Copy, Debug, Default)]
+#[reflect(Component, Default)]
+pub struct WeightedBlendedOit;
+
+impl ExtractComponent for WeightedBlendedOit {
+    type QueryData = &'static Self;
+    type QueryFilter = With<Camera3d>;
+    type Out = Self;
+
+    fn extract_component(item: &Self) -> Option<Self::Out> {
+        Some(*item)
+    }
+}
+
+/// A transparent mesh rendered with weighted blended order-independent transparency, for the
+/// cameras that have [`WeightedBlendedOit`].
+///
+/// This is the same as [`Transparent3d`](crate::core_3d::Transparent3d), in a separate phase
+/// that doesn't need to be sorted.
+pub struct WeightedBlendedTransparent3d {
+    pub distance: f32,
+    pub pipeline: CachedRenderPipelineId,
+    pub entity: Entity,
+    pub draw_function: DrawFunctionId,
+    pub batch_range: Range<u32>,
+    pub extra_index: PhaseItemExtraIndex,
+}
+
+impl PhaseItem for WeightedBlendedTransparent3d {
+    #[inline]
+    fn entity(&self) -> Entity {
+        self.entity
+    }
+
+    #[inline]
+    fn draw_function(&self) -> DrawFunctionId {
+        self.draw_function
+    }
+
+    #[inline]
+    fn batch_range(&self) -> &Range<u32> {
+        &self.batch_range
+    }
+
+    #[inline]
+    fn batch_range_mut(&mut self) -> &mut Range<u32> {
+        &mut self.batch_range
+    }
+
+    #[inline]
+    fn extra_index(&self) -> PhaseItemExtraIndex {
+        self.extra_index
+    }
+
+    #[inline]
+    fn batch_range_and_extra_index_mut(&mut self) -> (&mut Range<u32>, &mut PhaseItemExtraIndex) {
+        (&mut self.batch_range, &mut self.extra_index)
+    }
+}
+
+impl SortedPhaseItem for WeightedBlendedTransparent3d {
+    // NOTE: The order of the items doesn't change the result, so the phase is never sorted.
+    type SortKey = FloatOrd;
+
+    #[inline]
+    fn sort_key(&self) -> Self::SortKey {
+        FloatOrd(self.distance)
+    }
+}
+
+impl CachedRenderPipelinePhaseItem for WeightedBlendedTransparent3d {
+    #[inline]
+    fn cached_pipeline(&self) -> CachedRenderPipelineId {
+        self.pipeline
+    }
+}
+
+/// The handle of the `bevy_core_pipeline::weighted_blended_oit` shader import, used by the
+/// fragment shaders of the meshes.
+pub const WEIGHTED_BLENDED_OIT_SHADER_HANDLE: Handle<Shader> =
+    Handle::weak_from_u128(5213707941486202261);
+const WEIGHTED_BLENDED_OIT_RESOLVE_SHADER_HANDLE: Handle<Shader> =
+    Handle::weak_from_u128(1786094523460135873);
+
+/// Adds support for [`WeightedBlendedOit`].
+///
+/// The phase items are queued by the renderers of the meshes, such as the materials of
+/// `bevy_pbr`.
+pub struct WeightedBlendedOitPlugin;
+
+impl Plugin for WeightedBlendedOitPlugin {
+    fn build(&self, app: &mut App) {
+        load_internal_asset!(
+            app,
+            WEIGHTED_BLENDED_OIT_SHADER_HANDLE,
+            "weighted_blended_oit.wgsl",
+            Shader::from_wgsl
+        );
+        load_internal_asset!(
+            app,
+            WEIGHTED_BLENDED_OIT_RESOLVE_SHADER_HANDLE,
+            "weighted_blended_oit_resolve.wgsl",
+            Shader::from_wgsl
+        );
+
+        app.register_type::<WeightedBlendedOit>();
+        app.add_plugins(ExtractComponentPlugin::<WeightedBlendedOit>::default());
+
+        let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+        render_app
+            .init_resource::<DrawFunctions<WeightedBlendedTransparent3d>>()
+            .init_resource::<ViewSortedRenderPhases<WeightedBlendedTransparent3d>>()
+            .init_resource::<SpecializedRenderPipelines<WeightedBlendedOitResolvePipeline>>()
+            .add_systems(ExtractSchedule, extract_weighted_blended_transparent_phases)
+            .add_systems(
+                Render,
+                (
+                    prepare_weighted_blended_oit_pipelines.in_set(RenderSet::Prepare),
+                    prepare_weighted_blended_oit_textures.in_set(RenderSet::PrepareResources),
+                ),
+            )
+            .add_render_graph_node::<ViewNodeRunner<WeightedBlendedOitNode>>(
+                Core3d,
+                Node3d::WeightedBlendedTransparentPass,
+            )
+            .add_render_graph_edges(
+                Core3d,
+                (
+                    Node3d::MainTransmissivePass,
+                    Node3d::WeightedBlendedTransparentPass,
+                    Node3d::MainTransparentPass,
+                ),
+            );
+    }
+
+    fn finish(&self, app: &mut App) {
+        let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+        render_app.init_resource::<WeightedBlendedOitResolvePipeline>();
+    }
+}
+
+/// Creates the [`WeightedBlendedTransparent3d`] phase of the cameras with [`WeightedBlendedOit`].
+///
+/// The renderers of the meshes queue the alpha blended meshes in this phase when it exists for a
+/// view, and in the [`Transparent3d`](crate::core_3d::Transparent3d) phase otherwise.
+pub fn extract_weighted_blended_transparent_phases(
+    mut weighted_blended_transparent_phases: ResMut<
+        ViewSortedRenderPhases<WeightedBlendedTransparent3d>,
+    >,
+    cameras_3d: Extract<Query<(Entity, &Camera), (With<Camera3d>, With<WeightedBlendedOit>)>>,
+    mut live_entities: Local<EntityHashSet>,
+) {
+    live_entities.clear();
+
+    for (entity, camera) in &cameras_3d {
+        if !camera.is_active {
+            continue;
+        }
+
+        weighted_blended_transparent_phases.insert_or_clear(entity);
+
+        live_entities.insert(entity);
+    }
+
+    weighted_blended_transparent_phases.retain(|entity, _| live_entities.contains(entity));
+}
+
+#[derive(Resource)]
+pub struct WeightedBlendedOitResolvePipeline {
+    bind_group_layout: BindGroupLayout,
+}
+
+impl FromWorld for WeightedBlendedOitResolvePipeline {
+    fn from_world(render_world: &mut World) -> Self {
+        let render_device = render_world.resource::<RenderDevice>();
+        let bind_group_layout = render_device.create_bind_group_layout(
+            "weighted_blended_oit_resolve_bind_group_layout",
+            &BindGroupLayoutEntries::sequential(
+                ShaderStages::FRAGMENT,
+                (
+                    // Accumulation
+                    texture_2d(TextureSampleType::Float { filterable: false }),
+                    // Revealage
+                    texture_2d(TextureSampleType::Float { filterable: false }),
+                ),
+            ),
+        );
+
+        WeightedBlendedOitResolvePipeline { bind_group_layout }
+    }
+}
+
+#[derive(PartialEq, Eq, Hash, Clone, Copy)]
+pub struct WeightedBlendedOitResolvePipelineKey {
+    texture_format: TextureFormat,
+    samples: u32,
+}
+
+impl SpecializedRenderPipeline for WeightedBlendedOitResolvePipeline {
+    type Key = WeightedBlendedOitResolvePipelineKey;
+
+    fn specialize(&self, key: Self::Key) -> RenderPipelineDescriptor {
+        RenderPipelineDescriptor {
+            label: Some("weighted_blended_oit_resolve_pipeline".into()),
+            layout: vec![self.bind_group_layout.clone()],
+            vertex: fullscreen_shader_vertex_state(),
+            fragment: Some(FragmentState {
+                shader: WEIGHTED_BLENDED_OIT_RESOLVE_SHADER_HANDLE,
+                shader_defs: vec![],
+                entry_point: "resolve".into(),
+                targets: vec![Some(ColorTargetState {
+                    format: key.texture_format,
+                    // The average color is blended over the main texture with the total coverage
+                    // of the transparent fragments
+                    blend: Some(BlendState::ALPHA_BLENDING),
+                    write_mask: ColorWrites::ALL,
+                })],
+            }),
+            primitive: PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: MultisampleState {
+                count: key.samples,
+                ..MultisampleState::default()
+            },
+            push_constant_ranges: Vec::new(),
+        }
+    }
+}
+
+fn prepare_weighted_blended_oit_pipelines(
+    mut commands: Commands,
+    pipeline_cache: Res<PipelineCache>,
+    mut pipelines: ResMut<SpecializedRenderPipelines<WeightedBlendedOitResolvePipeline>>,
+    resolve_pipeline: Res<WeightedBlendedOitResolvePipeline>,
+    msaa: Res<Msaa>,
+    views: Query<(Entity, &ExtractedView), With<WeightedBlendedOit>>,
+) {
+    for (entity, view) in &views {
+        let texture_format = if view.hdr {
+            ViewTarget::TEXTURE_FORMAT_HDR
+        } else {
+            TextureFormat::bevy_default()
+        };
+        let pipeline_id = pipelines.specialize(
+            &pipeline_cache,
+            &resolve_pipeline,
+            WeightedBlendedOitResolvePipelineKey {
+                texture_format,
+                samples: msaa.samples(),
+            },
+        );
+
+        commands
+            .entity(entity)
+            .insert(ViewWeightedBlendedOitResolvePipeline(pipeline_id));
+    }
+}
+
+#[derive(Component)]
+pub struct ViewWeightedBlendedOitResolvePipeline(CachedRenderPipelineId);
+
+fn prepare_weighted_blended_oit_textures(
+    mut commands: Commands,
+    mut texture_cache: ResMut<TextureCache>,
+    render_device: Res<RenderDevice>,
+    msaa: Res<Msaa>,
+    views: Query<(Entity, &ExtractedCamera), With<WeightedBlendedOit>>,
+) {
+    for (entity, camera) in &views {
+        let Some(target_size) = camera.physical_target_size else {
+            continue;
+        };
+
+        let multisampled = msaa.samples() > 1;
+        let accumulation_descriptor = TextureDescriptor {
+            label: Some("weighted_blended_oit_accumulation_texture"),
+            size: Extent3d {
+                width: target_size.x,
+                height: target_size.y,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: msaa.samples(),
+            dimension: TextureDimension::D2,
+            format: WeightedBlendedOitTextures::ACCUMULATION_FORMAT,
+            usage: if multisampled {
+                TextureUsages::RENDER_ATTACHMENT
+            } else {
+                TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING
+            },
+            view_formats: &[],
+        };
+        let revealage_descriptor = TextureDescriptor {
+            label: Some("weighted_blended_oit_revealage_texture"),
+            format: WeightedBlendedOitTextures::REVEALAGE_FORMAT,
+            ..accumulation_descriptor.clone()
+        };
+        let resolved_descriptor =
+            |label, descriptor: &TextureDescriptor<'static>| TextureDescriptor {
+                label: Some(label),
+                sample_count: 1,
+                usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING,
+                ..descriptor.clone()
+            };
+
+        commands.entity(entity).insert(WeightedBlendedOitTextures {
+            accumulation: texture_cache.get(&render_device, accumulation_descriptor.clone()),
+            revealage: texture_cache.get(&render_device, revealage_descriptor.clone()),
+            resolved: multisampled.then(|| {
+                [
+                    resolved_descriptor(
+                        "weighted_blended_oit_resolved_accumulation_texture",
+                        &accumulation_descriptor,
+                    ),
+                    resolved_descriptor(
+                        "weighted_blended_oit_resolved_revealage_texture",
+                        &revealage_descriptor,
+                    ),
+                ]
+                .map(|descriptor| texture_cache.get(&render_device, descriptor))
+            }),
+        });
+    }
+}
+
+/// The textures that the transparent meshes of a [`WeightedBlendedOit`] camera are accumulated
+/// into.
+#[derive(Component)]
+pub struct WeightedBlendedOitTextures {
+    /// The sum of the weighted premultiplied colors of the fragments in the RGB channels, and the
+    /// sum of their weighted alphas in the alpha channel.
+    accumulation: CachedTexture,
+    /// The product of `1.0 - alpha` of the fragments, which is the fraction of the background that
+    /// is still visible.
+    revealage: CachedTexture,
+    /// The resolved accumulation and revealage textures, when MSAA is on.
+    resolved: Option<[CachedTexture; 2]>,
+}
+
+impl WeightedBlendedOitTextures {
+    /// The format of the accumulation texture, the first color target of the meshes.
+    pub const ACCUMULATION_FORMAT: TextureFormat = TextureFormat::Rgba16Float;
+    /// The format of the revealage texture, the second color target of the meshes.
+    pub const REVEALAGE_FORMAT: TextureFormat = TextureFormat::R16Float;
+
+    /// Returns the single sampled accumulation and revealage textures read by the resolve pass.
+    fn sampled(&self) -> [&CachedTexture; 2] {
+        match &self.resolved {
+            Some([accumulation, revealage]) => [accumulation, revealage],
+            None => [&self.accumulation, &self.revealage],
+        }
+    }
+}
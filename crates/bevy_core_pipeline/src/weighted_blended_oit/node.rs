@@ -0,0 +1,153 @@
+use crate::weighted_blended_oit::{
+    ViewWeightedBlendedOitResolvePipeline, WeightedBlendedOitResolvePipeline,
+    WeightedBlendedOitTextures, WeightedBlendedTransparent3d,
+};
+use bevy_color::LinearRgba;
+use bevy_ecs::{prelude::*, query::QueryItem};
+use bevy_render::{
+    camera::{ExtractedCamera, MainPassResolutionOverride, Viewport},
+    diagnostic::RecordDiagnostics,
+    render_graph::{NodeRunError, RenderGraphContext, ViewNode},
+    render_phase::ViewSortedRenderPhases,
+    render_resource::{
+        BindGroupEntries, LoadOp, Operations, PipelineCache, RenderPassColorAttachment,
+        RenderPassDescriptor, StoreOp,
+    },
+    renderer::RenderContext,
+    view::{ViewDepthTexture, ViewTarget},
+};
+#[cfg(feature = "trace")]
+use bevy_utils::tracing::info_span;
+
+/// A [`bevy_render::render_graph::Node`] that accumulates the [`WeightedBlendedTransparent3d`]
+/// [`SortedRenderPhase`](bevy_render::render_phase::SortedRenderPhase), and composites the result
+/// over the main texture.
+#[derive(Default)]
+pub struct WeightedBlendedOitNode;
+
+impl ViewNode for WeightedBlendedOitNode {
+    type ViewQuery = (
+        &'static ExtractedCamera,
+        &'static ViewTarget,
+        &'static ViewDepthTexture,
+        &'static ViewWeightedBlendedOitResolvePipeline,
+        &'static WeightedBlendedOitTextures,
+        Option<&'static MainPassResolutionOverride>,
+    );
+
+    fn run(
+        &self,
+        graph: &mut RenderGraphContext,
+        render_context: &mut RenderContext,
+        (camera, target, depth, resolve_pipeline, textures, resolution_override): QueryItem<
+            Self::ViewQuery,
+        >,
+        world: &World,
+    ) -> Result<(), NodeRunError> {
+        let view_entity = graph.view_entity();
+
+        let Some(weighted_blended_phases) =
+            world.get_resource::<ViewSortedRenderPhases<WeightedBlendedTransparent3d>>()
+        else {
+            return Ok(());
+        };
+
+        let Some(weighted_blended_phase) = weighted_blended_phases.get(&view_entity) else {
+            return Ok(());
+        };
+
+        if weighted_blended_phase.items.is_empty() {
+            return Ok(());
+        }
+
+        let pipeline_cache = world.resource::<PipelineCache>();
+        let Some(resolve_pipeline) = pipeline_cache.get_render_pipeline(resolve_pipeline.0) else {
+            return Ok(());
+        };
+
+        #[cfg(feature = "trace")]
+        let _weighted_blended_transparent_pass_3d_span =
+            info_span!("weighted_blended_transparent_pass_3d").entered();
+
+        let viewport =
+            Viewport::from_viewport_and_override(camera.viewport.as_ref(), resolution_override);
+        let [resolved_accumulation, resolved_revealage] = textures.sampled();
+        let resolve_targets = textures.resolved.as_ref().map(|[accumulation, revealage]| {
+            [&*accumulation.default_view, &*revealage.default_view]
+        });
+
+        // Accumulate the transparent meshes, in any order
+        {
+            let diagnostics = render_context.diagnostic_recorder();
+
+            let mut render_pass = render_context.begin_tracked_render_pass(RenderPassDescriptor {
+                label: Some("weighted_blended_transparent_pass_3d"),
+                color_attachments: &[
+                    Some(RenderPassColorAttachment {
+                        view: &textures.accumulation.default_view,
+                        resolve_target: resolve_targets.map(|[accumulation, _]| accumulation),
+                        ops: Operations {
+                            load: LoadOp::Clear(LinearRgba::NONE.into()),
+                            store: StoreOp::Store,
+                        },
+                    }),
+                    Some(RenderPassColorAttachment {
+                        view: &textures.revealage.default_view,
+                        resolve_target: resolve_targets.map(|[_, revealage]| revealage),
+                        ops: Operations {
+                            load: LoadOp::Clear(LinearRgba::WHITE.into()),
+                            store: StoreOp::Store,
+                        },
+                    }),
+                ],
+                // NOTE: Like the main transparent pass, this pass loads the depth buffer so that
+                // opaque meshes occlude transparent ones, and stores it to work around #3776.
+                depth_stencil_attachment: Some(depth.get_attachment(StoreOp::Store)),
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+
+            let pass_span =
+                diagnostics.pass_span(&mut render_pass, "weighted_blended_transparent_pass_3d");
+
+            if let Some(viewport) = viewport.as_ref() {
+                render_pass.set_camera_viewport(viewport);
+            }
+
+            weighted_blended_phase.render(&mut render_pass, world, view_entity);
+
+            pass_span.end(&mut render_pass);
+        }
+
+        // Divide the accumulated color by the accumulated weight, and blend it over the main
+        // texture
+        {
+            let bind_group = render_context.render_device().create_bind_group(
+                "weighted_blended_oit_resolve_bind_group",
+                &world
+                    .resource::<WeightedBlendedOitResolvePipeline>()
+                    .bind_group_layout,
+                &BindGroupEntries::sequential((
+                    &resolved_accumulation.default_view,
+                    &resolved_revealage.default_view,
+                )),
+            );
+
+            let mut render_pass = render_context.begin_tracked_render_pass(RenderPassDescriptor {
+                label: Some("weighted_blended_oit_resolve_pass"),
+                color_attachments: &[Some(target.get_color_attachment())],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            if let Some(viewport) = viewport.as_ref() {
+                render_pass.set_camera_viewport(viewport);
+            }
+            render_pass.set_render_pipeline(resolve_pipeline);
+            render_pass.set_bind_group(0, &bind_group, &[]);
+            render_pass.draw(0..3, 0..1);
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,31 @@
+// Weighted blended order-independent transparency, from "Weighted Blended Order-Independent
+// Transparency" by Morgan McGuire and Louis Bavoil:
+// https://jcgt.org/published/0002/02/09/
+//
+// The fragment shaders of the transparent meshes write `accumulation` to their first output and
+// `revealage` to their second one. The first target adds the weighted colors together, and the
+// second one multiplies `1.0 - alpha` together.
+
+#define_import_path bevy_core_pipeline::weighted_blended_oit
+
+struct WeightedBlendedOitOutput {
+    accumulation: vec4<f32>,
+    revealage: f32,
+}
+
+// Returns the weight of a fragment, which decreases with its distance to the camera so that the
+// nearer fragments dominate the average, as in equation 10 of the paper.
+fn weight(alpha: f32, view_distance: f32) -> f32 {
+    let z = abs(view_distance);
+    return alpha * clamp(10.0 / (1e-5 + pow(z / 5.0, 2.0) + pow(z / 200.0, 6.0)), 1e-2, 3e3);
+}
+
+// Returns the outputs of a fragment with the given premultiplied `color`, at `view_distance`
+// from the camera along its view direction.
+fn weighted_blended_oit_output(color: vec4<f32>, view_distance: f32) -> WeightedBlendedOitOutput {
+    let alpha = saturate(color.a);
+    var out: WeightedBlendedOitOutput;
+    out.accumulation = vec4(color.rgb, alpha) * weight(alpha, view_distance);
+    out.revealage = alpha;
+    return out;
+}
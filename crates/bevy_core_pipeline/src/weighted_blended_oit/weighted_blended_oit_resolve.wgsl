@@ -0,0 +1,22 @@
+// Composites the weighted blended transparent meshes over the main texture.
+
+#import bevy_core_pipeline::fullscreen_vertex_shader::FullscreenVertexOutput
+
+@group(0) @binding(0) var accumulation_texture: texture_2d<f32>;
+@group(0) @binding(1) var revealage_texture: texture_2d<f32>;
+
+@fragment
+fn resolve(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
+    let coords = vec2<i32>(floor(in.position.xy));
+
+    // Skip the pixels that no transparent fragment covers.
+    let revealage = textureLoad(revealage_texture, coords, 0).r;
+    if revealage >= 1.0 {
+        discard;
+    }
+
+    // The weighted average of the colors, blended with the total coverage of the fragments.
+    let accumulation = textureLoad(accumulation_texture, coords, 0);
+    let average_color = accumulation.rgb / max(accumulation.a, 1e-5);
+    return vec4(average_color, 1.0 - revealage);
+}
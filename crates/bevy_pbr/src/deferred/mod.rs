@@ -12,7 +12,9 @@ use bevy_core_pipeline::{
         copy_lighting_id::DeferredLightingIdDepthTexture, DEFERRED_LIGHTING_PASS_ID_DEPTH_FORMAT,
     },
     prepass::{DeferredPrepass, DepthPrepass, MotionVectorPrepass, NormalPrepass},
-    tonemapping::{DebandDither, Tonemapping},
+    tonemapping::{
+        custom_tonemapping_curve_shader_def, CustomTonemappingCurves, DebandDither, Tonemapping,
+    },
 };
 use bevy_ecs::{prelude::*, query::QueryItem};
 use bevy_render::{
@@ -281,6 +283,13 @@ impl SpecializedRenderPipeline for DeferredLightingLayout {
                 shader_defs.push("TONEMAP_METHOD_BLENDER_FILMIC".into());
             } else if method == MeshPipelineKey::TONEMAP_METHOD_TONY_MC_MAPFACE {
                 shader_defs.push("TONEMAP_METHOD_TONY_MC_MAPFACE".into());
+            } else if method == MeshPipelineKey::TONEMAP_METHOD_LUT {
+                shader_defs.push("TONEMAP_METHOD_LUT".into());
+            } else if method == MeshPipelineKey::TONEMAP_METHOD_CUSTOM {
+                shader_defs.push("TONEMAP_METHOD_CUSTOM".into());
+                if let Some(slot) = key.custom_tonemapping_curve() {
+                    shader_defs.push(custom_tonemapping_curve_shader_def(slot));
+                }
             }
 
             // Debanding is tied to tonemapping in the shader, cannot run without it.
@@ -423,6 +432,7 @@ pub fn prepare_deferred_lighting_pipelines(
     pipeline_cache: Res<PipelineCache>,
     mut pipelines: ResMut<SpecializedRenderPipelines<DeferredLightingLayout>>,
     deferred_lighting_layout: Res<DeferredLightingLayout>,
+    custom_tonemapping_curves: Res<CustomTonemappingCurves>,
     views: Query<
         (
             Entity,
@@ -490,7 +500,12 @@ pub fn prepare_deferred_lighting_pipelines(
                     }
                     Tonemapping::TonyMcMapface => MeshPipelineKey::TONEMAP_METHOD_TONY_MC_MAPFACE,
                     Tonemapping::BlenderFilmic => MeshPipelineKey::TONEMAP_METHOD_BLENDER_FILMIC,
+                    Tonemapping::Lut(_) => MeshPipelineKey::TONEMAP_METHOD_LUT,
+                    Tonemapping::Custom(_) => MeshPipelineKey::TONEMAP_METHOD_CUSTOM,
                 };
+                view_key |= MeshPipelineKey::from_custom_tonemapping_curve(
+                    custom_tonemapping_curves.slot(tonemapping),
+                );
             }
             if let Some(DebandDither::Enabled) = dither {
                 view_key |= MeshPipelineKey::DEBAND_DITHER;
@@ -21,6 +21,12 @@
     #import bevy_pbr::pbr_prepass_functions::calculate_motion_vector
 #endif
 
+#ifdef MESH_MASK_PREPASS
+#ifndef MESHLET_MESH_MATERIAL_PASS
+    #import bevy_pbr::mesh_functions
+#endif
+#endif
+
 // Creates the deferred gbuffer from a PbrInput.
 fn deferred_gbuffer_from_pbr_input(in: PbrInput) -> vec4<u32> {
      // Only monochrome occlusion supported. May not be worth including at all.
@@ -127,6 +133,12 @@ fn deferred_output(in: VertexOutput, pbr_input: PbrInput) -> FragmentOutput {
 #else
     out.motion_vector = calculate_motion_vector(in.world_position, in.previous_world_position);
 #endif
+#endif
+    // mesh mask if required
+#ifdef MESH_MASK_PREPASS
+#ifndef MESHLET_MESH_MATERIAL_PASS
+    out.mesh_mask = mesh_functions::get_mesh_mask(in.instance_index);
+#endif
 #endif
 
     return out;
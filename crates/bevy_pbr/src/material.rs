@@ -10,10 +10,12 @@ use bevy_core_pipeline::{
         AlphaMask3d, Camera3d, Opaque3d, Opaque3dBinKey, ScreenSpaceTransmissionQuality,
         Transmissive3d, Transparent3d,
     },
+    half_resolution_transparency::HalfResolutionTransparent3d,
     prepass::{
         DeferredPrepass, DepthPrepass, MotionVectorPrepass, NormalPrepass, OpaqueNoLightmap3dBinKey,
     },
-    tonemapping::{DebandDither, Tonemapping},
+    tonemapping::{CustomTonemappingCurves, DebandDither, Tonemapping},
+    weighted_blended_oit::WeightedBlendedTransparent3d,
 };
 use bevy_derive::{Deref, DerefMut};
 use bevy_ecs::{
@@ -260,6 +262,8 @@ where
                 .add_render_command::<Shadow, DrawPrepass<M>>()
                 .add_render_command::<Transmissive3d, DrawMaterial<M>>()
                 .add_render_command::<Transparent3d, DrawMaterial<M>>()
+                .add_render_command::<HalfResolutionTransparent3d, DrawMaterial<M>>()
+                .add_render_command::<WeightedBlendedTransparent3d, DrawMaterial<M>>()
                 .add_render_command::<Opaque3d, DrawMaterial<M>>()
                 .add_render_command::<AlphaMask3d, DrawMaterial<M>>()
                 .init_resource::<SpecializedMeshPipelines<MaterialPipeline<M>>>()
@@ -478,7 +482,7 @@ pub const fn alpha_mode_pipeline_key(alpha_mode: AlphaMode, msaa: &Msaa) -> Mesh
     }
 }
 
-pub const fn tonemapping_pipeline_key(tonemapping: Tonemapping) -> MeshPipelineKey {
+pub const fn tonemapping_pipeline_key(tonemapping: &Tonemapping) -> MeshPipelineKey {
     match tonemapping {
         Tonemapping::None => MeshPipelineKey::TONEMAP_METHOD_NONE,
         Tonemapping::Reinhard => MeshPipelineKey::TONEMAP_METHOD_REINHARD,
@@ -490,6 +494,8 @@ pub const fn tonemapping_pipeline_key(tonemapping: Tonemapping) -> MeshPipelineK
         }
         Tonemapping::TonyMcMapface => MeshPipelineKey::TONEMAP_METHOD_TONY_MC_MAPFACE,
         Tonemapping::BlenderFilmic => MeshPipelineKey::TONEMAP_METHOD_BLENDER_FILMIC,
+        Tonemapping::Lut(_) => MeshPipelineKey::TONEMAP_METHOD_LUT,
+        Tonemapping::Custom(_) => MeshPipelineKey::TONEMAP_METHOD_CUSTOM,
     }
 }
 
@@ -521,16 +527,20 @@ pub fn queue_material_meshes<M: Material>(
         alpha_mask_draw_functions,
         transmissive_draw_functions,
         transparent_draw_functions,
+        half_resolution_transparent_draw_functions,
+        weighted_blended_transparent_draw_functions,
     ): (
         Res<DrawFunctions<Opaque3d>>,
         Res<DrawFunctions<AlphaMask3d>>,
         Res<DrawFunctions<Transmissive3d>>,
         Res<DrawFunctions<Transparent3d>>,
+        Res<DrawFunctions<HalfResolutionTransparent3d>>,
+        Res<DrawFunctions<WeightedBlendedTransparent3d>>,
     ),
     material_pipeline: Res<MaterialPipeline<M>>,
     mut pipelines: ResMut<SpecializedMeshPipelines<MaterialPipeline<M>>>,
     pipeline_cache: Res<PipelineCache>,
-    msaa: Res<Msaa>,
+    (msaa, custom_tonemapping_curves): (Res<Msaa>, Res<CustomTonemappingCurves>),
     render_meshes: Res<RenderAssets<GpuMesh>>,
     render_materials: Res<RenderAssets<PreparedMaterial<M>>>,
     render_mesh_instances: Res<RenderMeshInstances>,
@@ -540,7 +550,15 @@ pub fn queue_material_meshes<M: Material>(
     mut opaque_render_phases: ResMut<ViewBinnedRenderPhases<Opaque3d>>,
     mut alpha_mask_render_phases: ResMut<ViewBinnedRenderPhases<AlphaMask3d>>,
     mut transmissive_render_phases: ResMut<ViewSortedRenderPhases<Transmissive3d>>,
-    mut transparent_render_phases: ResMut<ViewSortedRenderPhases<Transparent3d>>,
+    (
+        mut transparent_render_phases,
+        mut half_resolution_transparent_render_phases,
+        mut weighted_blended_transparent_render_phases,
+    ): (
+        ResMut<ViewSortedRenderPhases<Transparent3d>>,
+        ResMut<ViewSortedRenderPhases<HalfResolutionTransparent3d>>,
+        ResMut<ViewSortedRenderPhases<WeightedBlendedTransparent3d>>,
+    ),
     mut views: Query<(
         Entity,
         &ExtractedView,
@@ -595,11 +613,23 @@ pub fn queue_material_meshes<M: Material>(
         else {
             continue;
         };
+        // Only the views with half resolution transparency have this phase
+        let mut half_resolution_transparent_phase =
+            half_resolution_transparent_render_phases.get_mut(&view_entity);
+        // Only the views with weighted blended order-independent transparency have this phase
+        let mut weighted_blended_transparent_phase =
+            weighted_blended_transparent_render_phases.get_mut(&view_entity);
 
         let draw_opaque_pbr = opaque_draw_functions.read().id::<DrawMaterial<M>>();
         let draw_alpha_mask_pbr = alpha_mask_draw_functions.read().id::<DrawMaterial<M>>();
         let draw_transmissive_pbr = transmissive_draw_functions.read().id::<DrawMaterial<M>>();
         let draw_transparent_pbr = transparent_draw_functions.read().id::<DrawMaterial<M>>();
+        let draw_half_resolution_transparent_pbr = half_resolution_transparent_draw_functions
+            .read()
+            .id::<DrawMaterial<M>>();
+        let draw_weighted_blended_transparent_pbr = weighted_blended_transparent_draw_functions
+            .read()
+            .id::<DrawMaterial<M>>();
 
         let mut view_key = MeshPipelineKey::from_msaa_samples(msaa.samples())
             | MeshPipelineKey::from_hdr(view.hdr);
@@ -654,7 +684,10 @@ pub fn queue_material_meshes<M: Material>(
         if !view.hdr {
             if let Some(tonemapping) = tonemapping {
                 view_key |= MeshPipelineKey::TONEMAP_IN_SHADER;
-                view_key |= tonemapping_pipeline_key(*tonemapping);
+                view_key |= tonemapping_pipeline_key(tonemapping);
+                view_key |= MeshPipelineKey::from_custom_tonemapping_curve(
+                    custom_tonemapping_curves.slot(tonemapping),
+                );
             }
             if let Some(DebandDither::Enabled) = dither {
                 view_key |= MeshPipelineKey::DEBAND_DITHER;
@@ -701,6 +734,16 @@ pub fn queue_material_meshes<M: Material>(
                 mesh_key |= MeshPipelineKey::VISIBILITY_RANGE_DITHER;
             }
 
+            // Only the alpha modes that blend the mesh over what's behind it can be averaged
+            let weighted_blended = weighted_blended_transparent_phase.is_some()
+                && matches!(
+                    material.properties.alpha_mode,
+                    AlphaMode::Blend | AlphaMode::Premultiplied
+                );
+            if weighted_blended {
+                mesh_key |= MeshPipelineKey::WEIGHTED_BLENDED_OIT;
+            }
+
             if motion_vector_prepass {
                 // If the previous frame have skins or morph targets, note that.
                 if mesh_instance
@@ -794,14 +837,44 @@ pub fn queue_material_meshes<M: Material>(
                 _ => {
                     let distance = rangefinder.distance_translation(&mesh_instance.translation)
                         + material.properties.depth_bias;
-                    transparent_phase.add(Transparent3d {
-                        entity: *visible_entity,
-                        draw_function: draw_transparent_pbr,
-                        pipeline: pipeline_id,
-                        distance,
-                        batch_range: 0..1,
-                        extra_index: PhaseItemExtraIndex::NONE,
-                    });
+                    let half_resolution = mesh_instance
+                        .flags
+                        .contains(RenderMeshInstanceFlags::HALF_RESOLUTION);
+                    match (
+                        weighted_blended_transparent_phase.as_mut(),
+                        half_resolution_transparent_phase.as_mut(),
+                    ) {
+                        (Some(weighted_blended_transparent_phase), _) if weighted_blended => {
+                            weighted_blended_transparent_phase.add(WeightedBlendedTransparent3d {
+                                entity: *visible_entity,
+                                draw_function: draw_weighted_blended_transparent_pbr,
+                                pipeline: pipeline_id,
+                                distance,
+                                batch_range: 0..1,
+                                extra_index: PhaseItemExtraIndex::NONE,
+                            });
+                        }
+                        (_, Some(half_resolution_transparent_phase)) if half_resolution => {
+                            half_resolution_transparent_phase.add(HalfResolutionTransparent3d {
+                                entity: *visible_entity,
+                                draw_function: draw_half_resolution_transparent_pbr,
+                                pipeline: pipeline_id,
+                                distance,
+                                batch_range: 0..1,
+                                extra_index: PhaseItemExtraIndex::NONE,
+                            });
+                        }
+                        _ => {
+                            transparent_phase.add(Transparent3d {
+                                entity: *visible_entity,
+                                draw_function: draw_transparent_pbr,
+                                pipeline: pipeline_id,
+                                distance,
+                                batch_range: 0..1,
+                                extra_index: PhaseItemExtraIndex::NONE,
+                            });
+                        }
+                    }
                 }
             }
         }
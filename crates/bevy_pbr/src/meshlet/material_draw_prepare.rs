@@ -4,7 +4,7 @@ use bevy_asset::AssetServer;
 use bevy_core_pipeline::{
     core_3d::Camera3d,
     prepass::{DeferredPrepass, DepthPrepass, MotionVectorPrepass, NormalPrepass},
-    tonemapping::{DebandDither, Tonemapping},
+    tonemapping::{CustomTonemappingCurves, DebandDither, Tonemapping},
 };
 use bevy_derive::{Deref, DerefMut};
 use bevy_render::{
@@ -33,6 +33,7 @@ pub fn prepare_material_meshlet_meshes_main_opaque_pass<M: Material>(
     render_materials: Res<RenderAssets<PreparedMaterial<M>>>,
     render_material_instances: Res<RenderMaterialInstances<M>>,
     asset_server: Res<AssetServer>,
+    custom_tonemapping_curves: Res<CustomTonemappingCurves>,
     mut mesh_vertex_buffer_layouts: ResMut<MeshVertexBufferLayouts>,
     mut views: Query<
         (
@@ -124,7 +125,10 @@ pub fn prepare_material_meshlet_meshes_main_opaque_pass<M: Material>(
         if !view.hdr {
             if let Some(tonemapping) = tonemapping {
                 view_key |= MeshPipelineKey::TONEMAP_IN_SHADER;
-                view_key |= tonemapping_pipeline_key(*tonemapping);
+                view_key |= tonemapping_pipeline_key(tonemapping);
+                view_key |= MeshPipelineKey::from_custom_tonemapping_curve(
+                    custom_tonemapping_curves.slot(tonemapping),
+                );
             }
             if let Some(DebandDither::Enabled) = dither {
                 view_key |= MeshPipelineKey::DEBAND_DITHER;
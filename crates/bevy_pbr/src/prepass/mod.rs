@@ -437,6 +437,10 @@ where
             shader_defs.push("MOTION_VECTOR_PREPASS".into());
         }
 
+        if key.mesh_key.contains(MeshPipelineKey::MESH_MASK_PREPASS) {
+            shader_defs.push("MESH_MASK_PREPASS".into());
+        }
+
         if key.mesh_key.contains(MeshPipelineKey::HAS_PREVIOUS_SKIN) {
             shader_defs.push("HAS_PREVIOUS_SKIN".into());
         }
@@ -448,7 +452,8 @@ where
         if key.mesh_key.intersects(
             MeshPipelineKey::NORMAL_PREPASS
                 | MeshPipelineKey::MOTION_VECTOR_PREPASS
-                | MeshPipelineKey::DEFERRED_PREPASS,
+                | MeshPipelineKey::DEFERRED_PREPASS
+                | MeshPipelineKey::MESH_MASK_PREPASS,
         ) {
             shader_defs.push("PREPASS_FRAGMENT".into());
         }
@@ -465,12 +470,14 @@ where
 
         let vertex_buffer_layout = layout.0.get_layout(&vertex_attributes)?;
 
-        // Setup prepass fragment targets - normals in slot 0 (or None if not needed), motion vectors in slot 1
+        // Setup prepass fragment targets - normals in slot 0 (or None if not needed), motion vectors in slot 1,
+        // the deferred gbuffer in slots 2 and 3, and the mesh mask in slot 4
         let mut targets = prepass_target_descriptors(
             key.mesh_key.contains(MeshPipelineKey::NORMAL_PREPASS),
             key.mesh_key
                 .contains(MeshPipelineKey::MOTION_VECTOR_PREPASS),
             key.mesh_key.contains(MeshPipelineKey::DEFERRED_PREPASS),
+            key.mesh_key.contains(MeshPipelineKey::MESH_MASK_PREPASS),
         );
 
         if targets.iter().all(Option::is_none) {
@@ -697,6 +704,7 @@ pub fn queue_prepass_material_meshes<M: Material>(
             Option<&NormalPrepass>,
             Option<&MotionVectorPrepass>,
             Option<&DeferredPrepass>,
+            Has<MeshMaskPrepass>,
         ),
         With<ExtractedView>,
     >,
@@ -726,6 +734,7 @@ pub fn queue_prepass_material_meshes<M: Material>(
         normal_prepass,
         motion_vector_prepass,
         deferred_prepass,
+        mesh_mask_prepass,
     ) in &mut views
     {
         let (
@@ -759,6 +768,9 @@ pub fn queue_prepass_material_meshes<M: Material>(
         if motion_vector_prepass.is_some() {
             view_key |= MeshPipelineKey::MOTION_VECTOR_PREPASS;
         }
+        if mesh_mask_prepass {
+            view_key |= MeshPipelineKey::MESH_MASK_PREPASS;
+        }
 
         for visible_entity in visible_entities.iter::<WithMesh>() {
             let Some(material_asset_id) = render_material_instances.get(visible_entity) else {
@@ -196,6 +196,10 @@ fn fragment(in: VertexOutput) -> FragmentOutput {
     out.deferred_lighting_pass_id = 1u;
 #endif
 
+#ifdef MESH_MASK_PREPASS
+    out.mesh_mask = mesh_functions::get_mesh_mask(in.instance_index);
+#endif
+
     return out;
 }
 #endif // PREPASS_FRAGMENT
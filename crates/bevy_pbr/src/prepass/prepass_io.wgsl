@@ -87,6 +87,10 @@ struct FragmentOutput {
     @location(3) deferred_lighting_pass_id: u32,
 #endif
 
+#ifdef MESH_MASK_PREPASS
+    @location(4) mesh_mask: u32,
+#endif
+
 #ifdef DEPTH_CLAMP_ORTHO
     @builtin(frag_depth) frag_depth: f32,
 #endif // DEPTH_CLAMP_ORTHO
@@ -57,4 +57,8 @@ struct VertexOutput {
 
 struct FragmentOutput {
     @location(0) color: vec4<f32>,
+#ifdef WEIGHTED_BLENDED_OIT
+    // The `1.0 - alpha` of the fragment is multiplied into the revealage texture
+    @location(1) revealage: f32,
+#endif
 }
@@ -7,6 +7,7 @@ use bevy_ecs::{entity::EntityHashMap, system::lifetimeless::Read};
 use bevy_math::{Mat4, UVec4, Vec2, Vec3, Vec3Swizzles, Vec4, Vec4Swizzles};
 use bevy_render::mesh::Mesh;
 use bevy_render::{
+    camera::MainPassResolutionOverride,
     diagnostic::RecordDiagnostics,
     mesh::GpuMesh,
     primitives::{CascadesFrusta, CubemapFrusta, Frustum, HalfSpace},
@@ -521,6 +522,7 @@ pub fn prepare_lights(
         &ExtractedView,
         &ExtractedClusterConfig,
         Option<&RenderLayers>,
+        Option<&MainPassResolutionOverride>,
     )>,
     ambient_light: Res<AmbientLight>,
     point_light_shadow_map: Res<PointLightShadowMap>,
@@ -790,7 +792,7 @@ pub fn prepare_lights(
     live_shadow_mapping_lights.clear();
 
     // set up light data for each view
-    for (entity, extracted_view, clusters, maybe_layers) in &views {
+    for (entity, extracted_view, clusters, maybe_layers, resolution_override) in &views {
         let point_light_depth_texture = texture_cache.get(
             &render_device,
             TextureDescriptor {
@@ -839,14 +841,20 @@ pub fn prepare_lights(
             is_orthographic,
         );
 
+        // The clusters are looked up from the fragment coordinates, so they have to cover the
+        // resolution that the main passes are rendered at
+        let viewport_size = resolution_override
+            .map(|resolution_override| resolution_override.0)
+            .unwrap_or(extracted_view.viewport.zw());
+
         let n_clusters = clusters.dimensions.x * clusters.dimensions.y * clusters.dimensions.z;
         let mut gpu_lights = GpuLights {
             directional_lights: gpu_directional_lights,
             ambient_color: Vec4::from_slice(&LinearRgba::from(ambient_light.color).to_f32_array())
                 * ambient_light.brightness,
             cluster_factors: Vec4::new(
-                clusters.dimensions.x as f32 / extracted_view.viewport.z as f32,
-                clusters.dimensions.y as f32 / extracted_view.viewport.w as f32,
+                clusters.dimensions.x as f32 / viewport_size.x as f32,
+                clusters.dimensions.y as f32 / viewport_size.y as f32,
                 cluster_factors_zw.x,
                 cluster_factors_zw.y,
             ),
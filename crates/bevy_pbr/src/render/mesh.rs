@@ -2,9 +2,15 @@ use std::mem;
 
 use bevy_asset::{load_internal_asset, AssetId};
 use bevy_core_pipeline::{
+    bloom::BLOOM_LAYER_COUNT,
     core_3d::{AlphaMask3d, Opaque3d, Transmissive3d, Transparent3d, CORE_3D_DEPTH_FORMAT},
     deferred::{AlphaMask3dDeferred, Opaque3dDeferred},
+    experimental::taa::ResponsiveAntiAliasing,
+    half_resolution_transparency::{HalfResolutionTransparent3d, RenderAtHalfResolution},
+    motion_blur::NoMotionBlur,
     prepass::MotionVectorPrepass,
+    tonemapping::custom_tonemapping_curve_shader_def,
+    weighted_blended_oit::{WeightedBlendedOitTextures, WeightedBlendedTransparent3d},
 };
 use bevy_derive::{Deref, DerefMut};
 use bevy_ecs::entity::EntityHashMap;
@@ -33,8 +39,8 @@ use bevy_render::{
     renderer::{RenderDevice, RenderQueue},
     texture::{BevyDefault, DefaultImageSampler, ImageSampler, TextureFormatPixelInfo},
     view::{
-        prepare_view_targets, GpuCulling, RenderVisibilityRanges, ViewTarget, ViewUniformOffset,
-        ViewVisibility, VisibilityRange,
+        prepare_view_targets, GpuCulling, RenderLayers, RenderVisibilityRanges, ViewTarget,
+        ViewUniformOffset, ViewVisibility, VisibilityRange,
     },
     Extract,
 };
@@ -134,6 +140,8 @@ impl Plugin for MeshRenderPlugin {
             BinnedRenderPhasePlugin::<AlphaMask3dDeferred, MeshPipeline>::default(),
             SortedRenderPhasePlugin::<Transmissive3d, MeshPipeline>::default(),
             SortedRenderPhasePlugin::<Transparent3d, MeshPipeline>::default(),
+            SortedRenderPhasePlugin::<HalfResolutionTransparent3d, MeshPipeline>::default(),
+            SortedRenderPhasePlugin::<WeightedBlendedTransparent3d, MeshPipeline>::default(),
         ));
 
         if let Some(render_app) = app.get_sub_app_mut(RenderApp) {
@@ -359,6 +367,13 @@ bitflags::bitflags! {
         ///
         /// This will be `u16::MAX` if this mesh has no LOD.
         const LOD_INDEX_MASK              = (1 << 16) - 1;
+        /// Bitmask for the 3-bit index of the render layer used for per-layer
+        /// bloom intensity.
+        const BLOOM_LAYER_MASK            = 0b111 << Self::BLOOM_LAYER_SHIFT;
+        /// TAA relies less on the history of the mesh, see [`ResponsiveAntiAliasing`].
+        const RESPONSIVE_ANTI_ALIASING    = 1 << 27;
+        /// Motion blur skips the mesh, see [`NoMotionBlur`].
+        const NO_MOTION_BLUR              = 1 << 28;
         const SHADOW_RECEIVER             = 1 << 29;
         const TRANSMITTED_SHADOW_RECEIVER = 1 << 30;
         // Indicates the sign of the determinant of the 3x3 model matrix. If the sign is positive,
@@ -375,6 +390,9 @@ impl MeshFlags {
         lod_index: Option<NonMaxU16>,
         not_shadow_receiver: bool,
         transmitted_receiver: bool,
+        no_motion_blur: bool,
+        responsive_anti_aliasing: bool,
+        render_layers: Option<&RenderLayers>,
     ) -> MeshFlags {
         let mut mesh_flags = if not_shadow_receiver {
             MeshFlags::empty()
@@ -384,6 +402,12 @@ impl MeshFlags {
         if transmitted_receiver {
             mesh_flags |= MeshFlags::TRANSMITTED_SHADOW_RECEIVER;
         }
+        if no_motion_blur {
+            mesh_flags |= MeshFlags::NO_MOTION_BLUR;
+        }
+        if responsive_anti_aliasing {
+            mesh_flags |= MeshFlags::RESPONSIVE_ANTI_ALIASING;
+        }
         if transform.affine().matrix3.determinant().is_sign_positive() {
             mesh_flags |= MeshFlags::SIGN_DETERMINANT_MODEL_3X3;
         }
@@ -395,11 +419,23 @@ impl MeshFlags {
         mesh_flags |=
             MeshFlags::from_bits_retain((lod_index_bits as u32) << MeshFlags::LOD_INDEX_SHIFT);
 
+        // The bloom layer is the lowest render layer of the mesh, if it's one of the layers
+        // that `BloomSettings::layer_intensity` covers.
+        let bloom_layer = render_layers
+            .and_then(|render_layers| render_layers.iter().next())
+            .filter(|&layer| layer < BLOOM_LAYER_COUNT)
+            .unwrap_or(0);
+        mesh_flags |=
+            MeshFlags::from_bits_retain((bloom_layer as u32) << MeshFlags::BLOOM_LAYER_SHIFT);
+
         mesh_flags
     }
 
     /// The first bit of the LOD index.
     pub const LOD_INDEX_SHIFT: u32 = 0;
+
+    /// The first bit of the bloom layer index.
+    pub const BLOOM_LAYER_SHIFT: u32 = 16;
 }
 
 bitflags::bitflags! {
@@ -419,6 +455,9 @@ bitflags::bitflags! {
         /// The mesh had morph targets last frame and so they should be taken
         /// into account for motion vector computation.
         const HAS_PREVIOUS_MORPH      = 1 << 4;
+        /// The mesh is rendered at half resolution when it's transparent, for the
+        /// views that support it.
+        const HALF_RESOLUTION         = 1 << 5;
     }
 }
 
@@ -523,6 +562,7 @@ impl RenderMeshInstanceShared {
         handle: &Handle<Mesh>,
         not_shadow_caster: bool,
         no_automatic_batching: bool,
+        render_at_half_resolution: bool,
     ) -> Self {
         let mut mesh_instance_flags = RenderMeshInstanceFlags::empty();
         mesh_instance_flags.set(RenderMeshInstanceFlags::SHADOW_CASTER, !not_shadow_caster);
@@ -534,6 +574,10 @@ impl RenderMeshInstanceShared {
             RenderMeshInstanceFlags::HAS_PREVIOUS_TRANSFORM,
             previous_transform.is_some(),
         );
+        mesh_instance_flags.set(
+            RenderMeshInstanceFlags::HALF_RESOLUTION,
+            render_at_half_resolution,
+        );
 
         RenderMeshInstanceShared {
             mesh_asset_id: handle.id(),
@@ -815,9 +859,15 @@ pub fn extract_meshes_for_cpu_building(
             &Handle<Mesh>,
             Has<NotShadowReceiver>,
             Has<TransmittedShadowReceiver>,
-            Has<NotShadowCaster>,
-            Has<NoAutomaticBatching>,
+            (
+                Has<NotShadowCaster>,
+                Has<NoAutomaticBatching>,
+                Has<RenderAtHalfResolution>,
+            ),
             Has<VisibilityRange>,
+            Has<NoMotionBlur>,
+            Has<ResponsiveAntiAliasing>,
+            Option<&RenderLayers>,
         )>,
     >,
 ) {
@@ -832,9 +882,11 @@ pub fn extract_meshes_for_cpu_building(
             handle,
             not_shadow_receiver,
             transmitted_receiver,
-            not_shadow_caster,
-            no_automatic_batching,
+            (not_shadow_caster, no_automatic_batching, render_at_half_resolution),
             visibility_range,
+            no_motion_blur,
+            responsive_anti_aliasing,
+            render_layers,
         )| {
             if !view_visibility.get() {
                 return;
@@ -850,6 +902,9 @@ pub fn extract_meshes_for_cpu_building(
                 lod_index,
                 not_shadow_receiver,
                 transmitted_receiver,
+                no_motion_blur,
+                responsive_anti_aliasing,
+                render_layers,
             );
 
             let shared = RenderMeshInstanceShared::from_components(
@@ -857,6 +912,7 @@ pub fn extract_meshes_for_cpu_building(
                 handle,
                 not_shadow_caster,
                 no_automatic_batching,
+                render_at_half_resolution,
             );
 
             let world_from_local = transform.affine();
@@ -918,9 +974,15 @@ pub fn extract_meshes_for_gpu_building(
             &Handle<Mesh>,
             Has<NotShadowReceiver>,
             Has<TransmittedShadowReceiver>,
-            Has<NotShadowCaster>,
-            Has<NoAutomaticBatching>,
+            (
+                Has<NotShadowCaster>,
+                Has<NoAutomaticBatching>,
+                Has<RenderAtHalfResolution>,
+            ),
             Has<VisibilityRange>,
+            Has<NoMotionBlur>,
+            Has<ResponsiveAntiAliasing>,
+            Option<&RenderLayers>,
         )>,
     >,
     cameras_query: Extract<Query<(), (With<Camera>, With<GpuCulling>)>>,
@@ -952,9 +1014,11 @@ pub fn extract_meshes_for_gpu_building(
             handle,
             not_shadow_receiver,
             transmitted_receiver,
-            not_shadow_caster,
-            no_automatic_batching,
+            (not_shadow_caster, no_automatic_batching, render_at_half_resolution),
             visibility_range,
+            no_motion_blur,
+            responsive_anti_aliasing,
+            render_layers,
         )| {
             if !view_visibility.get() {
                 return;
@@ -970,6 +1034,9 @@ pub fn extract_meshes_for_gpu_building(
                 lod_index,
                 not_shadow_receiver,
                 transmitted_receiver,
+                no_motion_blur,
+                responsive_anti_aliasing,
+                render_layers,
             );
 
             let shared = RenderMeshInstanceShared::from_components(
@@ -977,6 +1044,7 @@ pub fn extract_meshes_for_gpu_building(
                 handle,
                 not_shadow_caster,
                 no_automatic_batching,
+                render_at_half_resolution,
             );
 
             let lightmap_uv_rect =
@@ -1412,7 +1480,9 @@ bitflags::bitflags! {
         const SCREEN_SPACE_REFLECTIONS          = 1 << 16;
         const HAS_PREVIOUS_SKIN                 = 1 << 17;
         const HAS_PREVIOUS_MORPH                = 1 << 18;
-        const LAST_FLAG                         = Self::HAS_PREVIOUS_MORPH.bits();
+        const MESH_MASK_PREPASS                 = 1 << 19;
+        const WEIGHTED_BLENDED_OIT              = 1 << 20; // Alpha blended meshes output to the weighted blended OIT textures
+        const LAST_FLAG                         = Self::WEIGHTED_BLENDED_OIT.bits();
 
         // Bitfields
         const MSAA_RESERVED_BITS                = Self::MSAA_MASK_BITS << Self::MSAA_SHIFT_BITS;
@@ -1431,6 +1501,8 @@ bitflags::bitflags! {
         const TONEMAP_METHOD_SOMEWHAT_BORING_DISPLAY_TRANSFORM = 5 << Self::TONEMAP_METHOD_SHIFT_BITS;
         const TONEMAP_METHOD_TONY_MC_MAPFACE     = 6 << Self::TONEMAP_METHOD_SHIFT_BITS;
         const TONEMAP_METHOD_BLENDER_FILMIC      = 7 << Self::TONEMAP_METHOD_SHIFT_BITS;
+        const TONEMAP_METHOD_LUT                 = 8 << Self::TONEMAP_METHOD_SHIFT_BITS;
+        const TONEMAP_METHOD_CUSTOM              = 9 << Self::TONEMAP_METHOD_SHIFT_BITS;
         const SHADOW_FILTER_METHOD_RESERVED_BITS = Self::SHADOW_FILTER_METHOD_MASK_BITS << Self::SHADOW_FILTER_METHOD_SHIFT_BITS;
         const SHADOW_FILTER_METHOD_HARDWARE_2X2  = 0 << Self::SHADOW_FILTER_METHOD_SHIFT_BITS;
         const SHADOW_FILTER_METHOD_GAUSSIAN      = 1 << Self::SHADOW_FILTER_METHOD_SHIFT_BITS;
@@ -1445,13 +1517,15 @@ bitflags::bitflags! {
         const SCREEN_SPACE_SPECULAR_TRANSMISSION_MEDIUM = 1 << Self::SCREEN_SPACE_SPECULAR_TRANSMISSION_SHIFT_BITS;
         const SCREEN_SPACE_SPECULAR_TRANSMISSION_HIGH = 2 << Self::SCREEN_SPACE_SPECULAR_TRANSMISSION_SHIFT_BITS;
         const SCREEN_SPACE_SPECULAR_TRANSMISSION_ULTRA = 3 << Self::SCREEN_SPACE_SPECULAR_TRANSMISSION_SHIFT_BITS;
+        const CUSTOM_TONEMAPPING_CURVE_RESERVED_BITS = Self::CUSTOM_TONEMAPPING_CURVE_MASK_BITS << Self::CUSTOM_TONEMAPPING_CURVE_SHIFT_BITS; // ← The slot of the custom curve plus one, or zero
         const ALL_RESERVED_BITS =
             Self::BLEND_RESERVED_BITS.bits() |
             Self::MSAA_RESERVED_BITS.bits() |
             Self::TONEMAP_METHOD_RESERVED_BITS.bits() |
             Self::SHADOW_FILTER_METHOD_RESERVED_BITS.bits() |
             Self::VIEW_PROJECTION_RESERVED_BITS.bits() |
-            Self::SCREEN_SPACE_SPECULAR_TRANSMISSION_RESERVED_BITS.bits();
+            Self::SCREEN_SPACE_SPECULAR_TRANSMISSION_RESERVED_BITS.bits() |
+            Self::CUSTOM_TONEMAPPING_CURVE_RESERVED_BITS.bits();
     }
 }
 
@@ -1462,7 +1536,7 @@ impl MeshPipelineKey {
     const BLEND_MASK_BITS: u64 = 0b111;
     const BLEND_SHIFT_BITS: u64 = Self::MSAA_MASK_BITS.count_ones() as u64 + Self::MSAA_SHIFT_BITS;
 
-    const TONEMAP_METHOD_MASK_BITS: u64 = 0b111;
+    const TONEMAP_METHOD_MASK_BITS: u64 = 0b1111;
     const TONEMAP_METHOD_SHIFT_BITS: u64 =
         Self::BLEND_MASK_BITS.count_ones() as u64 + Self::BLEND_SHIFT_BITS;
 
@@ -1479,6 +1553,11 @@ impl MeshPipelineKey {
     const SCREEN_SPACE_SPECULAR_TRANSMISSION_SHIFT_BITS: u64 =
         Self::VIEW_PROJECTION_MASK_BITS.count_ones() as u64 + Self::VIEW_PROJECTION_SHIFT_BITS;
 
+    const CUSTOM_TONEMAPPING_CURVE_MASK_BITS: u64 = 0b1111;
+    const CUSTOM_TONEMAPPING_CURVE_SHIFT_BITS: u64 =
+        Self::SCREEN_SPACE_SPECULAR_TRANSMISSION_MASK_BITS.count_ones() as u64
+            + Self::SCREEN_SPACE_SPECULAR_TRANSMISSION_SHIFT_BITS;
+
     pub fn from_msaa_samples(msaa_samples: u32) -> Self {
         let msaa_bits =
             (msaa_samples.trailing_zeros() as u64 & Self::MSAA_MASK_BITS) << Self::MSAA_SHIFT_BITS;
@@ -1497,6 +1576,22 @@ impl MeshPipelineKey {
         1 << ((self.bits() >> Self::MSAA_SHIFT_BITS) & Self::MSAA_MASK_BITS)
     }
 
+    /// Selects the curve in the given slot of the
+    /// [`CustomTonemappingCurves`](bevy_core_pipeline::tonemapping::CustomTonemappingCurves), for
+    /// [`MeshPipelineKey::TONEMAP_METHOD_CUSTOM`].
+    pub fn from_custom_tonemapping_curve(slot: Option<u32>) -> Self {
+        let bits = (slot.map_or(0, |slot| slot as u64 + 1)
+            & Self::CUSTOM_TONEMAPPING_CURVE_MASK_BITS)
+            << Self::CUSTOM_TONEMAPPING_CURVE_SHIFT_BITS;
+        Self::from_bits_retain(bits)
+    }
+
+    pub fn custom_tonemapping_curve(&self) -> Option<u32> {
+        let bits = (self.bits() >> Self::CUSTOM_TONEMAPPING_CURVE_SHIFT_BITS)
+            & Self::CUSTOM_TONEMAPPING_CURVE_MASK_BITS;
+        (bits as u32).checked_sub(1)
+    }
+
     pub fn from_primitive_topology(primitive_topology: PrimitiveTopology) -> Self {
         let primitive_topology_bits = ((primitive_topology as u64)
             & BaseMeshPipelineKey::PRIMITIVE_TOPOLOGY_MASK_BITS)
@@ -1673,6 +1768,11 @@ impl SpecializedMeshPipeline for MeshPipeline {
         if pass == MeshPipelineKey::BLEND_ALPHA {
             label = "alpha_blend_mesh_pipeline".into();
             blend = Some(BlendState::ALPHA_BLENDING);
+            // Weighted blended transparency expects premultiplied colors
+            if key.contains(MeshPipelineKey::WEIGHTED_BLENDED_OIT) {
+                shader_defs.push("PREMULTIPLY_ALPHA".into());
+                shader_defs.push("BLEND_ALPHA".into());
+            }
             // For the transparent pass, fragments that are closer will be alpha blended
             // but their depth is not written to the depth buffer
             depth_write_enabled = false;
@@ -1790,6 +1890,13 @@ impl SpecializedMeshPipeline for MeshPipeline {
                 shader_defs.push("TONEMAP_METHOD_BLENDER_FILMIC".into());
             } else if method == MeshPipelineKey::TONEMAP_METHOD_TONY_MC_MAPFACE {
                 shader_defs.push("TONEMAP_METHOD_TONY_MC_MAPFACE".into());
+            } else if method == MeshPipelineKey::TONEMAP_METHOD_LUT {
+                shader_defs.push("TONEMAP_METHOD_LUT".into());
+            } else if method == MeshPipelineKey::TONEMAP_METHOD_CUSTOM {
+                shader_defs.push("TONEMAP_METHOD_CUSTOM".into());
+                if let Some(slot) = key.custom_tonemapping_curve() {
+                    shader_defs.push(custom_tonemapping_curve_shader_def(slot));
+                }
             }
 
             // Debanding is tied to tonemapping in the shader, cannot run without it.
@@ -1860,6 +1967,49 @@ impl SpecializedMeshPipeline for MeshPipeline {
             TextureFormat::bevy_default()
         };
 
+        let targets = if key.contains(MeshPipelineKey::WEIGHTED_BLENDED_OIT)
+            && (pass == MeshPipelineKey::BLEND_ALPHA
+                || pass == MeshPipelineKey::BLEND_PREMULTIPLIED_ALPHA)
+        {
+            shader_defs.push("WEIGHTED_BLENDED_OIT".into());
+            // The weighted colors are added together, and the `1.0 - alpha` of the fragments are
+            // multiplied together
+            let accumulate = BlendComponent {
+                src_factor: BlendFactor::One,
+                dst_factor: BlendFactor::One,
+                operation: BlendOperation::Add,
+            };
+            let reveal = BlendComponent {
+                src_factor: BlendFactor::Zero,
+                dst_factor: BlendFactor::OneMinusSrc,
+                operation: BlendOperation::Add,
+            };
+            vec![
+                Some(ColorTargetState {
+                    format: WeightedBlendedOitTextures::ACCUMULATION_FORMAT,
+                    blend: Some(BlendState {
+                        color: accumulate,
+                        alpha: accumulate,
+                    }),
+                    write_mask: ColorWrites::ALL,
+                }),
+                Some(ColorTargetState {
+                    format: WeightedBlendedOitTextures::REVEALAGE_FORMAT,
+                    blend: Some(BlendState {
+                        color: reveal,
+                        alpha: reveal,
+                    }),
+                    write_mask: ColorWrites::ALL,
+                }),
+            ]
+        } else {
+            vec![Some(ColorTargetState {
+                format,
+                blend,
+                write_mask: ColorWrites::ALL,
+            })]
+        };
+
         // This is defined here so that custom shaders that use something other than
         // the mesh binding from bevy_pbr::mesh_bindings can easily make use of this
         // in their own shaders.
@@ -1881,11 +2031,7 @@ impl SpecializedMeshPipeline for MeshPipeline {
                 shader: MESH_SHADER_HANDLE,
                 shader_defs,
                 entry_point: "fragment".into(),
-                targets: vec![Some(ColorTargetState {
-                    format,
-                    blend,
-                    write_mask: ColorWrites::ALL,
-                })],
+                targets,
             }),
             layout: bind_group_layout,
             push_constant_ranges: vec![],
@@ -7,9 +7,16 @@
         VISIBILITY_RANGE_UNIFORM_BUFFER_SIZE
     },
     mesh_bindings::mesh,
-    mesh_types::MESH_FLAGS_SIGN_DETERMINANT_MODEL_3X3_BIT,
+    mesh_types::{
+        MESH_FLAGS_SIGN_DETERMINANT_MODEL_3X3_BIT, MESH_FLAGS_BLOOM_LAYER_BITS,
+        MESH_FLAGS_BLOOM_LAYER_SHIFT, MESH_FLAGS_RESPONSIVE_ANTI_ALIASING_BIT,
+        MESH_FLAGS_NO_MOTION_BLUR_BIT,
+    },
     view_transformations::position_world_to_clip,
 }
+#import bevy_core_pipeline::mesh_mask::{
+    MESH_MASK_RESPONSIVE_ANTI_ALIASING_BIT, MESH_MASK_NO_MOTION_BLUR_BIT,
+}
 #import bevy_render::maths::{affine3_to_square, mat2x4_f32_to_mat3x3_unpack}
 
 
@@ -21,6 +28,20 @@ fn get_previous_world_from_local(instance_index: u32) -> mat4x4<f32> {
     return affine3_to_square(mesh[instance_index].previous_world_from_local);
 }
 
+// Returns the flags of the mesh written by the mesh mask prepass, see
+// `bevy_core_pipeline::mesh_mask`.
+fn get_mesh_mask(instance_index: u32) -> u32 {
+    let flags = mesh[instance_index].flags;
+    var mask = (flags & MESH_FLAGS_BLOOM_LAYER_BITS) >> MESH_FLAGS_BLOOM_LAYER_SHIFT;
+    if (flags & MESH_FLAGS_RESPONSIVE_ANTI_ALIASING_BIT) != 0u {
+        mask |= MESH_MASK_RESPONSIVE_ANTI_ALIASING_BIT;
+    }
+    if (flags & MESH_FLAGS_NO_MOTION_BLUR_BIT) != 0u {
+        mask |= MESH_MASK_NO_MOTION_BLUR_BIT;
+    }
+    return mask;
+}
+
 fn mesh_position_local_to_world(world_from_local: mat4x4<f32>, vertex_position: vec4<f32>) -> vec4<f32> {
     return world_from_local * vertex_position;
 }
@@ -31,6 +31,13 @@ struct MorphWeights {
 
 // [2^0, 2^16)
 const MESH_FLAGS_VISIBILITY_RANGE_INDEX_BITS: u32 = 65535u;
+// [2^16, 2^19)
+const MESH_FLAGS_BLOOM_LAYER_BITS: u32 = 458752u;
+const MESH_FLAGS_BLOOM_LAYER_SHIFT: u32 = 16u;
+// 2^27
+const MESH_FLAGS_RESPONSIVE_ANTI_ALIASING_BIT: u32 = 134217728u;
+// 2^28
+const MESH_FLAGS_NO_MOTION_BLUR_BIT: u32 = 268435456u;
 // 2^29
 const MESH_FLAGS_SHADOW_RECEIVER_BIT: u32 = 536870912u;
 // 2^30
@@ -17,6 +17,11 @@
 }
 #endif
 
+#ifdef WEIGHTED_BLENDED_OIT
+#import bevy_pbr::view_transformations::position_world_to_view
+#import bevy_core_pipeline::weighted_blended_oit::weighted_blended_oit_output
+#endif
+
 #ifdef MESHLET_MESH_MATERIAL_PASS
 #import bevy_pbr::meshlet_visibility_buffer_resolve::resolve_vertex_output
 #endif
@@ -63,6 +68,14 @@ fn fragment(
     // apply in-shader post processing (fog, alpha-premultiply, and also tonemapping, debanding if the camera is non-hdr)
     // note this does not include fullscreen postprocessing effects like bloom.
     out.color = main_pass_post_lighting_processing(pbr_input, out.color);
+
+#ifdef WEIGHTED_BLENDED_OIT
+    // the color is premultiplied, and accumulated with a weight that depends on its depth
+    let view_distance = -position_world_to_view(pbr_input.world_position.xyz).z;
+    let oit_output = weighted_blended_oit_output(out.color, view_distance);
+    out.color = oit_output.accumulation;
+    out.revealage = oit_output.revealage;
+#endif
 #endif
 
     return out;
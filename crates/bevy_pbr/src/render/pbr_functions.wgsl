@@ -12,7 +12,9 @@
     shadows,
     ambient,
     irradiance_volume,
-    mesh_types::{MESH_FLAGS_SHADOW_RECEIVER_BIT, MESH_FLAGS_TRANSMITTED_SHADOW_RECEIVER_BIT},
+    mesh_types::{
+        MESH_FLAGS_SHADOW_RECEIVER_BIT, MESH_FLAGS_TRANSMITTED_SHADOW_RECEIVER_BIT,
+    },
 }
 #import bevy_render::maths::{E, powsafe}
 
@@ -797,6 +799,11 @@ fn premultiply_alpha(standard_material_flags: u32, color: vec4<f32>) -> vec4<f32
     // controlled by the source alpha channel
     return vec4<f32>(color.rgb * color.a, color.a);
 #endif
+// `Blend` only premultiplies for weighted blended order-independent transparency, which adds
+// the premultiplied colors together
+#ifdef BLEND_ALPHA
+    return vec4<f32>(color.rgb * color.a, color.a);
+#endif
 }
 #endif
 
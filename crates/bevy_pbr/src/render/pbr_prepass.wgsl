@@ -11,6 +11,8 @@
 
 #ifdef MESHLET_MESH_MATERIAL_PASS
 #import bevy_pbr::meshlet_visibility_buffer_resolve::resolve_vertex_output
+#else
+#import bevy_pbr::mesh_functions
 #endif
 
 #ifdef PREPASS_FRAGMENT
@@ -100,6 +102,12 @@ fn fragment(
 #else
     out.motion_vector = pbr_prepass_functions::calculate_motion_vector(in.world_position, in.previous_world_position);
 #endif
+#endif
+
+#ifdef MESH_MASK_PREPASS
+#ifndef MESHLET_MESH_MATERIAL_PASS
+    out.mesh_mask = mesh_functions::get_mesh_mask(in.instance_index);
+#endif
 #endif
 
     return out;
@@ -67,6 +67,23 @@ impl Default for Viewport {
     }
 }
 
+impl Viewport {
+    /// Returns the viewport of the main passes of a camera with the given `viewport` and
+    /// [`MainPassResolutionOverride`].
+    ///
+    /// Returns `None` when the main passes render to the whole render target.
+    pub fn from_viewport_and_override(
+        viewport: Option<&Self>,
+        main_pass_resolution_override: Option<&MainPassResolutionOverride>,
+    ) -> Option<Self> {
+        let mut viewport = viewport.cloned();
+        if let Some(resolution_override) = main_pass_resolution_override {
+            viewport.get_or_insert_with(Viewport::default).physical_size = resolution_override.0;
+        }
+        viewport
+    }
+}
+
 /// Information about the current [`RenderTarget`].
 #[derive(Default, Debug, Clone)]
 pub struct RenderTargetInfo {
@@ -148,7 +165,13 @@ impl Default for Exposure {
 
 /// Parameters based on physical camera characteristics for calculating EV100
 /// values for use with [`Exposure`]. This is also used for depth of field.
-#[derive(Clone, Copy)]
+///
+/// When added to a camera, the camera's exposure is computed from these
+/// parameters, overriding its [`Exposure`]. Depth of field also uses the
+/// aperture and sensor height of the camera, so that both effects stay
+/// consistent while the parameters are tuned.
+#[derive(Component, Clone, Copy, Reflect)]
+#[reflect(Component, Default)]
 pub struct PhysicalCameraParameters {
     /// <https://en.wikipedia.org/wiki/F-number>
     pub aperture_f_stops: f32,
@@ -838,6 +861,7 @@ pub fn extract_cameras(
             &Frustum,
             Option<&ColorGrading>,
             Option<&Exposure>,
+            Option<&PhysicalCameraParameters>,
             Option<&TemporalJitter>,
             Option<&RenderLayers>,
             Option<&Projection>,
@@ -857,6 +881,7 @@ pub fn extract_cameras(
         frustum,
         color_grading,
         exposure,
+        physical_camera_parameters,
         temporal_jitter,
         render_layers,
         projection,
@@ -900,9 +925,11 @@ pub fn extract_cameras(
                     clear_color: camera.clear_color,
                     // this will be set in sort_cameras
                     sorted_camera_index_for_target: 0,
-                    exposure: exposure
-                        .map(|e| e.exposure())
-                        .unwrap_or_else(|| Exposure::default().exposure()),
+                    exposure: physical_camera_parameters
+                        .map(|p| Exposure::from_physical_camera(*p))
+                        .or(exposure.copied())
+                        .unwrap_or_default()
+                        .exposure(),
                     hdr: camera.hdr,
                 },
                 ExtractedView {
@@ -1048,3 +1075,15 @@ impl TemporalJitter {
 #[derive(Default, Component, Reflect)]
 #[reflect(Default, Component)]
 pub struct MipBias(pub f32);
+
+/// Overrides the resolution of the main passes of a 3D camera, in the render world.
+///
+/// The main passes render to the top-left corner of the viewport of the camera, at this size, and
+/// an upscaler then fills the whole viewport from that corner, before the post-processing effects.
+/// The view uniforms use this size as the size of the viewport, so that the shaders of the main
+/// passes map the fragment positions to the screen correctly.
+///
+/// This is inserted by upscalers, such as the FSR upscaler of `bevy_core_pipeline`, rather than
+/// by users.
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq, Deref, DerefMut)]
+pub struct MainPassResolutionOverride(pub UVec2);
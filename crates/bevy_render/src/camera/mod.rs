@@ -28,6 +28,7 @@ impl Plugin for CameraPlugin {
             .register_type::<CameraRenderGraph>()
             .register_type::<CameraMainTextureUsages>()
             .register_type::<Exposure>()
+            .register_type::<PhysicalCameraParameters>()
             .register_type::<TemporalJitter>()
             .register_type::<MipBias>()
             .init_resource::<ManualTextureViews>()
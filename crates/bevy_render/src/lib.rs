@@ -70,7 +70,7 @@ use crate::{
     camera::CameraPlugin,
     mesh::{morph::MorphPlugin, MeshPlugin},
     render_asset::prepare_assets,
-    render_resource::{PipelineCache, Shader, ShaderLoader},
+    render_resource::{PipelineCache, Shader, ShaderCompilationError, ShaderLoader},
     renderer::{render_system, RenderInstance},
     settings::RenderCreation,
     view::{ViewPlugin, WindowRenderPlugin},
@@ -98,6 +98,11 @@ pub struct RenderPlugin {
     /// If `true`, disables asynchronous pipeline compilation.
     /// This has no effect on macOS, Wasm, iOS, or without the `multi_threaded` feature.
     pub synchronous_pipeline_compilation: bool,
+    /// The maximum number of queued pipelines that start being created each frame.
+    /// Pipelines over the limit are created on the following frames. `None` means no limit.
+    ///
+    /// See [`PipelineCache::set_max_pipeline_creations_per_frame`].
+    pub max_pipeline_creations_per_frame: Option<usize>,
 }
 
 /// The systems sets of the default [`App`] rendering schedule.
@@ -348,6 +353,8 @@ impl Plugin for RenderPlugin {
             BatchingPlugin,
         ));
 
+        app.add_event::<ShaderCompilationError>();
+
         app.init_resource::<RenderAssetBytesPerFrame>()
             .add_plugins(ExtractResourcePlugin::<RenderAssetBytesPerFrame>::default());
 
@@ -388,13 +395,17 @@ impl Plugin for RenderPlugin {
 
             let render_app = app.sub_app_mut(RenderApp);
 
+            let mut pipeline_cache = PipelineCache::new(
+                device.clone(),
+                render_adapter.clone(),
+                self.synchronous_pipeline_compilation,
+            );
+            pipeline_cache
+                .set_max_pipeline_creations_per_frame(self.max_pipeline_creations_per_frame);
+
             render_app
                 .insert_resource(instance)
-                .insert_resource(PipelineCache::new(
-                    device.clone(),
-                    render_adapter.clone(),
-                    self.synchronous_pipeline_compilation,
-                ))
+                .insert_resource(pipeline_cache)
                 .insert_resource(device)
                 .insert_resource(queue)
                 .insert_resource(render_adapter)
@@ -451,7 +462,14 @@ unsafe fn initialize_render_app(app: &mut App) {
         .add_schedule(Render::base_schedule())
         .init_resource::<render_graph::RenderGraph>()
         .insert_resource(app.world().resource::<AssetServer>().clone())
-        .add_systems(ExtractSchedule, PipelineCache::extract_shaders)
+        .init_resource::<Events<ShaderCompilationError>>()
+        .add_systems(
+            ExtractSchedule,
+            (
+                PipelineCache::extract_shaders,
+                PipelineCache::extract_compilation_errors,
+            ),
+        )
         .add_systems(
             Render,
             (
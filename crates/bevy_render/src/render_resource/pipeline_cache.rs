@@ -1,11 +1,14 @@
 use crate::{
     render_resource::*,
     renderer::{RenderAdapter, RenderDevice},
-    Extract,
+    Extract, MainWorld,
 };
-use bevy_asset::{AssetEvent, AssetId, Assets};
+use bevy_asset::{AssetEvent, AssetId, Assets, Handle};
 use bevy_ecs::system::{Res, ResMut};
-use bevy_ecs::{event::EventReader, system::Resource};
+use bevy_ecs::{
+    event::{Event, EventReader, Events},
+    system::Resource,
+};
 use bevy_tasks::Task;
 use bevy_utils::hashbrown::hash_map::EntryRef;
 use bevy_utils::{
@@ -44,6 +47,51 @@ pub enum PipelineDescriptor {
     ComputePipelineDescriptor(Box<ComputePipelineDescriptor>),
 }
 
+impl PipelineDescriptor {
+    fn label(&self) -> Option<&Cow<'static, str>> {
+        match self {
+            PipelineDescriptor::RenderPipelineDescriptor(descriptor) => descriptor.label.as_ref(),
+            PipelineDescriptor::ComputePipelineDescriptor(descriptor) => descriptor.label.as_ref(),
+        }
+    }
+
+    /// Returns the shader, shader defs and entry point of every stage of the pipeline, in the
+    /// order their shaders are processed.
+    fn stages(&self) -> Vec<PipelineStage<'_>> {
+        match self {
+            PipelineDescriptor::RenderPipelineDescriptor(descriptor) => {
+                let mut stages = vec![(
+                    &descriptor.vertex.shader,
+                    &descriptor.vertex.shader_defs[..],
+                    &descriptor.vertex.entry_point,
+                )];
+                if let Some(fragment) = &descriptor.fragment {
+                    stages.push((
+                        &fragment.shader,
+                        &fragment.shader_defs[..],
+                        &fragment.entry_point,
+                    ));
+                }
+                stages
+            }
+            PipelineDescriptor::ComputePipelineDescriptor(descriptor) => {
+                vec![(
+                    &descriptor.shader,
+                    &descriptor.shader_defs[..],
+                    &descriptor.entry_point,
+                )]
+            }
+        }
+    }
+}
+
+/// The shader, shader defs and entry point of a stage of a [`PipelineDescriptor`].
+type PipelineStage<'a> = (
+    &'a Handle<Shader>,
+    &'a [ShaderDefVal],
+    &'a Cow<'static, str>,
+);
+
 /// A pipeline defining the data layout and shader logic for a specific GPU task.
 ///
 /// Used to store an heterogenous collection of render and compute pipelines together.
@@ -53,7 +101,23 @@ pub enum Pipeline {
     ComputePipeline(ComputePipeline),
 }
 
-type CachedPipelineId = usize;
+/// The slot of a pipeline in a [`PipelineCache`], and the generation of that slot when the
+/// pipeline was stored in it.
+///
+/// The generation of a slot changes when its pipeline is released, so that the ID of a released
+/// pipeline never refers to the pipeline reusing its slot.
+#[derive(Copy, Clone, Debug, Hash, Eq, PartialEq, PartialOrd, Ord)]
+struct CachedPipelineId {
+    index: usize,
+    generation: u32,
+}
+
+impl CachedPipelineId {
+    const INVALID: Self = CachedPipelineId {
+        index: usize::MAX,
+        generation: 0,
+    };
+}
 
 /// Index of a cached render pipeline in a [`PipelineCache`].
 #[derive(Copy, Clone, Debug, Hash, Eq, PartialEq, PartialOrd, Ord)]
@@ -61,11 +125,23 @@ pub struct CachedRenderPipelineId(CachedPipelineId);
 
 impl CachedRenderPipelineId {
     /// An invalid cached render pipeline index, often used to initialize a variable.
-    pub const INVALID: Self = CachedRenderPipelineId(usize::MAX);
+    pub const INVALID: Self = CachedRenderPipelineId(CachedPipelineId::INVALID);
 
+    /// Returns the index of the slot the pipeline is stored in.
+    ///
+    /// The slot of a released pipeline is reused by a later one, so two IDs can share an index,
+    /// and this is not a stable identity of the pipeline. Compare or hash the IDs themselves
+    /// instead, or pair the index with the [`generation`](Self::generation).
     #[inline]
     pub fn id(&self) -> usize {
-        self.0
+        self.0.index
+    }
+
+    /// Returns the generation of the slot the pipeline is stored in, which changes every time
+    /// the pipeline in that slot is released.
+    #[inline]
+    pub fn generation(&self) -> u32 {
+        self.0.generation
     }
 }
 
@@ -75,11 +151,23 @@ pub struct CachedComputePipelineId(CachedPipelineId);
 
 impl CachedComputePipelineId {
     /// An invalid cached compute pipeline index, often used to initialize a variable.
-    pub const INVALID: Self = CachedComputePipelineId(usize::MAX);
+    pub const INVALID: Self = CachedComputePipelineId(CachedPipelineId::INVALID);
 
+    /// Returns the index of the slot the pipeline is stored in.
+    ///
+    /// The slot of a released pipeline is reused by a later one, so two IDs can share an index,
+    /// and this is not a stable identity of the pipeline. Compare or hash the IDs themselves
+    /// instead, or pair the index with the [`generation`](Self::generation).
     #[inline]
     pub fn id(&self) -> usize {
-        self.0
+        self.0.index
+    }
+
+    /// Returns the generation of the slot the pipeline is stored in, which changes every time
+    /// the pipeline in that slot is released.
+    #[inline]
+    pub fn generation(&self) -> u32 {
+        self.0.generation
     }
 }
 
@@ -101,6 +189,26 @@ pub enum CachedPipelineState {
     Err(PipelineCacheError),
 }
 
+/// An event sent when a pipeline in the [`PipelineCache`] fails to compile because one of
+/// its shaders could not be processed or turned into a shader module.
+///
+/// The event is sent in the render world, and mirrored to the main world during extraction
+/// so that tools can display shader errors in-app.
+#[derive(Event, Clone, Debug)]
+pub struct ShaderCompilationError {
+    /// The debug label of the pipeline that failed to compile, if any.
+    pub pipeline_label: Option<Cow<'static, str>>,
+    /// The path of the shader the error originated from, if known.
+    ///
+    /// This may be the path of an imported module rather than one of the pipeline's own shaders.
+    pub shader_path: Option<String>,
+    /// The entry point of the pipeline stage using the failing shader, if the error originated
+    /// from one of the pipeline's own shaders.
+    pub entry_point: Option<Cow<'static, str>>,
+    /// The formatted error, including the offending source span when naga reports one.
+    pub message: String,
+}
+
 impl CachedPipelineState {
     /// Convenience method to "unwrap" a pipeline state into its underlying GPU object.
     ///
@@ -126,10 +234,19 @@ impl CachedPipelineState {
     }
 }
 
+/// A shader module created from a [`Shader`] with a given set of shader defs.
+#[derive(Clone)]
+struct ProcessedShader {
+    module: ErasedShaderModule,
+    /// The entry points found in the module. This is `None` for SPIR-V shaders, which are
+    /// passed to wgpu as is and can't be reflected.
+    entry_points: Option<Vec<(String, naga::ShaderStage)>>,
+}
+
 #[derive(Default)]
 struct ShaderData {
     pipelines: HashSet<CachedPipelineId>,
-    processed_shaders: HashMap<Box<[ShaderDefVal]>, ErasedShaderModule>,
+    processed_shaders: HashMap<Box<[ShaderDefVal]>, ProcessedShader>,
     resolved_imports: HashMap<ShaderImport, AssetId<Shader>>,
     dependents: HashSet<AssetId<Shader>>,
 }
@@ -221,6 +338,13 @@ impl ShaderCache {
         Ok(())
     }
 
+    /// Returns `true` if the shader was processed with these shader defs without errors.
+    fn is_processed(&self, id: AssetId<Shader>, shader_defs: &[ShaderDefVal]) -> bool {
+        self.data
+            .get(&id)
+            .is_some_and(|data| data.processed_shaders.contains_key(shader_defs))
+    }
+
     #[allow(clippy::result_large_err)]
     fn get(
         &mut self,
@@ -228,6 +352,8 @@ impl ShaderCache {
         pipeline: CachedPipelineId,
         id: AssetId<Shader>,
         shader_defs: &[ShaderDefVal],
+        stage: naga::ShaderStage,
+        entry_point: &str,
     ) -> Result<ErasedShaderModule, PipelineCacheError> {
         let shader = self
             .shaders
@@ -274,9 +400,9 @@ impl ShaderCache {
                     "processing shader {:?}, with shader defs {:?}",
                     id, shader_defs
                 );
-                let shader_source = match &shader.source {
+                let (shader_source, entry_points) = match &shader.source {
                     #[cfg(feature = "shader_format_spirv")]
-                    Source::SpirV(data) => make_spirv(data),
+                    Source::SpirV(data) => (make_spirv(data), None),
                     #[cfg(not(feature = "shader_format_spirv"))]
                     Source::SpirV(_) => {
                         unimplemented!(
@@ -316,7 +442,16 @@ impl ShaderCache {
                             },
                         )?;
 
-                        wgpu::ShaderSource::Naga(Cow::Owned(naga))
+                        let entry_points = naga
+                            .entry_points
+                            .iter()
+                            .map(|entry_point| (entry_point.name.clone(), entry_point.stage))
+                            .collect();
+
+                        (
+                            wgpu::ShaderSource::Naga(Cow::Owned(naga)),
+                            Some(entry_points),
+                        )
                     }
                 };
 
@@ -341,11 +476,32 @@ impl ShaderCache {
                     return Err(PipelineCacheError::CreateShaderModule(description));
                 }
 
-                entry.insert(ErasedShaderModule::new(shader_module))
+                entry.insert(ProcessedShader {
+                    module: ErasedShaderModule::new(shader_module),
+                    entry_points,
+                })
             }
         };
 
-        Ok(module.clone())
+        if let Some(entry_points) = &module.entry_points {
+            if !entry_points
+                .iter()
+                .any(|(name, entry_stage)| name == entry_point && *entry_stage == stage)
+            {
+                return Err(PipelineCacheError::EntryPointNotFound {
+                    shader_path: shader.path.clone(),
+                    entry_point: entry_point.to_string(),
+                    stage,
+                    available: entry_points
+                        .iter()
+                        .filter(|(_, entry_stage)| *entry_stage == stage)
+                        .map(|(name, _)| name.clone())
+                        .collect(),
+                });
+            }
+        }
+
+        Ok(module.module.clone())
     }
 
     fn clear(&mut self, id: AssetId<Shader>) -> Vec<CachedPipelineId> {
@@ -400,6 +556,13 @@ impl ShaderCache {
         pipelines_to_queue
     }
 
+    /// Stops tracking a released pipeline, so shader changes don't queue it again.
+    fn release_pipeline(&mut self, pipeline: CachedPipelineId) {
+        for data in self.data.values_mut() {
+            data.pipelines.remove(&pipeline);
+        }
+    }
+
     fn remove(&mut self, id: AssetId<Shader>) -> Vec<CachedPipelineId> {
         let pipelines_to_queue = self.clear(id);
         if let Some(shader) = self.shaders.remove(&id) {
@@ -410,6 +573,166 @@ impl ShaderCache {
     }
 }
 
+/// A slot of a [`PipelineCache`], holding a pipeline until it is released.
+struct PipelineSlot {
+    /// The generation of the ID of the pipeline in the slot.
+    generation: u32,
+    /// The pipeline, or `None` once it is released and until the slot is reused.
+    pipeline: Option<CachedPipeline>,
+}
+
+/// The slots of the pipelines of a [`PipelineCache`], indexed by their [`CachedPipelineId`].
+#[derive(Default)]
+struct PipelineSlots(Vec<PipelineSlot>);
+
+impl PipelineSlots {
+    /// Returns the pipeline `id` refers to, or `None` if it was released.
+    fn get(&self, id: CachedPipelineId) -> Option<&CachedPipeline> {
+        self.0
+            .get(id.index)
+            .filter(|slot| slot.generation == id.generation)
+            .and_then(|slot| slot.pipeline.as_ref())
+    }
+
+    fn get_mut(&mut self, id: CachedPipelineId) -> Option<&mut CachedPipeline> {
+        self.0
+            .get_mut(id.index)
+            .filter(|slot| slot.generation == id.generation)
+            .and_then(|slot| slot.pipeline.as_mut())
+    }
+
+    fn len(&self) -> usize {
+        self.0.len()
+    }
+}
+
+/// Pipelines inserted into or released from a [`PipelineCache`] since its queue was last processed.
+#[derive(Default)]
+struct NewPipelines {
+    /// Inserted pipelines in the order they were queued, stored either after the last slot or in
+    /// the slot of a released pipeline.
+    inserted: Vec<(CachedPipelineId, CachedPipeline)>,
+    /// The number of inserted pipelines stored after the last slot.
+    appended: usize,
+    /// Pipelines to release.
+    released: Vec<CachedPipelineId>,
+    /// The IDs the pipelines reusing the slots of released pipelines get.
+    free: Vec<CachedPipelineId>,
+}
+
+impl NewPipelines {
+    fn insert(&mut self, slot_count: usize, pipeline: CachedPipeline) -> CachedPipelineId {
+        let id = self.free.pop().unwrap_or_else(|| {
+            self.appended += 1;
+            CachedPipelineId {
+                index: slot_count + self.appended - 1,
+                generation: 0,
+            }
+        });
+        self.inserted.push((id, pipeline));
+        id
+    }
+
+    /// Stores the inserted pipelines in `slots` and adds them to `waiting`, then releases the
+    /// pipelines to release.
+    ///
+    /// Returns the IDs of the released pipelines.
+    fn apply(
+        &mut self,
+        slots: &mut PipelineSlots,
+        waiting: &mut WaitingPipelines,
+    ) -> Vec<CachedPipelineId> {
+        for (id, pipeline) in self.inserted.drain(..) {
+            let slot = PipelineSlot {
+                generation: id.generation,
+                pipeline: Some(pipeline),
+            };
+            // Appended pipelines were given increasing indices after the last slot.
+            if id.index == slots.len() {
+                slots.0.push(slot);
+            } else {
+                slots.0[id.index] = slot;
+            }
+            waiting.insert(id);
+        }
+        self.appended = 0;
+
+        let mut released = Vec::new();
+        for id in self.released.drain(..) {
+            // Releasing twice, or releasing a pipeline whose slot was reused since, must not
+            // touch the slot again.
+            let Some(slot) = slots
+                .0
+                .get_mut(id.index)
+                .filter(|slot| slot.generation == id.generation && slot.pipeline.is_some())
+            else {
+                continue;
+            };
+            // This drops the GPU object, or the task still creating it.
+            slot.pipeline = None;
+            slot.generation = slot.generation.wrapping_add(1);
+            waiting.remove(&id);
+            self.free.push(CachedPipelineId {
+                index: id.index,
+                generation: slot.generation,
+            });
+            released.push(id);
+        }
+        released
+    }
+}
+
+/// The pipelines of a [`PipelineCache`] waiting to be processed, in the order they started waiting.
+#[derive(Default)]
+struct WaitingPipelines {
+    /// The waiting pipelines, with the position they started waiting at.
+    pipelines: HashMap<CachedPipelineId, u64>,
+    next_position: u64,
+}
+
+impl WaitingPipelines {
+    /// Adds a pipeline after all waiting pipelines, unless it is already waiting.
+    fn insert(&mut self, id: CachedPipelineId) {
+        let position = self.next_position;
+        self.pipelines.entry(id).or_insert_with(|| position);
+        self.next_position += 1;
+    }
+
+    fn remove(&mut self, id: &CachedPipelineId) {
+        self.pipelines.remove(id);
+    }
+
+    /// Removes and returns the waiting pipelines to process now, oldest first.
+    ///
+    /// Only the `max_creations` oldest queued pipelines are returned, the other queued pipelines
+    /// keep their place and wait for a later call.
+    fn take(
+        &mut self,
+        slots: &PipelineSlots,
+        max_creations: Option<usize>,
+    ) -> Vec<CachedPipelineId> {
+        let mut waiting = self.pipelines.drain().collect::<Vec<_>>();
+        waiting.sort_unstable_by_key(|&(_, position)| position);
+
+        let mut remaining_creations = max_creations.unwrap_or(usize::MAX);
+        let mut taken = Vec::with_capacity(waiting.len());
+        for (id, position) in waiting {
+            let Some(cached_pipeline) = slots.get(id) else {
+                continue;
+            };
+            if let CachedPipelineState::Queued = cached_pipeline.state {
+                if remaining_creations == 0 {
+                    self.pipelines.insert(id, position);
+                    continue;
+                }
+                remaining_creations -= 1;
+            }
+            taken.push(id);
+        }
+        taken
+    }
+}
+
 type LayoutCacheKey = (Vec<BindGroupLayoutId>, Vec<PushConstantRange>);
 #[derive(Default)]
 struct LayoutCache {
@@ -454,29 +777,43 @@ impl LayoutCache {
 /// Note that the cache does not perform automatic deduplication of identical pipelines. It is
 /// up to the user not to insert the same pipeline twice to avoid wasting GPU resources.
 ///
+/// Pipelines that are no longer needed can be released with [`PipelineCache::release_render_pipeline()`]
+/// and [`PipelineCache::release_compute_pipeline()`], which frees their GPU object and lets later
+/// pipelines reuse their slot. The IDs of released pipelines never refer to the later ones.
+///
 /// [`RenderSet::Render`]: crate::RenderSet::Render
 #[derive(Resource)]
 pub struct PipelineCache {
     layout_cache: Arc<Mutex<LayoutCache>>,
     shader_cache: Arc<Mutex<ShaderCache>>,
     device: RenderDevice,
-    pipelines: Vec<CachedPipeline>,
-    waiting_pipelines: HashSet<CachedPipelineId>,
-    new_pipelines: Mutex<Vec<CachedPipeline>>,
+    pipelines: PipelineSlots,
+    waiting_pipelines: WaitingPipelines,
+    new_pipelines: Mutex<NewPipelines>,
+    compilation_errors: Vec<ShaderCompilationError>,
     /// If `true`, disables asynchronous pipeline compilation.
     /// This has no effect on MacOS, wasm, or without the `multi_threaded` feature.
     synchronous_pipeline_compilation: bool,
+    /// The maximum number of queued pipelines that start being created per call to
+    /// [`PipelineCache::process_queue`], or `None` for no limit.
+    max_pipeline_creations_per_frame: Option<usize>,
 }
 
 impl PipelineCache {
     /// Returns an iterator over the pipelines in the pipeline cache.
+    ///
+    /// Released pipelines are skipped, so the position of a pipeline in the iterator isn't the
+    /// [`id`](CachedRenderPipelineId::id) of its ID.
     pub fn pipelines(&self) -> impl Iterator<Item = &CachedPipeline> {
-        self.pipelines.iter()
+        self.pipelines
+            .0
+            .iter()
+            .filter_map(|slot| slot.pipeline.as_ref())
     }
 
-    /// Returns a iterator of the IDs of all currently waiting pipelines.
-    pub fn waiting_pipelines(&self) -> impl Iterator<Item = CachedPipelineId> + '_ {
-        self.waiting_pipelines.iter().copied()
+    /// Returns a iterator of the slot indices of all currently waiting pipelines.
+    pub fn waiting_pipelines(&self) -> impl Iterator<Item = usize> + '_ {
+        self.waiting_pipelines.pipelines.keys().map(|id| id.index)
     }
 
     /// Create a new pipeline cache associated with the given render device.
@@ -492,35 +829,98 @@ impl PipelineCache {
             waiting_pipelines: default(),
             new_pipelines: default(),
             pipelines: default(),
+            compilation_errors: default(),
             synchronous_pipeline_compilation,
+            max_pipeline_creations_per_frame: None,
         }
     }
 
+    /// Limits how many queued pipelines start being created each frame.
+    ///
+    /// Pipelines over the limit stay queued and are created on the following frames, oldest
+    /// first. This spreads the cost of compiling many new pipelines at once (e.g. when a camera
+    /// suddenly sees content needing dozens of new variants) over several frames.
+    ///
+    /// `None` removes the limit, which is the default.
+    pub fn set_max_pipeline_creations_per_frame(&mut self, max: Option<usize>) {
+        self.max_pipeline_creations_per_frame = max;
+    }
+
+    /// Returns the limit set by [`PipelineCache::set_max_pipeline_creations_per_frame`].
+    pub fn max_pipeline_creations_per_frame(&self) -> Option<usize> {
+        self.max_pipeline_creations_per_frame
+    }
+
+    /// Returns the pipeline `id` refers to.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the pipeline was released, or if the queue wasn't processed since it was inserted.
+    fn cached_pipeline(&self, id: CachedPipelineId) -> &CachedPipeline {
+        self.pipelines.get(id).unwrap_or_else(|| {
+            panic!("{id:?} was released from the pipeline cache, or isn't stored in it yet.")
+        })
+    }
+
     /// Get the state of a cached render pipeline.
     ///
     /// See [`PipelineCache::queue_render_pipeline()`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the pipeline was released, or if the queue wasn't processed since it was inserted.
+    /// [`PipelineCache::try_get_render_pipeline_state()`] returns `None` instead.
     #[inline]
     pub fn get_render_pipeline_state(&self, id: CachedRenderPipelineId) -> &CachedPipelineState {
-        &self.pipelines[id.0].state
+        &self.cached_pipeline(id.0).state
+    }
+
+    /// Get the state of a cached render pipeline, or `None` if the pipeline was released or the
+    /// queue wasn't processed since it was inserted.
+    #[inline]
+    pub fn try_get_render_pipeline_state(
+        &self,
+        id: CachedRenderPipelineId,
+    ) -> Option<&CachedPipelineState> {
+        self.pipelines.get(id.0).map(|pipeline| &pipeline.state)
+    }
+
+    /// Get the state of a cached compute pipeline, or `None` if the pipeline was released or the
+    /// queue wasn't processed since it was inserted.
+    #[inline]
+    pub fn try_get_compute_pipeline_state(
+        &self,
+        id: CachedComputePipelineId,
+    ) -> Option<&CachedPipelineState> {
+        self.pipelines.get(id.0).map(|pipeline| &pipeline.state)
     }
 
     /// Get the state of a cached compute pipeline.
     ///
     /// See [`PipelineCache::queue_compute_pipeline()`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the pipeline was released, or if the queue wasn't processed since it was inserted.
+    /// [`PipelineCache::try_get_compute_pipeline_state()`] returns `None` instead.
     #[inline]
     pub fn get_compute_pipeline_state(&self, id: CachedComputePipelineId) -> &CachedPipelineState {
-        &self.pipelines[id.0].state
+        &self.cached_pipeline(id.0).state
     }
 
     /// Get the render pipeline descriptor a cached render pipeline was inserted from.
     ///
     /// See [`PipelineCache::queue_render_pipeline()`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the pipeline was released, or if the queue wasn't processed since it was inserted.
     #[inline]
     pub fn get_render_pipeline_descriptor(
         &self,
         id: CachedRenderPipelineId,
     ) -> &RenderPipelineDescriptor {
-        match &self.pipelines[id.0].descriptor {
+        match &self.cached_pipeline(id.0).descriptor {
             PipelineDescriptor::RenderPipelineDescriptor(descriptor) => descriptor,
             PipelineDescriptor::ComputePipelineDescriptor(_) => unreachable!(),
         }
@@ -529,12 +929,16 @@ impl PipelineCache {
     /// Get the compute pipeline descriptor a cached render pipeline was inserted from.
     ///
     /// See [`PipelineCache::queue_compute_pipeline()`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the pipeline was released, or if the queue wasn't processed since it was inserted.
     #[inline]
     pub fn get_compute_pipeline_descriptor(
         &self,
         id: CachedComputePipelineId,
     ) -> &ComputePipelineDescriptor {
-        match &self.pipelines[id.0].descriptor {
+        match &self.cached_pipeline(id.0).descriptor {
             PipelineDescriptor::RenderPipelineDescriptor(_) => unreachable!(),
             PipelineDescriptor::ComputePipelineDescriptor(descriptor) => descriptor,
         }
@@ -545,12 +949,12 @@ impl PipelineCache {
     /// # Returns
     ///
     /// This method returns a successfully created render pipeline if any, or `None` if the pipeline
-    /// was not created yet or if there was an error during creation. You can check the actual creation
-    /// state with [`PipelineCache::get_render_pipeline_state()`].
+    /// was not created yet, if there was an error during creation, or if it was released. You can
+    /// check the actual creation state with [`PipelineCache::get_render_pipeline_state()`].
     #[inline]
     pub fn get_render_pipeline(&self, id: CachedRenderPipelineId) -> Option<&RenderPipeline> {
         if let CachedPipelineState::Ok(Pipeline::RenderPipeline(pipeline)) =
-            &self.pipelines[id.0].state
+            &self.pipelines.get(id.0)?.state
         {
             Some(pipeline)
         } else {
@@ -559,13 +963,28 @@ impl PipelineCache {
     }
 
     /// Wait for a render pipeline to finish compiling.
+    ///
+    /// Does nothing if the pipeline was released.
     #[inline]
     pub fn block_on_render_pipeline(&mut self, id: CachedRenderPipelineId) {
-        if self.pipelines.len() <= id.0 {
+        // A new pipeline is only stored once the queue is processed.
+        if self.pipelines.get(id.0).is_none() {
             self.process_queue();
         }
 
-        let state = &mut self.pipelines[id.0].state;
+        // The pipeline may have been held back by the creation limit.
+        if let Some(CachedPipelineState::Queued) = self.pipelines.get(id.0).map(|p| &p.state) {
+            let mut pipelines = mem::take(&mut self.pipelines);
+            if let Some(cached_pipeline) = pipelines.get_mut(id.0) {
+                self.process_pipeline(cached_pipeline, id.0);
+            }
+            self.pipelines = pipelines;
+        }
+
+        let Some(cached_pipeline) = self.pipelines.get_mut(id.0) else {
+            return;
+        };
+        let state = &mut cached_pipeline.state;
         if let CachedPipelineState::Creating(task) = state {
             *state = match bevy_tasks::block_on(task) {
                 Ok(p) => CachedPipelineState::Ok(p),
@@ -579,12 +998,12 @@ impl PipelineCache {
     /// # Returns
     ///
     /// This method returns a successfully created compute pipeline if any, or `None` if the pipeline
-    /// was not created yet or if there was an error during creation. You can check the actual creation
-    /// state with [`PipelineCache::get_compute_pipeline_state()`].
+    /// was not created yet, if there was an error during creation, or if it was released. You can
+    /// check the actual creation state with [`PipelineCache::get_compute_pipeline_state()`].
     #[inline]
     pub fn get_compute_pipeline(&self, id: CachedComputePipelineId) -> Option<&ComputePipeline> {
         if let CachedPipelineState::Ok(Pipeline::ComputePipeline(pipeline)) =
-            &self.pipelines[id.0].state
+            &self.pipelines.get(id.0)?.state
         {
             Some(pipeline)
         } else {
@@ -613,12 +1032,13 @@ impl PipelineCache {
             .new_pipelines
             .lock()
             .unwrap_or_else(PoisonError::into_inner);
-        let id = CachedRenderPipelineId(self.pipelines.len() + new_pipelines.len());
-        new_pipelines.push(CachedPipeline {
-            descriptor: PipelineDescriptor::RenderPipelineDescriptor(Box::new(descriptor)),
-            state: CachedPipelineState::Queued,
-        });
-        id
+        CachedRenderPipelineId(new_pipelines.insert(
+            self.pipelines.len(),
+            CachedPipeline {
+                descriptor: PipelineDescriptor::RenderPipelineDescriptor(Box::new(descriptor)),
+                state: CachedPipelineState::Queued,
+            },
+        ))
     }
 
     /// Insert a compute pipeline into the cache, and queue its creation.
@@ -642,32 +1062,67 @@ impl PipelineCache {
             .new_pipelines
             .lock()
             .unwrap_or_else(PoisonError::into_inner);
-        let id = CachedComputePipelineId(self.pipelines.len() + new_pipelines.len());
-        new_pipelines.push(CachedPipeline {
-            descriptor: PipelineDescriptor::ComputePipelineDescriptor(Box::new(descriptor)),
-            state: CachedPipelineState::Queued,
-        });
-        id
+        CachedComputePipelineId(new_pipelines.insert(
+            self.pipelines.len(),
+            CachedPipeline {
+                descriptor: PipelineDescriptor::ComputePipelineDescriptor(Box::new(descriptor)),
+                state: CachedPipelineState::Queued,
+            },
+        ))
+    }
+
+    /// Release a render pipeline that is no longer needed.
+    ///
+    /// The pipeline GPU object is freed the next time the queue is processed. After that,
+    /// [`PipelineCache::get_render_pipeline()`] returns `None` for `id`, and releasing it again
+    /// does nothing, even once a later pipeline reuses its slot.
+    pub fn release_render_pipeline(&self, id: CachedRenderPipelineId) {
+        self.new_pipelines
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .released
+            .push(id.0);
+    }
+
+    /// Release a compute pipeline that is no longer needed.
+    ///
+    /// See [`PipelineCache::release_render_pipeline()`].
+    pub fn release_compute_pipeline(&self, id: CachedComputePipelineId) {
+        self.new_pipelines
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .released
+            .push(id.0);
     }
 
     fn set_shader(&mut self, id: AssetId<Shader>, shader: &Shader) {
-        let mut shader_cache = self.shader_cache.lock().unwrap();
-        let pipelines_to_queue = shader_cache.set_shader(id, shader.clone());
+        let pipelines_to_queue = self
+            .shader_cache
+            .lock()
+            .unwrap()
+            .set_shader(id, shader.clone());
         for cached_pipeline in pipelines_to_queue {
-            self.pipelines[cached_pipeline].state = CachedPipelineState::Queued;
-            self.waiting_pipelines.insert(cached_pipeline);
+            self.requeue_pipeline(cached_pipeline);
         }
     }
 
     fn remove_shader(&mut self, shader: AssetId<Shader>) {
-        let mut shader_cache = self.shader_cache.lock().unwrap();
-        let pipelines_to_queue = shader_cache.remove(shader);
+        let pipelines_to_queue = self.shader_cache.lock().unwrap().remove(shader);
         for cached_pipeline in pipelines_to_queue {
-            self.pipelines[cached_pipeline].state = CachedPipelineState::Queued;
-            self.waiting_pipelines.insert(cached_pipeline);
+            self.requeue_pipeline(cached_pipeline);
         }
     }
 
+    fn requeue_pipeline(&mut self, id: CachedPipelineId) {
+        // A pipeline still being created when it was released may have registered itself
+        // with the shader cache again.
+        let Some(cached_pipeline) = self.pipelines.get_mut(id) else {
+            return;
+        };
+        cached_pipeline.state = CachedPipelineState::Queued;
+        self.waiting_pipelines.insert(id);
+    }
+
     fn start_create_render_pipeline(
         &mut self,
         id: CachedPipelineId,
@@ -686,6 +1141,8 @@ impl PipelineCache {
                     id,
                     descriptor.vertex.shader.id(),
                     &descriptor.vertex.shader_defs,
+                    naga::ShaderStage::Vertex,
+                    &descriptor.vertex.entry_point,
                 ) {
                     Ok(module) => module,
                     Err(err) => return Err(err),
@@ -698,6 +1155,8 @@ impl PipelineCache {
                             id,
                             fragment.shader.id(),
                             &fragment.shader_defs,
+                            naga::ShaderStage::Fragment,
+                            &fragment.entry_point,
                         ) {
                             Ok(module) => Some(module),
                             Err(err) => return Err(err),
@@ -795,6 +1254,8 @@ impl PipelineCache {
                     id,
                     descriptor.shader.id(),
                     &descriptor.shader_defs,
+                    naga::ShaderStage::Compute,
+                    &descriptor.entry_point,
                 ) {
                     Ok(module) => module,
                     Err(err) => return Err(err),
@@ -840,7 +1301,6 @@ impl PipelineCache {
     ///
     /// [`RenderSet::Render`]: crate::RenderSet::Render
     pub fn process_queue(&mut self) {
-        let mut waiting_pipelines = mem::take(&mut self.waiting_pipelines);
         let mut pipelines = mem::take(&mut self.pipelines);
 
         {
@@ -848,21 +1308,28 @@ impl PipelineCache {
                 .new_pipelines
                 .lock()
                 .unwrap_or_else(PoisonError::into_inner);
-            for new_pipeline in new_pipelines.drain(..) {
-                let id = pipelines.len();
-                pipelines.push(new_pipeline);
-                waiting_pipelines.insert(id);
+            let released = new_pipelines.apply(&mut pipelines, &mut self.waiting_pipelines);
+            if !released.is_empty() {
+                let mut shader_cache = self.shader_cache.lock().unwrap();
+                for id in released {
+                    shader_cache.release_pipeline(id);
+                }
             }
         }
 
+        let waiting_pipelines = self
+            .waiting_pipelines
+            .take(&pipelines, self.max_pipeline_creations_per_frame);
         for id in waiting_pipelines {
-            self.process_pipeline(&mut pipelines[id], id);
+            if let Some(cached_pipeline) = pipelines.get_mut(id) {
+                self.process_pipeline(cached_pipeline, id);
+            }
         }
 
         self.pipelines = pipelines;
     }
 
-    fn process_pipeline(&mut self, cached_pipeline: &mut CachedPipeline, id: usize) {
+    fn process_pipeline(&mut self, cached_pipeline: &mut CachedPipeline, id: CachedPipelineId) {
         match &mut cached_pipeline.state {
             CachedPipelineState::Queued => {
                 cached_pipeline.state = match &cached_pipeline.descriptor {
@@ -886,7 +1353,7 @@ impl PipelineCache {
                 }
             }
 
-            CachedPipelineState::Err(err) => match err {
+            CachedPipelineState::Err(err) => match &*err {
                 // Retry
                 PipelineCacheError::ShaderNotLoaded(_)
                 | PipelineCacheError::ShaderImportNotYetAvailable => {
@@ -895,13 +1362,56 @@ impl PipelineCache {
 
                 // Shader could not be processed ... retrying won't help
                 PipelineCacheError::ProcessShaderError(err) => {
-                    let error_detail =
-                        err.emit_to_string(&self.shader_cache.lock().unwrap().composer);
+                    let shader_cache = self.shader_cache.lock().unwrap();
+                    let error_detail = err.emit_to_string(&shader_cache.composer);
                     error!("failed to process shader:\n{}", error_detail);
+
+                    // Several stages may use the same shader. Processing stops at the first
+                    // failing stage, so the earlier stages using it were processed successfully.
+                    let shader_path = err.source.path(&shader_cache.composer);
+                    let entry_point = cached_pipeline
+                        .descriptor
+                        .stages()
+                        .into_iter()
+                        .find(|(shader, shader_defs, _)| {
+                            shader_cache
+                                .shaders
+                                .get(&shader.id())
+                                .is_some_and(|shader| &shader.path == shader_path)
+                                && !shader_cache.is_processed(shader.id(), shader_defs)
+                        })
+                        .map(|(_, _, entry_point)| entry_point.clone());
+                    self.compilation_errors.push(ShaderCompilationError {
+                        pipeline_label: cached_pipeline.descriptor.label().cloned(),
+                        shader_path: Some(shader_path.clone()),
+                        entry_point,
+                        message: error_detail,
+                    });
                     return;
                 }
                 PipelineCacheError::CreateShaderModule(description) => {
                     error!("failed to create shader module: {}", description);
+                    self.compilation_errors.push(ShaderCompilationError {
+                        pipeline_label: cached_pipeline.descriptor.label().cloned(),
+                        shader_path: None,
+                        entry_point: None,
+                        message: description.clone(),
+                    });
+                    return;
+                }
+                PipelineCacheError::EntryPointNotFound {
+                    shader_path,
+                    entry_point,
+                    ..
+                } => {
+                    let label = cached_pipeline.descriptor.label();
+                    error!("failed to create pipeline {:?}: {}", label, err);
+                    self.compilation_errors.push(ShaderCompilationError {
+                        pipeline_label: label.cloned(),
+                        shader_path: Some(shader_path.clone()),
+                        entry_point: Some(Cow::Owned(entry_point.clone())),
+                        message: err.to_string(),
+                    });
                     return;
                 }
             },
@@ -913,8 +1423,27 @@ impl PipelineCache {
         self.waiting_pipelines.insert(id);
     }
 
-    pub(crate) fn process_pipeline_queue_system(mut cache: ResMut<Self>) {
+    pub(crate) fn process_pipeline_queue_system(
+        mut cache: ResMut<Self>,
+        mut compilation_errors: ResMut<Events<ShaderCompilationError>>,
+    ) {
         cache.process_queue();
+
+        // The render world doesn't run the event update systems, so swap the buffers here once
+        // per frame. Events stay readable until the end of the next frame's render step.
+        compilation_errors.update();
+        compilation_errors.send_batch(cache.compilation_errors.drain(..));
+    }
+
+    /// Mirrors the [`ShaderCompilationError`]s sent in the render world to the main world.
+    pub(crate) fn extract_compilation_errors(
+        mut main_world: ResMut<MainWorld>,
+        mut compilation_errors: EventReader<ShaderCompilationError>,
+    ) {
+        if compilation_errors.is_empty() {
+            return;
+        }
+        main_world.send_event_batch(compilation_errors.read().cloned());
     }
 
     pub(crate) fn extract_shaders(
@@ -988,6 +1517,15 @@ pub enum PipelineCacheError {
     ShaderImportNotYetAvailable,
     #[error("Could not create shader module: {0}")]
     CreateShaderModule(String),
+    #[error(
+        "Entry point `{entry_point}` for the {stage:?} stage was not found in shader `{shader_path}`. Available {stage:?} entry points: {available:?}"
+    )]
+    EntryPointNotFound {
+        shader_path: String,
+        entry_point: String,
+        stage: naga::ShaderStage,
+        available: Vec<String>,
+    },
 }
 
 // TODO: This needs to be kept up to date with the capabilities in the `create_validator` function in wgpu-core
@@ -1075,3 +1613,140 @@ fn get_capabilities(
 
     (capabilities, subgroup_stages)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn compute_pipeline(label: &'static str) -> CachedPipeline {
+        CachedPipeline {
+            descriptor: PipelineDescriptor::ComputePipelineDescriptor(Box::new(
+                ComputePipelineDescriptor {
+                    label: Some(label.into()),
+                    layout: Vec::new(),
+                    push_constant_ranges: Vec::new(),
+                    shader: Handle::default(),
+                    shader_defs: Vec::new(),
+                    entry_point: "main".into(),
+                },
+            )),
+            state: CachedPipelineState::Queued,
+        }
+    }
+
+    fn label(slots: &PipelineSlots, id: CachedPipelineId) -> Option<&str> {
+        slots
+            .get(id)
+            .and_then(|pipeline| pipeline.descriptor.label())
+            .map(|label| label.as_ref())
+    }
+
+    #[test]
+    fn release_pipeline() {
+        let mut new_pipelines = NewPipelines::default();
+        let mut slots = PipelineSlots::default();
+        let mut waiting = WaitingPipelines::default();
+
+        let a = new_pipelines.insert(slots.len(), compute_pipeline("a"));
+        let b = new_pipelines.insert(slots.len(), compute_pipeline("b"));
+        assert!(new_pipelines.apply(&mut slots, &mut waiting).is_empty());
+        assert_eq!(label(&slots, a), Some("a"));
+        assert_eq!(label(&slots, b), Some("b"));
+
+        new_pipelines.released.push(a);
+        assert_eq!(new_pipelines.apply(&mut slots, &mut waiting), vec![a]);
+        assert_eq!(label(&slots, a), None);
+        assert_eq!(label(&slots, b), Some("b"));
+        assert!(!waiting.pipelines.contains_key(&a));
+        assert_eq!(slots.len(), 2);
+    }
+
+    #[test]
+    fn reused_slot_gets_a_new_id() {
+        let mut new_pipelines = NewPipelines::default();
+        let mut slots = PipelineSlots::default();
+        let mut waiting = WaitingPipelines::default();
+
+        let a = new_pipelines.insert(slots.len(), compute_pipeline("a"));
+        new_pipelines.apply(&mut slots, &mut waiting);
+        new_pipelines.released.push(a);
+        new_pipelines.apply(&mut slots, &mut waiting);
+
+        let c = new_pipelines.insert(slots.len(), compute_pipeline("c"));
+        new_pipelines.apply(&mut slots, &mut waiting);
+        assert_eq!(c.index, a.index);
+        assert_ne!(c, a);
+        assert_eq!(slots.len(), 1);
+        assert_eq!(label(&slots, c), Some("c"));
+        // The ID of the released pipeline doesn't refer to the one reusing its slot.
+        assert_eq!(label(&slots, a), None);
+    }
+
+    #[test]
+    fn stale_release_is_ignored() {
+        let mut new_pipelines = NewPipelines::default();
+        let mut slots = PipelineSlots::default();
+        let mut waiting = WaitingPipelines::default();
+
+        let a = new_pipelines.insert(slots.len(), compute_pipeline("a"));
+        new_pipelines.apply(&mut slots, &mut waiting);
+        new_pipelines.released.push(a);
+        new_pipelines.apply(&mut slots, &mut waiting);
+
+        // The slot is reused and the stale ID released again before the queue is processed.
+        let c = new_pipelines.insert(slots.len(), compute_pipeline("c"));
+        new_pipelines.released.push(a);
+        assert!(new_pipelines.apply(&mut slots, &mut waiting).is_empty());
+        assert_eq!(label(&slots, c), Some("c"));
+        assert!(waiting.pipelines.contains_key(&c));
+
+        // Releasing twice only frees the slot once.
+        new_pipelines.released.extend([c, c]);
+        assert_eq!(new_pipelines.apply(&mut slots, &mut waiting), vec![c]);
+        assert_eq!(new_pipelines.free.len(), 1);
+    }
+
+    #[test]
+    fn pipeline_queued_and_released_before_processing() {
+        let mut new_pipelines = NewPipelines::default();
+        let mut slots = PipelineSlots::default();
+        let mut waiting = WaitingPipelines::default();
+
+        let a = new_pipelines.insert(slots.len(), compute_pipeline("a"));
+        new_pipelines.released.push(a);
+        assert_eq!(new_pipelines.apply(&mut slots, &mut waiting), vec![a]);
+        assert_eq!(label(&slots, a), None);
+        assert!(waiting.pipelines.is_empty());
+    }
+
+    #[test]
+    fn pipeline_creations_are_limited_per_frame() {
+        let mut new_pipelines = NewPipelines::default();
+        let mut slots = PipelineSlots::default();
+        let mut waiting = WaitingPipelines::default();
+
+        // Free the first slot, so the last pipeline gets a lower index than the one queued
+        // before it.
+        let a = new_pipelines.insert(slots.len(), compute_pipeline("a"));
+        let b = new_pipelines.insert(slots.len(), compute_pipeline("b"));
+        new_pipelines.released.push(a);
+        new_pipelines.apply(&mut slots, &mut waiting);
+        let c = new_pipelines.insert(slots.len(), compute_pipeline("c"));
+        let d = new_pipelines.insert(slots.len(), compute_pipeline("d"));
+        new_pipelines.apply(&mut slots, &mut waiting);
+        assert!(c.index < b.index);
+
+        // Each frame creates at most two pipelines, oldest first.
+        assert_eq!(waiting.take(&slots, Some(2)), vec![b, c]);
+        assert_eq!(waiting.take(&slots, Some(2)), vec![d]);
+        assert!(waiting.take(&slots, Some(2)).is_empty());
+
+        // Deferred pipelines keep their place ahead of pipelines queued later.
+        let e = new_pipelines.insert(slots.len(), compute_pipeline("e"));
+        let f = new_pipelines.insert(slots.len(), compute_pipeline("f"));
+        new_pipelines.apply(&mut slots, &mut waiting);
+        assert_eq!(waiting.take(&slots, Some(1)), vec![e]);
+        waiting.insert(b);
+        assert_eq!(waiting.take(&slots, None), vec![f, b]);
+    }
+}
@@ -0,0 +1,159 @@
+use std::marker::PhantomData;
+
+use bevy_app::{App, Plugin};
+use bevy_ecs::prelude::*;
+
+use crate::{
+    render_resource::{
+        CachedPipelineState, CachedRenderPipelineId, PipelineCache, PipelineCacheError,
+        SpecializedRenderPipeline, SpecializedRenderPipelines,
+    },
+    ExtractSchedule, MainWorld, Render, RenderApp, RenderSet,
+};
+
+/// A [`SpecializedRenderPipeline`] that knows which keys it is likely to be specialized with.
+///
+/// Adding a [`PipelinePrewarmPlugin`] for it specializes all of these keys up front, so their
+/// pipelines are compiled before they are first needed (e.g. while a loading screen is shown)
+/// instead of causing a hitch the first time an effect is enabled.
+///
+/// Prewarming is opt-in, e.g. the tonemapping, FXAA and contrast adaptive sharpening pipelines
+/// of `bevy_core_pipeline` implement this trait, and are prewarmed by adding
+/// `PipelinePrewarmPlugin::<TonemappingPipeline>::default()` after their own plugins.
+pub trait PrewarmRenderPipeline: SpecializedRenderPipeline + Resource {
+    /// Returns the keys that should be specialized ahead of time.
+    fn warm_keys(&self) -> Vec<Self::Key>;
+}
+
+/// This plugin specializes the [`warm_keys`](PrewarmRenderPipeline::warm_keys) of a
+/// [`PrewarmRenderPipeline`] into its [`SpecializedRenderPipelines`] on the first rendered frame
+/// where the pipeline resource exists.
+///
+/// Progress across all prewarmed pipelines is reported in the main world through the
+/// [`PipelinePrewarmProgress`] resource.
+pub struct PipelinePrewarmPlugin<P: PrewarmRenderPipeline>(PhantomData<P>);
+
+impl<P: PrewarmRenderPipeline> Default for PipelinePrewarmPlugin<P> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<P: PrewarmRenderPipeline> Plugin for PipelinePrewarmPlugin<P>
+where
+    P::Key: Send + Sync,
+{
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PipelinePrewarmProgress>();
+
+        let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+
+        // The progress tracking is shared by every prewarmed pipeline type, so only add it once.
+        if !render_app.world().contains_resource::<PrewarmedPipelines>() {
+            render_app
+                .init_resource::<PrewarmedPipelines>()
+                .add_systems(ExtractSchedule, extract_prewarm_progress);
+        }
+
+        render_app
+            .init_resource::<SpecializedRenderPipelines<P>>()
+            .add_systems(
+                Render,
+                prewarm_render_pipelines::<P>
+                    .in_set(RenderSet::Prepare)
+                    .run_if(resource_exists::<P>),
+            );
+    }
+}
+
+/// The progress of pipelines queued by [`PipelinePrewarmPlugin`]s, available in the main world.
+///
+/// Pipelines are queued on the first rendered frame, and the progress is updated during
+/// extraction, so it lags the render world by one frame.
+#[derive(Resource, Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PipelinePrewarmProgress {
+    /// The number of prewarmed pipelines queued for compilation so far.
+    pub queued: usize,
+    /// The number of prewarmed pipelines that finished compiling, successfully or not.
+    pub finished: usize,
+}
+
+impl PipelinePrewarmProgress {
+    /// Returns `true` once some pipelines were queued and all of them finished compiling.
+    pub fn is_done(&self) -> bool {
+        self.queued > 0 && self.finished == self.queued
+    }
+}
+
+/// The pipelines queued by [`PipelinePrewarmPlugin`]s, in the render world.
+#[derive(Resource, Default)]
+struct PrewarmedPipelines {
+    /// The pipelines that didn't finish compiling yet.
+    pending: Vec<CachedRenderPipelineId>,
+    queued: usize,
+    finished: usize,
+}
+
+fn prewarm_render_pipelines<P: PrewarmRenderPipeline>(
+    mut prewarmed: Local<bool>,
+    pipeline: Res<P>,
+    mut pipelines: ResMut<SpecializedRenderPipelines<P>>,
+    pipeline_cache: Res<PipelineCache>,
+    mut prewarmed_pipelines: ResMut<PrewarmedPipelines>,
+) where
+    P::Key: Send + Sync,
+{
+    if *prewarmed {
+        return;
+    }
+    *prewarmed = true;
+
+    for key in pipeline.warm_keys() {
+        let id = pipelines.specialize(&pipeline_cache, &pipeline, key);
+        prewarmed_pipelines.pending.push(id);
+        prewarmed_pipelines.queued += 1;
+    }
+}
+
+fn extract_prewarm_progress(
+    mut main_world: ResMut<MainWorld>,
+    pipeline_cache: Res<PipelineCache>,
+    mut prewarmed_pipelines: ResMut<PrewarmedPipelines>,
+) {
+    let PrewarmedPipelines {
+        pending,
+        queued,
+        finished,
+    } = &mut *prewarmed_pipelines;
+    pending.retain(|id| {
+        // Pipelines are prewarmed before the queue is processed in the same frame, so a
+        // pipeline missing from the cache was released and will never finish compiling. Shaders
+        // that weren't loaded yet are retried.
+        let compiling = matches!(
+            pipeline_cache.try_get_render_pipeline_state(*id),
+            Some(
+                CachedPipelineState::Queued
+                    | CachedPipelineState::Creating(_)
+                    | CachedPipelineState::Err(
+                        PipelineCacheError::ShaderNotLoaded(_)
+                            | PipelineCacheError::ShaderImportNotYetAvailable
+                    )
+            )
+        );
+        if !compiling {
+            *finished += 1;
+        }
+        compiling
+    });
+
+    let progress = PipelinePrewarmProgress {
+        queued: *queued,
+        finished: *finished,
+    };
+    if let Some(mut current) = main_world.get_resource_mut::<PipelinePrewarmProgress>() {
+        // Avoid triggering change detection every frame once prewarming is done.
+        current.set_if_neq(progress);
+    }
+}
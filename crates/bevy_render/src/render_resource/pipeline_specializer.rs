@@ -3,8 +3,8 @@ use crate::render_resource::CachedComputePipelineId;
 use crate::{
     mesh::MissingVertexAttributeError,
     render_resource::{
-        CachedRenderPipelineId, ComputePipelineDescriptor, PipelineCache, RenderPipelineDescriptor,
-        VertexBufferLayout,
+        CachedPipelineState, CachedRenderPipelineId, ComputePipelineDescriptor, PipelineCache,
+        RenderPipelineDescriptor, VertexBufferLayout,
     },
 };
 use bevy_ecs::system::Resource;
@@ -20,7 +20,7 @@ pub trait SpecializedRenderPipeline {
 
 #[derive(Resource)]
 pub struct SpecializedRenderPipelines<S: SpecializedRenderPipeline> {
-    cache: HashMap<S::Key, CachedRenderPipelineId>,
+    cache: Specializations<S::Key, CachedRenderPipelineId>,
 }
 
 impl<S: SpecializedRenderPipeline> Default for SpecializedRenderPipelines<S> {
@@ -36,11 +36,81 @@ impl<S: SpecializedRenderPipeline> SpecializedRenderPipelines<S> {
         specialize_pipeline: &S,
         key: S::Key,
     ) -> CachedRenderPipelineId {
-        *self.cache.entry(key.clone()).or_insert_with(|| {
+        self.cache.get_or_insert_with(key, |key| {
             let descriptor = specialize_pipeline.specialize(key);
             cache.queue_render_pipeline(descriptor)
         })
     }
+
+    /// Removes the pipeline specialized for `key`, and releases it from the [`PipelineCache`].
+    ///
+    /// Returns the ID of the released pipeline, if `key` was specialized.
+    pub fn remove(
+        &mut self,
+        cache: &PipelineCache,
+        key: &S::Key,
+    ) -> Option<CachedRenderPipelineId> {
+        let id = self.cache.remove(key)?;
+        cache.release_render_pipeline(id);
+        Some(id)
+    }
+
+    /// Returns the specialized keys and the IDs of their pipelines.
+    pub fn iter(&self) -> impl Iterator<Item = (&S::Key, CachedRenderPipelineId)> {
+        self.cache.iter()
+    }
+
+    /// Returns the specialized keys, the IDs of their pipelines and the compile state of these
+    /// pipelines, which is `None` until the [`PipelineCache`] processed its queue.
+    pub fn states<'a>(
+        &'a self,
+        cache: &'a PipelineCache,
+    ) -> impl Iterator<
+        Item = (
+            &'a S::Key,
+            CachedRenderPipelineId,
+            Option<&'a CachedPipelineState>,
+        ),
+    > {
+        self.cache
+            .iter()
+            .map(|(key, id)| (key, id, cache.try_get_render_pipeline_state(id)))
+    }
+
+    /// Returns the number of calls to [`evict_unused`](Self::evict_unused) since `key` was last
+    /// specialized, or `None` if it isn't specialized.
+    pub fn unused_for(&self, key: &S::Key) -> Option<u32> {
+        self.cache.unused_for(key)
+    }
+
+    /// Returns the keys that weren't specialized since the last `max_age` calls to
+    /// [`evict_unused`](Self::evict_unused).
+    pub fn unused_keys(&self, max_age: u32) -> impl Iterator<Item = &S::Key> {
+        self.cache.unused(max_age)
+    }
+
+    /// Removes the pipelines whose keys weren't specialized since the last `max_age` calls to
+    /// this method, and releases them from the [`PipelineCache`].
+    ///
+    /// Calling this once per frame, e.g. in [`RenderSet::Cleanup`](crate::RenderSet::Cleanup),
+    /// evicts the pipelines that weren't used for `max_age` frames, so caches whose keys churn
+    /// (e.g. per resolution or texture format) don't grow unboundedly.
+    ///
+    /// Returns the number of released pipelines.
+    pub fn evict_unused(&mut self, cache: &PipelineCache, max_age: u32) -> usize {
+        let evicted = self.cache.evict_unused(max_age);
+        for &id in &evicted {
+            cache.release_render_pipeline(id);
+        }
+        evicted.len()
+    }
+
+    /// Removes all specialized pipelines, and releases them from the [`PipelineCache`].
+    pub fn clear(&mut self, cache: &PipelineCache) {
+        for id in self.cache.clear() {
+            cache.release_render_pipeline(id);
+        }
+    }
 }
 
 pub trait SpecializedComputePipeline {
@@ -50,7 +120,7 @@ pub trait SpecializedComputePipeline {
 
 #[derive(Resource)]
 pub struct SpecializedComputePipelines<S: SpecializedComputePipeline> {
-    cache: HashMap<S::Key, CachedComputePipelineId>,
+    cache: Specializations<S::Key, CachedComputePipelineId>,
 }
 
 impl<S: SpecializedComputePipeline> Default for SpecializedComputePipelines<S> {
@@ -66,11 +136,175 @@ impl<S: SpecializedComputePipeline> SpecializedComputePipelines<S> {
         specialize_pipeline: &S,
         key: S::Key,
     ) -> CachedComputePipelineId {
-        *self.cache.entry(key.clone()).or_insert_with(|| {
+        self.cache.get_or_insert_with(key, |key| {
             let descriptor = specialize_pipeline.specialize(key);
             cache.queue_compute_pipeline(descriptor)
         })
     }
+
+    /// Removes the pipeline specialized for `key`, and releases it from the [`PipelineCache`].
+    ///
+    /// Returns the ID of the released pipeline, if `key` was specialized.
+    pub fn remove(
+        &mut self,
+        cache: &PipelineCache,
+        key: &S::Key,
+    ) -> Option<CachedComputePipelineId> {
+        let id = self.cache.remove(key)?;
+        cache.release_compute_pipeline(id);
+        Some(id)
+    }
+
+    /// Returns the specialized keys and the IDs of their pipelines.
+    pub fn iter(&self) -> impl Iterator<Item = (&S::Key, CachedComputePipelineId)> {
+        self.cache.iter()
+    }
+
+    /// Returns the specialized keys, the IDs of their pipelines and the compile state of these
+    /// pipelines, which is `None` until the [`PipelineCache`] processed its queue.
+    pub fn states<'a>(
+        &'a self,
+        cache: &'a PipelineCache,
+    ) -> impl Iterator<
+        Item = (
+            &'a S::Key,
+            CachedComputePipelineId,
+            Option<&'a CachedPipelineState>,
+        ),
+    > {
+        self.cache
+            .iter()
+            .map(|(key, id)| (key, id, cache.try_get_compute_pipeline_state(id)))
+    }
+
+    /// Returns the number of calls to [`evict_unused`](Self::evict_unused) since `key` was last
+    /// specialized, or `None` if it isn't specialized.
+    pub fn unused_for(&self, key: &S::Key) -> Option<u32> {
+        self.cache.unused_for(key)
+    }
+
+    /// Returns the keys that weren't specialized since the last `max_age` calls to
+    /// [`evict_unused`](Self::evict_unused).
+    pub fn unused_keys(&self, max_age: u32) -> impl Iterator<Item = &S::Key> {
+        self.cache.unused(max_age)
+    }
+
+    /// Removes the pipelines whose keys weren't specialized since the last `max_age` calls to
+    /// this method, and releases them from the [`PipelineCache`].
+    ///
+    /// Calling this once per frame, e.g. in [`RenderSet::Cleanup`](crate::RenderSet::Cleanup),
+    /// evicts the pipelines that weren't used for `max_age` frames, so caches whose keys churn
+    /// (e.g. per resolution or texture format) don't grow unboundedly.
+    ///
+    /// Returns the number of released pipelines.
+    pub fn evict_unused(&mut self, cache: &PipelineCache, max_age: u32) -> usize {
+        let evicted = self.cache.evict_unused(max_age);
+        for &id in &evicted {
+            cache.release_compute_pipeline(id);
+        }
+        evicted.len()
+    }
+
+    /// Removes all specialized pipelines, and releases them from the [`PipelineCache`].
+    pub fn clear(&mut self, cache: &PipelineCache) {
+        for id in self.cache.clear() {
+            cache.release_compute_pipeline(id);
+        }
+    }
+}
+
+/// The pipelines of a [`SpecializedRenderPipelines`] or [`SpecializedComputePipelines`], with
+/// the eviction pass each key was last specialized in.
+struct Specializations<K, I> {
+    entries: HashMap<K, Specialization<I>>,
+    /// The number of eviction passes so far.
+    pass: u32,
+}
+
+struct Specialization<I> {
+    id: I,
+    last_used: u32,
+}
+
+impl<K, I> Default for Specializations<K, I> {
+    fn default() -> Self {
+        Self {
+            entries: default(),
+            pass: 0,
+        }
+    }
+}
+
+impl<K: Clone + Hash + Eq, I: Copy> Specializations<K, I> {
+    fn get_or_insert_with(&mut self, key: K, insert: impl FnOnce(K) -> I) -> I {
+        let pass = self.pass;
+        match self.entries.entry(key) {
+            Entry::Occupied(entry) => {
+                let specialization = entry.into_mut();
+                specialization.last_used = pass;
+                specialization.id
+            }
+            Entry::Vacant(entry) => {
+                let id = insert(entry.key().clone());
+                entry.insert(Specialization {
+                    id,
+                    last_used: pass,
+                });
+                id
+            }
+        }
+    }
+
+    fn remove(&mut self, key: &K) -> Option<I> {
+        self.entries
+            .remove(key)
+            .map(|specialization| specialization.id)
+    }
+
+    fn age(&self, specialization: &Specialization<I>) -> u32 {
+        self.pass.wrapping_sub(specialization.last_used)
+    }
+
+    fn iter(&self) -> impl Iterator<Item = (&K, I)> {
+        self.entries
+            .iter()
+            .map(|(key, specialization)| (key, specialization.id))
+    }
+
+    fn unused_for(&self, key: &K) -> Option<u32> {
+        self.entries
+            .get(key)
+            .map(|specialization| self.age(specialization))
+    }
+
+    fn unused(&self, max_age: u32) -> impl Iterator<Item = &K> {
+        self.entries
+            .iter()
+            .filter(move |(_, specialization)| self.age(specialization) >= max_age)
+            .map(|(key, _)| key)
+    }
+
+    /// Removes the entries unused for `max_age` passes, and starts a new pass.
+    fn evict_unused(&mut self, max_age: u32) -> Vec<I> {
+        let mut evicted = Vec::new();
+        let pass = self.pass;
+        self.entries.retain(|_, specialization| {
+            let unused = pass.wrapping_sub(specialization.last_used) >= max_age;
+            if unused {
+                evicted.push(specialization.id);
+            }
+            !unused
+        });
+        self.pass = self.pass.wrapping_add(1);
+        evicted
+    }
+
+    fn clear(&mut self) -> Vec<I> {
+        self.entries
+            .drain()
+            .map(|(_, specialization)| specialization.id)
+            .collect()
+    }
 }
 
 pub trait SpecializedMeshPipeline {
@@ -186,3 +420,42 @@ pub enum SpecializedMeshPipelineError {
     #[error(transparent)]
     MissingVertexAttribute(#[from] MissingVertexAttributeError),
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn evict_unused_specializations() {
+        let mut specializations = Specializations::<&str, u32>::default();
+        specializations.get_or_insert_with("a", |_| 0);
+        specializations.get_or_insert_with("b", |_| 1);
+        assert!(specializations.evict_unused(2).is_empty());
+
+        // Only "a" is used during the second pass.
+        assert_eq!(
+            specializations.get_or_insert_with("a", |_| unreachable!()),
+            0
+        );
+        assert_eq!(specializations.unused(1).collect::<Vec<_>>(), vec![&"b"]);
+        assert!(specializations.evict_unused(2).is_empty());
+        assert_eq!(specializations.evict_unused(2), vec![1]);
+        assert_eq!(specializations.evict_unused(2), vec![0]);
+        assert!(specializations.entries.is_empty());
+    }
+
+    #[test]
+    fn list_specializations() {
+        let mut specializations = Specializations::<&str, u32>::default();
+        specializations.get_or_insert_with("a", |_| 0);
+        specializations.evict_unused(u32::MAX);
+        specializations.get_or_insert_with("b", |_| 1);
+
+        let mut entries = specializations.iter().collect::<Vec<_>>();
+        entries.sort_unstable();
+        assert_eq!(entries, vec![(&"a", 0), (&"b", 1)]);
+        assert_eq!(specializations.unused_for(&"a"), Some(1));
+        assert_eq!(specializations.unused_for(&"b"), Some(0));
+        assert_eq!(specializations.unused_for(&"c"), None);
+    }
+}
@@ -0,0 +1,260 @@
+use std::{fmt::Write, str::FromStr};
+
+use bevy_math::Vec3;
+use bevy_reflect::{std_traits::ReflectDefault, Reflect};
+use thiserror::Error;
+
+/// A color correction in the [ASC CDL] format, the standard way of exchanging
+/// primary grades between color grading tools such as `DaVinci` Resolve.
+///
+/// Each channel of a color is corrected with:
+///
+/// ```text
+/// out = (i × slope + offset)^power
+/// ```
+///
+/// and the saturation of the result is then adjusted, using the ITU-R BT.709
+/// luminance.
+///
+/// A correction can be read from the XML of `.cc`, `.cdl`, and `.ccc` files
+/// with [`AscCdl::from_xml`] and applied to a camera with
+/// [`ColorGrading::from_asc_cdl`](super::ColorGrading::from_asc_cdl), or to a
+/// single section with
+/// [`ColorGradingSection::from_asc_cdl`](super::ColorGradingSection::from_asc_cdl).
+///
+/// ```
+/// # use bevy_render::view::{AscCdl, ColorGrading};
+/// let cdl: AscCdl = r#"
+///     <ColorCorrection id="shot_010">
+///         <SOPNode>
+///             <Slope>1.1 1.0 0.9</Slope>
+///             <Offset>0.01 0.0 -0.01</Offset>
+///             <Power>1.0 1.0 1.0</Power>
+///         </SOPNode>
+///         <SatNode>
+///             <Saturation>0.8</Saturation>
+///         </SatNode>
+///     </ColorCorrection>
+/// "#
+/// .parse()
+/// .unwrap();
+///
+/// let color_grading = ColorGrading::from_asc_cdl(&cdl);
+/// assert_eq!(color_grading.midtones.saturation, 0.8);
+/// ```
+///
+/// [ASC CDL]: https://en.wikipedia.org/wiki/ASC_CDL
+#[derive(Clone, Copy, Debug, PartialEq, Reflect)]
+#[reflect(Default)]
+pub struct AscCdl {
+    /// The factor that each channel is multiplied by.
+    pub slope: Vec3,
+    /// The term added to each channel after the slope.
+    pub offset: Vec3,
+    /// The exponent that each channel is raised to after the offset.
+    pub power: Vec3,
+    /// The saturation adjustment applied after the slope, offset, and power.
+    pub saturation: f32,
+}
+
+impl Default for AscCdl {
+    fn default() -> Self {
+        Self {
+            slope: Vec3::ONE,
+            offset: Vec3::ZERO,
+            power: Vec3::ONE,
+            saturation: 1.0,
+        }
+    }
+}
+
+/// An error that occurred while reading an [`AscCdl`].
+#[non_exhaustive]
+#[derive(Debug, Error, PartialEq)]
+pub enum AscCdlError {
+    #[error("The CDL has no ColorCorrection element")]
+    MissingColorCorrection,
+    #[error("The CDL has no {0} element")]
+    MissingElement(&'static str),
+    #[error("Invalid value for the {element} element of the CDL: {value:?}")]
+    InvalidValue {
+        element: &'static str,
+        value: String,
+    },
+}
+
+impl AscCdl {
+    /// Reads the first `ColorCorrection` element of a `.cc`, `.cdl`, or `.ccc`
+    /// file.
+    ///
+    /// A missing `SOPNode` or `SatNode` leaves the corresponding values at
+    /// their defaults, which have no effect.
+    pub fn from_xml(xml: &str) -> Result<AscCdl, AscCdlError> {
+        let correction =
+            element(xml, "ColorCorrection").ok_or(AscCdlError::MissingColorCorrection)?;
+
+        let mut cdl = AscCdl::default();
+        if let Some(sop) = element(correction, "SOPNode") {
+            cdl.slope = parse_vec3(sop, "Slope")?;
+            cdl.offset = parse_vec3(sop, "Offset")?;
+            cdl.power = parse_vec3(sop, "Power")?;
+        }
+        // Some tools write `SATNode` instead of the `SatNode` of the
+        // specification.
+        if let Some(sat) = element(correction, "SatNode").or_else(|| element(correction, "SATNode"))
+        {
+            let value = element(sat, "Saturation")
+                .ok_or(AscCdlError::MissingElement("Saturation"))?
+                .trim();
+            cdl.saturation = value.parse().map_err(|_| AscCdlError::InvalidValue {
+                element: "Saturation",
+                value: value.to_string(),
+            })?;
+        }
+        Ok(cdl)
+    }
+
+    /// Writes this correction as the XML of a `.cc` file, with the given `id`.
+    pub fn to_xml(&self, id: &str) -> String {
+        let [slope, offset, power] = [self.slope, self.offset, self.power]
+            .map(|value| format!("{} {} {}", value.x, value.y, value.z));
+
+        let mut xml = String::new();
+        let _ = writeln!(xml, r#"<?xml version="1.0" encoding="UTF-8"?>"#);
+        let _ = writeln!(
+            xml,
+            r#"<ColorCorrection xmlns="urn:ASC:CDL:v1.01" id="{}">"#,
+            escape_attribute(id)
+        );
+        let _ = writeln!(xml, "    <SOPNode>");
+        let _ = writeln!(xml, "        <Slope>{slope}</Slope>");
+        let _ = writeln!(xml, "        <Offset>{offset}</Offset>");
+        let _ = writeln!(xml, "        <Power>{power}</Power>");
+        let _ = writeln!(xml, "    </SOPNode>");
+        let _ = writeln!(xml, "    <SatNode>");
+        let _ = writeln!(xml, "        <Saturation>{}</Saturation>", self.saturation);
+        let _ = writeln!(xml, "    </SatNode>");
+        let _ = writeln!(xml, "</ColorCorrection>");
+        xml
+    }
+}
+
+impl FromStr for AscCdl {
+    type Err = AscCdlError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        AscCdl::from_xml(s)
+    }
+}
+
+/// Returns the contents of the first element called `name` in `xml`.
+///
+/// CDL files are simple enough that a full XML parser isn't needed: elements
+/// of the same name are never nested, and the values are plain text.
+fn element<'a>(xml: &'a str, name: &str) -> Option<&'a str> {
+    let open = format!("<{name}");
+    let mut rest = xml;
+    loop {
+        let start = rest.find(&open)? + open.len();
+        rest = &rest[start..];
+        // Skip elements that only start with `name`, like `SatNodeExtra`.
+        match rest.chars().next()? {
+            '>' => break,
+            c if c.is_whitespace() => break,
+            _ => {}
+        }
+    }
+    let body_start = rest.find('>')? + 1;
+    let body = &rest[body_start..];
+    let end = body.find(&format!("</{name}>"))?;
+    Some(&body[..end])
+}
+
+fn parse_vec3(xml: &str, name: &'static str) -> Result<Vec3, AscCdlError> {
+    let value = element(xml, name).ok_or(AscCdlError::MissingElement(name))?;
+    let mut values = value.split_whitespace().map(str::parse::<f32>);
+    let (Some(Ok(x)), Some(Ok(y)), Some(Ok(z)), None) =
+        (values.next(), values.next(), values.next(), values.next())
+    else {
+        return Err(AscCdlError::InvalidValue {
+            element: name,
+            value: value.trim().to_string(),
+        });
+    };
+    Ok(Vec3::new(x, y, z))
+}
+
+fn escape_attribute(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('"', "&quot;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AscCdl, AscCdlError};
+    use bevy_math::Vec3;
+
+    #[test]
+    fn parse_color_decision_list() {
+        let cdl = AscCdl::from_xml(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+            <ColorDecisionList xmlns="urn:ASC:CDL:v1.01">
+                <ColorDecision>
+                    <ColorCorrection id="A001">
+                        <SOPNode>
+                            <Description>Warm</Description>
+                            <Slope>1.2 1.0 0.8</Slope>
+                            <Offset>0.05 0 -0.05</Offset>
+                            <Power>0.9 1.0 1.1</Power>
+                        </SOPNode>
+                        <SATNode>
+                            <Saturation>1.25</Saturation>
+                        </SATNode>
+                    </ColorCorrection>
+                </ColorDecision>
+            </ColorDecisionList>"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            cdl,
+            AscCdl {
+                slope: Vec3::new(1.2, 1.0, 0.8),
+                offset: Vec3::new(0.05, 0.0, -0.05),
+                power: Vec3::new(0.9, 1.0, 1.1),
+                saturation: 1.25,
+            }
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        let cdl = AscCdl {
+            slope: Vec3::new(1.1, 0.95, 1.0),
+            offset: Vec3::new(-0.02, 0.0, 0.03),
+            power: Vec3::new(1.0, 1.2, 0.8),
+            saturation: 0.7,
+        };
+        assert_eq!(AscCdl::from_xml(&cdl.to_xml("shot \"1\"")), Ok(cdl));
+    }
+
+    #[test]
+    fn invalid() {
+        assert_eq!(
+            AscCdl::from_xml("<ColorDecisionList></ColorDecisionList>"),
+            Err(AscCdlError::MissingColorCorrection)
+        );
+        assert_eq!(
+            AscCdl::from_xml(
+                "<ColorCorrection><SOPNode><Slope>1 1</Slope></SOPNode></ColorCorrection>"
+            ),
+            Err(AscCdlError::InvalidValue {
+                element: "Slope",
+                value: "1 1".to_string(),
+            })
+        );
+    }
+}
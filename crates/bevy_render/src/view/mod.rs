@@ -1,6 +1,8 @@
+mod asc_cdl;
 pub mod visibility;
 pub mod window;
 
+pub use asc_cdl::*;
 use bevy_asset::{load_internal_asset, Handle};
 pub use visibility::*;
 pub use window::*;
@@ -8,7 +10,7 @@ pub use window::*;
 use crate::{
     camera::{
         CameraMainTextureUsages, ClearColor, ClearColorConfig, Exposure, ExtractedCamera,
-        ManualTextureViews, MipBias, TemporalJitter,
+        MainPassResolutionOverride, ManualTextureViews, MipBias, TemporalJitter,
     },
     extract_resource::{ExtractResource, ExtractResourcePlugin},
     prelude::Shader,
@@ -29,7 +31,7 @@ use bevy_ecs::prelude::*;
 use bevy_math::{mat3, vec2, vec3, Mat3, Mat4, UVec4, Vec2, Vec3, Vec4, Vec4Swizzles};
 use bevy_reflect::{std_traits::ReflectDefault, Reflect};
 use bevy_transform::components::GlobalTransform;
-use bevy_utils::HashMap;
+use bevy_utils::{default, HashMap};
 use std::{
     ops::Range,
     sync::{
@@ -199,7 +201,7 @@ impl ExtractedView {
 /// [`Camera`](crate::camera::Camera) entity, with the sole exception of the
 /// `post_saturation` value in [`ColorGradingGlobal`], which is applied after
 /// tonemapping.
-#[derive(Component, Reflect, Debug, Default, Clone)]
+#[derive(Component, Reflect, Debug, Default, Clone, PartialEq)]
 #[reflect(Component, Default)]
 pub struct ColorGrading {
     /// Filmic color grading values applied to the image as a whole (as opposed
@@ -228,7 +230,7 @@ pub struct ColorGrading {
 
 /// Filmic color grading values applied to the image as a whole (as opposed to
 /// individual sections, like shadows and highlights).
-#[derive(Clone, Debug, Reflect)]
+#[derive(Clone, Debug, Reflect, PartialEq)]
 #[reflect(Default)]
 pub struct ColorGradingGlobal {
     /// Exposure value (EV) offset, measured in stops.
@@ -286,6 +288,9 @@ struct ColorGradingUniform {
     gamma: Vec3,
     gain: Vec3,
     lift: Vec3,
+    slope: Mat3,
+    offset: Mat3,
+    power: Mat3,
     midtone_range: Vec2,
     exposure: f32,
     hue: f32,
@@ -346,6 +351,35 @@ pub struct ColorGradingSection {
     ///
     /// [ASC CDL]: https://en.wikipedia.org/wiki/ASC_CDL#Combined_Function
     pub lift: f32,
+
+    /// The per-channel *slope* of an [ASC CDL] correction, which multiplies
+    /// the red, green, and blue channels.
+    ///
+    /// The slope, offset, and power of a section are applied before its other
+    /// values, so that [`ColorGradingSection::saturation`] plays the part of the
+    /// CDL saturation. See [`AscCdl`] to load these values from a grade made
+    /// in another tool.
+    ///
+    /// The default value is 1.0 for every channel.
+    ///
+    /// [ASC CDL]: https://en.wikipedia.org/wiki/ASC_CDL#Combined_Function
+    pub slope: Vec3,
+
+    /// The per-channel *offset* of an [ASC CDL] correction, which is added to
+    /// the red, green, and blue channels after the slope.
+    ///
+    /// The default value is 0.0 for every channel.
+    ///
+    /// [ASC CDL]: https://en.wikipedia.org/wiki/ASC_CDL#Combined_Function
+    pub offset: Vec3,
+
+    /// The per-channel *power* of an [ASC CDL] correction, the exponent that
+    /// the red, green, and blue channels are raised to after the offset.
+    ///
+    /// The default value is 1.0 for every channel.
+    ///
+    /// [ASC CDL]: https://en.wikipedia.org/wiki/ASC_CDL#Combined_Function
+    pub power: Vec3,
 }
 
 impl Default for ColorGradingGlobal {
@@ -369,6 +403,9 @@ impl Default for ColorGradingSection {
             gamma: 1.0,
             gain: 1.0,
             lift: 0.0,
+            slope: Vec3::ONE,
+            offset: Vec3::ZERO,
+            power: Vec3::ONE,
         }
     }
 }
@@ -402,6 +439,46 @@ impl ColorGrading {
     pub fn all_sections_mut(&mut self) -> impl Iterator<Item = &mut ColorGradingSection> {
         [&mut self.shadows, &mut self.midtones, &mut self.highlights].into_iter()
     }
+
+    /// Creates a new [`ColorGrading`] instance that applies the given [ASC CDL]
+    /// correction to the whole image.
+    ///
+    /// [ASC CDL]: https://en.wikipedia.org/wiki/ASC_CDL
+    pub fn from_asc_cdl(cdl: &AscCdl) -> ColorGrading {
+        ColorGrading::with_identical_sections(default(), ColorGradingSection::from_asc_cdl(cdl))
+    }
+}
+
+impl ColorGradingSection {
+    /// Creates a new [`ColorGradingSection`] that applies the given [ASC CDL]
+    /// correction and nothing else.
+    ///
+    /// [ASC CDL]: https://en.wikipedia.org/wiki/ASC_CDL
+    pub fn from_asc_cdl(cdl: &AscCdl) -> ColorGradingSection {
+        ColorGradingSection {
+            saturation: cdl.saturation,
+            slope: cdl.slope,
+            offset: cdl.offset,
+            power: cdl.power,
+            ..default()
+        }
+    }
+
+    /// Returns the [ASC CDL] part of this section, so that it can be exported
+    /// to other tools.
+    ///
+    /// Only the slope, offset, power, and saturation have an equivalent in a
+    /// CDL; the contrast, gamma, gain, and lift are ignored.
+    ///
+    /// [ASC CDL]: https://en.wikipedia.org/wiki/ASC_CDL
+    pub fn asc_cdl(&self) -> AscCdl {
+        AscCdl {
+            slope: self.slope,
+            offset: self.offset,
+            power: self.power,
+            saturation: self.saturation,
+        }
+    }
 }
 
 #[derive(Clone, ShaderType)]
@@ -529,6 +606,21 @@ impl From<ColorGrading> for ColorGradingUniform {
                 component.midtones.lift,
                 component.highlights.lift,
             ),
+            slope: Mat3::from_cols(
+                component.shadows.slope,
+                component.midtones.slope,
+                component.highlights.slope,
+            ),
+            offset: Mat3::from_cols(
+                component.shadows.offset,
+                component.midtones.offset,
+                component.highlights.offset,
+            ),
+            power: Mat3::from_cols(
+                component.shadows.power,
+                component.midtones.power,
+                component.highlights.power,
+            ),
             midtone_range: vec2(
                 component.global.midtones_range.start,
                 component.global.midtones_range.end,
@@ -722,6 +814,7 @@ pub fn prepare_view_uniforms(
         Option<&Frustum>,
         Option<&TemporalJitter>,
         Option<&MipBias>,
+        Option<&MainPassResolutionOverride>,
     )>,
 ) {
     let view_iter = views.iter();
@@ -733,8 +826,21 @@ pub fn prepare_view_uniforms(
     else {
         return;
     };
-    for (entity, extracted_camera, extracted_view, frustum, temporal_jitter, mip_bias) in &views {
-        let viewport = extracted_view.viewport.as_vec4();
+    for (
+        entity,
+        extracted_camera,
+        extracted_view,
+        frustum,
+        temporal_jitter,
+        mip_bias,
+        resolution_override,
+    ) in &views
+    {
+        let mut viewport = extracted_view.viewport.as_vec4();
+        if let Some(resolution_override) = resolution_override {
+            viewport.z = resolution_override.x as f32;
+            viewport.w = resolution_override.y as f32;
+        }
         let unjittered_projection = extracted_view.clip_from_view;
         let mut clip_from_view = unjittered_projection;
 
@@ -7,6 +7,9 @@ struct ColorGrading {
     gamma: vec3<f32>,
     gain: vec3<f32>,
     lift: vec3<f32>,
+    slope: mat3x3<f32>,
+    offset: mat3x3<f32>,
+    power: mat3x3<f32>,
     midtone_range: vec2<f32>,
     exposure: f32,
     hue: f32,
@@ -3,7 +3,8 @@ use bevy_asset::{load_internal_asset, AssetId, Handle};
 
 use bevy_core_pipeline::core_2d::Transparent2d;
 use bevy_core_pipeline::tonemapping::{
-    get_lut_bind_group_layout_entries, get_lut_bindings, Tonemapping, TonemappingLuts,
+    custom_tonemapping_curve_shader_def, get_lut_bind_group_layout_entries, get_lut_bindings,
+    Tonemapping, TonemappingLuts,
 };
 use bevy_derive::{Deref, DerefMut};
 use bevy_ecs::entity::EntityHashMap;
@@ -398,6 +399,9 @@ bitflags::bitflags! {
         const TONEMAP_METHOD_SOMEWHAT_BORING_DISPLAY_TRANSFORM = 5 << Self::TONEMAP_METHOD_SHIFT_BITS;
         const TONEMAP_METHOD_TONY_MC_MAPFACE    = 6 << Self::TONEMAP_METHOD_SHIFT_BITS;
         const TONEMAP_METHOD_BLENDER_FILMIC     = 7 << Self::TONEMAP_METHOD_SHIFT_BITS;
+        const TONEMAP_METHOD_LUT                = 8 << Self::TONEMAP_METHOD_SHIFT_BITS;
+        const TONEMAP_METHOD_CUSTOM             = 9 << Self::TONEMAP_METHOD_SHIFT_BITS;
+        const CUSTOM_TONEMAPPING_CURVE_RESERVED_BITS = Self::CUSTOM_TONEMAPPING_CURVE_MASK_BITS << Self::CUSTOM_TONEMAPPING_CURVE_SHIFT_BITS;
     }
 }
 
@@ -406,9 +410,12 @@ impl Mesh2dPipelineKey {
     const MSAA_SHIFT_BITS: u32 = 32 - Self::MSAA_MASK_BITS.count_ones();
     const PRIMITIVE_TOPOLOGY_MASK_BITS: u32 = 0b111;
     const PRIMITIVE_TOPOLOGY_SHIFT_BITS: u32 = Self::MSAA_SHIFT_BITS - 3;
-    const TONEMAP_METHOD_MASK_BITS: u32 = 0b111;
+    const TONEMAP_METHOD_MASK_BITS: u32 = 0b1111;
     const TONEMAP_METHOD_SHIFT_BITS: u32 =
         Self::PRIMITIVE_TOPOLOGY_SHIFT_BITS - Self::TONEMAP_METHOD_MASK_BITS.count_ones();
+    const CUSTOM_TONEMAPPING_CURVE_MASK_BITS: u32 = 0b1111;
+    const CUSTOM_TONEMAPPING_CURVE_SHIFT_BITS: u32 =
+        Self::TONEMAP_METHOD_SHIFT_BITS - Self::CUSTOM_TONEMAPPING_CURVE_MASK_BITS.count_ones();
 
     pub fn from_msaa_samples(msaa_samples: u32) -> Self {
         let msaa_bits =
@@ -428,6 +435,21 @@ impl Mesh2dPipelineKey {
         1 << ((self.bits() >> Self::MSAA_SHIFT_BITS) & Self::MSAA_MASK_BITS)
     }
 
+    /// Selects the curve in the given slot of the
+    /// [`CustomTonemappingCurves`](bevy_core_pipeline::tonemapping::CustomTonemappingCurves), for
+    /// [`Mesh2dPipelineKey::TONEMAP_METHOD_CUSTOM`].
+    pub fn from_custom_tonemapping_curve(slot: Option<u32>) -> Self {
+        let bits = (slot.map_or(0, |slot| slot + 1) & Self::CUSTOM_TONEMAPPING_CURVE_MASK_BITS)
+            << Self::CUSTOM_TONEMAPPING_CURVE_SHIFT_BITS;
+        Self::from_bits_retain(bits)
+    }
+
+    pub fn custom_tonemapping_curve(&self) -> Option<u32> {
+        ((self.bits() >> Self::CUSTOM_TONEMAPPING_CURVE_SHIFT_BITS)
+            & Self::CUSTOM_TONEMAPPING_CURVE_MASK_BITS)
+            .checked_sub(1)
+    }
+
     pub fn from_primitive_topology(primitive_topology: PrimitiveTopology) -> Self {
         let primitive_topology_bits = ((primitive_topology as u32)
             & Self::PRIMITIVE_TOPOLOGY_MASK_BITS)
@@ -523,6 +545,15 @@ impl SpecializedMeshPipeline for Mesh2dPipeline {
                 Mesh2dPipelineKey::TONEMAP_METHOD_TONY_MC_MAPFACE => {
                     shader_defs.push("TONEMAP_METHOD_TONY_MC_MAPFACE".into());
                 }
+                Mesh2dPipelineKey::TONEMAP_METHOD_LUT => {
+                    shader_defs.push("TONEMAP_METHOD_LUT".into());
+                }
+                Mesh2dPipelineKey::TONEMAP_METHOD_CUSTOM => {
+                    shader_defs.push("TONEMAP_METHOD_CUSTOM".into());
+                    if let Some(slot) = key.custom_tonemapping_curve() {
+                        shader_defs.push(custom_tonemapping_curve_shader_def(slot));
+                    }
+                }
                 _ => {}
             }
             // Debanding is tied to tonemapping in the shader, cannot run without it.
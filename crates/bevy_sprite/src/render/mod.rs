@@ -9,8 +9,8 @@ use bevy_color::{ColorToComponents, LinearRgba};
 use bevy_core_pipeline::{
     core_2d::Transparent2d,
     tonemapping::{
-        get_lut_bind_group_layout_entries, get_lut_bindings, DebandDither, Tonemapping,
-        TonemappingLuts,
+        custom_tonemapping_curve_shader_def, get_lut_bind_group_layout_entries, get_lut_bindings,
+        CustomTonemappingCurves, DebandDither, Tonemapping, TonemappingLuts,
     },
 };
 use bevy_ecs::{entity::EntityHashMap, query::ROQueryItem};
@@ -150,15 +150,21 @@ bitflags::bitflags! {
         const TONEMAP_METHOD_SOMEWHAT_BORING_DISPLAY_TRANSFORM = 5 << Self::TONEMAP_METHOD_SHIFT_BITS;
         const TONEMAP_METHOD_TONY_MC_MAPFACE    = 6 << Self::TONEMAP_METHOD_SHIFT_BITS;
         const TONEMAP_METHOD_BLENDER_FILMIC     = 7 << Self::TONEMAP_METHOD_SHIFT_BITS;
+        const TONEMAP_METHOD_LUT                = 8 << Self::TONEMAP_METHOD_SHIFT_BITS;
+        const TONEMAP_METHOD_CUSTOM             = 9 << Self::TONEMAP_METHOD_SHIFT_BITS;
+        const CUSTOM_TONEMAPPING_CURVE_RESERVED_BITS = Self::CUSTOM_TONEMAPPING_CURVE_MASK_BITS << Self::CUSTOM_TONEMAPPING_CURVE_SHIFT_BITS;
     }
 }
 
 impl SpritePipelineKey {
     const MSAA_MASK_BITS: u32 = 0b111;
     const MSAA_SHIFT_BITS: u32 = 32 - Self::MSAA_MASK_BITS.count_ones();
-    const TONEMAP_METHOD_MASK_BITS: u32 = 0b111;
+    const TONEMAP_METHOD_MASK_BITS: u32 = 0b1111;
     const TONEMAP_METHOD_SHIFT_BITS: u32 =
         Self::MSAA_SHIFT_BITS - Self::TONEMAP_METHOD_MASK_BITS.count_ones();
+    const CUSTOM_TONEMAPPING_CURVE_MASK_BITS: u32 = 0b1111;
+    const CUSTOM_TONEMAPPING_CURVE_SHIFT_BITS: u32 =
+        Self::TONEMAP_METHOD_SHIFT_BITS - Self::CUSTOM_TONEMAPPING_CURVE_MASK_BITS.count_ones();
 
     #[inline]
     pub const fn from_msaa_samples(msaa_samples: u32) -> Self {
@@ -180,6 +186,22 @@ impl SpritePipelineKey {
             SpritePipelineKey::NONE
         }
     }
+
+    /// Selects the curve in the given slot of the [`CustomTonemappingCurves`], for
+    /// [`SpritePipelineKey::TONEMAP_METHOD_CUSTOM`].
+    #[inline]
+    pub fn from_custom_tonemapping_curve(slot: Option<u32>) -> Self {
+        let bits = (slot.map_or(0, |slot| slot + 1) & Self::CUSTOM_TONEMAPPING_CURVE_MASK_BITS)
+            << Self::CUSTOM_TONEMAPPING_CURVE_SHIFT_BITS;
+        Self::from_bits_retain(bits)
+    }
+
+    #[inline]
+    pub const fn custom_tonemapping_curve(&self) -> Option<u32> {
+        ((self.bits() >> Self::CUSTOM_TONEMAPPING_CURVE_SHIFT_BITS)
+            & Self::CUSTOM_TONEMAPPING_CURVE_MASK_BITS)
+            .checked_sub(1)
+    }
 }
 
 impl SpecializedRenderPipeline for SpritePipeline {
@@ -217,6 +239,13 @@ impl SpecializedRenderPipeline for SpritePipeline {
                 shader_defs.push("TONEMAP_METHOD_BLENDER_FILMIC".into());
             } else if method == SpritePipelineKey::TONEMAP_METHOD_TONY_MC_MAPFACE {
                 shader_defs.push("TONEMAP_METHOD_TONY_MC_MAPFACE".into());
+            } else if method == SpritePipelineKey::TONEMAP_METHOD_LUT {
+                shader_defs.push("TONEMAP_METHOD_LUT".into());
+            } else if method == SpritePipelineKey::TONEMAP_METHOD_CUSTOM {
+                shader_defs.push("TONEMAP_METHOD_CUSTOM".into());
+                if let Some(slot) = key.custom_tonemapping_curve() {
+                    shader_defs.push(custom_tonemapping_curve_shader_def(slot));
+                }
             }
 
             // Debanding is tied to tonemapping in the shader, cannot run without it.
@@ -472,6 +501,7 @@ pub fn queue_sprites(
     mut pipelines: ResMut<SpecializedRenderPipelines<SpritePipeline>>,
     pipeline_cache: Res<PipelineCache>,
     msaa: Res<Msaa>,
+    custom_tonemapping_curves: Res<CustomTonemappingCurves>,
     extracted_sprites: Res<ExtractedSprites>,
     mut transparent_render_phases: ResMut<ViewSortedRenderPhases<Transparent2d>>,
     mut views: Query<(
@@ -509,7 +539,12 @@ pub fn queue_sprites(
                     }
                     Tonemapping::TonyMcMapface => SpritePipelineKey::TONEMAP_METHOD_TONY_MC_MAPFACE,
                     Tonemapping::BlenderFilmic => SpritePipelineKey::TONEMAP_METHOD_BLENDER_FILMIC,
+                    Tonemapping::Lut(_) => SpritePipelineKey::TONEMAP_METHOD_LUT,
+                    Tonemapping::Custom(_) => SpritePipelineKey::TONEMAP_METHOD_CUSTOM,
                 };
+                view_key |= SpritePipelineKey::from_custom_tonemapping_curve(
+                    custom_tonemapping_curves.slot(tonemapping),
+                );
             }
             if let Some(DebandDither::Enabled) = dither {
                 view_key |= SpritePipelineKey::DEBAND_DITHER;
@@ -1,4 +1,4 @@
-//! This example compares MSAA (Multi-Sample Anti-aliasing), FXAA (Fast Approximate Anti-aliasing), and TAA (Temporal Anti-aliasing).
+//! This example compares MSAA (Multi-Sample Anti-aliasing), FXAA (Fast Approximate Anti-aliasing), SMAA (Subpixel Morphological Anti-aliasing), and TAA (Temporal Anti-aliasing).
 
 use std::f32::consts::PI;
 use std::fmt::Write;
@@ -10,11 +10,13 @@ use bevy::{
             TemporalAntiAliasBundle, TemporalAntiAliasPlugin, TemporalAntiAliasSettings,
         },
         fxaa::{Fxaa, Sensitivity},
+        prepass::MotionVectorPrepass,
         smaa::{SmaaPreset, SmaaSettings},
     },
     pbr::CascadeShadowConfigBuilder,
     prelude::*,
     render::{
+        camera::TemporalJitter,
         render_asset::RenderAssetUsages,
         render_resource::{Extent3d, TextureDimension, TextureFormat},
         texture::{ImageSampler, ImageSamplerDescriptor},
@@ -133,6 +135,19 @@ fn modify_aa(
         if keys.just_pressed(KeyCode::KeyR) {
             smaa.preset = SmaaPreset::Ultra;
         }
+        if keys.just_pressed(KeyCode::KeyT) {
+            smaa.preset = SmaaPreset::T2x;
+        }
+
+        // SMAA T2x jitters the camera and reprojects the previous frame with
+        // the motion vectors.
+        if smaa.is_changed() {
+            if smaa.preset == SmaaPreset::T2x {
+                camera.insert((TemporalJitter::default(), MotionVectorPrepass));
+            } else {
+                camera.remove::<(TemporalJitter, MotionVectorPrepass)>();
+            }
+        }
     }
 
     // TAA
@@ -232,6 +247,7 @@ fn update_ui(
         draw_selectable_menu_item(ui, "Medium", 'W', smaa.preset == SmaaPreset::Medium);
         draw_selectable_menu_item(ui, "High", 'E', smaa.preset == SmaaPreset::High);
         draw_selectable_menu_item(ui, "Ultra", 'R', smaa.preset == SmaaPreset::Ultra);
+        draw_selectable_menu_item(ui, "T2x", 'T', smaa.preset == SmaaPreset::T2x);
     }
 
     ui.push_str("\n----------\n\n");
@@ -3,7 +3,7 @@
 use bevy::{
     color::palettes::basic::GRAY,
     core_pipeline::{
-        bloom::{BloomCompositeMode, BloomSettings},
+        bloom::{BloomCompositeMode, BloomLensFlare, BloomSettings},
         tonemapping::Tonemapping,
     },
     prelude::*,
@@ -142,6 +142,10 @@ fn update_bloom_settings(
                 "(U/J) Threshold softness: {}\n",
                 bloom_settings.prefilter_settings.threshold_softness
             ));
+            text.push_str(&format!(
+                "(L) Lens flare: {}\n",
+                bloom_settings.lens_flare.is_some()
+            ));
 
             if keycode.just_pressed(KeyCode::Space) {
                 commands.entity(entity).remove::<BloomSettings>();
@@ -208,6 +212,13 @@ fn update_bloom_settings(
                 .prefilter_settings
                 .threshold_softness
                 .clamp(0.0, 1.0);
+
+            if keycode.just_pressed(KeyCode::KeyL) {
+                bloom_settings.lens_flare = match bloom_settings.lens_flare {
+                    Some(_) => None,
+                    None => Some(BloomLensFlare::default()),
+                };
+            }
         }
 
         (entity, None) => {
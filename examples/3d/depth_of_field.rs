@@ -143,7 +143,8 @@ fn change_mode(input: Res<ButtonInput<KeyCode>>, mut app_settings: ResMut<AppSet
     }
 
     app_settings.mode = match app_settings.mode {
-        Some(DepthOfFieldMode::Bokeh) => Some(DepthOfFieldMode::Gaussian),
+        Some(DepthOfFieldMode::Bokeh) => Some(DepthOfFieldMode::Aperture),
+        Some(DepthOfFieldMode::Aperture) => Some(DepthOfFieldMode::Gaussian),
         Some(DepthOfFieldMode::Gaussian) => None,
         None => Some(DepthOfFieldMode::Bokeh),
     }
@@ -179,8 +180,8 @@ fn update_dof_settings(
             None => {
                 commands.entity(view).remove::<DepthOfFieldSettings>();
             }
-            Some(dof_settings) => {
-                commands.entity(view).insert(dof_settings);
+            Some(ref dof_settings) => {
+                commands.entity(view).insert(dof_settings.clone());
             }
         }
     }
@@ -263,6 +264,7 @@ Mode: {} (Press Space to change)",
             dof::calculate_focal_length(sensor_height, fov) * 1000.0,
             match mode {
                 DepthOfFieldMode::Bokeh => "Bokeh",
+                DepthOfFieldMode::Aperture => "Aperture",
                 DepthOfFieldMode::Gaussian => "Gaussian",
             }
         )
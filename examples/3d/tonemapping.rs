@@ -405,7 +405,7 @@ fn update_color_grading_settings(
 
     if keys.just_pressed(KeyCode::Enter) && current_scene.0 == 1 {
         for (mapper, grading) in per_method_settings.settings.iter_mut() {
-            *grading = PerMethodSettings::basic_scene_recommendation(*mapper);
+            *grading = PerMethodSettings::basic_scene_recommendation(mapper);
         }
     }
 }
@@ -419,7 +419,7 @@ fn update_ui(
     keys: Res<ButtonInput<KeyCode>>,
 ) {
     let (method, color_grading) = settings.single();
-    let method = *method;
+    let method = method.clone();
 
     let mut text = text.single_mut();
     let text = &mut text.sections[0].value;
@@ -545,7 +545,7 @@ struct PerMethodSettings {
 }
 
 impl PerMethodSettings {
-    fn basic_scene_recommendation(method: Tonemapping) -> ColorGrading {
+    fn basic_scene_recommendation(method: &Tonemapping) -> ColorGrading {
         match method {
             Tonemapping::Reinhard | Tonemapping::ReinhardLuminance => ColorGrading {
                 global: ColorGradingGlobal {
@@ -591,10 +591,8 @@ impl Default for PerMethodSettings {
             Tonemapping::TonyMcMapface,
             Tonemapping::BlenderFilmic,
         ] {
-            settings.insert(
-                method,
-                PerMethodSettings::basic_scene_recommendation(method),
-            );
+            let recommendation = PerMethodSettings::basic_scene_recommendation(&method);
+            settings.insert(method, recommendation);
         }
 
         Self { settings }
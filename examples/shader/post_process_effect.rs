@@ -0,0 +1,113 @@
+//! This example shows how to write a custom post processing effect with only a shader and a
+//! settings component, using [`PostProcessEffect`].
+//!
+//! The example shader is the same chromatic aberration as in the `post_processing` example,
+//! which shows how to write the render graph node and the pipeline of an effect by hand.
+
+use bevy::{
+    core_pipeline::post_process_effect::{
+        PostProcessEffect, PostProcessEffectPlugin, PostProcessOrder,
+    },
+    prelude::*,
+    render::render_resource::{ShaderRef, ShaderType},
+};
+
+/// This example uses a shader source file from the assets subdirectory
+const SHADER_ASSET_PATH: &str = "shaders/post_processing.wgsl";
+
+fn main() {
+    App::new()
+        .add_plugins((
+            DefaultPlugins,
+            // This plugin extracts the settings to the render world, uploads them to the GPU,
+            // and creates the pipeline and the render graph node of the effect.
+            PostProcessEffectPlugin::<ChromaticAberration>::default(),
+        ))
+        .add_systems(Startup, setup)
+        .add_systems(Update, (rotate, update_settings))
+        .run();
+}
+
+// This is the component that enables the effect on a camera, and that will get passed to the
+// shader
+#[derive(Component, Default, Clone, Copy, ShaderType)]
+struct ChromaticAberration {
+    intensity: f32,
+    // WebGL2 structs must be 16 byte aligned.
+    #[cfg(feature = "webgl2")]
+    _webgl2_padding: Vec3,
+}
+
+impl PostProcessEffect for ChromaticAberration {
+    fn fragment_shader() -> ShaderRef {
+        SHADER_ASSET_PATH.into()
+    }
+
+    fn order() -> PostProcessOrder {
+        PostProcessOrder::AfterTonemapping
+    }
+}
+
+/// Set up a simple 3D scene
+fn setup(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    // camera
+    commands.spawn((
+        Camera3dBundle {
+            transform: Transform::from_translation(Vec3::new(0.0, 0.0, 5.0))
+                .looking_at(Vec3::default(), Vec3::Y),
+            camera: Camera {
+                clear_color: Color::WHITE.into(),
+                ..default()
+            },
+            ..default()
+        },
+        // Add the effect to the camera.
+        ChromaticAberration {
+            intensity: 0.02,
+            ..default()
+        },
+    ));
+
+    // cube
+    commands.spawn((
+        PbrBundle {
+            mesh: meshes.add(Cuboid::default()),
+            material: materials.add(Color::srgb(0.8, 0.7, 0.6)),
+            transform: Transform::from_xyz(0.0, 0.5, 0.0),
+            ..default()
+        },
+        Rotates,
+    ));
+    // light
+    commands.spawn(DirectionalLightBundle {
+        directional_light: DirectionalLight {
+            illuminance: 1_000.,
+            ..default()
+        },
+        ..default()
+    });
+}
+
+#[derive(Component)]
+struct Rotates;
+
+/// Rotates any entity around the x and y axis
+fn rotate(time: Res<Time>, mut query: Query<&mut Transform, With<Rotates>>) {
+    for mut transform in &mut query {
+        transform.rotate_x(0.55 * time.delta_seconds());
+        transform.rotate_z(0.15 * time.delta_seconds());
+    }
+}
+
+// Change the intensity over time to show that the effect is controlled from the main world
+fn update_settings(mut settings: Query<&mut ChromaticAberration>, time: Res<Time>) {
+    for mut setting in &mut settings {
+        // Remap the intensity to 0..1 because it can't be negative, and scale it to a more
+        // reasonable level
+        setting.intensity = (time.elapsed_seconds().sin() * 0.5 + 0.5) * 0.015;
+    }
+}